@@ -1,10 +1,46 @@
+use chrono::{Local, Utc};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::BufWriter,
+    path::{Path, PathBuf},
+};
+use tiny_skia::Pixmap;
 use tui::{
     backend::Backend,
     layout::{Constraint, Direction, Layout},
     Frame,
 };
 
+use crate::config::{expand_home, tilde_path, Config};
+use crate::domain::{
+    batch_import::ImportPlan,
+    bib_import::BibImport,
+    bibtex::{self, BibtexEntry},
+    bulk,
+    citation::{self, BibliographyFormat, Citation},
+    export::{self, ExportRow},
+    folder,
+    identifiers::{Identifiers, SCANNED_PAGES},
+    identity::{self, FileIdentity},
+    importer::{self, Importer},
+    library_store::{DocumentRecord, LibraryStore},
+    metadata::{self, HttpResolver, Lookup, MetadataResolver},
+    organize::{self, Filed, OrganizePlan},
+    pdf_file::PdfFile,
+    pdf_info::{self, PdfInfo, PdfInfoCache},
+    pdf_text,
+    query::Query,
+    thumbnail::ThumbnailCache,
+    triage::{Outcome, TriageQueue},
+};
+use crate::error::{Error, Result};
+use crate::graphics::Placement;
+use crate::logging;
+use crate::opener;
 use crate::state::AppState;
+use crate::watcher::DirWatcher;
+use crate::worker::Worker;
 use crate::{
     actions::{Action, Actions},
     components::{inbox::InboxComponent, DrawableComponent},
@@ -12,7 +48,12 @@ use crate::{
 use crate::{
     components::{
         command::{self, CommandInfo},
-        inbox::InboxFocus,
+        error_popup::ErrorPopup,
+        help::HelpComponent,
+        inbox::{collection_tree::CollectionEntry, saved_searches::SavedSearchEntry, InboxFocus},
+        log::LogComponent,
+        status_bar::StatusBarComponent,
+        CommandBlocking,
     },
     key_config::KeyConfig,
 };
@@ -21,7 +62,20 @@ use crate::{
     inputs::key::Key,
 };
 
-use crate::components::pdf_import_popup::PdfImportPopup;
+use crate::components::{
+    batch_import_popup::BatchImportRequest,
+    bib_import_popup::BibImportRequest,
+    bulk_popup::{BulkAction, BulkRequest},
+    citation_popup::CitationRequest,
+    collection_popup::CollectionRequest,
+    confirm_popup::ConfirmRequest,
+    folder_popup::FolderRequest,
+    organize_popup::OrganizeRequest,
+    pdf_import_popup::{ImportHints, ImportRequest},
+    popups::{PopupRequest, Popups},
+    save_search_popup::SaveSearchRequest,
+    triage_popup::TriageRequest,
+};
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum AppReturn {
@@ -35,13 +89,73 @@ enum Focus {
     Inbox,
 }
 
+/// extracts the text of a page of a file for the preview
+type PreviewWorker = Worker<(PathBuf, u32), (PathBuf, u32, Result<String>)>;
+
+/// renders the first page of a file for the detail pane
+type ThumbnailWorker = Worker<PdfFile, (PathBuf, Result<Pixmap>)>;
+
+/// resolves the lookup of a managed file into its fetched citation
+type MetadataWorker = Worker<(PathBuf, Lookup), (PathBuf, Lookup, Result<Option<Citation>>)>;
+
+/// reads the page count and the other info of a file for the lists
+type InfoWorker = Worker<PdfFile, (PdfFile, PdfInfo)>;
+
+/// hashes managed files to find their records again once they are renamed
+type IdentityWorker = Worker<PathBuf, (PathBuf, Result<FileIdentity>)>;
+
+/// reads and parses a BibTeX file, into its entries and the errors of the skipped ones
+type BibWorker = Worker<PathBuf, (PathBuf, Result<(Vec<BibtexEntry>, Vec<String>)>)>;
+
+/// scans the unmanaged files to plan a batch import
+type PlanWorker = Worker<Vec<PathBuf>, Vec<(PathBuf, Option<Identifiers>)>>;
+
 /// if you want to need feature or screen, add it Focus and App
 pub struct App {
     /// Contextual actions
     actions: Actions,
     state: AppState,
     inbox: InboxComponent,
-    pdf_import_popup: PdfImportPopup,
+    /// the popups which ask for a request, see `popup_request`
+    popups: Popups,
+    importer: Importer,
+    library: LibraryStore,
+    /// scans the text of managed files for identifiers
+    identifier_worker: Worker<PathBuf, (PathBuf, Result<Identifiers>)>,
+    /// files submitted to `identifier_worker` whose results are pending
+    scanning: HashSet<PathBuf>,
+    info_worker: InfoWorker,
+    /// files submitted to `info_worker` whose results are pending
+    reading_info: HashSet<PathBuf>,
+    /// the info read before, saved once `reading_info` is done
+    info_cache: PdfInfoCache,
+    identity_worker: IdentityWorker,
+    /// files submitted to `identity_worker` whose results are pending
+    hashing: HashSet<PathBuf>,
+    /// the files could not all be listed, the records of the missing ones are kept as
+    /// they are
+    listing_failed: bool,
+    /// the records without a file which have been reported
+    orphans: HashSet<String>,
+    /// reloads the lists once files are changed outside pdfstore
+    watcher: DirWatcher,
+    /// scans the files of a batch import for the duplicates and the name templates
+    plan_worker: PlanWorker,
+    bib_worker: BibWorker,
+    /// looks up the metadata of managed files
+    metadata_worker: MetadataWorker,
+    preview_worker: PreviewWorker,
+    /// the page submitted to `preview_worker`, one at a time so that moving through
+    /// the list does not queue up every file passed by
+    extracting: Option<(PathBuf, u32)>,
+    thumbnail_worker: ThumbnailWorker,
+    /// the file submitted to `thumbnail_worker`, one at a time like `extracting`
+    rendering: Option<PathBuf>,
+    config: Config,
+    help: HelpComponent,
+    error_popup: ErrorPopup,
+    log: LogComponent,
+    status_bar: StatusBarComponent,
     focus: Focus,
     pub key_config: KeyConfig,
     do_quit: bool,
@@ -49,41 +163,226 @@ pub struct App {
 
 impl App {
     #[allow(clippy::new_without_default)]
-    pub fn new(key_config: KeyConfig) -> Self {
+    pub fn new(config: Config, key_config: KeyConfig) -> Self {
         let actions = vec![Action::Quit].into();
         let state = AppState::initialized();
+        let resolver = HttpResolver::new(&config.metadata);
+        let thumbnails = ThumbnailCache::new(&config.preview.thumbnail_dir);
+        let (library, library_error) = match LibraryStore::open(&config.library_file) {
+            Ok(library) => (library, None),
+            Err(err) => (LibraryStore::read_only(&config.library_file), Some(err)),
+        };
 
-        Self {
+        let mut app = Self {
             actions,
             state,
-            inbox: InboxComponent::new(key_config.clone()),
-            pdf_import_popup: PdfImportPopup::new(),
+            inbox: InboxComponent::new(key_config.clone(), &config),
+            popups: Popups::new(&key_config, config.managed_dir.clone()),
+            importer: Importer::new(&config.managed_dir),
+            library,
+            identifier_worker: Worker::spawn("identifier-scanner", scan_identifiers),
+            scanning: HashSet::new(),
+            info_worker: Worker::spawn("info-reader", |pdf_file: PdfFile| {
+                let info = pdf_info::read_or_default(&pdf_file.path);
+                (pdf_file, info)
+            }),
+            reading_info: HashSet::new(),
+            info_cache: PdfInfoCache::open(&config.preview.info_file),
+            identity_worker: Worker::spawn("file-hasher", |path: PathBuf| {
+                let result = FileIdentity::of(&path);
+                (path, result)
+            }),
+            hashing: HashSet::new(),
+            listing_failed: false,
+            orphans: HashSet::new(),
+            watcher: DirWatcher::new(&config.managed_dir, &config.unmanaged_dir),
+            plan_worker: Worker::spawn("import-planner", |paths: Vec<PathBuf>| {
+                paths
+                    .into_iter()
+                    .map(|path| match scan_identifiers(path) {
+                        (path, Ok(identifiers)) => (path, Some(identifiers)),
+                        (path, Err(err)) => {
+                            log::warn!("{}", err);
+                            (path, None)
+                        }
+                    })
+                    .collect()
+            }),
+            bib_worker: Worker::spawn("bib-parser", |path: PathBuf| {
+                let result = fs::read_to_string(&path)
+                    .map(|content| bibtex::parse(&content))
+                    .map_err(|err| Error::reading_file(&path, err));
+                (path, result)
+            }),
+            metadata_worker: Worker::spawn("metadata-resolver", move |(path, lookup)| {
+                let result = resolver.resolve(&lookup);
+                (path, lookup, result)
+            }),
+            preview_worker: Worker::spawn("preview-extractor", |(path, page): (PathBuf, u32)| {
+                let result = pdf_text::page(&path, page);
+                (path, page, result)
+            }),
+            extracting: None,
+            thumbnail_worker: Worker::spawn("thumbnail-renderer", move |pdf_file: PdfFile| {
+                let result = thumbnails.load_or_render(&pdf_file);
+                (pdf_file.path, result)
+            }),
+            rendering: None,
+            help: HelpComponent::new(key_config.clone()),
+            error_popup: ErrorPopup::new(key_config.clone()),
+            log: LogComponent::new(key_config.clone(), config.log.level),
+            status_bar: StatusBarComponent::new(),
             focus: Focus::Inbox,
+            config,
             key_config,
             do_quit: false,
+        };
+        if let Some(err) = library_error {
+            app.report_errors(vec![err]);
         }
+        app
     }
 
     pub fn draw<B: Backend>(&mut self, f: &mut Frame<B>) -> anyhow::Result<()> {
         let size = f.size();
 
         let chunks_main = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([Constraint::Percentage(100)].as_ref())
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Length(2)].as_ref())
             .split(size);
 
+        let chunks_body = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(if self.log.is_visible() {
+                [Constraint::Percentage(70), Constraint::Percentage(30)]
+            } else {
+                [Constraint::Percentage(100), Constraint::Percentage(0)]
+            })
+            .split(chunks_main[0]);
+
+        self.update_detail();
         self.inbox
-            .draw(f, chunks_main[0], matches!(self.focus, Focus::Inbox))?;
+            .draw(f, chunks_body[0], matches!(self.focus, Focus::Inbox))?;
+        self.log.draw(f, chunks_body[1], false)?;
+
+        self.status_bar.set_cmds(self.quick_commands());
+        self.status_bar.set_list_status(self.inbox.list_status());
+        self.status_bar.draw(f, chunks_main[1], false)?;
+
+        // popups are drawn last so that they are rendered on top of the inbox
+        self.popups.draw(f)?;
+        self.help.draw(f, size, false)?;
+        self.error_popup.draw(f, size, false)?;
         Ok(())
     }
 
-    // fn commands(&self) -> Vec<CommandInfo> {
-    //     let mut res = vec![
-    //         CommandInfo::new(command::scroll(&self.key_config)),
-    //         CommandInfo::new(command::move_focus(&self.key_config)),
-    //     ];
-    //     res
-    // }
+    /// shows the selected file of the last focused list and extracts the previewed page
+    fn update_detail(&mut self) {
+        let (pdf_file, record) = if self.inbox.list_focus == InboxFocus::UnmanagedPdfList {
+            (self.inbox.unmanaged_pdf_list.selected_file().cloned(), None)
+        } else {
+            let pdf_file = self.inbox.managed_pdf_list.selected_file().cloned();
+            let record = pdf_file.as_ref().map(|pdf_file| {
+                LibraryStore::key_for(&self.config.managed_dir, &pdf_file.path)
+                    .and_then(|key| self.library.record(&key).cloned())
+                    .unwrap_or_default()
+            });
+            (pdf_file, record)
+        };
+        self.inbox.pdf_detail.set_file(pdf_file, record);
+
+        if self.extracting.is_none() {
+            // the triage covers the detail pane
+            let missing = match self.popups.triage.is_visible() {
+                true => self.popups.triage.missing_preview(),
+                false => self.inbox.pdf_detail.missing_page(),
+            };
+            if let Some(page) = missing {
+                self.preview_worker.submit(page.clone());
+                self.extracting = Some(page);
+            }
+        }
+        if self.rendering.is_none() {
+            if let Some(pdf_file) = self.inbox.pdf_detail.missing_thumbnail() {
+                self.rendering = Some(pdf_file.path.clone());
+                self.thumbnail_worker.submit(pdf_file.clone());
+            }
+        }
+    }
+
+    fn collect_previews(&mut self) {
+        for (path, page, result) in self.preview_worker.results() {
+            if let Err(err) = &result {
+                log::warn!("{}", err);
+            }
+            self.extracting = None;
+            if !self.popups.triage.set_preview(&path, page, &result) {
+                self.inbox.pdf_detail.set_page_text(path, page, result);
+            }
+        }
+    }
+
+    fn collect_thumbnails(&mut self) {
+        for (path, result) in self.thumbnail_worker.results() {
+            if let Err(err) = &result {
+                log::warn!("{}", err);
+            }
+            self.rendering = None;
+            self.inbox.pdf_detail.set_thumbnail(path, result);
+        }
+    }
+
+    /// the image the terminal draws over the detail pane, none while a popup may cover it
+    pub fn placed_image(&self) -> Option<(Placement, &Pixmap)> {
+        if self.is_popup_visible() {
+            return None;
+        }
+        self.inbox.pdf_detail.placed_image()
+    }
+
+    fn is_popup_visible(&self) -> bool {
+        self.popups.is_visible() || self.help.is_visible() || self.error_popup.is_visible()
+    }
+
+    /// commands available in the current context, used to build the help popup
+    fn commands(&self) -> Vec<CommandInfo> {
+        let mut res = vec![
+            CommandInfo::new(command::help(&self.key_config)),
+            CommandInfo::new(command::quit(&self.key_config)),
+        ];
+        match self.focus {
+            Focus::Inbox => {
+                self.inbox.commands(&mut res, false);
+            }
+        }
+        self.log.commands(&mut res, false);
+        res
+    }
+
+    /// commands of the focused pane, shown in the status bar
+    fn quick_commands(&self) -> Vec<CommandInfo> {
+        let mut res = Vec::new();
+        if self.error_popup.commands(&mut res, false) == CommandBlocking::Blocking {
+            return res;
+        }
+        if self.popups.commands(&mut res) == CommandBlocking::Blocking {
+            return res;
+        }
+        if self.help.commands(&mut res, false) == CommandBlocking::Blocking {
+            return res;
+        }
+        match self.focus {
+            Focus::Inbox => {
+                self.inbox.commands(&mut res, false);
+            }
+        }
+        res
+    }
+
+    fn open_help(&mut self) {
+        self.help.set_cmds(self.commands());
+        self.help.show();
+    }
 
     fn check_quit(&mut self, key: Key) -> bool {
         if key == self.key_config.quit || key == self.key_config.exit {
@@ -97,8 +396,138 @@ impl App {
         self.do_quit
     }
 
+    /// called on every tick event
+    pub async fn on_tick(&mut self) -> anyhow::Result<()> {
+        self.state.incr_tick();
+        self.status_bar.tick();
+        // not under a popup, which may show the files being changed
+        if !self.is_popup_visible() && self.watcher.changed() {
+            log::debug!("reloading the lists changed on disk");
+            self.update_inbox_list().await?;
+        }
+        self.collect_info();
+        self.collect_identifiers();
+        self.collect_identities();
+        self.collect_plan();
+        self.collect_bib();
+        self.collect_metadata();
+        self.collect_previews();
+        self.collect_thumbnails();
+        logging::flush();
+        Ok(())
+    }
+
+    /// called when the terminal has been resized, layouts are recomputed on the next draw
+    pub fn resize(&mut self, width: u16, height: u16) {
+        log::trace!("resize: {}x{}", width, height);
+    }
+
     pub async fn event(&mut self, key: Key) -> anyhow::Result<EventState> {
         log::trace!("event: {:?}", key.clone());
+        // popups take precedence over everything else, including quitting
+        if self.error_popup.event(key)?.is_consumed() {
+            return Ok(EventState::Consumed);
+        }
+        if self.help.event(key)?.is_consumed() {
+            return Ok(EventState::Consumed);
+        }
+        if self.popups.event(key)?.is_consumed() {
+            if let Some(request) = self.popups.take_request() {
+                self.popup_request(request).await?;
+            }
+            return Ok(EventState::Consumed);
+        }
+
+        // while typing a filter, keys must not trigger any shortcut
+        if self.inbox.is_searching() && self.inbox.event(key)?.is_consumed() {
+            return Ok(EventState::Consumed);
+        }
+
+        if key == self.key_config.open_help {
+            self.open_help();
+            return Ok(EventState::Consumed);
+        }
+
+        // before the bulk operations as moving a selected folder moves the folder
+        if self.open_folder_popup(key) {
+            return Ok(EventState::Consumed);
+        }
+
+        if self.open_bulk_popup(key) {
+            return Ok(EventState::Consumed);
+        }
+
+        if key == self.key_config.import_all && self.plan_batch_import() {
+            return Ok(EventState::Consumed);
+        }
+
+        if key == self.key_config.organize && self.plan_organize() {
+            return Ok(EventState::Consumed);
+        }
+
+        if key == self.key_config.triage {
+            self.open_triage();
+            return Ok(EventState::Consumed);
+        }
+
+        if key == self.key_config.open_pdf && self.open_selected() {
+            return Ok(EventState::Consumed);
+        }
+
+        if key == self.key_config.import && self.open_import_popup()? {
+            return Ok(EventState::Consumed);
+        }
+
+        if key == self.key_config.export {
+            self.export();
+            return Ok(EventState::Consumed);
+        }
+
+        if key == self.key_config.export_bibliography {
+            let count = self.exported_files().len();
+            self.popups.bibliography.open(count);
+            return Ok(EventState::Consumed);
+        }
+
+        if key == self.key_config.import_bib {
+            self.popups.bib_import.open();
+            return Ok(EventState::Consumed);
+        }
+
+        if key == self.key_config.edit_citation && self.open_citation_popup()? {
+            return Ok(EventState::Consumed);
+        }
+
+        if key == self.key_config.fetch_metadata && self.fetch_metadata() {
+            return Ok(EventState::Consumed);
+        }
+
+        if self.edit_reading(key) {
+            return Ok(EventState::Consumed);
+        }
+
+        if self.open_collection_popup(key) {
+            return Ok(EventState::Consumed);
+        }
+
+        if self.edit_saved_searches(key) {
+            return Ok(EventState::Consumed);
+        }
+
+        // the preview can be toggled and paged through from the lists too
+        let preview_keys = [
+            self.key_config.toggle_preview,
+            self.key_config.next_page,
+            self.key_config.previous_page,
+        ];
+        if preview_keys.contains(&key) && self.inbox.pdf_detail.event(key)?.is_consumed() {
+            return Ok(EventState::Consumed);
+        }
+
+        if self.log.event(key)?.is_consumed() {
+            return Ok(EventState::Consumed);
+        }
+
         if self.check_quit(key) {
             return Ok(EventState::NotConsumed);
         }
@@ -117,8 +546,27 @@ impl App {
         Ok(EventState::NotConsumed)
     }
 
+    /// carries out what the user confirmed in a popup
+    async fn popup_request(&mut self, request: PopupRequest) -> anyhow::Result<()> {
+        match request {
+            PopupRequest::Import(request) => self.import(request).await?,
+            PopupRequest::Citation(request) => self.save_citation(request),
+            PopupRequest::BibImport(request) => self.import_bib(request),
+            PopupRequest::Bibliography(request) => self.export_bibliography(request.format),
+            PopupRequest::Collection(request) => self.edit_collections(request),
+            PopupRequest::SaveSearch(request) => self.save_search(request),
+            PopupRequest::Bulk(request) => self.run_bulk(request).await?,
+            PopupRequest::BatchImport(request) => self.batch_import(request).await?,
+            PopupRequest::Folder(request) => self.edit_folders(request).await?,
+            PopupRequest::Organize(request) => self.organize(request).await?,
+            PopupRequest::Triage(request) => self.triage(request).await?,
+            PopupRequest::Confirm(request) => self.confirmed(request),
+        }
+        Ok(())
+    }
+
     /// handling focus to each component
-    pub fn focus_inbox(&mut self, key: Key) -> anyhow::Result<EventState> {
+    pub fn focus_inbox(&mut self, _key: Key) -> anyhow::Result<EventState> {
         self.focus = Focus::Inbox;
         Ok(EventState::Consumed)
     }
@@ -134,7 +582,23 @@ impl App {
                 let state = self.inbox.unmanaged_pdf_list.event(key)?;
                 return Ok(state);
             }
-            InboxFocus::PdfDetail => {}
+            InboxFocus::CollectionTree => {
+                if self.inbox.collection_tree.event(key)?.is_consumed() {
+                    self.update_collection_filter();
+                    return Ok(EventState::Consumed);
+                }
+            }
+            InboxFocus::SavedSearches => {
+                if self.inbox.saved_searches.event(key)?.is_consumed() {
+                    self.update_saved_search_filter();
+                    return Ok(EventState::Consumed);
+                }
+            }
+            InboxFocus::PdfDetail => {
+                if self.inbox.pdf_detail.event(key)?.is_consumed() {
+                    return Ok(EventState::Consumed);
+                }
+            }
             InboxFocus::Searchbar => {}
         }
         if self.inbox.event(key)?.is_consumed() {
@@ -144,19 +608,1302 @@ impl App {
     }
 
     pub async fn update_inbox_list(&mut self) -> anyhow::Result<()> {
-        self.inbox.update().await?;
+        let errors = self.inbox.update().await;
+        self.listing_failed = !errors.is_empty();
+        self.report_errors(errors);
+        self.read_info();
+        self.hash_files();
+        self.reconcile();
+        self.library_changed();
+        self.scan_identifiers();
+        Ok(())
+    }
+
+    /// passes the collections of the library with their listed documents to the tree,
+    /// and filters the managed list by the selected one
+    fn update_collections(&mut self) {
+        let listed = self.listed_keys();
+        let entries = self
+            .library
+            .collections()
+            .map(|collection| CollectionEntry {
+                collection: collection.clone(),
+                count: self
+                    .library
+                    .keys_in_collection(collection)
+                    .filter(|key| listed.contains(*key))
+                    .count(),
+            })
+            .collect();
+        self.inbox
+            .collection_tree
+            .set_collections(entries, listed.len());
+        self.update_collection_filter();
+    }
+
+    /// the library keys of the files in the managed list
+    fn listed_keys(&self) -> HashSet<String> {
+        self.inbox
+            .managed_pdf_list
+            .pdf_files
+            .iter()
+            .filter_map(|pdf_file| LibraryStore::key_for(&self.config.managed_dir, &pdf_file.path))
+            .collect()
+    }
+
+    fn update_collection_filter(&mut self) {
+        let filter = self.inbox.collection_tree.selected().map(|collection| {
+            let paths = self
+                .library
+                .keys_in_collection(collection)
+                .map(|key| self.config.managed_dir.join(key))
+                .collect();
+            (collection.clone(), paths)
+        });
+        self.inbox.managed_pdf_list.set_collection_filter(filter);
+    }
+
+    /// opens the collection picker for the selected managed file, or creates or deletes
+    /// the selected collection of the tree, returns false for other keys
+    fn open_collection_popup(&mut self, key: Key) -> bool {
+        match self.inbox.focus {
+            InboxFocus::ManagedPdfList if key == self.key_config.add_to_collection => {
+                let pdf_file = match self.inbox.managed_pdf_list.selected_file() {
+                    Some(pdf_file) => pdf_file,
+                    None => return false,
+                };
+                let record_key =
+                    match LibraryStore::key_for(&self.config.managed_dir, &pdf_file.path) {
+                        Some(record_key) => record_key,
+                        None => return false,
+                    };
+                let current = self
+                    .library
+                    .record(&record_key)
+                    .map(|record| record.collections.clone())
+                    .unwrap_or_default();
+                let collections = self
+                    .library
+                    .collections()
+                    .map(|collection| (collection.clone(), current.contains(collection)))
+                    .collect();
+                self.popups.collection.pick(
+                    pdf_file.path.clone(),
+                    pdf_file.file_name.clone(),
+                    collections,
+                );
+                true
+            }
+            InboxFocus::CollectionTree if key == self.key_config.new_collection => {
+                let parent = self.inbox.collection_tree.selected().cloned();
+                self.popups.collection.create(parent);
+                true
+            }
+            InboxFocus::CollectionTree if key == self.key_config.delete_collection => {
+                let collection = match self.inbox.collection_tree.selected() {
+                    Some(collection) => collection.clone(),
+                    None => return false,
+                };
+                self.popups
+                    .confirm
+                    .open(ConfirmRequest::DeleteCollection(collection));
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn edit_collections(&mut self, request: CollectionRequest) {
+        let managed_dir = &self.config.managed_dir;
+        match request {
+            CollectionRequest::Toggle { path, collection } => {
+                if let Some(key) = LibraryStore::key_for(managed_dir, &path) {
+                    self.library.toggle_collection(&key, &collection);
+                }
+            }
+            CollectionRequest::Create { collection, path } => {
+                match path.and_then(|path| LibraryStore::key_for(managed_dir, &path)) {
+                    Some(key) => self.library.add_to_collection(&key, &collection),
+                    None => self.library.add_collection(&collection),
+                }
+            }
+        }
+        self.save_library();
+    }
+
+    /// updates the panes showing the library after it changed, the saved searches and
+    /// filters are evaluated again
+    fn library_changed(&mut self) {
+        let records = self.listed_records();
+        self.update_searches(&records);
+        self.inbox.managed_pdf_list.set_records(records);
+        self.update_collections();
+    }
+
+    /// the records of the files in the managed list, by path
+    fn listed_records(&self) -> HashMap<PathBuf, DocumentRecord> {
+        self.inbox
+            .managed_pdf_list
+            .pdf_files
+            .iter()
+            .filter_map(|pdf_file| {
+                let key = LibraryStore::key_for(&self.config.managed_dir, &pdf_file.path)?;
+                let record = self.library.record(&key)?.clone();
+                Some((pdf_file.path.clone(), record))
+            })
+            .collect()
+    }
+
+    /// passes the saved searches with the number of managed files matching them to the
+    /// pane, and filters the managed list by the selected one
+    fn update_searches(&mut self, records: &HashMap<PathBuf, DocumentRecord>) {
+        let pdf_files = &self.inbox.managed_pdf_list.pdf_files;
+        let entries = self
+            .library
+            .searches()
+            .map(|(name, query)| {
+                let parsed = saved_query(query);
+                SavedSearchEntry {
+                    name: name.to_string(),
+                    query: query.to_string(),
+                    count: pdf_files
+                        .iter()
+                        .filter(|pdf_file| parsed.matches(pdf_file, records.get(&pdf_file.path)))
+                        .count(),
+                }
+            })
+            .collect();
+        self.inbox.saved_searches.set_searches(entries);
+        self.update_saved_search_filter();
+    }
+
+    fn update_saved_search_filter(&mut self) {
+        let saved_search = self
+            .inbox
+            .saved_searches
+            .selected()
+            .map(|entry| (entry.name.clone(), saved_query(&entry.query)));
+        self.inbox.managed_pdf_list.set_saved_search(saved_search);
+    }
+
+    /// saves the searchbar query or deletes the selected saved search with the keys of
+    /// the saved searches pane, returns false for other keys
+    fn edit_saved_searches(&mut self, key: Key) -> bool {
+        if !matches!(self.inbox.focus, InboxFocus::SavedSearches) {
+            return false;
+        }
+        if key == self.key_config.save_search {
+            let query = self.inbox.searchbar.query().trim();
+            if query.is_empty() {
+                self.status_bar
+                    .show_error("Type a query in the searchbar to save it".to_string());
+            } else if let Err(err) = self.inbox.searchbar.parsed() {
+                self.status_bar.show_error(err.to_string());
+            } else {
+                self.popups.save_search.open(query.to_string());
+            }
+            return true;
+        }
+        if key == self.key_config.delete_search {
+            let name = match self.inbox.saved_searches.selected() {
+                Some(entry) => entry.name.clone(),
+                None => return false,
+            };
+            self.popups.confirm.open(ConfirmRequest::DeleteSearch(name));
+            return true;
+        }
+        false
+    }
+
+    /// makes the change confirmed by the user
+    fn confirmed(&mut self, request: ConfirmRequest) {
+        match request {
+            ConfirmRequest::DeleteCollection(collection) => {
+                self.library.remove_collection(&collection);
+                self.save_library();
+                self.status_bar.show_info(format!(
+                    "Deleted the collection {}, its documents are kept",
+                    collection
+                ));
+            }
+            ConfirmRequest::DeleteSearch(name) => {
+                self.library.remove_search(&name);
+                self.save_library();
+                self.status_bar
+                    .show_info(format!("Deleted the saved search {}", name));
+            }
+        }
+    }
+
+    fn save_search(&mut self, request: SaveSearchRequest) {
+        self.library.save_search(&request.name, &request.query);
+        self.save_library();
+        self.status_bar
+            .show_info(format!("Saved the search {}", request.name));
+    }
+
+    /// changes the reading of the selected managed file with the status, page and
+    /// rating keys, returns false for other keys or if there is no such file
+    fn edit_reading(&mut self, key: Key) -> bool {
+        let key_config = &self.key_config;
+        let rating = key_config
+            .rate
+            .iter()
+            .position(|rate| *rate == key)
+            .and_then(|stars| u8::try_from(stars).ok());
+        let page_keys = [key_config.next_read_page, key_config.previous_read_page];
+        if key != key_config.cycle_status && !page_keys.contains(&key) && rating.is_none() {
+            return false;
+        }
+        if !matches!(self.inbox.focus, InboxFocus::ManagedPdfList) {
+            return false;
+        }
+        let pdf_file = match self.inbox.managed_pdf_list.selected_file() {
+            Some(pdf_file) => pdf_file,
+            None => return false,
+        };
+        let record_key = match LibraryStore::key_for(&self.config.managed_dir, &pdf_file.path) {
+            Some(record_key) => record_key,
+            None => return false,
+        };
+        let pages = pdf_file.info.pages;
+        let reading = &mut self.library.record_mut(&record_key).reading;
+        if key == key_config.cycle_status {
+            reading.status = reading.status.next();
+        } else if let Some(rating) = rating {
+            reading.rate(rating);
+        } else {
+            reading.turn_page(key == key_config.next_read_page, pages);
+        }
+        self.save_library();
+        true
+    }
+
+    /// opens the import popup for the selected unmanaged file and scans it in the
+    /// background, returns false if there is none
+    fn open_import_popup(&mut self) -> anyhow::Result<bool> {
+        if !matches!(self.inbox.focus, InboxFocus::UnmanagedPdfList) {
+            return Ok(false);
+        }
+        let pdf_file = match self.inbox.unmanaged_pdf_list.selected_file() {
+            Some(pdf_file) => pdf_file,
+            None => return Ok(false),
+        };
+        let managed_pdf_list = &self.inbox.managed_pdf_list;
+        self.popups.pdf_import.open(
+            pdf_file,
+            managed_pdf_list.folders().cloned().collect(),
+            managed_pdf_list.current_folder(),
+        )?;
+        if self.scanning.insert(pdf_file.path.clone()) {
+            self.identifier_worker.submit(pdf_file.path.clone());
+        }
+        Ok(true)
+    }
+
+    /// what the scan of an unmanaged file tells before it is imported
+    fn import_hints(&self, result: Result<Identifiers>) -> ImportHints {
+        let identifiers = match result {
+            Ok(identifiers) => Some(identifiers),
+            Err(err) => {
+                log::warn!("{}", err);
+                None
+            }
+        };
+        let duplicate_of = identifiers.as_ref().and_then(|identifiers| {
+            let (key, shared) = self.library.find_duplicate(identifiers, None)?;
+            Some(format!("{} (same {})", key, shared))
+        });
+        ImportHints {
+            identifiers,
+            duplicate_of,
+        }
+    }
+
+    /// opens the selected file of the last focused list in the viewer of the system, a
+    /// managed file is recorded as opened. returns false if no file is selected
+    fn open_selected(&mut self) -> bool {
+        let path = match self.inbox.list_focus {
+            InboxFocus::UnmanagedPdfList => self.inbox.unmanaged_pdf_list.selected_file(),
+            _ => self.inbox.managed_pdf_list.selected_file(),
+        };
+        let path = match path {
+            Some(pdf_file) => pdf_file.path.clone(),
+            None => return false,
+        };
+        if let Err(err) = opener::open(&path) {
+            self.report_errors(vec![err]);
+            return true;
+        }
+        if let Some(key) = LibraryStore::key_for(&self.config.managed_dir, &path) {
+            self.library.record_mut(&key).opened = Some(Utc::now());
+            self.save_library();
+        }
+        self.status_bar
+            .show_info(format!("Opened {}", tilde_path(&path)));
+        true
+    }
+
+    async fn import(&mut self, request: ImportRequest) -> anyhow::Result<()> {
+        let result = self
+            .importer
+            .import_into(&request.src, &request.folder, Some(&request.name));
+        match result {
+            Ok(dest) => {
+                let key = LibraryStore::key_for(&self.config.managed_dir, &dest);
+                if let (Some(key), Some(identifiers)) = (key, request.identifiers) {
+                    self.library.record_mut(&key).identifiers = Some(identifiers);
+                }
+                let filed = self.record_imported(vec![dest]);
+                self.save_library();
+                self.update_inbox_list().await?;
+                self.status_bar.show_info(format!(
+                    "Imported {}{}",
+                    tilde_path(&filed.paths[0]),
+                    left_in_place(&filed.left)
+                ));
+            }
+            Err(err) => self.report_errors(vec![err]),
+        }
+        Ok(())
+    }
+
+    /// records when the documents just imported were, for the recently added list, and
+    /// files them by the organize policy of the config if it applies to imports, returns
+    /// the path of each document afterwards and the ones left in place.
+    ///
+    /// the records are not saved
+    fn record_imported(&mut self, imported: Vec<PathBuf>) -> Filed {
+        self.library
+            .record_imports(&self.config.managed_dir, &imported);
+        let organize = &self.config.organize;
+        match organize.policy().filter(|_| organize.on_import) {
+            Some(Ok(policy)) => organize::file_imported(
+                &policy,
+                &imported,
+                &self.config.managed_dir,
+                &mut self.library,
+            ),
+            Some(Err(err)) => {
+                self.report_errors(vec![err]);
+                Filed {
+                    paths: imported,
+                    left: Vec::new(),
+                }
+            }
+            None => Filed {
+                paths: imported,
+                left: Vec::new(),
+            },
+        }
+    }
+
+    /// plans filing every managed document by the organize policy of the config for review,
+    /// returns false if the managed list is not focused
+    fn plan_organize(&mut self) -> bool {
+        if !matches!(self.inbox.focus, InboxFocus::ManagedPdfList) {
+            return false;
+        }
+        let policy = match self.config.organize.policy() {
+            Some(Ok(policy)) => policy,
+            Some(Err(err)) => {
+                self.report_errors(vec![err]);
+                return true;
+            }
+            None => {
+                self.status_bar
+                    .show_error("No folder policy, set organize.folder in the config");
+                return true;
+            }
+        };
+        let paths = self
+            .inbox
+            .managed_pdf_list
+            .pdf_files
+            .iter()
+            .map(|pdf_file| pdf_file.path.clone());
+        let plan = OrganizePlan::new(&policy, paths, &self.config.managed_dir, &self.library);
+        self.popups.organize.review(plan);
+        true
+    }
+
+    async fn organize(&mut self, request: OrganizeRequest) -> anyhow::Result<()> {
+        let OrganizeRequest::Execute(plan) = request;
+        let results = plan.execute(&self.config.managed_dir, &mut self.library);
+        for (src, result) in &results {
+            if let Err(err) = result {
+                log::warn!("not organized {}: {}", src.display(), err);
+            }
+        }
+        let moved = results.iter().filter(|(_, result)| result.is_ok()).count();
+        let total = results.len();
+        self.popups.organize.show_results(results);
+        self.save_library();
+        self.update_inbox_list().await?;
+        self.status_bar
+            .show_info(format!("Moved {} of {} documents", moved, total));
+        Ok(())
+    }
+
+    /// goes through the unmanaged files one at a time, except the ones skipped before
+    fn open_triage(&mut self) {
+        let pdf_files = self.inbox.unmanaged_pdf_list.pdf_files.clone();
+        let paths = pdf_files
+            .iter()
+            .map(|pdf_file| pdf_file.path.clone())
+            .collect::<Vec<_>>();
+        if self.library.retain_triage_skipped(&paths) {
+            self.save_library();
+        }
+        let queue = TriageQueue::new(paths, self.library.triage_skipped());
+        if queue.is_empty() {
+            self.status_bar
+                .show_info("No unmanaged files to triage".to_string());
+            return;
+        }
+        self.popups.triage.open(queue, pdf_files);
+        self.scan_triaged();
+    }
+
+    /// scans the current file of the triage for its identifiers in the background, like
+    /// the import popup
+    fn scan_triaged(&mut self) {
+        let path = match self.popups.triage.missing_hints() {
+            Some(path) => path,
+            None => return,
+        };
+        if self.scanning.insert(path.clone()) {
+            self.identifier_worker.submit(path);
+        }
+    }
+
+    /// sets the hints of the current file of the triage once it is scanned
+    fn set_triage_hints(&mut self, path: PathBuf, result: Result<Identifiers>) {
+        let hints = self.import_hints(result);
+        // the suggested name unless a file of the library has it already
+        let name = hints
+            .identifiers
+            .as_ref()
+            .and_then(Identifiers::suggested_file_name)
+            .filter(|name| {
+                self.importer
+                    .destination(&path, Path::new(""), Some(name))
+                    .is_ok_and(|dest| !dest.exists())
+            })
+            .unwrap_or_else(|| {
+                path.file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into()
+            });
+        self.popups.triage.set_hints(path, hints, name);
+    }
+
+    /// performs the step chosen for the current file of the triage, which stays current
+    /// if it fails
+    async fn triage(&mut self, request: TriageRequest) -> anyhow::Result<()> {
+        let outcome = match request {
+            TriageRequest::Import {
+                src,
+                name,
+                identifiers,
+                tags,
+            } => {
+                let dest = match self.importer.import(&src, Some(&name)) {
+                    Ok(dest) => dest,
+                    Err(err) => {
+                        self.report_errors(vec![err]);
+                        return Ok(());
+                    }
+                };
+                if let Some(key) = LibraryStore::key_for(&self.config.managed_dir, &dest) {
+                    let record = self.library.record_mut(&key);
+                    if identifiers.is_some() {
+                        record.identifiers = identifiers;
+                    }
+                    for tag in tags {
+                        if !record.tags.contains(&tag) {
+                            record.tags.push(tag);
+                        }
+                    }
+                }
+                let filed = self.record_imported(vec![dest]);
+                self.library.set_triage_skipped(&src, false);
+                self.status_bar.show_info(format!(
+                    "Imported {}{}",
+                    tilde_path(&filed.paths[0]),
+                    left_in_place(&filed.left)
+                ));
+                Outcome::Imported
+            }
+            TriageRequest::Skip(path) => {
+                self.library.set_triage_skipped(&path, true);
+                Outcome::Skipped
+            }
+            TriageRequest::Trash(path) => {
+                if let Err(err) = importer::trash(&path) {
+                    self.report_errors(vec![err]);
+                    return Ok(());
+                }
+                self.library.set_triage_skipped(&path, false);
+                self.status_bar
+                    .show_info(format!("Moved {} to the trash", tilde_path(&path)));
+                Outcome::Trashed
+            }
+            TriageRequest::Open(path) => {
+                if let Err(err) = opener::open(&path) {
+                    self.report_errors(vec![err]);
+                }
+                return Ok(());
+            }
+        };
+        self.popups.triage.advance(outcome);
+        self.save_library();
+        if outcome != Outcome::Skipped {
+            self.update_inbox_list().await?;
+        }
+        self.scan_triaged();
+        Ok(())
+    }
+
+    /// asks for a new folder in the folder of the selection of the managed list, or for the
+    /// new name or place of the selected folder, returns false for other keys.
+    ///
+    /// moving the marked files goes through the bulk popup instead
+    fn open_folder_popup(&mut self, key: Key) -> bool {
+        if !matches!(self.inbox.focus, InboxFocus::ManagedPdfList) {
+            return false;
+        }
+        let list = &self.inbox.managed_pdf_list;
+        let key_config = &self.key_config;
+        if key == key_config.new_folder {
+            self.popups.folder.create(list.current_folder());
+            return true;
+        }
+        let folder = match list.selected_folder() {
+            Some(folder) => folder,
+            None => return false,
+        };
+        if key == key_config.rename_folder {
+            self.popups.folder.rename(folder);
+        } else if key == key_config.move_files && !list.has_marks() {
+            self.popups.folder.move_folder(folder);
+        } else {
+            return false;
+        }
+        true
+    }
+
+    async fn edit_folders(&mut self, request: FolderRequest) -> anyhow::Result<()> {
+        let managed_dir = &self.config.managed_dir;
+        let (folder, info) = match request {
+            FolderRequest::Create(folder) => match folder::create(managed_dir, &folder) {
+                Ok(_) => {
+                    let info = format!("Created the folder {}", folder.display());
+                    (folder, info)
+                }
+                Err(err) => {
+                    self.report_errors(vec![err]);
+                    return Ok(());
+                }
+            },
+            FolderRequest::Move { from, to } if from == to => return Ok(()),
+            FolderRequest::Move { from, to } => {
+                match folder::move_folder(managed_dir, &from, &to, &mut self.library) {
+                    Ok(_) => {
+                        let info = format!("Moved {} to {}", from.display(), to.display());
+                        (to, info)
+                    }
+                    Err(err) => {
+                        self.report_errors(vec![err]);
+                        return Ok(());
+                    }
+                }
+            }
+        };
+        if let Err(err) = self.library.save() {
+            self.report_errors(vec![err]);
+        }
+        self.update_inbox_list().await?;
+        self.inbox.managed_pdf_list.select_folder(&folder);
+        self.status_bar.show_info(info);
+        Ok(())
+    }
+
+    /// asks to confirm an operation on the marked files of the focused list, or on its
+    /// selected file, returns false for other keys or if there is no such file.
+    ///
+    /// importing a single file goes through the import popup instead
+    fn open_bulk_popup(&mut self, key: Key) -> bool {
+        let key_config = &self.key_config;
+        let (action, paths) = match self.inbox.focus {
+            InboxFocus::ManagedPdfList => {
+                let action = if key == key_config.delete_files {
+                    BulkAction::Delete
+                } else if key == key_config.move_files {
+                    BulkAction::Move
+                } else if key == key_config.tag_files {
+                    BulkAction::Tag
+                } else {
+                    return false;
+                };
+                (action, self.inbox.managed_pdf_list.marked_or_selected())
+            }
+            InboxFocus::UnmanagedPdfList => {
+                let list = &self.inbox.unmanaged_pdf_list;
+                let action = if key == key_config.delete_files {
+                    BulkAction::Delete
+                } else if key == key_config.move_files {
+                    BulkAction::Move
+                } else if key == key_config.import && list.has_marks() {
+                    BulkAction::Import
+                } else {
+                    return false;
+                };
+                (action, list.marked_or_selected())
+            }
+            _ => return false,
+        };
+        if paths.is_empty() {
+            return false;
+        }
+        self.popups.bulk.open(action, paths);
+        true
+    }
+
+    /// runs a confirmed operation on several files, the marks of the list are cleared
+    async fn run_bulk(&mut self, request: BulkRequest) -> anyhow::Result<()> {
+        let managed_dir = &self.config.managed_dir;
+        let paths = &request.paths;
+        let unmanaged = self.inbox.list_focus == InboxFocus::UnmanagedPdfList;
+        let mut left = Vec::new();
+        let (verb, report) = match request.action {
+            BulkAction::Import => {
+                let mut report = bulk::import(paths, &self.importer);
+                let filed = self.record_imported(report.imported);
+                report.imported = filed.paths;
+                left = filed.left;
+                ("Imported", report)
+            }
+            BulkAction::Delete => (
+                "Trashed",
+                bulk::delete(paths, managed_dir, &mut self.library),
+            ),
+            BulkAction::Tag => {
+                let verb = match request.input.starts_with('-') {
+                    true => "Untagged",
+                    false => "Tagged",
+                };
+                let report = bulk::tag(paths, &request.input, managed_dir, &mut self.library);
+                (verb, report)
+            }
+            BulkAction::Move => {
+                // the popup accepts only a directory inside the one of the list
+                let list_dir = match unmanaged {
+                    true => &self.config.unmanaged_dir,
+                    false => managed_dir,
+                };
+                let dir = list_dir.join(&request.input);
+                let report = bulk::move_to(paths, &dir, managed_dir, &mut self.library);
+                ("Moved", report)
+            }
+        };
+        if let Err(err) = self.library.save() {
+            self.report_errors(vec![err]);
+        }
+        match unmanaged {
+            true => self.inbox.unmanaged_pdf_list.clear_marks(),
+            false => self.inbox.managed_pdf_list.clear_marks(),
+        }
+        self.update_inbox_list().await?;
+
+        let summary = format!(
+            "{} {} of {} files{}",
+            verb,
+            report.done,
+            paths.len(),
+            left_in_place(&left)
+        );
+        log::info!("{}", summary);
+        match report.errors.as_slice() {
+            [] => self.status_bar.show_info(summary),
+            errors => {
+                for err in errors {
+                    log::warn!("{}", err);
+                }
+                self.status_bar.show_error(format!(
+                    "{}, {} failed: {}",
+                    summary,
+                    errors.len(),
+                    errors[0]
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// scans the unmanaged files in the background to plan their batch import, returns
+    /// false if the unmanaged list is not focused
+    fn plan_batch_import(&mut self) -> bool {
+        if !matches!(self.inbox.focus, InboxFocus::UnmanagedPdfList) {
+            return false;
+        }
+        let paths = self
+            .inbox
+            .unmanaged_pdf_list
+            .pdf_files
+            .iter()
+            .map(|pdf_file| pdf_file.path.clone())
+            .collect::<Vec<_>>();
+        if paths.is_empty() {
+            self.status_bar.show_info("No unmanaged files to import");
+            return true;
+        }
+        self.popups.batch_import.scanning(paths.len());
+        self.plan_worker.submit(paths);
+        true
+    }
+
+    /// shows the plan of the files scanned since the last tick, unless the popup was
+    /// closed meanwhile
+    fn collect_plan(&mut self) {
+        for scanned in self.plan_worker.results() {
+            if !self.popups.batch_import.is_scanning() {
+                continue;
+            }
+            let import = &self.config.import;
+            let plan = ImportPlan::new(
+                scanned,
+                &import.rules,
+                import.skip_duplicates,
+                &self.config.managed_dir,
+                &self.library,
+            );
+            self.popups.batch_import.review(plan);
+        }
+    }
+
+    async fn batch_import(&mut self, request: BatchImportRequest) -> anyhow::Result<()> {
+        let BatchImportRequest::Execute(plan) = request;
+        let mut results = plan.execute(&self.config.managed_dir, &mut self.library);
+        let imported = results
+            .iter()
+            .filter_map(|(_, result)| result.as_ref().ok().cloned())
+            .collect();
+        let filed = self.record_imported(imported);
+        let mut organized = filed.paths.into_iter();
+        for (_, result) in &mut results {
+            if let (Ok(dest), Some(organized)) = (result.as_mut(), organized.next()) {
+                *dest = organized;
+            }
+        }
+        for (src, result) in &results {
+            if let Err(err) = result {
+                log::warn!("not imported {}: {}", src.display(), err);
+            }
+        }
+        let imported = results.iter().filter(|(_, result)| result.is_ok()).count();
+        let total = results.len();
+        self.popups.batch_import.show_results(results);
+        if let Err(err) = self.library.save() {
+            self.report_errors(vec![err]);
+        }
+        self.update_inbox_list().await?;
+        self.status_bar.show_info(format!(
+            "Imported {} of {} files{}",
+            imported,
+            total,
+            left_in_place(&filed.left)
+        ));
+        Ok(())
+    }
+
+    /// sets the cached info of the listed files, and reads the info of the others in the
+    /// background
+    fn read_info(&mut self) {
+        let mut infos = HashMap::new();
+        for pdf_file in self.inbox.pdf_files() {
+            match self.info_cache.cached(pdf_file) {
+                Some(info) => {
+                    infos.insert(pdf_file.path.clone(), info);
+                }
+                None if self.reading_info.insert(pdf_file.path.clone()) => {
+                    self.info_worker.submit(pdf_file.clone());
+                }
+                None => {}
+            }
+        }
+        // the files which are gone are forgotten
+        let listed = self
+            .inbox
+            .pdf_files()
+            .map(|pdf_file| pdf_file.path.as_path())
+            .collect();
+        self.info_cache.retain(&listed);
+        self.inbox.set_info(&infos);
+        self.save_info_cache();
+    }
+
+    /// sets the info read since the last tick
+    fn collect_info(&mut self) {
+        let results = self.info_worker.results();
+        if results.is_empty() {
+            return;
+        }
+        let mut infos = HashMap::new();
+        for (pdf_file, info) in results {
+            self.reading_info.remove(&pdf_file.path);
+            self.info_cache.insert(&pdf_file, info.clone());
+            infos.insert(pdf_file.path, info);
+        }
+        self.inbox.set_info(&infos);
+        self.popups.triage.set_info(&infos);
+        self.save_info_cache();
+    }
+
+    /// saves the info cache once every file has been read, not after every batch
+    fn save_info_cache(&mut self) {
+        if !self.reading_info.is_empty() {
+            return;
+        }
+        if let Err(err) = self.info_cache.save() {
+            log::warn!("failed to save the info cache: {}", err);
+        }
+    }
+
+    /// scans the managed files which have not been scanned yet in the background
+    fn scan_identifiers(&mut self) {
+        for pdf_file in &self.inbox.managed_pdf_list.pdf_files {
+            let scanned = LibraryStore::key_for(&self.config.managed_dir, &pdf_file.path)
+                .and_then(|key| self.library.record(&key))
+                .is_some_and(|record| record.identifiers.is_some());
+            if !scanned && self.scanning.insert(pdf_file.path.clone()) {
+                self.identifier_worker.submit(pdf_file.path.clone());
+            }
+        }
+    }
+
+    /// stores the identifiers found by the background scans since the last tick, or gives
+    /// them to the import popup or the triage waiting for them
+    fn collect_identifiers(&mut self) {
+        let results = self.identifier_worker.results();
+        if results.is_empty() {
+            return;
+        }
+        let mut stored = false;
+        for (path, result) in results {
+            self.scanning.remove(&path);
+            if self.popups.pdf_import.is_scanning(&path) {
+                let hints = self.import_hints(result);
+                self.popups.pdf_import.set_hints(hints);
+                continue;
+            }
+            if self.popups.triage.missing_hints().as_ref() == Some(&path) {
+                self.set_triage_hints(path, result);
+                continue;
+            }
+            let key = match LibraryStore::key_for(&self.config.managed_dir, &path) {
+                Some(key) => key,
+                None => continue,
+            };
+            // a file which cannot be scanned is not scanned again on every reload
+            let identifiers = result.unwrap_or_else(|err| {
+                log::warn!("{}", err);
+                Identifiers::default()
+            });
+            self.library.record_mut(&key).identifiers = Some(identifiers);
+            stored = true;
+        }
+        if stored {
+            self.save_library();
+        }
+    }
+
+    /// moves the records of the files renamed or moved outside pdfstore to their new
+    /// paths, and reports the records whose file is gone, returns whether records moved
+    /// or were removed.
+    ///
+    /// waits for every listed file to be hashed, and for a listing without errors
+    fn reconcile(&mut self) -> bool {
+        // an unmounted library is not a library whose files are all gone
+        if !self.config.managed_dir.is_dir() || self.listing_failed || !self.hashing.is_empty() {
+            return false;
+        }
+        let paths = self
+            .inbox
+            .managed_pdf_list
+            .pdf_files
+            .iter()
+            .map(|pdf_file| pdf_file.path.clone())
+            .collect::<Vec<_>>();
+        let found = identity::reconcile(&paths, &self.config.managed_dir, &mut self.library);
+        let changed = !found.moved.is_empty() || !found.forgotten.is_empty();
+        if changed {
+            if let Err(err) = self.library.save() {
+                self.report_errors(vec![err]);
+            }
+        }
+        let orphans = found
+            .orphans
+            .iter()
+            .filter(|key| !self.orphans.contains(*key))
+            .collect::<Vec<_>>();
+        for key in &orphans {
+            log::warn!("the file of {} is gone, its record is kept", key);
+        }
+        let mut summary = Vec::new();
+        if !found.moved.is_empty() {
+            summary.push(format!("Followed {} renamed files", found.moved.len()));
+        }
+        if !orphans.is_empty() {
+            summary.push(format!("{} records without a file, see log", orphans.len()));
+        }
+        if !summary.is_empty() {
+            self.status_bar.show_info(summary.join(", "));
+        }
+        self.orphans = found.orphans.into_iter().collect();
+        changed
+    }
+
+    /// hashes the managed files whose identity is not known yet in the background
+    fn hash_files(&mut self) {
+        let paths = self
+            .inbox
+            .managed_pdf_list
+            .pdf_files
+            .iter()
+            .map(|pdf_file| pdf_file.path.clone())
+            .collect::<Vec<_>>();
+        for path in identity::stale(&paths, &self.config.managed_dir, &self.library) {
+            if self.hashing.insert(path.clone()) {
+                self.identity_worker.submit(path);
+            }
+        }
+    }
+
+    /// stores the identities hashed since the last tick
+    fn collect_identities(&mut self) {
+        let results = self.identity_worker.results();
+        if results.is_empty() {
+            return;
+        }
+        let mut changed = false;
+        for (path, result) in results {
+            self.hashing.remove(&path);
+            let key = match LibraryStore::key_for(&self.config.managed_dir, &path) {
+                Some(key) => key,
+                None => continue,
+            };
+            // the file may have been moved meanwhile
+            match result {
+                Ok(identity) if path.exists() => {
+                    let stored = &mut self.library.record_mut(&key).identity;
+                    changed |= stored.as_ref() != Some(&identity);
+                    *stored = Some(identity);
+                }
+                Ok(_) => {}
+                Err(err) => log::warn!("{}", err),
+            }
+        }
+        // the renamed files can be told once every file is hashed
+        changed |= self.reconcile();
+        if changed {
+            self.save_library();
+        }
+    }
+
+    /// saves the library and updates the panes showing it
+    fn save_library(&mut self) {
+        if let Err(err) = self.library.save() {
+            self.report_errors(vec![err]);
+        }
+        self.library_changed();
+    }
+
+    /// writes the catalogue of the marked managed files, or of all the loaded ones, to a new
+    /// file in the export directory
+    fn export(&mut self) {
+        let export = &self.config.export;
+        let path = export.dir.join(format!(
+            "pdfstore-{}.{}",
+            Local::now().format("%Y%m%d-%H%M%S"),
+            export.format.extension()
+        ));
+        let managed_pdf_list = &self.inbox.managed_pdf_list;
+        let pdf_files = match managed_pdf_list.has_marks() {
+            true => managed_pdf_list.marked_files().cloned().collect(),
+            false => managed_pdf_list.pdf_files.clone(),
+        };
+        let rows = ExportRow::catalogue(&self.config.managed_dir, &pdf_files, &self.library);
+        let result = fs::File::create(&path)
+            .and_then(|file| {
+                export::export(
+                    &mut BufWriter::new(file),
+                    &rows,
+                    &export.fields,
+                    export.format,
+                )
+            })
+            .map_err(|err| Error::writing_file(&path, err));
+        match result {
+            Ok(count) => self.status_bar.show_info(format!(
+                "Exported {} files to {}",
+                count,
+                tilde_path(&path)
+            )),
+            Err(err) => self.report_errors(vec![err]),
+        }
+    }
+
+    /// the marked managed files, or the listed ones if none is marked
+    fn exported_files(&self) -> HashSet<&PathBuf> {
+        let managed_pdf_list = &self.inbox.managed_pdf_list;
+        let listed = match managed_pdf_list.has_marks() {
+            true => managed_pdf_list.marked_files().collect::<Vec<_>>(),
+            false => managed_pdf_list.filtered_files().collect(),
+        };
+        listed.into_iter().map(|pdf_file| &pdf_file.path).collect()
+    }
+
+    /// writes the bibliography of the marked managed files, or of the listed ones, in
+    /// `format` to a new file in the export directory
+    fn export_bibliography(&mut self, format: BibliographyFormat) {
+        let path = self.config.export.dir.join(format!(
+            "pdfstore-{}.{}",
+            Local::now().format("%Y%m%d-%H%M%S"),
+            format.extension()
+        ));
+        let listed = self.exported_files();
+        // citekeys are resolved over the whole library, then the listed files are kept
+        let mut entries = citation::bibliography(
+            &self.config.managed_dir,
+            &self.inbox.managed_pdf_list.pdf_files,
+            &self.library,
+        );
+        entries.retain(|entry| listed.contains(&entry.path));
+        let result = fs::File::create(&path)
+            .and_then(|file| {
+                citation::write_bibliography(&mut BufWriter::new(file), &entries, format)
+            })
+            .map_err(|err| Error::writing_file(&path, err));
+        match result {
+            Ok(count) => self.status_bar.show_info(format!(
+                "Exported {} citations to {}",
+                count,
+                tilde_path(&path)
+            )),
+            Err(err) => self.report_errors(vec![err]),
+        }
+    }
+
+    /// opens the citation popup for the selected managed file, returns false if there is none
+    fn open_citation_popup(&mut self) -> anyhow::Result<bool> {
+        if !matches!(self.inbox.focus, InboxFocus::ManagedPdfList) {
+            return Ok(false);
+        }
+        let pdf_file = match self.inbox.managed_pdf_list.selected_file() {
+            Some(pdf_file) => pdf_file,
+            None => return Ok(false),
+        };
+        let citation = LibraryStore::key_for(&self.config.managed_dir, &pdf_file.path)
+            .and_then(|key| self.library.record(&key))
+            .and_then(|record| record.citation.clone())
+            .unwrap_or_else(|| Citation::from_file(pdf_file));
+        self.popups
+            .citation
+            .open(pdf_file.path.clone(), &citation)?;
+        Ok(true)
+    }
+
+    /// looks up the metadata of the selected managed file, returns false if there is none
+    fn fetch_metadata(&mut self) -> bool {
+        if !matches!(self.inbox.focus, InboxFocus::ManagedPdfList) {
+            return false;
+        }
+        let path = match self.inbox.managed_pdf_list.selected_file() {
+            Some(pdf_file) => pdf_file.path.clone(),
+            None => return false,
+        };
+        let key = match LibraryStore::key_for(&self.config.managed_dir, &path) {
+            Some(key) => key,
+            None => return false,
+        };
+        match self.library.record(&key).and_then(Lookup::for_record) {
+            Some(lookup) => {
+                self.status_bar
+                    .show_info(format!("Fetching the metadata of {}…", key));
+                self.metadata_worker.submit((path, lookup));
+            }
+            None => self
+                .status_bar
+                .show_info(format!("No DOI or arXiv id known for {}", key)),
+        }
+        true
+    }
+
+    /// shows the changes of the metadata fetched since the last tick
+    fn collect_metadata(&mut self) {
+        for (path, lookup, result) in self.metadata_worker.results() {
+            let key = match LibraryStore::key_for(&self.config.managed_dir, &path) {
+                Some(key) => key,
+                None => continue,
+            };
+            let fetched = match result {
+                Ok(Some(fetched)) => fetched,
+                Ok(None) => {
+                    self.status_bar
+                        .show_info(format!("No metadata found for {}", lookup));
+                    continue;
+                }
+                Err(err) => {
+                    self.report_errors(vec![err]);
+                    continue;
+                }
+            };
+            let current = self
+                .library
+                .record(&key)
+                .and_then(|record| record.citation.clone())
+                .or_else(|| {
+                    let pdf_files = &self.inbox.managed_pdf_list.pdf_files;
+                    let pdf_file = pdf_files.iter().find(|pdf_file| pdf_file.path == path)?;
+                    Some(Citation::from_file(pdf_file))
+                })
+                .unwrap_or_default();
+            let mut updated = current.clone();
+            updated.update_from(fetched);
+            let changes = metadata::changes(&current, &updated);
+            if changes.is_empty() {
+                self.status_bar
+                    .show_info(format!("The citation of {} is up to date", key));
+                continue;
+            }
+            let title = format!("{} ({})", key, lookup);
+            self.popups.metadata.open(path, title, changes, updated);
+        }
+    }
+
+    fn save_citation(&mut self, request: CitationRequest) {
+        let key = match LibraryStore::key_for(&self.config.managed_dir, &request.path) {
+            Some(key) => key,
+            None => return,
+        };
+        self.library.record_mut(&key).citation = Some(request.citation);
+        self.library_changed();
+        if let Err(err) = self.library.save() {
+            self.report_errors(vec![err]);
+            return;
+        }
+        self.status_bar
+            .show_info(format!("Saved the citation of {}", key));
+    }
+
+    /// matches the entries of the BibTeX file read since the last tick and shows them
+    fn collect_bib(&mut self) {
+        for (path, result) in self.bib_worker.results() {
+            if !self.popups.bib_import.is_reading(&path) {
+                continue;
+            }
+            let (entries, errors) = match result {
+                Ok(parsed) => parsed,
+                Err(err) => {
+                    self.popups.bib_import.open();
+                    self.report_errors(vec![err]);
+                    continue;
+                }
+            };
+            for err in errors {
+                log::warn!("skipped entry of {} at {}", path.display(), err);
+            }
+            let bib_dir = path.parent().unwrap_or(&path);
+            let import = BibImport::match_entries(
+                entries,
+                bib_dir,
+                &self.config.managed_dir,
+                &self.inbox.managed_pdf_list.pdf_files,
+                &self.library,
+            );
+            self.popups.bib_import.review(path, import);
+        }
+    }
+
+    fn import_bib(&mut self, request: BibImportRequest) {
+        let managed_dir = &self.config.managed_dir;
+        match request {
+            BibImportRequest::Match(path) => {
+                let path = expand_home(&path);
+                self.popups.bib_import.reading(path.clone());
+                self.bib_worker.submit(path);
+            }
+            BibImportRequest::Apply(import) => {
+                let count = import.apply(managed_dir, &mut self.library);
+                self.library_changed();
+                if let Err(err) = self.library.save() {
+                    self.report_errors(vec![err]);
+                    return;
+                }
+                self.status_bar.show_info(format!(
+                    "Attached {} entries, {} unmatched",
+                    count,
+                    import.unmatched.len()
+                ));
+            }
+        }
+    }
+
+    /// shows an error which interrupted an operation, the app keeps running
+    pub fn show_error(&mut self, err: anyhow::Error) {
+        log::error!("{:#}", err);
+        self.error_popup.show(format!("{:#}", err));
+    }
+
+    /// logs the errors and shows them to the user.
+    ///
+    /// recoverable errors are summarized in the status bar so that they don't interrupt
+    /// the user, the others are shown in the error popup
+    fn report_errors(&mut self, errors: Vec<Error>) {
+        let (recoverable, fatal): (Vec<_>, Vec<_>) =
+            errors.into_iter().partition(Error::is_recoverable);
+
+        for err in &recoverable {
+            log::warn!("{}", err);
+        }
+        match recoverable.as_slice() {
+            [] => {}
+            [err] => self.status_bar.show_error(err.to_string()),
+            [err, rest @ ..] => self.status_bar.show_error(format!(
+                "{} (and {} more errors, see log)",
+                err,
+                rest.len()
+            )),
+        }
+
+        for err in fatal {
+            self.show_error(err.into());
+        }
+    }
+
+    async fn reload_inbox_list(&mut self) -> anyhow::Result<()> {
+        self.update_inbox_list().await?;
+        self.status_bar.show_info(format!(
+            "Reloaded {} managed and {} unmanaged files",
+            self.inbox.managed_pdf_list.pdf_files.len(),
+            self.inbox.unmanaged_pdf_list.pdf_files.len()
+        ));
         Ok(())
     }
 
     pub async fn components_event(&mut self, key: Key) -> anyhow::Result<EventState> {
         match self.focus {
             Focus::Inbox => {
-                let state = self.inbox.event(key)?;
+                if self.inbox.event(key)?.is_consumed() {
+                    return Ok(EventState::Consumed);
+                }
                 if key == self.key_config.enter {
-                    self.update_inbox_list().await?;
+                    self.reload_inbox_list().await?;
                     return Ok(EventState::Consumed);
                 }
-                Ok(state)
+                Ok(EventState::NotConsumed)
             }
         }
     }
@@ -168,3 +1915,28 @@ impl App {
         &self.state
     }
 }
+
+/// parses a saved query, one which no longer parses matches nothing
+fn saved_query(query: &str) -> Query {
+    Query::parse(query).unwrap_or_else(|err| {
+        log::debug!("saved search \"{}\": {}", query, err);
+        Query::nothing()
+    })
+}
+
+/// extracts the identifiers printed on the first pages of a pdf, run by the identifier worker
+fn scan_identifiers(path: PathBuf) -> (PathBuf, Result<Identifiers>) {
+    let result = pdf_text::first_pages(&path, SCANNED_PAGES)
+        .map(|pages| Identifiers::scan(&pages.join("\n")));
+    (path, result)
+}
+
+/// the end of an import status which tells the files the organize policy left in place,
+/// their reasons are logged
+fn left_in_place(left: &[(PathBuf, String)]) -> String {
+    match left {
+        [] => String::new(),
+        [(_, reason)] => format!(", left in place: {}", reason),
+        left => format!(", {} left in place, see the log", left.len()),
+    }
+}
@@ -0,0 +1,856 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use clap::{Parser, Subcommand};
+use serde::Serialize;
+
+use crate::{
+    components::inbox::PdfFileLoader,
+    config::Config,
+    domain::{
+        batch_import::{ImportPlan, PlannedAction},
+        bib_import::BibImport,
+        bibtex,
+        citation::{self, BibliographyFormat, Citation},
+        export::{self, ExportField, ExportFormat, ExportRow},
+        identifiers::{Identifiers, SCANNED_PAGES},
+        identity::{self, FileIdentity},
+        importer::Importer,
+        library_store::LibraryStore,
+        metadata::{self, HttpResolver, Lookup, MetadataResolver},
+        organize::{self, Filed, FolderPolicy, OrganizePlan, PlannedMove},
+        pdf_file::PdfFile,
+        pdf_info::PdfInfoCache,
+        pdf_text,
+        query::Query,
+        reading::Readings,
+        sort_order::SortOrder,
+    },
+    error::Error,
+};
+
+/// Exit codes of the headless commands
+pub mod exit_code {
+    pub const SUCCESS: i32 = 0;
+    /// `search` found nothing, `import-bib` matched no entry or `import-all` has nothing to
+    /// import
+    pub const NO_MATCH: i32 = 1;
+    /// invalid arguments, reported by clap, an invalid query or `organize` without a policy
+    pub const USAGE: i32 = 2;
+    /// the given file is missing, unreadable, not a pdf or the given name is invalid
+    pub const INPUT: i32 = 3;
+    /// `import` would overwrite an existing file
+    pub const CONFLICT: i32 = 4;
+    /// any other failure, e.g. the library directory cannot be read
+    pub const FAILURE: i32 = 5;
+}
+
+/// pdfstore manages your pdf files.
+///
+/// Without a subcommand the terminal UI is started.
+#[derive(Parser)]
+#[clap(name = "pdfstore", disable_version_flag = true)]
+pub struct Cli {
+    /// Print the version and exit
+    #[clap(short = 'V', long)]
+    pub version: bool,
+    /// Print JSON instead of plain text
+    #[clap(long, global = true)]
+    pub json: bool,
+    #[clap(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// List the pdf files of the library
+    List {
+        /// List the unmanaged directory instead of the library
+        #[clap(long)]
+        unmanaged: bool,
+    },
+    /// Move a pdf file into the library
+    Import {
+        file: PathBuf,
+        /// New file name in the library, `.pdf` is appended if missing
+        #[clap(long)]
+        name: Option<String>,
+        /// Import the file even if a document with the same DOI, arXiv id or ISBN is managed
+        #[clap(long)]
+        allow_duplicate: bool,
+    },
+    /// Import every pdf of the unmanaged directory by the `import.rules` of the config
+    ImportAll {
+        /// Only print the plan, do not move any file
+        #[clap(long)]
+        dry_run: bool,
+    },
+    /// File the documents of the library into folders by the `organize.folder` policy of
+    /// the config
+    Organize {
+        /// Only print the planned moves, do not move any file
+        #[clap(long)]
+        dry_run: bool,
+    },
+    /// Find the records of the files renamed or moved outside pdfstore and report the
+    /// records whose file is gone
+    Reconcile {
+        /// Only report, do not change the library
+        #[clap(long)]
+        dry_run: bool,
+    },
+    /// List the pdf files matching the query, e.g. `attention tag:nlp year:>2016`
+    Search {
+        query: String,
+        /// Search the unmanaged directory instead of the library
+        #[clap(long)]
+        unmanaged: bool,
+    },
+    /// Show the details of a pdf file
+    Info { file: PathBuf },
+    /// Write the library catalogue as JSON Lines or CSV
+    Export {
+        /// jsonl or csv, defaults to the `export.format` config
+        #[clap(long)]
+        format: Option<ExportFormat>,
+        /// Comma separated columns, defaults to the `export.fields` config
+        #[clap(long, use_value_delimiter = true)]
+        fields: Option<Vec<ExportField>>,
+        /// Write to this file instead of stdout
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Write the citations of the library as BibTeX or CSL-JSON
+    #[clap(alias = "bib")]
+    Bibliography {
+        /// Only these files of the library, all files by default
+        files: Vec<PathBuf>,
+        /// bibtex or csl-json
+        #[clap(long, default_value = "bibtex")]
+        format: BibliographyFormat,
+        /// Only files with this tag, can be repeated to accept several tags
+        #[clap(long)]
+        tag: Vec<String>,
+        /// Write to this file instead of stdout
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Attach the entries of a BibTeX file to the matching files of the library
+    ImportBib {
+        file: PathBuf,
+        /// Only report the matches, do not change the library
+        #[clap(long)]
+        dry_run: bool,
+    },
+    /// Fill in the citation of a file of the library from its DOI or arXiv id
+    Fetch {
+        file: PathBuf,
+        /// Only show the changes, do not change the library
+        #[clap(long)]
+        dry_run: bool,
+    },
+}
+
+/// runs a headless command and returns the process exit code
+pub fn run(command: Command, json: bool, config: &Config) -> i32 {
+    let result = match command {
+        Command::List { unmanaged } => list(config, unmanaged, None, json),
+        Command::Search { query, unmanaged } => list(config, unmanaged, Some(&query), json),
+        Command::Import {
+            file,
+            name,
+            allow_duplicate,
+        } => import(config, &file, name.as_deref(), allow_duplicate, json),
+        Command::ImportAll { dry_run } => import_all(config, dry_run, json),
+        Command::Organize { dry_run } => organize(config, dry_run, json),
+        Command::Reconcile { dry_run } => reconcile(config, dry_run, json),
+        Command::Info { file } => info(config, &file, json),
+        Command::Export {
+            format,
+            fields,
+            output,
+        } => export(
+            config,
+            format.unwrap_or(config.export.format),
+            fields.as_deref().unwrap_or(&config.export.fields),
+            output.as_deref(),
+        ),
+        Command::Bibliography {
+            files,
+            format,
+            tag,
+            output,
+        } => bibliography(config, &files, &tag, format, output.as_deref()),
+        Command::ImportBib { file, dry_run } => import_bib(config, &file, dry_run, json),
+        Command::Fetch { file, dry_run } => fetch(config, &file, dry_run, json),
+    };
+    match result {
+        Ok(code) => code,
+        Err(err) => {
+            log::error!("{}", err);
+            eprintln!("pdfstore: {}", err);
+            exit_code_of(&err)
+        }
+    }
+}
+
+fn exit_code_of(err: &Error) -> i32 {
+    match err {
+        Error::UnreadableFile { .. }
+        | Error::PermissionDenied { .. }
+        | Error::BrokenPdf { .. }
+        | Error::InvalidName { .. } => exit_code::INPUT,
+        Error::AlreadyExists { .. } | Error::Duplicate { .. } => exit_code::CONFLICT,
+        Error::InvalidQuery { .. } => exit_code::USAGE,
+        Error::UnreadableDirectory { .. }
+        | Error::UnwritableFile { .. }
+        | Error::UnremovableFile { .. }
+        | Error::MetadataUnavailable { .. }
+        | Error::ViewerUnavailable { .. }
+        | Error::TrashFailed { .. }
+        | Error::CorruptLibrary { .. }
+        | Error::Io(_) => exit_code::FAILURE,
+    }
+}
+
+fn list(config: &Config, unmanaged: bool, query: Option<&str>, json: bool) -> Result<i32, Error> {
+    let dir = if unmanaged {
+        &config.unmanaged_dir
+    } else {
+        &config.managed_dir
+    };
+    let mut loader = info_loader(config);
+    // the library includes the files of its folders
+    let mut pdf_files = match unmanaged {
+        true => loader.load_files(dir)?,
+        false => loader.load_tree(dir)?,
+    };
+    for err in loader.take_errors() {
+        eprintln!("pdfstore: skipped: {}", err);
+    }
+    save_info(&mut loader);
+
+    if let Some(query) = query {
+        let query = Query::parse(query)?;
+        // the unmanaged files have no record
+        let store = if unmanaged {
+            None
+        } else {
+            Some(LibraryStore::open(&config.library_file)?)
+        };
+        pdf_files.retain(|file| {
+            let record = store.as_ref().and_then(|store| {
+                let key = LibraryStore::key_for(&config.managed_dir, &file.path)?;
+                store.record(&key)
+            });
+            query.matches(file, record)
+        });
+    }
+    SortOrder::default().sort(&mut pdf_files, &Readings::default());
+
+    if json {
+        print_json(&pdf_files)?;
+    } else {
+        let mut out = io::stdout().lock();
+        for file in &pdf_files {
+            writeln!(out, "{}", file.path.display())?;
+        }
+    }
+
+    if query.is_some() && pdf_files.is_empty() {
+        return Ok(exit_code::NO_MATCH);
+    }
+    Ok(exit_code::SUCCESS)
+}
+
+fn import(
+    config: &Config,
+    file: &Path,
+    name: Option<&str>,
+    allow_duplicate: bool,
+    json: bool,
+) -> Result<i32, Error> {
+    let policy = import_policy(config)?;
+    let mut store = LibraryStore::open(&config.library_file)?;
+    let identifiers = scan_identifiers(file);
+    if let Some(identifiers) = identifiers.as_ref().filter(|_| !allow_duplicate) {
+        if let Some((existing, shared)) = store.find_duplicate(identifiers, None) {
+            return Err(Error::Duplicate {
+                path: file.to_path_buf(),
+                existing: existing.to_string(),
+                identifier: shared.to_string(),
+            });
+        }
+    }
+
+    let dest = Importer::new(&config.managed_dir).import(file, name)?;
+    let key = LibraryStore::key_for(&config.managed_dir, &dest);
+    if let (Some(key), Some(identifiers)) = (key, identifiers) {
+        store.record_mut(&key).identifiers = Some(identifiers);
+    }
+    store.record_imports(&config.managed_dir, std::slice::from_ref(&dest));
+    let dest = match &policy {
+        Some(policy) => {
+            let filed = organize::file_imported(policy, &[dest], &config.managed_dir, &mut store);
+            print_left(&filed);
+            filed.paths[0].clone()
+        }
+        None => dest,
+    };
+    store.save()?;
+
+    if json {
+        let pdf_file = load_with_info(config, &dest)?;
+        print_json(&pdf_file)?;
+    } else {
+        println!("{}", dest.display());
+    }
+    Ok(exit_code::SUCCESS)
+}
+
+fn import_all(config: &Config, dry_run: bool, json: bool) -> Result<i32, Error> {
+    let policy = import_policy(config)?;
+    let mut store = LibraryStore::open(&config.library_file)?;
+    let mut loader = PdfFileLoader::without_info();
+    let mut pdf_files = loader.load_files(&config.unmanaged_dir)?;
+    for err in loader.take_errors() {
+        eprintln!("pdfstore: skipped: {}", err);
+    }
+    SortOrder::default().sort(&mut pdf_files, &Readings::default());
+    let scanned = pdf_files
+        .into_iter()
+        .map(|pdf_file| {
+            let identifiers = scan_identifiers(&pdf_file.path);
+            (pdf_file.path, identifiers)
+        })
+        .collect();
+    let rules = &config.import;
+    let plan = ImportPlan::new(
+        scanned,
+        &rules.rules,
+        rules.skip_duplicates,
+        &config.managed_dir,
+        &store,
+    );
+    let mut results = HashMap::new();
+    if !dry_run {
+        let mut executed = plan.execute(&config.managed_dir, &mut store);
+        let imported = executed
+            .iter()
+            .filter_map(|(_, result)| result.as_ref().ok().cloned())
+            .collect::<Vec<_>>();
+        store.record_imports(&config.managed_dir, &imported);
+        if let Some(policy) = &policy {
+            let filed = organize::file_imported(policy, &imported, &config.managed_dir, &mut store);
+            print_left(&filed);
+            let mut organized = filed.paths.into_iter();
+            for (_, result) in &mut executed {
+                if let (Ok(dest), Some(organized)) = (result.as_mut(), organized.next()) {
+                    *dest = organized;
+                }
+            }
+        }
+        results.extend(executed);
+        store.save()?;
+    }
+
+    // the status of each file, with its destination or why it stays
+    let rows = plan.files.iter().map(|planned| {
+        let (status, dest, reason) = match (&planned.action, results.get(&planned.src)) {
+            (PlannedAction::Skip(reason), _) => ("skipped", None, Some(reason.clone())),
+            (PlannedAction::Import { .. }, Some(Err(err))) => {
+                ("failed", None, Some(err.to_string()))
+            }
+            (PlannedAction::Import { dest, .. }, _) if dry_run => ("import", Some(dest), None),
+            (PlannedAction::Import { .. }, Some(Ok(dest))) => ("imported", Some(dest), None),
+            (PlannedAction::Import { dest, .. }, _) => ("imported", Some(dest), None),
+        };
+        (planned, status, dest, reason)
+    });
+    if json {
+        let rows = rows.map(|(planned, status, dest, reason)| {
+            serde_json::json!({
+                "path": planned.src,
+                "rule": planned.rule.map(|rule| rule + 1),
+                "status": status,
+                "dest": dest,
+                "reason": reason,
+            })
+        });
+        print_json(&rows.collect::<Vec<_>>())?;
+    } else {
+        let mut out = io::stdout().lock();
+        for (planned, status, dest, reason) in rows {
+            match dest {
+                Some(dest) => writeln!(
+                    out,
+                    "{:9}{} -> {}",
+                    status,
+                    planned.src.display(),
+                    dest.display()
+                )?,
+                None => writeln!(
+                    out,
+                    "{:9}{}: {}",
+                    status,
+                    planned.src.display(),
+                    reason.unwrap_or_default()
+                )?,
+            }
+        }
+    }
+
+    if let Some(err) = results.values().find_map(|result| result.as_ref().err()) {
+        return Ok(exit_code_of(err));
+    }
+    if plan.imports().next().is_none() {
+        return Ok(exit_code::NO_MATCH);
+    }
+    Ok(exit_code::SUCCESS)
+}
+
+/// tells which imported files were not filed by the organize policy
+fn print_left(filed: &Filed) {
+    for (path, reason) in &filed.left {
+        eprintln!("pdfstore: left {} in place: {}", path.display(), reason);
+    }
+}
+
+/// the organize policy of the config if it applies to imports
+fn import_policy(config: &Config) -> Result<Option<FolderPolicy>, Error> {
+    let organize = &config.organize;
+    organize.policy().filter(|_| organize.on_import).transpose()
+}
+
+fn organize(config: &Config, dry_run: bool, json: bool) -> Result<i32, Error> {
+    let policy = match config.organize.policy() {
+        Some(policy) => policy?,
+        None => {
+            eprintln!("pdfstore: no folder policy, set organize.folder in the config");
+            return Ok(exit_code::USAGE);
+        }
+    };
+    let mut store = LibraryStore::open(&config.library_file)?;
+    let mut loader = PdfFileLoader::without_info();
+    let mut pdf_files = loader.load_tree(&config.managed_dir)?;
+    for err in loader.take_errors() {
+        eprintln!("pdfstore: skipped: {}", err);
+    }
+    SortOrder::default().sort(&mut pdf_files, &Readings::default());
+    let paths = pdf_files.into_iter().map(|pdf_file| pdf_file.path);
+    let plan = OrganizePlan::new(&policy, paths, &config.managed_dir, &store);
+    let mut results = HashMap::new();
+    if !dry_run {
+        results.extend(plan.execute(&config.managed_dir, &mut store));
+        store.save()?;
+    }
+
+    // the status of each document, with its destination or why it stays
+    let rows = plan.files.iter().map(|(src, action)| {
+        let (status, dest, reason) = match (action, results.get(src)) {
+            (PlannedMove::Stay(reason), _) => ("stays", None, Some(reason.clone())),
+            (PlannedMove::Move(_), Some(Err(err))) => ("failed", None, Some(err.to_string())),
+            (PlannedMove::Move(dest), _) if dry_run => ("move", Some(dest), None),
+            (PlannedMove::Move(dest), _) => ("moved", Some(dest), None),
+        };
+        (src, status, dest, reason)
+    });
+    if json {
+        let rows = rows.map(|(src, status, dest, reason)| {
+            serde_json::json!({
+                "path": src,
+                "status": status,
+                "dest": dest,
+                "reason": reason,
+            })
+        });
+        print_json(&rows.collect::<Vec<_>>())?;
+    } else {
+        let mut out = io::stdout().lock();
+        for (src, status, dest, reason) in rows {
+            match dest {
+                Some(dest) => writeln!(out, "{:7}{} -> {}", status, src.display(), dest.display())?,
+                None => writeln!(
+                    out,
+                    "{:7}{}: {}",
+                    status,
+                    src.display(),
+                    reason.unwrap_or_default()
+                )?,
+            }
+        }
+    }
+
+    match results.values().find_map(|result| result.as_ref().err()) {
+        Some(err) => Ok(exit_code_of(err)),
+        None => Ok(exit_code::SUCCESS),
+    }
+}
+
+fn reconcile(config: &Config, dry_run: bool, json: bool) -> Result<i32, Error> {
+    let mut store = LibraryStore::open(&config.library_file)?;
+    let mut loader = PdfFileLoader::without_info();
+    let pdf_files = loader.load_tree(&config.managed_dir)?;
+    for err in loader.take_errors() {
+        eprintln!("pdfstore: skipped: {}", err);
+    }
+    let paths = pdf_files
+        .into_iter()
+        .map(|pdf_file| pdf_file.path)
+        .collect::<Vec<_>>();
+    let found = identity::reconcile(&paths, &config.managed_dir, &mut store);
+    if !dry_run {
+        // the files are hashed so that they are found once they are renamed
+        for path in identity::stale(&paths, &config.managed_dir, &store) {
+            match (
+                LibraryStore::key_for(&config.managed_dir, &path),
+                FileIdentity::of(&path),
+            ) {
+                (Some(key), Ok(identity)) => store.record_mut(&key).identity = Some(identity),
+                (_, Err(err)) => eprintln!("pdfstore: not hashed: {}", err),
+                (None, Ok(_)) => {}
+            }
+        }
+        store.save()?;
+    }
+
+    // the status of each record which is not where its file is, with its new key
+    let rows = found
+        .moved
+        .iter()
+        .map(|(from, to)| (from, "moved", Some(to)))
+        .chain(found.orphans.iter().map(|key| (key, "orphan", None)))
+        .chain(found.forgotten.iter().map(|key| (key, "forgotten", None)));
+    if json {
+        let rows = rows.map(|(key, status, to)| {
+            serde_json::json!({
+                "key": key,
+                "status": status,
+                "to": to,
+            })
+        });
+        print_json(&rows.collect::<Vec<_>>())?;
+    } else {
+        let mut out = io::stdout().lock();
+        for (key, status, to) in rows {
+            match to {
+                Some(to) => writeln!(out, "{:10}{} -> {}", status, key, to)?,
+                None => writeln!(out, "{:10}{}", status, key)?,
+            }
+        }
+    }
+    Ok(exit_code::SUCCESS)
+}
+
+fn info(config: &Config, file: &Path, json: bool) -> Result<i32, Error> {
+    let pdf_file = load_with_info(config, file)?;
+    let identifiers = scan_identifiers(file).unwrap_or_default();
+
+    if json {
+        let mut value = serde_json::to_value(&pdf_file).map_err(io::Error::from)?;
+        value["identifiers"] = serde_json::to_value(&identifiers).map_err(io::Error::from)?;
+        print_json(&value)?;
+    } else {
+        print_info(&pdf_file, &identifiers)?;
+    }
+    Ok(exit_code::SUCCESS)
+}
+
+fn export(
+    config: &Config,
+    format: ExportFormat,
+    fields: &[ExportField],
+    output: Option<&Path>,
+) -> Result<i32, Error> {
+    let store = LibraryStore::open(&config.library_file)?;
+    let mut loader = PdfFileLoader::without_info();
+    let mut pdf_files = loader.load_tree(&config.managed_dir)?;
+    for err in loader.take_errors() {
+        eprintln!("pdfstore: skipped: {}", err);
+    }
+    SortOrder::default().sort(&mut pdf_files, &Readings::default());
+
+    let rows = ExportRow::catalogue(&config.managed_dir, &pdf_files, &store);
+    match output {
+        Some(path) => {
+            let file = fs::File::create(path).map_err(|err| Error::writing_file(path, err))?;
+            export::export(&mut io::BufWriter::new(file), &rows, fields, format)?;
+        }
+        None => {
+            export::export(&mut io::stdout().lock(), &rows, fields, format)?;
+        }
+    }
+    Ok(exit_code::SUCCESS)
+}
+
+fn bibliography(
+    config: &Config,
+    files: &[PathBuf],
+    tags: &[String],
+    format: BibliographyFormat,
+    output: Option<&Path>,
+) -> Result<i32, Error> {
+    let store = LibraryStore::open(&config.library_file)?;
+    let mut loader = PdfFileLoader::without_info();
+    let pdf_files = loader.load_tree(&config.managed_dir)?;
+    for err in loader.take_errors() {
+        eprintln!("pdfstore: skipped: {}", err);
+    }
+
+    let selected = files
+        .iter()
+        .map(|file| fs::canonicalize(file).map_err(|err| Error::reading_file(file, err)))
+        .collect::<Result<Vec<_>, _>>()?;
+    let managed_dir = fs::canonicalize(&config.managed_dir)
+        .map_err(|err| Error::reading_file(&config.managed_dir, err))?;
+    if let Some(file) = selected.iter().find(|file| !file.starts_with(&managed_dir)) {
+        eprintln!("pdfstore: not in the library: {}", file.display());
+        return Ok(exit_code::INPUT);
+    }
+
+    let mut entries = citation::bibliography(&config.managed_dir, &pdf_files, &store);
+    entries.retain(|entry| {
+        let in_files = selected.is_empty()
+            || fs::canonicalize(&entry.path).is_ok_and(|path| selected.contains(&path));
+        let has_tag = tags.is_empty()
+            || LibraryStore::key_for(&config.managed_dir, &entry.path)
+                .and_then(|key| store.record(&key))
+                .is_some_and(|record| record.tags.iter().any(|tag| tags.contains(tag)));
+        in_files && has_tag
+    });
+
+    match output {
+        Some(path) => {
+            let file = fs::File::create(path).map_err(|err| Error::writing_file(path, err))?;
+            citation::write_bibliography(&mut io::BufWriter::new(file), &entries, format)?;
+        }
+        None => {
+            citation::write_bibliography(&mut io::stdout().lock(), &entries, format)?;
+        }
+    }
+    Ok(exit_code::SUCCESS)
+}
+
+fn import_bib(config: &Config, file: &Path, dry_run: bool, json: bool) -> Result<i32, Error> {
+    let content = fs::read_to_string(file).map_err(|err| Error::reading_file(file, err))?;
+    let (entries, errors) = bibtex::parse(&content);
+    for err in errors {
+        eprintln!("pdfstore: skipped entry at {}", err);
+    }
+
+    let mut store = LibraryStore::open(&config.library_file)?;
+    let mut loader = PdfFileLoader::without_info();
+    let pdf_files = loader.load_tree(&config.managed_dir)?;
+    for err in loader.take_errors() {
+        eprintln!("pdfstore: skipped: {}", err);
+    }
+
+    let bib_dir = file.parent().unwrap_or_else(|| Path::new("."));
+    let import =
+        BibImport::match_entries(entries, bib_dir, &config.managed_dir, &pdf_files, &store);
+    if !dry_run {
+        import.apply(&config.managed_dir, &mut store);
+        store.save()?;
+    }
+
+    if json {
+        let matched = import.matched.iter().map(|matched| {
+            serde_json::json!({
+                "citekey": matched.entry.citekey,
+                "path": matched.path,
+                "reason": matched.reason.to_string(),
+            })
+        });
+        let unmatched = import.unmatched.iter().map(|entry| {
+            serde_json::json!({
+                "citekey": entry.citekey,
+                "title": entry.to_citation().title,
+            })
+        });
+        print_json(&serde_json::json!({
+            "matched": matched.collect::<Vec<_>>(),
+            "unmatched": unmatched.collect::<Vec<_>>(),
+        }))?;
+    } else {
+        let mut out = io::stdout().lock();
+        for matched in &import.matched {
+            writeln!(
+                out,
+                "matched    {} -> {} ({})",
+                matched.entry.citekey,
+                matched.path.display(),
+                matched.reason
+            )?;
+        }
+        for entry in &import.unmatched {
+            writeln!(
+                out,
+                "unmatched  {} \"{}\"",
+                entry.citekey,
+                entry.to_citation().title
+            )?;
+        }
+    }
+
+    if import.matched.is_empty() && !import.unmatched.is_empty() {
+        return Ok(exit_code::NO_MATCH);
+    }
+    Ok(exit_code::SUCCESS)
+}
+
+fn fetch(config: &Config, file: &Path, dry_run: bool, json: bool) -> Result<i32, Error> {
+    let path = fs::canonicalize(file).map_err(|err| Error::reading_file(file, err))?;
+    let managed_dir = fs::canonicalize(&config.managed_dir)
+        .map_err(|err| Error::reading_file(&config.managed_dir, err))?;
+    let key = match LibraryStore::key_for(&managed_dir, &path) {
+        Some(key) => key,
+        None => {
+            eprintln!("pdfstore: not in the library: {}", path.display());
+            return Ok(exit_code::INPUT);
+        }
+    };
+    let pdf_file = PdfFileLoader::without_info().load_file(&path)?;
+
+    let mut store = LibraryStore::open(&config.library_file)?;
+    if store
+        .record(&key)
+        .is_none_or(|record| record.identifiers.is_none())
+    {
+        store.record_mut(&key).identifiers = scan_identifiers(&path);
+    }
+    let record = store.record(&key).cloned().unwrap_or_default();
+    let lookup = match Lookup::for_record(&record) {
+        Some(lookup) => lookup,
+        None => {
+            eprintln!("pdfstore: no DOI or arXiv id known for {}", key);
+            return Ok(exit_code::NO_MATCH);
+        }
+    };
+    let fetched = match HttpResolver::new(&config.metadata).resolve(&lookup)? {
+        Some(fetched) => fetched,
+        None => {
+            eprintln!("pdfstore: no metadata found for {}", lookup);
+            return Ok(exit_code::NO_MATCH);
+        }
+    };
+
+    let current = record
+        .citation
+        .unwrap_or_else(|| Citation::from_file(&pdf_file));
+    let mut updated = current.clone();
+    updated.update_from(fetched);
+    let changes = metadata::changes(&current, &updated);
+    let apply = !dry_run && !changes.is_empty();
+    if apply {
+        store.record_mut(&key).citation = Some(updated);
+    }
+    if !dry_run {
+        store.save()?;
+    }
+
+    if json {
+        let changes = changes.iter().map(|change| {
+            serde_json::json!({
+                "field": change.field,
+                "old": change.old,
+                "new": change.new,
+            })
+        });
+        print_json(&serde_json::json!({
+            "lookup": lookup.to_string(),
+            "changes": changes.collect::<Vec<_>>(),
+            "applied": apply,
+        }))?;
+    } else if changes.is_empty() {
+        println!("{} is up to date ({})", key, lookup);
+    } else {
+        let mut out = io::stdout().lock();
+        for change in &changes {
+            writeln!(out, "{}:", change.field)?;
+            if !change.old.is_empty() {
+                writeln!(out, "  - {}", change.old)?;
+            }
+            writeln!(out, "  + {}", change.new)?;
+        }
+    }
+    Ok(exit_code::SUCCESS)
+}
+
+/// a loader reading the info of the files through the cache of the terminal UI, so that
+/// the unchanged files are not parsed again
+fn info_loader(config: &Config) -> PdfFileLoader {
+    PdfFileLoader::with_info(PdfInfoCache::open(&config.preview.info_file))
+}
+
+/// loads the file at `path` with its info, see `info_loader`
+fn load_with_info(config: &Config, path: &Path) -> Result<PdfFile, Error> {
+    let mut loader = info_loader(config);
+    let pdf_file = loader.load_file(path)?;
+    save_info(&mut loader);
+    Ok(pdf_file)
+}
+
+/// saves the info read by `loader`, a failure only costs reading it again next time
+fn save_info(loader: &mut PdfFileLoader) {
+    if let Err(err) = loader.save_info() {
+        log::warn!("failed to save the info cache: {}", err);
+    }
+}
+
+/// the identifiers printed on the first pages, `None` if the text cannot be extracted
+fn scan_identifiers(file: &Path) -> Option<Identifiers> {
+    match pdf_text::first_pages(file, SCANNED_PAGES) {
+        Ok(pages) => Some(Identifiers::scan(&pages.join("\n"))),
+        Err(err) => {
+            eprintln!("pdfstore: not scanned for identifiers: {}", err);
+            None
+        }
+    }
+}
+
+fn print_info(pdf_file: &PdfFile, identifiers: &Identifiers) -> io::Result<()> {
+    let mut out = io::stdout().lock();
+    writeln!(out, "name:     {}", pdf_file.file_name)?;
+    writeln!(out, "path:     {}", pdf_file.path.display())?;
+    writeln!(out, "size:     {}", pdf_file.size)?;
+    if let Some(created) = pdf_file.created {
+        writeln!(out, "created:  {}", created.to_rfc3339())?;
+    }
+    if let Some(modified) = pdf_file.modified {
+        writeln!(out, "modified: {}", modified.to_rfc3339())?;
+    }
+    let info = &pdf_file.info;
+    if let Some(pages) = info.pages {
+        writeln!(out, "pages:    {}", pages)?;
+    }
+    if let Some(version) = &info.version {
+        writeln!(out, "version:  {}", version)?;
+    }
+    if let Some(producer) = &info.producer {
+        writeln!(out, "producer: {}", producer)?;
+    }
+    let flags = info.flags();
+    if !flags.is_empty() {
+        writeln!(out, "flags:    {}", flags.join(", "))?;
+    }
+    if let Some(doi) = &identifiers.doi {
+        writeln!(out, "doi:      {}", doi)?;
+    }
+    if let Some(arxiv) = &identifiers.arxiv {
+        writeln!(out, "arxiv:    {}", arxiv)?;
+    }
+    if let Some(isbn) = &identifiers.isbn {
+        writeln!(out, "isbn:     {}", isbn)?;
+    }
+    Ok(())
+}
+
+fn print_json<T: Serialize + ?Sized>(value: &T) -> io::Result<()> {
+    let mut out = io::stdout().lock();
+    serde_json::to_writer_pretty(&mut out, value)?;
+    writeln!(out)
+}
@@ -0,0 +1,279 @@
+use std::path::{Path, PathBuf};
+use tui::backend::Backend;
+use tui::layout::{Constraint, Direction, Layout, Rect};
+use tui::style::{Color, Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, BorderType, Borders, Clear, List, ListItem, ListState, Paragraph};
+use tui::Frame;
+
+use super::{
+    command::{self, CommandInfo},
+    utils::{popup::centered_rect, vertical_scroll::VerticalScroll},
+    CommandBlocking, Component, DrawableComponent, EventState,
+};
+use crate::{domain::bib_import::BibImport, inputs::key::Key, key_config::KeyConfig};
+
+/// A step confirmed by the user, performed by the app
+pub enum BibImportRequest {
+    /// read the BibTeX file in the background and match its entries, then call `review`
+    Match(PathBuf),
+    /// attach the matched entries to the library
+    Apply(BibImport),
+}
+
+enum Stage {
+    Closed,
+    /// typing the path of the BibTeX file
+    Path(String),
+    /// the file is read and parsed in the background
+    Reading(PathBuf),
+    /// reviewing the matches before they are applied
+    Review {
+        path: PathBuf,
+        import: BibImport,
+    },
+}
+
+/// Imports a BibTeX file: asks for its path, then lists matched and unmatched entries
+pub struct BibImportPopup {
+    stage: Stage,
+    request: Option<BibImportRequest>,
+    list_state: ListState,
+    selection: usize,
+    scroll: VerticalScroll,
+    key_config: KeyConfig,
+}
+
+impl BibImportPopup {
+    pub fn new(key_config: KeyConfig) -> Self {
+        Self {
+            stage: Stage::Closed,
+            request: None,
+            list_state: ListState::default(),
+            selection: 0,
+            scroll: VerticalScroll::new(),
+            key_config,
+        }
+    }
+
+    pub fn open(&mut self) {
+        self.stage = Stage::Path(String::new());
+    }
+
+    /// waits for the file requested by `BibImportRequest::Match`
+    pub fn reading(&mut self, path: PathBuf) {
+        self.stage = Stage::Reading(path);
+    }
+
+    /// whether the popup waits for `path`, it may have been closed meanwhile
+    pub fn is_reading(&self, path: &Path) -> bool {
+        matches!(&self.stage, Stage::Reading(reading) if reading == path)
+    }
+
+    /// shows the matches of the file requested by `BibImportRequest::Match`
+    pub fn review(&mut self, path: PathBuf, import: BibImport) {
+        self.selection = 0;
+        self.stage = Stage::Review { path, import };
+    }
+
+    pub fn is_visible(&self) -> bool {
+        !matches!(self.stage, Stage::Closed)
+    }
+
+    /// the step confirmed by the last event, if any
+    pub fn take_request(&mut self) -> Option<BibImportRequest> {
+        self.request.take()
+    }
+
+    fn draw_path<B: Backend>(&self, f: &mut Frame<B>, path: &str) {
+        let area = centered_rect(60, 25, f.size());
+        f.render_widget(Clear, area);
+
+        let text = vec![
+            Spans::from(vec![
+                Span::styled("BibTeX file: ", Style::default().fg(Color::LightCyan)),
+                Span::raw(path.to_string()),
+            ]),
+            Spans::from(""),
+            Spans::from(Span::styled(
+                command::match_bib(&self.key_config).name,
+                Style::default().fg(Color::Gray),
+            )),
+        ];
+        let body = Paragraph::new(text).block(popup_block("Import BibTeX".to_string()));
+        f.render_widget(body, area);
+
+        let cursor_x = area.x + 1 + "BibTeX file: ".len() as u16 + path.chars().count() as u16;
+        f.set_cursor(cursor_x.min(area.right().saturating_sub(2)), area.y + 1);
+    }
+
+    fn draw_reading<B: Backend>(&self, f: &mut Frame<B>, path: &Path) {
+        let area = centered_rect(60, 25, f.size());
+        f.render_widget(Clear, area);
+
+        let text = vec![
+            Spans::from(format!("Reading {}…", path.display())),
+            Spans::from(""),
+            Spans::from(Span::styled(
+                command::exit_popup(&self.key_config).name,
+                Style::default().fg(Color::Gray),
+            )),
+        ];
+        let body = Paragraph::new(text).block(popup_block("Import BibTeX".to_string()));
+        f.render_widget(body, area);
+    }
+
+    fn draw_review<B: Backend>(&mut self, f: &mut Frame<B>, path: &Path, import: &BibImport) {
+        let area = centered_rect(80, 70, f.size());
+        f.render_widget(Clear, area);
+
+        let title = format!(
+            "{}: {} matched, {} unmatched",
+            path.display(),
+            import.matched.len(),
+            import.unmatched.len()
+        );
+        let block = popup_block(title);
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Length(1)].as_ref())
+            .split(inner);
+
+        // unmatched entries first, they are the ones to review
+        let unmatched = import.unmatched.iter().map(|entry| {
+            ListItem::new(Spans::from(vec![
+                Span::styled("✗ ", Style::default().fg(Color::LightRed)),
+                Span::raw(format!(
+                    "{} \"{}\"",
+                    entry.citekey,
+                    entry.to_citation().title
+                )),
+            ]))
+        });
+        let matched = import.matched.iter().map(|matched| {
+            let file_name = matched
+                .path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            ListItem::new(Spans::from(vec![
+                Span::styled("✓ ", Style::default().fg(Color::LightGreen)),
+                Span::raw(format!(
+                    "{} → {} ({})",
+                    matched.entry.citekey, file_name, matched.reason
+                )),
+            ]))
+        });
+        let items = unmatched.chain(matched).collect::<Vec<_>>();
+        let len = items.len();
+
+        self.list_state.select(Some(self.selection));
+        let list = List::new(items).highlight_style(Style::default().add_modifier(Modifier::BOLD));
+        f.render_stateful_widget(list, chunks[0], &mut self.list_state);
+
+        self.scroll
+            .update(self.selection, len, usize::from(chunks[0].height));
+        self.scroll.draw(f, area);
+
+        f.render_widget(
+            Paragraph::new(Span::styled(
+                command::apply_bib(&self.key_config).name,
+                Style::default().fg(Color::Gray),
+            )),
+            chunks[1],
+        );
+    }
+}
+
+fn popup_block(title: String) -> Block<'static> {
+    Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Plain)
+        .border_style(Style::default().fg(Color::Magenta))
+        .title(title)
+}
+
+impl DrawableComponent for BibImportPopup {
+    fn draw<B: Backend>(
+        &mut self,
+        f: &mut Frame<B>,
+        _area: Rect,
+        _focused: bool,
+    ) -> anyhow::Result<()> {
+        // the stage is moved out while drawing as the review borrows the list state
+        match std::mem::replace(&mut self.stage, Stage::Closed) {
+            Stage::Closed => {}
+            Stage::Path(path) => {
+                self.draw_path(f, &path);
+                self.stage = Stage::Path(path);
+            }
+            Stage::Reading(path) => {
+                self.draw_reading(f, &path);
+                self.stage = Stage::Reading(path);
+            }
+            Stage::Review { path, import } => {
+                self.draw_review(f, &path, &import);
+                self.stage = Stage::Review { path, import };
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Component for BibImportPopup {
+    fn commands(&self, out: &mut Vec<CommandInfo>, _force_all: bool) -> CommandBlocking {
+        match self.stage {
+            Stage::Closed => return CommandBlocking::PassingOn,
+            Stage::Path(_) => out.push(CommandInfo::new(command::match_bib(&self.key_config))),
+            Stage::Reading(_) => out.push(CommandInfo::new(command::exit_popup(&self.key_config))),
+            Stage::Review { .. } => {
+                out.push(CommandInfo::new(command::scroll(&self.key_config)));
+                out.push(CommandInfo::new(command::apply_bib(&self.key_config)));
+            }
+        }
+        CommandBlocking::Blocking
+    }
+
+    fn event(&mut self, key: Key) -> anyhow::Result<EventState> {
+        if key == self.key_config.exit_popup && self.is_visible() {
+            self.stage = Stage::Closed;
+            return Ok(EventState::Consumed);
+        }
+
+        match &mut self.stage {
+            Stage::Closed => return Ok(EventState::NotConsumed),
+            Stage::Path(path) => {
+                if key == self.key_config.enter {
+                    if !path.is_empty() {
+                        self.request = Some(BibImportRequest::Match(PathBuf::from(path.trim())));
+                    }
+                } else if let Key::Char(c) = key {
+                    path.push(c);
+                } else if key == Key::Backspace {
+                    path.pop();
+                }
+            }
+            Stage::Reading(_) => {}
+            Stage::Review { import, .. } => {
+                let len = import.matched.len() + import.unmatched.len();
+                if key == self.key_config.enter {
+                    let import = std::mem::take(import);
+                    self.stage = Stage::Closed;
+                    self.request = Some(BibImportRequest::Apply(import));
+                } else if key == self.key_config.scroll_down {
+                    self.selection = (self.selection + 1).min(len.saturating_sub(1));
+                } else if key == self.key_config.scroll_up {
+                    self.selection = self.selection.saturating_sub(1);
+                }
+            }
+        }
+        Ok(EventState::Consumed)
+    }
+
+    fn focused(&self) -> bool {
+        self.is_visible()
+    }
+}
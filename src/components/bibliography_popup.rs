@@ -0,0 +1,140 @@
+use tui::backend::Backend;
+use tui::layout::Rect;
+use tui::style::{Color, Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, BorderType, Borders, Clear, Paragraph};
+use tui::Frame;
+
+use super::{
+    command::{self, CommandInfo},
+    utils::popup::centered_rect,
+    CommandBlocking, Component, DrawableComponent, EventState,
+};
+use crate::{domain::citation::BibliographyFormat, inputs::key::Key, key_config::KeyConfig};
+
+/// A bibliography export confirmed by the user, written by the app
+pub struct BibliographyRequest {
+    pub format: BibliographyFormat,
+}
+
+/// Chooses the format the bibliography of the marked or listed files is exported in
+pub struct BibliographyPopup {
+    /// the number of files to export, `None` while closed
+    count: Option<usize>,
+    /// kept between exports
+    selection: usize,
+    request: Option<BibliographyRequest>,
+    key_config: KeyConfig,
+}
+
+impl BibliographyPopup {
+    pub fn new(key_config: KeyConfig) -> Self {
+        Self {
+            count: None,
+            selection: 0,
+            request: None,
+            key_config,
+        }
+    }
+
+    pub fn open(&mut self, count: usize) {
+        self.count = Some(count);
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.count.is_some()
+    }
+
+    /// the export confirmed by the last event, if any
+    pub fn take_request(&mut self) -> Option<BibliographyRequest> {
+        self.request.take()
+    }
+}
+
+impl DrawableComponent for BibliographyPopup {
+    fn draw<B: Backend>(
+        &mut self,
+        f: &mut Frame<B>,
+        _area: Rect,
+        _focused: bool,
+    ) -> anyhow::Result<()> {
+        let count = match self.count {
+            Some(count) => count,
+            None => return Ok(()),
+        };
+
+        let area = centered_rect(50, 25, f.size());
+        f.render_widget(Clear, area);
+
+        let files = if count == 1 { "file" } else { "files" };
+        let mut text = vec![Spans::from(format!(
+            "Export the citations of {} {} as:",
+            count, files
+        ))];
+        text.extend(
+            BibliographyFormat::ALL
+                .iter()
+                .enumerate()
+                .map(|(i, format)| {
+                    let label = format!("  {} (.{})", format.name(), format.extension());
+                    match i == self.selection {
+                        true => Spans::from(Span::styled(
+                            label,
+                            Style::default().add_modifier(Modifier::BOLD | Modifier::REVERSED),
+                        )),
+                        false => Spans::from(label),
+                    }
+                }),
+        );
+        text.push(Spans::from(""));
+        text.push(Spans::from(Span::styled(
+            command::choose_format(&self.key_config).name,
+            Style::default().fg(Color::Gray),
+        )));
+
+        let body = Paragraph::new(text).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Plain)
+                .border_style(Style::default().fg(Color::Magenta))
+                .title("Export bibliography"),
+        );
+        f.render_widget(body, area);
+        Ok(())
+    }
+}
+
+impl Component for BibliographyPopup {
+    fn commands(&self, out: &mut Vec<CommandInfo>, _force_all: bool) -> CommandBlocking {
+        if !self.is_visible() {
+            return CommandBlocking::PassingOn;
+        }
+        out.push(CommandInfo::new(command::choose_format(&self.key_config)));
+        CommandBlocking::Blocking
+    }
+
+    fn event(&mut self, key: Key) -> anyhow::Result<EventState> {
+        if !self.is_visible() {
+            return Ok(EventState::NotConsumed);
+        }
+
+        let last = BibliographyFormat::ALL.len() - 1;
+        if key == self.key_config.exit_popup {
+            self.count = None;
+        } else if key == self.key_config.enter {
+            self.count = None;
+            self.request = Some(BibliographyRequest {
+                format: BibliographyFormat::ALL[self.selection],
+            });
+        } else if key == self.key_config.scroll_down || key == self.key_config.focus_down {
+            self.selection = (self.selection + 1).min(last);
+        } else if key == self.key_config.scroll_up || key == self.key_config.focus_up {
+            self.selection = self.selection.saturating_sub(1);
+        }
+        Ok(EventState::Consumed)
+    }
+
+    fn focused(&self) -> bool {
+        self.is_visible()
+    }
+}
@@ -0,0 +1,253 @@
+use std::path::{Component as PathComponent, Path, PathBuf};
+
+use tui::backend::Backend;
+use tui::layout::Rect;
+use tui::style::{Color, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, BorderType, Borders, Clear, Paragraph};
+use tui::Frame;
+
+use super::{
+    command::{self, CommandInfo},
+    utils::popup::centered_rect,
+    CommandBlocking, Component, DrawableComponent, EventState,
+};
+use crate::{inputs::key::Key, key_config::KeyConfig};
+
+/// the number of file names listed in the summary, the others are counted
+const LISTED_FILES: usize = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BulkAction {
+    Import,
+    Delete,
+    Tag,
+    Move,
+}
+
+impl BulkAction {
+    fn verb(self) -> &'static str {
+        match self {
+            Self::Import => "Import",
+            Self::Delete => "Trash",
+            Self::Tag => "Tag",
+            Self::Move => "Move",
+        }
+    }
+
+    /// the label of the input line, `None` if the action takes no input
+    fn input_label(self) -> Option<&'static str> {
+        match self {
+            Self::Tag => Some("Tag: "),
+            Self::Move => Some("To: "),
+            Self::Import | Self::Delete => None,
+        }
+    }
+
+    fn note(self) -> &'static str {
+        match self {
+            Self::Import => "The files are moved into the managed directory under their names",
+            Self::Delete => "The files are moved to the trash, where they can be restored from",
+            Self::Tag => "A leading - removes the tag instead",
+            Self::Move => "The directory is inside the directory of the list",
+        }
+    }
+}
+
+/// An operation on several files confirmed by the user, run by the app
+pub struct BulkRequest {
+    pub action: BulkAction,
+    pub paths: Vec<PathBuf>,
+    /// the tag or the directory typed by the user
+    pub input: String,
+}
+
+/// Summarizes an operation on the marked files and asks for its confirmation
+pub struct BulkPopup {
+    /// the action and the files it works on, `None` while closed
+    pending: Option<(BulkAction, Vec<PathBuf>)>,
+    input: String,
+    message: Option<String>,
+    request: Option<BulkRequest>,
+    key_config: KeyConfig,
+}
+
+impl BulkPopup {
+    pub fn new(key_config: KeyConfig) -> Self {
+        Self {
+            pending: None,
+            input: String::new(),
+            message: None,
+            request: None,
+            key_config,
+        }
+    }
+
+    pub fn open(&mut self, action: BulkAction, paths: Vec<PathBuf>) {
+        self.pending = Some((action, paths));
+        self.input.clear();
+        self.message = None;
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// the operation confirmed by the last event, if any
+    pub fn take_request(&mut self) -> Option<BulkRequest> {
+        self.request.take()
+    }
+
+    fn confirm(&mut self) {
+        let action = match &self.pending {
+            Some((action, _)) => *action,
+            None => return,
+        };
+        let input = self.input.trim();
+        let missing = match action {
+            BulkAction::Tag if input.trim_start_matches('-').is_empty() => {
+                Some("the tag cannot be empty")
+            }
+            BulkAction::Move if input.is_empty() => Some("the directory cannot be empty"),
+            BulkAction::Move if !is_inside(Path::new(input)) => {
+                Some("the directory must be inside the directory of the list")
+            }
+            _ => None,
+        };
+        if let Some(missing) = missing {
+            self.message = Some(missing.to_string());
+            return;
+        }
+        let input = input.to_string();
+        if let Some((action, paths)) = self.pending.take() {
+            self.request = Some(BulkRequest {
+                action,
+                paths,
+                input,
+            });
+        }
+    }
+}
+
+/// whether `dir` stays inside the directory it is relative to, it is not absolute and
+/// does not go up with `..`
+fn is_inside(dir: &Path) -> bool {
+    !dir.starts_with("~")
+        && dir
+            .components()
+            .all(|part| matches!(part, PathComponent::Normal(_) | PathComponent::CurDir))
+}
+
+impl DrawableComponent for BulkPopup {
+    fn draw<B: Backend>(
+        &mut self,
+        f: &mut Frame<B>,
+        _area: Rect,
+        _focused: bool,
+    ) -> anyhow::Result<()> {
+        let (action, paths) = match &self.pending {
+            Some((action, paths)) => (*action, paths),
+            None => return Ok(()),
+        };
+
+        let area = centered_rect(60, 50, f.size());
+        f.render_widget(Clear, area);
+
+        let dim = Style::default().fg(Color::DarkGray);
+        let mut text = paths
+            .iter()
+            .take(LISTED_FILES)
+            .map(|path| {
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                Spans::from(Span::raw(format!("  {}", name)))
+            })
+            .collect::<Vec<_>>();
+        if paths.len() > LISTED_FILES {
+            text.push(Spans::from(Span::styled(
+                format!("  … and {} more", paths.len() - LISTED_FILES),
+                dim,
+            )));
+        }
+        text.push(Spans::from(""));
+        let input_row = text.len();
+        if let Some(label) = action.input_label() {
+            text.push(Spans::from(vec![
+                Span::styled(label, Style::default().fg(Color::LightCyan)),
+                Span::raw(self.input.clone()),
+            ]));
+        }
+        let note_style = match action {
+            BulkAction::Delete => Style::default().fg(Color::LightRed),
+            _ => dim,
+        };
+        text.push(Spans::from(Span::styled(action.note(), note_style)));
+        if let Some(message) = &self.message {
+            text.push(Spans::from(Span::styled(
+                message.clone(),
+                Style::default().fg(Color::LightRed),
+            )));
+        }
+        text.push(Spans::from(""));
+        text.push(Spans::from(Span::styled(
+            command::confirm(&self.key_config).name,
+            Style::default().fg(Color::Gray),
+        )));
+
+        let files = if paths.len() == 1 { "file" } else { "files" };
+        let title = format!("{} {} {}", action.verb(), paths.len(), files);
+        let body = Paragraph::new(text).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Plain)
+                .border_style(Style::default().fg(Color::Magenta))
+                .title(title),
+        );
+        f.render_widget(body, area);
+
+        if let Some(label) = action.input_label() {
+            let cursor_x = area.x + 1 + (label.len() + self.input.chars().count()) as u16;
+            f.set_cursor(
+                cursor_x.min(area.right().saturating_sub(2)),
+                area.y + 1 + input_row as u16,
+            );
+        }
+        Ok(())
+    }
+}
+
+impl Component for BulkPopup {
+    fn commands(&self, out: &mut Vec<CommandInfo>, _force_all: bool) -> CommandBlocking {
+        if !self.is_visible() {
+            return CommandBlocking::PassingOn;
+        }
+        out.push(CommandInfo::new(command::confirm(&self.key_config)));
+        CommandBlocking::Blocking
+    }
+
+    fn event(&mut self, key: Key) -> anyhow::Result<EventState> {
+        let action = match &self.pending {
+            Some((action, _)) => *action,
+            None => return Ok(EventState::NotConsumed),
+        };
+
+        if key == self.key_config.exit_popup {
+            self.pending = None;
+        } else if key == self.key_config.enter {
+            self.confirm();
+        } else if action.input_label().is_some() {
+            match key {
+                Key::Char(c) => self.input.push(c),
+                Key::Backspace => {
+                    self.input.pop();
+                }
+                _ => return Ok(EventState::Consumed),
+            }
+            self.message = None;
+        }
+        Ok(EventState::Consumed)
+    }
+
+    fn focused(&self) -> bool {
+        self.is_visible()
+    }
+}
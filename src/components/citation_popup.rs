@@ -0,0 +1,231 @@
+use std::path::PathBuf;
+use tui::backend::Backend;
+use tui::layout::Rect;
+use tui::style::{Color, Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, BorderType, Borders, Clear, Paragraph};
+use tui::Frame;
+
+use super::{
+    command::{self, CommandInfo},
+    utils::popup::centered_rect,
+    CommandBlocking, Component, DrawableComponent, EventState,
+};
+use crate::{domain::citation::Citation, inputs::key::Key, key_config::KeyConfig};
+
+const LABELS: [&str; 6] = ["Citekey", "Authors", "Title", "Year", "Venue", "DOI"];
+const LABEL_WIDTH: usize = 9;
+/// separates the authors in the authors field, as in BibTeX
+const AUTHOR_SEPARATOR: &str = " and ";
+
+/// A citation edited by the user, saved by the app
+pub struct CitationRequest {
+    pub path: PathBuf,
+    pub citation: Citation,
+}
+
+/// Edits the citation record of a managed pdf file
+pub struct CitationPopup {
+    path: Option<PathBuf>,
+    /// values of the fields, in the order of `LABELS`
+    fields: [String; 6],
+    selection: usize,
+    message: Option<String>,
+    request: Option<CitationRequest>,
+    key_config: KeyConfig,
+}
+
+impl CitationPopup {
+    pub fn new(key_config: KeyConfig) -> Self {
+        Self {
+            path: None,
+            fields: Default::default(),
+            selection: 0,
+            message: None,
+            request: None,
+            key_config,
+        }
+    }
+
+    pub fn open(&mut self, path: PathBuf, citation: &Citation) -> anyhow::Result<()> {
+        self.path = Some(path);
+        self.fields = [
+            citation.citekey.clone(),
+            citation.authors.join(AUTHOR_SEPARATOR),
+            citation.title.clone(),
+            citation
+                .year
+                .map(|year| year.to_string())
+                .unwrap_or_default(),
+            citation.venue.clone(),
+            citation.doi.clone(),
+        ];
+        self.selection = 0;
+        self.message = None;
+        Ok(())
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.path.is_some()
+    }
+
+    /// the citation saved by the last event, if any
+    pub fn take_request(&mut self) -> Option<CitationRequest> {
+        self.request.take()
+    }
+
+    fn close(&mut self) {
+        self.path = None;
+    }
+
+    /// the citation in the fields, or why it is invalid
+    fn citation(&self) -> Result<Citation, String> {
+        let [citekey, authors, title, year, venue, doi] = &self.fields;
+        let year = match year.trim() {
+            "" => None,
+            year => Some(
+                year.parse::<i32>()
+                    .map_err(|_| format!("invalid year \"{}\"", year))?,
+            ),
+        };
+        if citekey.contains(|c: char| c.is_whitespace() || c == ',' || c == '{' || c == '}') {
+            return Err("the citekey cannot contain spaces, commas or braces".to_string());
+        }
+        Ok(Citation {
+            citekey: citekey.trim().to_string(),
+            authors: authors
+                .split(AUTHOR_SEPARATOR)
+                .map(str::trim)
+                .filter(|author| !author.is_empty())
+                .map(String::from)
+                .collect(),
+            title: title.trim().to_string(),
+            year,
+            venue: venue.trim().to_string(),
+            doi: doi.trim().to_string(),
+        })
+    }
+}
+
+impl DrawableComponent for CitationPopup {
+    fn draw<B: Backend>(
+        &mut self,
+        f: &mut Frame<B>,
+        _area: Rect,
+        _focused: bool,
+    ) -> anyhow::Result<()> {
+        if !self.is_visible() {
+            return Ok(());
+        }
+
+        let area = centered_rect(70, 40, f.size());
+        f.render_widget(Clear, area);
+
+        let label_style = Style::default().fg(Color::LightCyan);
+        let mut text = LABELS
+            .iter()
+            .zip(&self.fields)
+            .enumerate()
+            .map(|(idx, (label, value))| {
+                let label = format!("{:width$}", format!("{}:", label), width = LABEL_WIDTH);
+                let label = if idx == self.selection {
+                    Span::styled(label, label_style.add_modifier(Modifier::BOLD))
+                } else {
+                    Span::styled(label, label_style)
+                };
+                let value = if idx == 0 && value.is_empty() {
+                    // show which citekey is generated when none is set
+                    let generated = self
+                        .citation()
+                        .map(|citation| citation.generated_citekey())
+                        .unwrap_or_default();
+                    Span::styled(
+                        format!("(auto: {})", generated),
+                        Style::default().fg(Color::DarkGray),
+                    )
+                } else {
+                    Span::raw(value.clone())
+                };
+                Spans::from(vec![label, value])
+            })
+            .collect::<Vec<_>>();
+        text.push(Spans::from(""));
+        if let Some(message) = &self.message {
+            text.push(Spans::from(Span::styled(
+                message.clone(),
+                Style::default().fg(Color::LightRed),
+            )));
+        }
+        text.push(Spans::from(Span::styled(
+            format!(
+                "{} | {}",
+                command::save_citation(&self.key_config).name,
+                command::select_field(&self.key_config).name
+            ),
+            Style::default().fg(Color::Gray),
+        )));
+
+        let body = Paragraph::new(text).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Plain)
+                .border_style(Style::default().fg(Color::Magenta))
+                .title("Citation"),
+        );
+        f.render_widget(body, area);
+
+        let value = &self.fields[self.selection];
+        let cursor_x = area.x + 1 + LABEL_WIDTH as u16 + value.chars().count() as u16;
+        f.set_cursor(
+            cursor_x.min(area.right().saturating_sub(2)),
+            area.y + 1 + self.selection as u16,
+        );
+
+        Ok(())
+    }
+}
+
+impl Component for CitationPopup {
+    fn commands(&self, out: &mut Vec<CommandInfo>, _force_all: bool) -> CommandBlocking {
+        if !self.is_visible() {
+            return CommandBlocking::PassingOn;
+        }
+        out.push(CommandInfo::new(command::save_citation(&self.key_config)));
+        out.push(CommandInfo::new(command::select_field(&self.key_config)));
+        CommandBlocking::Blocking
+    }
+
+    fn event(&mut self, key: Key) -> anyhow::Result<EventState> {
+        if !self.is_visible() {
+            return Ok(EventState::NotConsumed);
+        }
+
+        if key == self.key_config.exit_popup {
+            self.close();
+        } else if key == self.key_config.enter {
+            match self.citation() {
+                Ok(citation) => {
+                    if let Some(path) = self.path.take() {
+                        self.request = Some(CitationRequest { path, citation });
+                    }
+                }
+                Err(message) => self.message = Some(message),
+            }
+        } else if key == self.key_config.focus_down || key == Key::Tab {
+            self.selection = (self.selection + 1) % LABELS.len();
+        } else if key == self.key_config.focus_up {
+            self.selection = (self.selection + LABELS.len() - 1) % LABELS.len();
+        } else if let Key::Char(c) = key {
+            self.fields[self.selection].push(c);
+            self.message = None;
+        } else if key == Key::Backspace {
+            self.fields[self.selection].pop();
+            self.message = None;
+        }
+        Ok(EventState::Consumed)
+    }
+
+    fn focused(&self) -> bool {
+        self.is_visible()
+    }
+}
@@ -0,0 +1,337 @@
+use std::path::PathBuf;
+use tui::backend::Backend;
+use tui::layout::Rect;
+use tui::style::{Color, Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, BorderType, Borders, Clear, List, ListItem, ListState, Paragraph};
+use tui::Frame;
+
+use super::{
+    command::{self, CommandInfo},
+    utils::popup::centered_rect,
+    CommandBlocking, Component, DrawableComponent, EventState,
+};
+use crate::{domain::collection::CollectionPath, inputs::key::Key, key_config::KeyConfig};
+
+/// A change of the collections chosen by the user, applied by the app
+pub enum CollectionRequest {
+    /// adds the file to the collection, or removes it if it is already in it
+    Toggle {
+        path: PathBuf,
+        collection: CollectionPath,
+    },
+    /// creates the collection and adds the file to it, if there is one
+    Create {
+        collection: CollectionPath,
+        path: Option<PathBuf>,
+    },
+}
+
+enum Mode {
+    Closed,
+    /// the collections with whether the file is in them
+    Pick {
+        path: PathBuf,
+        file_name: String,
+        collections: Vec<(CollectionPath, bool)>,
+    },
+    /// the name of a new collection nested in `parent`
+    Name {
+        parent: Option<CollectionPath>,
+        path: Option<PathBuf>,
+        name: String,
+    },
+}
+
+/// Adds a managed pdf file to collections, or creates a collection
+pub struct CollectionPopup {
+    mode: Mode,
+    selection: usize,
+    list_state: ListState,
+    message: Option<String>,
+    request: Option<CollectionRequest>,
+    key_config: KeyConfig,
+}
+
+impl CollectionPopup {
+    pub fn new(key_config: KeyConfig) -> Self {
+        Self {
+            mode: Mode::Closed,
+            selection: 0,
+            list_state: ListState::default(),
+            message: None,
+            request: None,
+            key_config,
+        }
+    }
+
+    /// opens the picker of the collections `path` is in or not, in tree order
+    pub fn pick(
+        &mut self,
+        path: PathBuf,
+        file_name: String,
+        collections: Vec<(CollectionPath, bool)>,
+    ) {
+        self.mode = Mode::Pick {
+            path,
+            file_name,
+            collections,
+        };
+        self.selection = 0;
+        self.message = None;
+    }
+
+    /// asks the name of a new collection nested in `parent`, or a top level one
+    pub fn create(&mut self, parent: Option<CollectionPath>) {
+        self.mode = Mode::Name {
+            parent,
+            path: None,
+            name: String::new(),
+        };
+        self.message = None;
+    }
+
+    pub fn is_visible(&self) -> bool {
+        !matches!(self.mode, Mode::Closed)
+    }
+
+    /// the change made by the last event, if any
+    pub fn take_request(&mut self) -> Option<CollectionRequest> {
+        self.request.take()
+    }
+
+    fn close(&mut self) {
+        self.mode = Mode::Closed;
+    }
+
+    fn draw_pick<B: Backend>(
+        &mut self,
+        f: &mut Frame<B>,
+        file_name: &str,
+        collections: &[(CollectionPath, bool)],
+    ) {
+        let area = centered_rect(50, 50, f.size());
+        f.render_widget(Clear, area);
+        let block = popup_block(format!("Collections of {}", file_name));
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        let items = if collections.is_empty() {
+            vec![ListItem::new(Span::styled(
+                "no collection yet",
+                Style::default().fg(Color::DarkGray),
+            ))]
+        } else {
+            collections
+                .iter()
+                .map(|(collection, checked)| {
+                    ListItem::new(format!(
+                        "{}[{}] {}",
+                        "  ".repeat(collection.depth()),
+                        if *checked { 'x' } else { ' ' },
+                        collection.name()
+                    ))
+                })
+                .collect()
+        };
+        let list_area = Rect {
+            height: inner.height.saturating_sub(1),
+            ..inner
+        };
+        self.list_state.select(Some(self.selection));
+        let list = List::new(items)
+            .highlight_style(Style::default().add_modifier(Modifier::BOLD | Modifier::REVERSED));
+        f.render_stateful_widget(list, list_area, &mut self.list_state);
+
+        let help = Paragraph::new(Span::styled(
+            format!(
+                "{} | {} | {}",
+                command::toggle_collection(&self.key_config).name,
+                command::new_collection(&self.key_config).name,
+                command::exit_popup(&self.key_config).name
+            ),
+            Style::default().fg(Color::Gray),
+        ));
+        f.render_widget(
+            help,
+            Rect {
+                y: inner.bottom().saturating_sub(1),
+                height: 1,
+                ..inner
+            },
+        );
+    }
+
+    fn draw_name<B: Backend>(&self, f: &mut Frame<B>, parent: Option<&CollectionPath>, name: &str) {
+        let area = centered_rect(50, 20, f.size());
+        f.render_widget(Clear, area);
+
+        let prefix = parent.map_or(String::new(), |parent| {
+            format!("{}{}", parent, CollectionPath::SEPARATOR)
+        });
+        let mut text = vec![
+            Spans::from(vec![
+                Span::styled(prefix.clone(), Style::default().fg(Color::DarkGray)),
+                Span::raw(name.to_string()),
+            ]),
+            Spans::from(""),
+        ];
+        if let Some(message) = &self.message {
+            text.push(Spans::from(Span::styled(
+                message.clone(),
+                Style::default().fg(Color::LightRed),
+            )));
+        }
+        text.push(Spans::from(Span::styled(
+            format!(
+                "nest with \"{}\" | {}",
+                CollectionPath::SEPARATOR.trim(),
+                command::save_collection(&self.key_config).name
+            ),
+            Style::default().fg(Color::Gray),
+        )));
+        f.render_widget(
+            Paragraph::new(text).block(popup_block("New collection".to_string())),
+            area,
+        );
+
+        let cursor_x = area.x + 1 + (prefix.chars().count() + name.chars().count()) as u16;
+        f.set_cursor(cursor_x.min(area.right().saturating_sub(2)), area.y + 1);
+    }
+
+    fn pick_event(&mut self, key: Key) {
+        let (path, collections) = match &mut self.mode {
+            Mode::Pick {
+                path, collections, ..
+            } => (path, collections),
+            _ => return,
+        };
+        if key == self.key_config.scroll_down {
+            self.selection = (self.selection + 1).min(collections.len().saturating_sub(1));
+        } else if key == self.key_config.scroll_up {
+            self.selection = self.selection.saturating_sub(1);
+        } else if key == self.key_config.enter || key == Key::Char(' ') {
+            if let Some((collection, checked)) = collections.get_mut(self.selection) {
+                *checked = !*checked;
+                self.request = Some(CollectionRequest::Toggle {
+                    path: path.clone(),
+                    collection: collection.clone(),
+                });
+            }
+        } else if key == self.key_config.new_collection {
+            self.mode = Mode::Name {
+                parent: None,
+                path: Some(path.clone()),
+                name: String::new(),
+            };
+        }
+    }
+
+    fn name_event(&mut self, key: Key) {
+        let (parent, path, name) = match &mut self.mode {
+            Mode::Name { parent, path, name } => (parent, path, name),
+            _ => return,
+        };
+        if key == self.key_config.enter {
+            let relative = match CollectionPath::parse(name) {
+                Some(relative) => relative,
+                None => {
+                    self.message = Some("a collection name cannot be empty".to_string());
+                    return;
+                }
+            };
+            let collection = match parent {
+                Some(parent) => parent.join(&relative),
+                None => relative,
+            };
+            self.request = Some(CollectionRequest::Create {
+                collection,
+                path: path.take(),
+            });
+            self.close();
+        } else if let Key::Char(c) = key {
+            name.push(c);
+            self.message = None;
+        } else if key == Key::Backspace {
+            name.pop();
+            self.message = None;
+        }
+    }
+}
+
+fn popup_block(title: String) -> Block<'static> {
+    Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Plain)
+        .border_style(Style::default().fg(Color::Magenta))
+        .title(title)
+}
+
+impl DrawableComponent for CollectionPopup {
+    fn draw<B: Backend>(
+        &mut self,
+        f: &mut Frame<B>,
+        _area: Rect,
+        _focused: bool,
+    ) -> anyhow::Result<()> {
+        // the mode is moved out while drawing as the picker borrows the list state
+        match std::mem::replace(&mut self.mode, Mode::Closed) {
+            Mode::Closed => {}
+            Mode::Pick {
+                path,
+                file_name,
+                collections,
+            } => {
+                self.draw_pick(f, &file_name, &collections);
+                self.mode = Mode::Pick {
+                    path,
+                    file_name,
+                    collections,
+                };
+            }
+            Mode::Name { parent, path, name } => {
+                self.draw_name(f, parent.as_ref(), &name);
+                self.mode = Mode::Name { parent, path, name };
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Component for CollectionPopup {
+    fn commands(&self, out: &mut Vec<CommandInfo>, _force_all: bool) -> CommandBlocking {
+        match self.mode {
+            Mode::Closed => return CommandBlocking::PassingOn,
+            Mode::Pick { .. } => {
+                out.push(CommandInfo::new(command::scroll(&self.key_config)));
+                out.push(CommandInfo::new(command::toggle_collection(
+                    &self.key_config,
+                )));
+                out.push(CommandInfo::new(command::new_collection(&self.key_config)));
+                out.push(CommandInfo::new(command::exit_popup(&self.key_config)));
+            }
+            Mode::Name { .. } => {
+                out.push(CommandInfo::new(command::save_collection(&self.key_config)));
+            }
+        }
+        CommandBlocking::Blocking
+    }
+
+    fn event(&mut self, key: Key) -> anyhow::Result<EventState> {
+        if !self.is_visible() {
+            return Ok(EventState::NotConsumed);
+        }
+        if key == self.key_config.exit_popup {
+            self.close();
+        } else if matches!(self.mode, Mode::Pick { .. }) {
+            self.pick_event(key);
+        } else {
+            self.name_event(key);
+        }
+        Ok(EventState::Consumed)
+    }
+
+    fn focused(&self) -> bool {
+        self.is_visible()
+    }
+}
@@ -1,3 +1,4 @@
+use crate::inputs::key::Key;
 use crate::key_config::KeyConfig;
 
 #[derive(Clone, PartialEq, PartialOrd, Ord, Eq)]
@@ -12,6 +13,7 @@ impl CommandText {
     }
 }
 
+#[derive(Clone)]
 pub struct CommandInfo {
     pub text: CommandText,
 }
@@ -22,12 +24,32 @@ impl CommandInfo {
     }
 }
 
-static CMD_GROUP_GENERAL: &str = "-- General --";
-static CMD_GROUP_INBOX: &str = "-- Inbox --";
+pub static CMD_GROUP_GENERAL: &str = "-- General --";
+pub static CMD_GROUP_INBOX: &str = "-- Inbox --";
+pub static CMD_GROUP_HELP: &str = "-- Help --";
+pub static CMD_GROUP_LOG: &str = "-- Log --";
 
 pub fn scroll(key: &KeyConfig) -> CommandText {
     CommandText::new(
-        format!("Scroll up/down [{},{}]", "up", "down"),
+        format!("Scroll up/down [{},{}]", key.scroll_up, key.scroll_down),
+        CMD_GROUP_GENERAL,
+    )
+}
+
+pub fn help(key: &KeyConfig) -> CommandText {
+    CommandText::new(format!("Help [{}]", key.open_help), CMD_GROUP_GENERAL)
+}
+
+pub fn quit(key: &KeyConfig) -> CommandText {
+    CommandText::new(
+        format!("Quit [{},{}]", key.quit, key.exit),
+        CMD_GROUP_GENERAL,
+    )
+}
+
+pub fn exit_popup(key: &KeyConfig) -> CommandText {
+    CommandText::new(
+        format!("Close popup [{}]", key.exit_popup),
         CMD_GROUP_GENERAL,
     )
 }
@@ -42,10 +64,398 @@ pub fn move_focus(key: &KeyConfig) -> CommandText {
     )
 }
 
+pub fn reload(key: &KeyConfig) -> CommandText {
+    CommandText::new(format!("Reload lists [{}]", key.enter), CMD_GROUP_INBOX)
+}
+
+pub fn sort(key: &KeyConfig) -> CommandText {
+    CommandText::new(format!("Change sort order [{}]", key.sort), CMD_GROUP_INBOX)
+}
+
+pub fn search(key: &KeyConfig) -> CommandText {
+    CommandText::new(format!("Search [{}]", key.search), CMD_GROUP_INBOX)
+}
+
+pub fn submit_search(key: &KeyConfig) -> CommandText {
+    CommandText::new(
+        format!("Apply/clear filter [{},{}]", key.enter, key.exit_popup),
+        CMD_GROUP_INBOX,
+    )
+}
+
+pub fn import_pdf(key: &KeyConfig) -> CommandText {
+    CommandText::new(format!("Import [{}]", key.import), CMD_GROUP_INBOX)
+}
+
+pub fn export_catalogue(key: &KeyConfig) -> CommandText {
+    CommandText::new(format!("Export library [{}]", key.export), CMD_GROUP_INBOX)
+}
+
+pub fn export_bibliography(key: &KeyConfig) -> CommandText {
+    CommandText::new(
+        format!(
+            "Export bibliography of marked or listed files [{}]",
+            key.export_bibliography
+        ),
+        CMD_GROUP_INBOX,
+    )
+}
+
+pub fn import_all(key: &KeyConfig) -> CommandText {
+    CommandText::new(
+        format!("Import all by the rules [{}]", key.import_all),
+        CMD_GROUP_INBOX,
+    )
+}
+
+pub fn execute_batch_import(key: &KeyConfig) -> CommandText {
+    CommandText::new(
+        format!("Import/cancel [{},{}]", key.enter, key.exit_popup),
+        CMD_GROUP_INBOX,
+    )
+}
+
+pub fn import_bib(key: &KeyConfig) -> CommandText {
+    CommandText::new(
+        format!("Import BibTeX [{}]", key.import_bib),
+        CMD_GROUP_INBOX,
+    )
+}
+
+pub fn match_bib(key: &KeyConfig) -> CommandText {
+    CommandText::new(
+        format!("Match entries/cancel [{},{}]", key.enter, key.exit_popup),
+        CMD_GROUP_INBOX,
+    )
+}
+
+pub fn apply_bib(key: &KeyConfig) -> CommandText {
+    CommandText::new(
+        format!(
+            "Attach matched entries/cancel [{},{}]",
+            key.enter, key.exit_popup
+        ),
+        CMD_GROUP_INBOX,
+    )
+}
+
+pub fn edit_citation(key: &KeyConfig) -> CommandText {
+    CommandText::new(
+        format!("Edit citation [{}]", key.edit_citation),
+        CMD_GROUP_INBOX,
+    )
+}
+
+pub fn fetch_metadata(key: &KeyConfig) -> CommandText {
+    CommandText::new(
+        format!("Fetch metadata [{}]", key.fetch_metadata),
+        CMD_GROUP_INBOX,
+    )
+}
+
+pub fn apply_metadata(key: &KeyConfig) -> CommandText {
+    CommandText::new(
+        format!("Apply/discard [{},{}]", key.enter, key.exit_popup),
+        CMD_GROUP_INBOX,
+    )
+}
+
+pub fn save_citation(key: &KeyConfig) -> CommandText {
+    CommandText::new(
+        format!("Save/cancel [{},{}]", key.enter, key.exit_popup),
+        CMD_GROUP_INBOX,
+    )
+}
+
+pub fn select_field(key: &KeyConfig) -> CommandText {
+    CommandText::new(
+        format!("Next/previous field [{},{}]", key.focus_down, key.focus_up),
+        CMD_GROUP_INBOX,
+    )
+}
+
+pub fn use_suggested_name(_key: &KeyConfig) -> CommandText {
+    CommandText::new(
+        format!("Use suggested name [{}]", Key::Tab),
+        CMD_GROUP_INBOX,
+    )
+}
+
+pub fn confirm_import(key: &KeyConfig) -> CommandText {
+    CommandText::new(
+        format!("Import/cancel [{},{}]", key.enter, key.exit_popup),
+        CMD_GROUP_INBOX,
+    )
+}
+
+pub fn choose_folder(key: &KeyConfig) -> CommandText {
+    CommandText::new(
+        format!("Next/previous folder [{},{}]", key.focus_down, key.focus_up),
+        CMD_GROUP_INBOX,
+    )
+}
+
 pub fn open_pdf(key: &KeyConfig) -> CommandText {
     CommandText::new(
-        format!("Open [{}]", ""),
+        format!("Open [{}]", key.open_pdf),
         // NOTE: CMD_GROUP_SEARCH may be needed
         CMD_GROUP_INBOX,
     )
 }
+
+pub fn recent_lists(key: &KeyConfig) -> CommandText {
+    CommandText::new(
+        format!(
+            "Recently added/opened [{},{}]",
+            key.recently_added, key.recently_opened
+        ),
+        CMD_GROUP_INBOX,
+    )
+}
+
+pub fn toggle_preview(key: &KeyConfig) -> CommandText {
+    CommandText::new(
+        format!("Switch detail view [{}]", key.toggle_preview),
+        CMD_GROUP_INBOX,
+    )
+}
+
+pub fn preview_page(key: &KeyConfig) -> CommandText {
+    CommandText::new(
+        format!(
+            "Previous/next page [{},{}]",
+            key.previous_page, key.next_page
+        ),
+        CMD_GROUP_INBOX,
+    )
+}
+
+pub fn reading_status(key: &KeyConfig) -> CommandText {
+    CommandText::new(
+        format!("Next reading status [{}]", key.cycle_status),
+        CMD_GROUP_INBOX,
+    )
+}
+
+pub fn read_page(key: &KeyConfig) -> CommandText {
+    CommandText::new(
+        format!(
+            "Last page read back/forward [{},{}]",
+            key.previous_read_page, key.next_read_page
+        ),
+        CMD_GROUP_INBOX,
+    )
+}
+
+pub fn rate(key: &KeyConfig) -> CommandText {
+    let [clear, stars @ ..] = &key.rate;
+    let stars = stars
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(",");
+    CommandText::new(
+        format!("Rate [{}], clear the rating [{}]", stars, clear),
+        CMD_GROUP_INBOX,
+    )
+}
+
+pub fn filter_status(key: &KeyConfig) -> CommandText {
+    CommandText::new(
+        format!("Filter by reading status [{}]", key.filter_status),
+        CMD_GROUP_INBOX,
+    )
+}
+
+pub fn add_to_collection(key: &KeyConfig) -> CommandText {
+    CommandText::new(
+        format!("Add to collection [{}]", key.add_to_collection),
+        CMD_GROUP_INBOX,
+    )
+}
+
+pub fn new_collection(key: &KeyConfig) -> CommandText {
+    CommandText::new(
+        format!("New collection [{}]", key.new_collection),
+        CMD_GROUP_INBOX,
+    )
+}
+
+pub fn delete_collection(key: &KeyConfig) -> CommandText {
+    CommandText::new(
+        format!("Delete collection [{}]", key.delete_collection),
+        CMD_GROUP_INBOX,
+    )
+}
+
+pub fn toggle_collection(key: &KeyConfig) -> CommandText {
+    CommandText::new(format!("Add/remove [{}]", key.enter), CMD_GROUP_INBOX)
+}
+
+pub fn save_collection(key: &KeyConfig) -> CommandText {
+    CommandText::new(
+        format!("Create/cancel [{},{}]", key.enter, key.exit_popup),
+        CMD_GROUP_INBOX,
+    )
+}
+
+pub fn save_search(key: &KeyConfig) -> CommandText {
+    CommandText::new(
+        format!("Save the search query [{}]", key.save_search),
+        CMD_GROUP_INBOX,
+    )
+}
+
+pub fn delete_search(key: &KeyConfig) -> CommandText {
+    CommandText::new(
+        format!("Delete saved search [{}]", key.delete_search),
+        CMD_GROUP_INBOX,
+    )
+}
+
+pub fn save_search_name(key: &KeyConfig) -> CommandText {
+    CommandText::new(
+        format!("Save/cancel [{},{}]", key.enter, key.exit_popup),
+        CMD_GROUP_INBOX,
+    )
+}
+
+pub fn toggle_mark(key: &KeyConfig) -> CommandText {
+    CommandText::new(
+        format!(
+            "Mark/unmark, clear [{},{}]",
+            key.toggle_mark, key.exit_popup
+        ),
+        CMD_GROUP_INBOX,
+    )
+}
+
+pub fn visual_mark(key: &KeyConfig) -> CommandText {
+    CommandText::new(
+        format!("Mark a range [{}]", key.visual_mark),
+        CMD_GROUP_INBOX,
+    )
+}
+
+pub fn mark_all(key: &KeyConfig) -> CommandText {
+    CommandText::new(
+        format!("Mark all/invert [{},{}]", key.mark_all, key.invert_marks),
+        CMD_GROUP_INBOX,
+    )
+}
+
+pub fn delete_files(key: &KeyConfig) -> CommandText {
+    CommandText::new(format!("Delete [{}]", key.delete_files), CMD_GROUP_INBOX)
+}
+
+pub fn tag_files(key: &KeyConfig) -> CommandText {
+    CommandText::new(format!("Tag [{}]", key.tag_files), CMD_GROUP_INBOX)
+}
+
+pub fn move_files(key: &KeyConfig) -> CommandText {
+    CommandText::new(format!("Move [{}]", key.move_files), CMD_GROUP_INBOX)
+}
+
+pub fn toggle_folder(key: &KeyConfig) -> CommandText {
+    CommandText::new(
+        format!(
+            "Expand/collapse folder [{},{}]",
+            key.expand_folder, key.collapse_folder
+        ),
+        CMD_GROUP_INBOX,
+    )
+}
+
+pub fn new_folder(key: &KeyConfig) -> CommandText {
+    CommandText::new(format!("New folder [{}]", key.new_folder), CMD_GROUP_INBOX)
+}
+
+pub fn rename_folder(key: &KeyConfig) -> CommandText {
+    CommandText::new(
+        format!("Rename folder [{}]", key.rename_folder),
+        CMD_GROUP_INBOX,
+    )
+}
+
+pub fn organize(key: &KeyConfig) -> CommandText {
+    CommandText::new(
+        format!("Organize by the policy [{}]", key.organize),
+        CMD_GROUP_INBOX,
+    )
+}
+
+pub fn execute_organize(key: &KeyConfig) -> CommandText {
+    CommandText::new(
+        format!("Move/cancel [{},{}]", key.enter, key.exit_popup),
+        CMD_GROUP_INBOX,
+    )
+}
+
+pub fn triage(key: &KeyConfig) -> CommandText {
+    CommandText::new(
+        format!("Triage one by one [{}]", key.triage),
+        CMD_GROUP_INBOX,
+    )
+}
+
+pub fn triage_actions(key: &KeyConfig) -> CommandText {
+    CommandText::new(
+        format!(
+            "Import/import and tag/skip/trash/open [{},{},{},{},{}]",
+            key.import, key.tag_files, key.skip_file, key.delete_files, key.open_pdf
+        ),
+        CMD_GROUP_INBOX,
+    )
+}
+
+pub fn save_triage_tags(key: &KeyConfig) -> CommandText {
+    CommandText::new(
+        format!(
+            "Import with the tags/cancel [{},{}]",
+            key.enter, key.exit_popup
+        ),
+        CMD_GROUP_INBOX,
+    )
+}
+
+pub fn save_folder(key: &KeyConfig) -> CommandText {
+    CommandText::new(
+        format!("Save/cancel [{},{}]", key.enter, key.exit_popup),
+        CMD_GROUP_INBOX,
+    )
+}
+
+pub fn choose_format(key: &KeyConfig) -> CommandText {
+    CommandText::new(
+        format!(
+            "Choose/export/cancel [{},{},{},{}]",
+            key.scroll_down, key.scroll_up, key.enter, key.exit_popup
+        ),
+        CMD_GROUP_INBOX,
+    )
+}
+
+pub fn confirm(key: &KeyConfig) -> CommandText {
+    CommandText::new(
+        format!("Confirm/cancel [{},{}]", key.enter, key.exit_popup),
+        CMD_GROUP_INBOX,
+    )
+}
+
+pub fn toggle_log(key: &KeyConfig) -> CommandText {
+    CommandText::new(format!("Toggle log [{}]", key.toggle_log), CMD_GROUP_LOG)
+}
+
+pub fn log_level(key: &KeyConfig) -> CommandText {
+    CommandText::new(
+        format!(
+            "More/less verbose [{},{}]",
+            key.log_level_up, key.log_level_down
+        ),
+        CMD_GROUP_LOG,
+    )
+}
+
+pub fn search_help(key: &KeyConfig) -> CommandText {
+    CommandText::new(format!("Search commands [{}]", key.search), CMD_GROUP_HELP)
+}
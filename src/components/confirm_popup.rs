@@ -0,0 +1,132 @@
+use tui::backend::Backend;
+use tui::layout::Rect;
+use tui::style::{Color, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, BorderType, Borders, Clear, Paragraph, Wrap};
+use tui::Frame;
+
+use super::{
+    command::{self, CommandInfo},
+    utils::popup::centered_rect,
+    CommandBlocking, Component, DrawableComponent, EventState,
+};
+use crate::{domain::collection::CollectionPath, inputs::key::Key, key_config::KeyConfig};
+
+/// A change of the library confirmed by the user, made by the app
+pub enum ConfirmRequest {
+    DeleteCollection(CollectionPath),
+    DeleteSearch(String),
+}
+
+impl ConfirmRequest {
+    fn title(&self) -> &'static str {
+        match self {
+            Self::DeleteCollection(_) => "Delete collection",
+            Self::DeleteSearch(_) => "Delete saved search",
+        }
+    }
+
+    fn question(&self) -> String {
+        match self {
+            Self::DeleteCollection(collection) => format!(
+                "Delete the collection {} and the collections nested in it? \
+                 Its documents are kept.",
+                collection
+            ),
+            Self::DeleteSearch(name) => format!("Delete the saved search {}?", name),
+        }
+    }
+}
+
+/// Asks for the confirmation of a change which cannot be undone
+pub struct ConfirmPopup {
+    /// the change to confirm, `None` while closed
+    pending: Option<ConfirmRequest>,
+    request: Option<ConfirmRequest>,
+    key_config: KeyConfig,
+}
+
+impl ConfirmPopup {
+    pub fn new(key_config: KeyConfig) -> Self {
+        Self {
+            pending: None,
+            request: None,
+            key_config,
+        }
+    }
+
+    pub fn open(&mut self, request: ConfirmRequest) {
+        self.pending = Some(request);
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// the change confirmed by the last event, if any
+    pub fn take_request(&mut self) -> Option<ConfirmRequest> {
+        self.request.take()
+    }
+}
+
+impl DrawableComponent for ConfirmPopup {
+    fn draw<B: Backend>(
+        &mut self,
+        f: &mut Frame<B>,
+        _area: Rect,
+        _focused: bool,
+    ) -> anyhow::Result<()> {
+        let pending = match &self.pending {
+            Some(pending) => pending,
+            None => return Ok(()),
+        };
+
+        let area = centered_rect(50, 20, f.size());
+        f.render_widget(Clear, area);
+
+        let text = vec![
+            Spans::from(pending.question()),
+            Spans::from(""),
+            Spans::from(Span::styled(
+                command::confirm(&self.key_config).name,
+                Style::default().fg(Color::Gray),
+            )),
+        ];
+        let body = Paragraph::new(text).wrap(Wrap { trim: true }).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Plain)
+                .border_style(Style::default().fg(Color::Magenta))
+                .title(pending.title()),
+        );
+        f.render_widget(body, area);
+        Ok(())
+    }
+}
+
+impl Component for ConfirmPopup {
+    fn commands(&self, out: &mut Vec<CommandInfo>, _force_all: bool) -> CommandBlocking {
+        if !self.is_visible() {
+            return CommandBlocking::PassingOn;
+        }
+        out.push(CommandInfo::new(command::confirm(&self.key_config)));
+        CommandBlocking::Blocking
+    }
+
+    fn event(&mut self, key: Key) -> anyhow::Result<EventState> {
+        if !self.is_visible() {
+            return Ok(EventState::NotConsumed);
+        }
+
+        if key == self.key_config.exit_popup {
+            self.pending = None;
+        } else if key == self.key_config.enter {
+            self.request = self.pending.take();
+        }
+        Ok(EventState::Consumed)
+    }
+
+    fn focused(&self) -> bool {
+        self.is_visible()
+    }
+}
@@ -0,0 +1,112 @@
+use std::collections::VecDeque;
+use tui::{
+    backend::Backend,
+    layout::{Alignment, Rect},
+    style::{Color, Style},
+    text::{Span, Spans},
+    widgets::{Block, BorderType, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+
+use super::{
+    command::{self, CommandInfo},
+    utils::popup::centered_rect,
+    CommandBlocking, Component, DrawableComponent, EventState,
+};
+use crate::{inputs::key::Key, key_config::KeyConfig};
+
+/// Modal popup showing errors one after another until they are dismissed
+pub struct ErrorPopup {
+    messages: VecDeque<String>,
+    key_config: KeyConfig,
+}
+
+impl ErrorPopup {
+    pub fn new(key_config: KeyConfig) -> Self {
+        Self {
+            messages: VecDeque::new(),
+            key_config,
+        }
+    }
+
+    pub fn show(&mut self, message: String) {
+        // the same failure is often raised on every frame or key, show it once
+        if self.messages.back() == Some(&message) {
+            return;
+        }
+        self.messages.push_back(message);
+    }
+
+    pub fn is_visible(&self) -> bool {
+        !self.messages.is_empty()
+    }
+}
+
+impl DrawableComponent for ErrorPopup {
+    fn draw<B: Backend>(
+        &mut self,
+        f: &mut Frame<B>,
+        _area: Rect,
+        _focused: bool,
+    ) -> anyhow::Result<()> {
+        let message = match self.messages.front() {
+            Some(message) => message,
+            None => return Ok(()),
+        };
+
+        let title = if self.messages.len() > 1 {
+            format!("Error (1/{})", self.messages.len())
+        } else {
+            "Error".to_string()
+        };
+
+        let area = centered_rect(50, 30, f.size());
+        f.render_widget(Clear, area);
+
+        let text = vec![
+            Spans::from(Span::raw(message.clone())),
+            Spans::from(""),
+            Spans::from(Span::styled(
+                command::exit_popup(&self.key_config).name,
+                Style::default().fg(Color::Gray),
+            )),
+        ];
+        let body = Paragraph::new(text)
+            .alignment(Alignment::Left)
+            .wrap(Wrap { trim: true })
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Plain)
+                    .border_style(Style::default().fg(Color::LightRed))
+                    .title(title),
+            );
+        f.render_widget(body, area);
+
+        Ok(())
+    }
+}
+
+impl Component for ErrorPopup {
+    fn commands(&self, out: &mut Vec<CommandInfo>, _force_all: bool) -> CommandBlocking {
+        if !self.is_visible() {
+            return CommandBlocking::PassingOn;
+        }
+        out.push(CommandInfo::new(command::exit_popup(&self.key_config)));
+        CommandBlocking::Blocking
+    }
+
+    fn event(&mut self, key: Key) -> anyhow::Result<EventState> {
+        if !self.is_visible() {
+            return Ok(EventState::NotConsumed);
+        }
+        if key == self.key_config.exit_popup || key == self.key_config.enter {
+            self.messages.pop_front();
+        }
+        Ok(EventState::Consumed)
+    }
+
+    fn focused(&self) -> bool {
+        self.is_visible()
+    }
+}
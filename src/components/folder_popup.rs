@@ -0,0 +1,262 @@
+use std::path::{Path, PathBuf};
+
+use tui::backend::Backend;
+use tui::layout::Rect;
+use tui::style::{Color, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, BorderType, Borders, Clear, Paragraph};
+use tui::Frame;
+
+use super::{
+    command::{self, CommandInfo},
+    utils::popup::centered_rect,
+    CommandBlocking, Component, DrawableComponent, EventState,
+};
+use crate::{
+    domain::{folder, importer::validate_name},
+    error::Result,
+    inputs::key::Key,
+    key_config::KeyConfig,
+};
+
+/// A change of the folders of the managed directory confirmed by the user, made by the app.
+///
+/// the folders are relative to the managed directory
+pub enum FolderRequest {
+    Create(PathBuf),
+    /// renames or moves a folder with the files in it
+    Move {
+        from: PathBuf,
+        to: PathBuf,
+    },
+}
+
+enum Mode {
+    /// a new folder nested in `parent`
+    Create { parent: PathBuf },
+    /// a new name for `folder`
+    Rename { folder: PathBuf },
+    /// the folder to move `folder` into
+    Move { folder: PathBuf },
+}
+
+impl Mode {
+    fn title(&self) -> String {
+        match self {
+            Self::Create { .. } => "New folder".to_string(),
+            Self::Rename { folder } => format!("Rename {}", folder.display()),
+            Self::Move { folder } => format!("Move {}", folder.display()),
+        }
+    }
+
+    /// what is typed before the input
+    fn prefix(&self) -> String {
+        let parent = match self {
+            Self::Create { parent } => parent.as_path(),
+            Self::Rename { folder } => folder.parent().unwrap_or_else(|| Path::new("")),
+            Self::Move { .. } => return String::new(),
+        };
+        match parent.as_os_str().is_empty() {
+            true => String::new(),
+            false => format!("{}/", parent.display()),
+        }
+    }
+
+    fn note(&self) -> &'static str {
+        match self {
+            Self::Create { .. } => "nest with \"/\"",
+            Self::Rename { .. } => "the records of its files follow them",
+            Self::Move { .. } => "a folder of the library, empty for the library itself",
+        }
+    }
+
+    /// the request for the typed input
+    fn request(&self, input: &str) -> Result<FolderRequest> {
+        Ok(match self {
+            Self::Create { parent } => FolderRequest::Create(parent.join(folder::parse(input)?)),
+            Self::Rename { folder } => {
+                let name = input.trim();
+                validate_name(name)?;
+                FolderRequest::Move {
+                    from: folder.clone(),
+                    to: folder.with_file_name(name),
+                }
+            }
+            Self::Move { folder } => FolderRequest::Move {
+                from: folder.clone(),
+                to: folder::parse(input)?.join(folder.file_name().unwrap_or_default()),
+            },
+        })
+    }
+}
+
+/// Asks for the name of a new folder of the managed directory, or for the new name or
+/// place of a folder
+pub struct FolderPopup {
+    mode: Option<Mode>,
+    input: String,
+    message: Option<String>,
+    request: Option<FolderRequest>,
+    key_config: KeyConfig,
+}
+
+impl FolderPopup {
+    pub fn new(key_config: KeyConfig) -> Self {
+        Self {
+            mode: None,
+            input: String::new(),
+            message: None,
+            request: None,
+            key_config,
+        }
+    }
+
+    /// asks the name of a new folder nested in `parent`, the managed directory if empty
+    pub fn create(&mut self, parent: &Path) {
+        self.open(
+            Mode::Create {
+                parent: parent.to_path_buf(),
+            },
+            String::new(),
+        );
+    }
+
+    pub fn rename(&mut self, folder: &Path) {
+        let name = folder.file_name().unwrap_or_default().to_string_lossy();
+        self.open(
+            Mode::Rename {
+                folder: folder.to_path_buf(),
+            },
+            name.to_string(),
+        );
+    }
+
+    pub fn move_folder(&mut self, folder: &Path) {
+        let parent = folder.parent().unwrap_or_else(|| Path::new(""));
+        self.open(
+            Mode::Move {
+                folder: folder.to_path_buf(),
+            },
+            parent.display().to_string(),
+        );
+    }
+
+    fn open(&mut self, mode: Mode, input: String) {
+        self.mode = Some(mode);
+        self.input = input;
+        self.message = None;
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.mode.is_some()
+    }
+
+    /// the change confirmed by the last event, if any
+    pub fn take_request(&mut self) -> Option<FolderRequest> {
+        self.request.take()
+    }
+
+    fn confirm(&mut self) {
+        let mode = match &self.mode {
+            Some(mode) => mode,
+            None => return,
+        };
+        let request = match mode.request(&self.input) {
+            Ok(FolderRequest::Create(folder)) if folder.as_os_str().is_empty() => {
+                self.message = Some("the name cannot be empty".to_string());
+                return;
+            }
+            Ok(request) => request,
+            Err(err) => {
+                self.message = Some(err.to_string());
+                return;
+            }
+        };
+        self.request = Some(request);
+        self.mode = None;
+    }
+}
+
+impl DrawableComponent for FolderPopup {
+    fn draw<B: Backend>(
+        &mut self,
+        f: &mut Frame<B>,
+        _area: Rect,
+        _focused: bool,
+    ) -> anyhow::Result<()> {
+        let mode = match &self.mode {
+            Some(mode) => mode,
+            None => return Ok(()),
+        };
+
+        let area = centered_rect(50, 20, f.size());
+        f.render_widget(Clear, area);
+
+        let prefix = mode.prefix();
+        let mut text = vec![
+            Spans::from(vec![
+                Span::styled(prefix.clone(), Style::default().fg(Color::DarkGray)),
+                Span::raw(self.input.clone()),
+            ]),
+            Spans::from(""),
+        ];
+        if let Some(message) = &self.message {
+            text.push(Spans::from(Span::styled(
+                message.clone(),
+                Style::default().fg(Color::LightRed),
+            )));
+        }
+        text.push(Spans::from(Span::styled(
+            format!(
+                "{} | {}",
+                mode.note(),
+                command::save_folder(&self.key_config).name
+            ),
+            Style::default().fg(Color::Gray),
+        )));
+        let body = Paragraph::new(text).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Plain)
+                .border_style(Style::default().fg(Color::Magenta))
+                .title(mode.title()),
+        );
+        f.render_widget(body, area);
+
+        let cursor_x = area.x + 1 + (prefix.chars().count() + self.input.chars().count()) as u16;
+        f.set_cursor(cursor_x.min(area.right().saturating_sub(2)), area.y + 1);
+        Ok(())
+    }
+}
+
+impl Component for FolderPopup {
+    fn commands(&self, out: &mut Vec<CommandInfo>, _force_all: bool) -> CommandBlocking {
+        if !self.is_visible() {
+            return CommandBlocking::PassingOn;
+        }
+        out.push(CommandInfo::new(command::save_folder(&self.key_config)));
+        CommandBlocking::Blocking
+    }
+
+    fn event(&mut self, key: Key) -> anyhow::Result<EventState> {
+        if !self.is_visible() {
+            return Ok(EventState::NotConsumed);
+        }
+        if key == self.key_config.exit_popup {
+            self.mode = None;
+        } else if key == self.key_config.enter {
+            self.confirm();
+        } else if let Key::Char(c) = key {
+            self.input.push(c);
+            self.message = None;
+        } else if key == Key::Backspace {
+            self.input.pop();
+            self.message = None;
+        }
+        Ok(EventState::Consumed)
+    }
+
+    fn focused(&self) -> bool {
+        self.is_visible()
+    }
+}
@@ -0,0 +1,228 @@
+use std::cmp;
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, BorderType, Borders, Clear, Paragraph},
+    Frame,
+};
+
+use super::{
+    command::{self, CommandInfo},
+    utils::{popup::centered_rect, vertical_scroll::VerticalScroll},
+    CommandBlocking, Component, DrawableComponent, EventState, ScrollType,
+};
+use crate::{inputs::key::Key, key_config::KeyConfig};
+
+/// Popup listing the commands available in the current context, grouped by `CommandText::group`
+pub struct HelpComponent {
+    cmds: Vec<CommandInfo>,
+    visible: bool,
+    selection: usize,
+    scroll: VerticalScroll,
+    query: String,
+    searching: bool,
+    key_config: KeyConfig,
+}
+
+impl HelpComponent {
+    pub fn new(key_config: KeyConfig) -> Self {
+        Self {
+            cmds: Vec::new(),
+            visible: false,
+            selection: 0,
+            scroll: VerticalScroll::new(),
+            query: String::new(),
+            searching: false,
+            key_config,
+        }
+    }
+
+    pub fn set_cmds(&mut self, mut cmds: Vec<CommandInfo>) {
+        // keep the order of registration inside a group
+        cmds.sort_by_key(|cmd| cmd.text.group);
+        cmds.dedup_by(|a, b| a.text == b.text);
+        self.cmds = cmds;
+        self.selection = 0;
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn show(&mut self) {
+        self.visible = true;
+        self.selection = 0;
+        self.scroll.reset();
+    }
+
+    pub fn hide(&mut self) {
+        self.visible = false;
+        self.searching = false;
+        self.query.clear();
+    }
+
+    fn filtered_cmds(&self) -> Vec<&CommandInfo> {
+        let query = self.query.to_lowercase();
+        self.cmds
+            .iter()
+            .filter(|cmd| cmd.text.name.to_lowercase().contains(&query))
+            .collect()
+    }
+
+    fn move_selection(&mut self, scroll: ScrollType) -> bool {
+        let selection_max = self.filtered_cmds().len().saturating_sub(1);
+        let new_selection = match scroll {
+            ScrollType::Up => self.selection.saturating_sub(1),
+            ScrollType::Down => self.selection.saturating_add(1),
+        };
+        let new_selection = cmp::min(new_selection, selection_max);
+        let needs_update = new_selection != self.selection;
+        self.selection = new_selection;
+        needs_update
+    }
+
+    /// builds the lines to render and returns them with the line index of the selected command
+    fn get_text(&self) -> (Vec<Spans<'_>>, usize) {
+        let mut lines = Vec::new();
+        let mut selected_line = 0;
+        let mut current_group = None;
+
+        for (i, cmd) in self.filtered_cmds().into_iter().enumerate() {
+            if current_group != Some(cmd.text.group) {
+                current_group = Some(cmd.text.group);
+                lines.push(Spans::from(Span::styled(
+                    cmd.text.group,
+                    Style::default().add_modifier(Modifier::BOLD),
+                )));
+            }
+
+            let style = if i == self.selection {
+                selected_line = lines.len();
+                Style::default().bg(Color::Blue).fg(Color::White)
+            } else {
+                Style::default()
+            };
+            lines.push(Spans::from(Span::styled(
+                format!(" {}", cmd.text.name),
+                style,
+            )));
+        }
+
+        (lines, selected_line)
+    }
+
+    fn search_event(&mut self, key: Key) {
+        match key {
+            Key::Char(c) => {
+                self.query.push(c);
+                self.selection = 0;
+            }
+            Key::Backspace => {
+                self.query.pop();
+                self.selection = 0;
+            }
+            Key::Enter => self.searching = false,
+            Key::Esc => {
+                self.searching = false;
+                self.query.clear();
+                self.selection = 0;
+            }
+            _ => {}
+        }
+    }
+}
+
+impl DrawableComponent for HelpComponent {
+    fn draw<B: Backend>(
+        &mut self,
+        f: &mut Frame<B>,
+        _area: Rect,
+        _focused: bool,
+    ) -> anyhow::Result<()> {
+        if !self.visible {
+            return Ok(());
+        }
+
+        let area = centered_rect(60, 70, f.size());
+        f.render_widget(Clear, area);
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Plain)
+            .border_style(Style::default().fg(Color::LightGreen))
+            .title("Help");
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Length(1)].as_ref())
+            .split(inner);
+
+        let (lines, selected_line) = self.get_text();
+        let visual_height = usize::from(chunks[0].height);
+        let top = self
+            .scroll
+            .update(selected_line, lines.len(), visual_height);
+        let lines = lines.into_iter().skip(top).collect::<Vec<_>>();
+        f.render_widget(Paragraph::new(lines), chunks[0]);
+        self.scroll.draw(f, area);
+
+        let search_style = if self.searching {
+            Style::default().fg(Color::LightCyan)
+        } else {
+            Style::default().fg(Color::Gray)
+        };
+        let search = if self.searching || !self.query.is_empty() {
+            format!("{}{}", self.key_config.search, self.query)
+        } else {
+            command::search_help(&self.key_config).name
+        };
+        f.render_widget(
+            Paragraph::new(Spans::from(Span::styled(search, search_style))),
+            chunks[1],
+        );
+
+        Ok(())
+    }
+}
+
+impl Component for HelpComponent {
+    fn commands(&self, out: &mut Vec<CommandInfo>, _force_all: bool) -> CommandBlocking {
+        if !self.visible {
+            out.push(CommandInfo::new(command::help(&self.key_config)));
+            return CommandBlocking::PassingOn;
+        }
+        out.push(CommandInfo::new(command::exit_popup(&self.key_config)));
+        out.push(CommandInfo::new(command::scroll(&self.key_config)));
+        out.push(CommandInfo::new(command::search_help(&self.key_config)));
+        CommandBlocking::Blocking
+    }
+
+    fn event(&mut self, key: Key) -> anyhow::Result<EventState> {
+        if !self.visible {
+            return Ok(EventState::NotConsumed);
+        }
+
+        if self.searching {
+            self.search_event(key);
+        } else if key == self.key_config.exit_popup || key == self.key_config.open_help {
+            self.hide();
+        } else if key == self.key_config.scroll_down || key == self.key_config.focus_down {
+            self.move_selection(ScrollType::Down);
+        } else if key == self.key_config.scroll_up || key == self.key_config.focus_up {
+            self.move_selection(ScrollType::Up);
+        } else if key == self.key_config.search {
+            self.searching = true;
+        }
+
+        // the popup is modal, so every key is consumed while it is visible
+        Ok(EventState::Consumed)
+    }
+
+    fn focused(&self) -> bool {
+        self.visible
+    }
+}
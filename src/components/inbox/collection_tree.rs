@@ -0,0 +1,153 @@
+use std::cmp;
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, BorderType, Borders, List, ListItem, ListState},
+    Frame,
+};
+
+use crate::{
+    components::{
+        command::{self, CommandInfo},
+        utils::vertical_scroll::VerticalScroll,
+        CommandBlocking, Component, DrawableComponent, EventState, ScrollType,
+    },
+    domain::collection::CollectionPath,
+    inputs::key::Key,
+    key_config::KeyConfig,
+};
+
+/// A collection of the tree and the number of listed documents in it or nested in it
+pub struct CollectionEntry {
+    pub collection: CollectionPath,
+    pub count: usize,
+}
+
+/// The tree of collections, whose selection filters the managed list.
+///
+/// the first row stands for all the documents of the library
+pub struct CollectionTreeComponent {
+    entries: Vec<CollectionEntry>,
+    /// documents of the library, shown on the first row
+    total: usize,
+    /// 0 selects all documents, then the entries
+    selection: usize,
+    list_state: ListState,
+    scroll: VerticalScroll,
+    key_config: KeyConfig,
+}
+
+impl CollectionTreeComponent {
+    pub fn new(key_config: KeyConfig) -> Self {
+        Self {
+            entries: Vec::new(),
+            total: 0,
+            selection: 0,
+            list_state: ListState::default(),
+            scroll: VerticalScroll::new(),
+            key_config,
+        }
+    }
+
+    /// sets the collections in tree order, the selected collection stays selected
+    /// unless it is gone
+    pub fn set_collections(&mut self, entries: Vec<CollectionEntry>, total: usize) {
+        let selected = self.selected().cloned();
+        self.entries = entries;
+        self.total = total;
+        self.selection = selected
+            .and_then(|selected| self.entries.iter().position(|e| e.collection == selected))
+            .map_or(0, |idx| idx + 1);
+    }
+
+    /// the selected collection, `None` for all documents
+    pub fn selected(&self) -> Option<&CollectionPath> {
+        let idx = self.selection.checked_sub(1)?;
+        self.entries.get(idx).map(|entry| &entry.collection)
+    }
+
+    fn move_selection(&mut self, scroll: ScrollType) -> bool {
+        let new_selection = match scroll {
+            ScrollType::Up => self.selection.saturating_sub(1),
+            ScrollType::Down => cmp::min(self.selection + 1, self.entries.len()),
+        };
+        let changed = new_selection != self.selection;
+        self.selection = new_selection;
+        changed
+    }
+}
+
+impl DrawableComponent for CollectionTreeComponent {
+    fn draw<B: Backend>(
+        &mut self,
+        f: &mut Frame<B>,
+        area: Rect,
+        focused: bool,
+    ) -> anyhow::Result<()> {
+        let dim = Style::default().fg(Color::DarkGray);
+        let all = ListItem::new(Spans::from(vec![
+            Span::raw("All documents"),
+            Span::styled(format!("  {}", self.total), dim),
+        ]));
+        let items = std::iter::once(all)
+            .chain(self.entries.iter().map(|entry| {
+                let indent = "  ".repeat(entry.collection.depth() + 1);
+                ListItem::new(Spans::from(vec![
+                    Span::raw(format!("{}{}", indent, entry.collection.name())),
+                    Span::styled(format!("  {}", entry.count), dim),
+                ]))
+            }))
+            .collect::<Vec<_>>();
+
+        self.list_state.select(Some(self.selection));
+        let list = List::new(items)
+            .highlight_style(
+                Style::default()
+                    .bg(Color::Cyan)
+                    .fg(Color::White)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Plain)
+                    .border_style(if focused {
+                        Style::default().fg(Color::Cyan)
+                    } else {
+                        Style::default().fg(Color::Gray)
+                    })
+                    .title("Collections"),
+            );
+        f.render_stateful_widget(list, area, &mut self.list_state);
+
+        let visual_height = usize::from(area.height.saturating_sub(2));
+        self.scroll
+            .update(self.selection, self.entries.len() + 1, visual_height);
+        self.scroll.draw(f, area);
+        Ok(())
+    }
+}
+
+impl Component for CollectionTreeComponent {
+    fn commands(&self, out: &mut Vec<CommandInfo>, _force_all: bool) -> CommandBlocking {
+        out.push(CommandInfo::new(command::scroll(&self.key_config)));
+        out.push(CommandInfo::new(command::new_collection(&self.key_config)));
+        out.push(CommandInfo::new(command::delete_collection(
+            &self.key_config,
+        )));
+        CommandBlocking::PassingOn
+    }
+
+    fn event(&mut self, key: Key) -> anyhow::Result<EventState> {
+        let changed = if key == self.key_config.scroll_down {
+            self.move_selection(ScrollType::Down)
+        } else if key == self.key_config.scroll_up {
+            self.move_selection(ScrollType::Up)
+        } else {
+            false
+        };
+        Ok(changed.into())
+    }
+}
@@ -0,0 +1,207 @@
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use crate::domain::{folder, pdf_file::PdfFile};
+
+/// A row of the managed list
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Row {
+    /// a folder relative to the managed directory, with the number of listed files in it
+    /// or nested in it
+    Folder {
+        path: PathBuf,
+        depth: usize,
+        count: usize,
+    },
+    /// a listed file, by its index into the files
+    File { idx: usize, depth: usize },
+}
+
+impl Row {
+    pub fn depth(&self) -> usize {
+        match self {
+            Self::Folder { depth, .. } | Self::File { depth, .. } => *depth,
+        }
+    }
+}
+
+/// The folders of the managed directory and the ones expanded in the list
+#[derive(Default)]
+pub struct Folders {
+    /// relative to the managed directory
+    folders: BTreeSet<PathBuf>,
+    expanded: HashSet<PathBuf>,
+}
+
+impl Folders {
+    /// sets the folders found in the managed directory, the ones which are gone are
+    /// forgotten
+    pub fn set(&mut self, folders: Vec<PathBuf>) {
+        self.folders = folders.into_iter().collect();
+        let folders = &self.folders;
+        self.expanded.retain(|folder| folders.contains(folder));
+    }
+
+    /// every folder in tree order
+    pub fn all(&self) -> impl Iterator<Item = &PathBuf> {
+        self.folders.iter()
+    }
+
+    pub fn is_expanded(&self, folder: &Path) -> bool {
+        self.expanded.contains(folder)
+    }
+
+    pub fn toggle(&mut self, folder: &Path) {
+        if !self.expanded.remove(folder) {
+            self.expand(folder);
+        }
+    }
+
+    /// expands `folder` and the folders it is nested in
+    pub fn expand(&mut self, folder: &Path) {
+        self.expanded.extend(
+            folder
+                .ancestors()
+                .filter(|ancestor| !ancestor.as_os_str().is_empty())
+                .map(Path::to_path_buf),
+        );
+    }
+
+    pub fn collapse(&mut self, folder: &Path) {
+        self.expanded.remove(folder);
+    }
+
+    /// the rows of the files at `indices` in the tree of their folders, each folder
+    /// followed by its folders and then by its files in the order of `indices`.
+    ///
+    /// while the list is `filtered` every folder is expanded and the folders without a
+    /// listed file are left out
+    pub fn rows(
+        &self,
+        managed_dir: &Path,
+        pdf_files: &[PdfFile],
+        indices: &[usize],
+        filtered: bool,
+    ) -> Vec<Row> {
+        let mut files = HashMap::<&Path, Vec<usize>>::new();
+        let mut counts = HashMap::<&Path, usize>::new();
+        let mut folders = self
+            .folders
+            .iter()
+            .map(PathBuf::as_path)
+            .collect::<BTreeSet<_>>();
+        for &idx in indices {
+            let parent = folder::of_file(managed_dir, &pdf_files[idx].path);
+            files.entry(parent).or_default().push(idx);
+            for ancestor in parent.ancestors().filter(|a| !a.as_os_str().is_empty()) {
+                *counts.entry(ancestor).or_default() += 1;
+                folders.insert(ancestor);
+            }
+        }
+
+        let mut rows = Vec::new();
+        let mut stack = vec![(Path::new(""), 0, false)];
+        // folders are pushed in reverse so that they are popped in order, a folder is
+        // popped a second time once its folders are done to add its files
+        while let Some((parent, depth, done)) = stack.pop() {
+            if done {
+                let parent_files = files.get(parent).map_or(&[][..], Vec::as_slice);
+                let depth = depth + usize::from(!parent.as_os_str().is_empty());
+                rows.extend(parent_files.iter().map(|&idx| Row::File { idx, depth }));
+                continue;
+            }
+            if !parent.as_os_str().is_empty() {
+                let count = counts.get(parent).copied().unwrap_or_default();
+                if filtered && count == 0 {
+                    continue;
+                }
+                rows.push(Row::Folder {
+                    path: parent.to_path_buf(),
+                    depth,
+                    count,
+                });
+                if !filtered && !self.expanded.contains(parent) {
+                    continue;
+                }
+            }
+            stack.push((parent, depth, true));
+            let child_depth = depth + usize::from(!parent.as_os_str().is_empty());
+            stack.extend(
+                folders
+                    .iter()
+                    .filter(|folder| folder.parent() == Some(parent))
+                    .rev()
+                    .map(|folder| (*folder, child_depth, false)),
+            );
+        }
+        rows
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Folders, Row};
+    use crate::domain::{pdf_file::PdfFile, pdf_info::PdfInfo};
+    use std::path::{Path, PathBuf};
+
+    fn file(path: &str) -> PdfFile {
+        PdfFile {
+            file_name: path.rsplit('/').next().unwrap().to_string(),
+            path: Path::new("/papers").join(path),
+            size: 0,
+            created: None,
+            modified: None,
+            info: PdfInfo::default(),
+        }
+    }
+
+    #[test]
+    fn test_rows_of_expanded_and_filtered_tree() {
+        let pdf_files = ["b.pdf", "ml/nlp/a.pdf", "ml/c.pdf"].map(file);
+        let mut folders = Folders::default();
+        folders.set(["empty", "ml", "ml/nlp"].map(PathBuf::from).to_vec());
+        let folder = |path: &str, depth, count| Row::Folder {
+            path: PathBuf::from(path),
+            depth,
+            count,
+        };
+        let root = Path::new("/papers");
+
+        let rows = folders.rows(root, &pdf_files, &[0, 1, 2], false);
+        assert_eq!(
+            rows,
+            [
+                folder("empty", 0, 0),
+                folder("ml", 0, 2),
+                Row::File { idx: 0, depth: 0 }
+            ]
+        );
+
+        folders.expand(Path::new("ml/nlp"));
+        let rows = folders.rows(root, &pdf_files, &[2, 1, 0], false);
+        assert_eq!(
+            rows,
+            [
+                folder("empty", 0, 0),
+                folder("ml", 0, 2),
+                folder("ml/nlp", 1, 1),
+                Row::File { idx: 1, depth: 2 },
+                Row::File { idx: 2, depth: 1 },
+                Row::File { idx: 0, depth: 0 }
+            ]
+        );
+
+        folders.collapse(Path::new("ml"));
+        let rows = folders.rows(root, &pdf_files, &[1], true);
+        assert_eq!(
+            rows,
+            [
+                folder("ml", 0, 1),
+                folder("ml/nlp", 1, 1),
+                Row::File { idx: 1, depth: 2 }
+            ]
+        );
+    }
+}
@@ -1,52 +1,466 @@
-use std::{cmp, path::Path};
+use chrono::Utc;
+use std::{
+    cmp::{self, Reverse},
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
 use tui::{
     backend::Backend,
-    layout::{Alignment, Rect},
+    layout::Rect,
     style::{Color, Modifier, Style},
     text::{Span, Spans},
-    widgets::{Block, BorderType, Borders, List, ListItem, ListState, Paragraph},
+    widgets::{Block, BorderType, Borders, List, ListItem, ListState},
     Frame,
 };
 
 use crate::{
     components::{
-        utils::vertical_scroll::VerticalScroll, Component, DrawableComponent, EventState,
-        ScrollType,
+        command::{self, CommandInfo},
+        status_bar::ListStatus,
+        utils::vertical_scroll::VerticalScroll,
+        CommandBlocking, Component, DrawableComponent, EventState, ScrollType,
+    },
+    config::tilde_path,
+    domain::{
+        collection::CollectionPath,
+        folder,
+        history::{relative_time, Recent},
+        library_store::DocumentRecord,
+        pdf_file::PdfFile,
+        pdf_info::PdfInfo,
+        query::Query,
+        reading::{ReadingStatus, Readings},
+        sort_order::SortOrder,
     },
-    domain::pdf_file::PdfFile,
+    error::{Error, Result},
     inputs::key::Key,
     key_config::KeyConfig,
 };
 
-use super::pdf_file_loader::PdfFileLoader;
+use super::{
+    folders::{Folders, Row},
+    marks::Marks,
+    pdf_file_loader::PdfFileLoader,
+};
 
 pub struct ManagedPdfListComponent {
     pub pdf_files: Vec<PdfFile>,
+    /// indices into `pdf_files` matching `filter`, in display order
+    filtered_indices: Vec<usize>,
+    /// the folders of the managed directory and the ones which are expanded
+    folders: Folders,
+    /// the listed folders and files in tree order, `selection` is one of them
+    rows: Vec<Row>,
+    filter: String,
+    query: Query,
+    /// only the files with this status are listed
+    status_filter: Option<ReadingStatus>,
+    /// only the files in this collection or nested in it are listed
+    collection_filter: Option<(CollectionPath, HashSet<PathBuf>)>,
+    /// only the files matching this saved search are listed
+    saved_search: Option<(String, Query)>,
+    /// only the files recently added or opened are listed, the newest first and outside
+    /// of their folders
+    recent: Option<Recent>,
+    sort_order: SortOrder,
+    /// the records of the files in the library, set by the app
+    records: HashMap<PathBuf, DocumentRecord>,
+    /// the reading status, progress and rating of the files, from `records`
+    readings: Readings,
+    /// directory the files are loaded from
+    dir: PathBuf,
     pdf_file_loader: PdfFileLoader,
     list_state: ListState,
     selection: usize,
+    /// the files the bulk operations work on, a marked folder marks the files in it
+    marks: Marks,
     scroll: VerticalScroll,
     key_config: KeyConfig,
 }
 
 impl ManagedPdfListComponent {
-    pub fn new(key_config: KeyConfig) -> Self {
+    pub fn new(key_config: KeyConfig, dir: PathBuf) -> Self {
         Self {
             pdf_files: Vec::new(),
-            pdf_file_loader: PdfFileLoader::new(),
+            filtered_indices: Vec::new(),
+            folders: Folders::default(),
+            rows: Vec::new(),
+            filter: String::new(),
+            query: Query::default(),
+            status_filter: None,
+            collection_filter: None,
+            saved_search: None,
+            recent: None,
+            sort_order: SortOrder::default(),
+            records: HashMap::new(),
+            readings: Readings::default(),
+            dir,
+            pdf_file_loader: PdfFileLoader::without_info(),
             list_state: ListState::default(),
             selection: 0,
+            marks: Marks::default(),
             scroll: VerticalScroll::new(),
             key_config: key_config.clone(),
         }
     }
 
-    pub fn load_files(&mut self, p: &Path) -> anyhow::Result<Vec<PdfFile>> {
-        self.pdf_file_loader.load_files(p)
+    /// loads the files of the managed directory and of its folders, with the folders
+    pub fn load_files(&mut self) -> Result<(Vec<PdfFile>, Vec<PathBuf>)> {
+        let pdf_files = self.pdf_file_loader.load_tree(&self.dir)?;
+        Ok((pdf_files, self.pdf_file_loader.take_folders()))
     }
 
-    pub fn update(&mut self, pdf_files: Vec<PdfFile>) {
+    /// errors of the files skipped by the last `load_files`
+    pub fn take_load_errors(&mut self) -> Vec<Error> {
+        self.pdf_file_loader.take_errors()
+    }
+
+    pub fn update(&mut self, pdf_files: Vec<PdfFile>, folders: Vec<PathBuf>) {
         self.pdf_files = pdf_files;
+        self.folders.set(folders);
+        let folder_paths = self
+            .folders
+            .all()
+            .map(|folder| self.dir.join(folder))
+            .collect::<Vec<_>>();
+        let loaded = self
+            .pdf_files
+            .iter()
+            .map(|pdf_file| pdf_file.path.as_path())
+            .chain(folder_paths.iter().map(PathBuf::as_path))
+            .collect();
+        self.marks.retain(&loaded);
+        self.sort_order.sort(&mut self.pdf_files, &self.readings);
+        self.apply_filter();
+    }
+
+    /// sets the records the rows and the queries use, the list is sorted and filtered
+    /// again with the selected file kept selected
+    pub fn set_records(&mut self, records: HashMap<PathBuf, DocumentRecord>) {
+        let readings = records
+            .iter()
+            .map(|(path, record)| (path.clone(), record.reading))
+            .collect();
+        self.readings = Readings::new(readings);
+        self.records = records;
+        self.resort();
+    }
+
+    /// sets the info read from the files in the background, the list is filtered again as
+    /// queries may use it
+    pub fn set_info(&mut self, infos: &HashMap<PathBuf, PdfInfo>) {
+        let mut changed = false;
+        for pdf_file in &mut self.pdf_files {
+            if let Some(info) = infos.get(&pdf_file.path) {
+                pdf_file.info = info.clone();
+                changed = true;
+            }
+        }
+        if changed {
+            self.resort();
+        }
+    }
+
+    /// lists the files of the next reading status, after the last one all files
+    pub fn cycle_status_filter(&mut self) {
+        self.status_filter = match self.status_filter {
+            None => Some(ReadingStatus::ALL[0]),
+            Some(status) => ReadingStatus::ALL
+                .into_iter()
+                .skip_while(|s| *s != status)
+                .nth(1),
+        };
+        self.apply_filter();
+    }
+
+    /// lists only the files of a collection, given with the paths of its files
+    pub fn set_collection_filter(&mut self, filter: Option<(CollectionPath, HashSet<PathBuf>)>) {
+        self.collection_filter = filter;
+        self.apply_filter();
+    }
+
+    /// lists only the files matching a saved search, given with its name
+    pub fn set_saved_search(&mut self, saved_search: Option<(String, Query)>) {
+        self.saved_search = saved_search;
+        self.apply_filter();
+    }
+
+    /// lists only the files recently added or opened, or all files again if they are
+    /// listed already
+    pub fn toggle_recent(&mut self, recent: Recent) {
+        self.recent = match self.recent {
+            Some(current) if current == recent => None,
+            _ => Some(recent),
+        };
+        self.selection = 0;
+        self.apply_filter();
+    }
+
+    /// lists the files matching `query`, parsed from `filter`
+    pub fn set_filter(&mut self, filter: &str, query: &Query) {
+        if self.filter == filter {
+            return;
+        }
+        self.filter = filter.to_string();
+        self.query = query.clone();
+        self.apply_filter();
+    }
+
+    pub fn toggle_sort(&mut self) {
+        self.sort_order = self.sort_order.next(true);
+        self.resort();
+    }
+
+    fn resort(&mut self) {
+        let selected = self.selected_file().map(|f| f.path.clone());
+        self.sort_order.sort(&mut self.pdf_files, &self.readings);
+        self.apply_filter();
+        let position = selected.and_then(|selected| {
+            self.rows.iter().position(|row| match row {
+                Row::File { idx, .. } => self.pdf_files[*idx].path == selected,
+                Row::Folder { .. } => false,
+            })
+        });
+        if let Some(selection) = position {
+            self.selection = selection;
+        }
+    }
+
+    pub fn selected_file(&self) -> Option<&PdfFile> {
+        match self.rows.get(self.selection)? {
+            Row::File { idx, .. } => Some(&self.pdf_files[*idx]),
+            Row::Folder { .. } => None,
+        }
+    }
+
+    /// the selected folder, relative to the managed directory
+    pub fn selected_folder(&self) -> Option<&Path> {
+        match self.rows.get(self.selection)? {
+            Row::Folder { path, .. } => Some(path),
+            Row::File { .. } => None,
+        }
+    }
+
+    /// the selected folder or the folder of the selected file, relative to the managed
+    /// directory which is the empty path
+    pub fn current_folder(&self) -> &Path {
+        match self.selected_file() {
+            Some(pdf_file) => folder::of_file(&self.dir, &pdf_file.path),
+            None => self.selected_folder().unwrap_or_else(|| Path::new("")),
+        }
+    }
+
+    /// every folder of the managed directory in tree order, relative to it
+    pub fn folders(&self) -> impl Iterator<Item = &PathBuf> {
+        self.folders.all()
+    }
+
+    /// selects `folder`, the folders it is nested in are expanded to show it
+    pub fn select_folder(&mut self, folder: &Path) {
+        if let Some(parent) = folder.parent() {
+            self.folders.expand(parent);
+        }
+        self.update_rows();
+        let position = self.rows.iter().position(|row| match row {
+            Row::Folder { path, .. } => path == folder,
+            Row::File { .. } => false,
+        });
+        if let Some(selection) = position {
+            self.selection = selection;
+        }
+    }
+
+    /// the files matching the filter, in display order
+    pub fn filtered_files(&self) -> impl Iterator<Item = &PdfFile> {
+        self.filtered_indices
+            .iter()
+            .map(|&idx| &self.pdf_files[idx])
+    }
+
+    pub fn has_marks(&self) -> bool {
+        !self.marks.is_empty()
+    }
+
+    /// the marked files and the files in the marked folders, including the ones filtered
+    /// out, in display order
+    pub fn marked_files(&self) -> impl Iterator<Item = &PdfFile> {
+        self.pdf_files
+            .iter()
+            .filter(|pdf_file| self.is_marked(&pdf_file.path))
+    }
+
+    /// whether the file or folder at `path` or a folder it is in is marked
+    fn is_marked(&self, path: &Path) -> bool {
+        path.ancestors()
+            .take_while(|ancestor| *ancestor != self.dir)
+            .any(|ancestor| self.marks.is_marked(ancestor))
+    }
+
+    /// the paths of the rows, a folder is marked by its path
+    fn row_paths(&self) -> Vec<PathBuf> {
+        self.rows
+            .iter()
+            .map(|row| match row {
+                Row::Folder { path, .. } => self.dir.join(path),
+                Row::File { idx, .. } => self.pdf_files[*idx].path.clone(),
+            })
+            .collect()
+    }
+
+    /// the paths of the marked files, or of the selected file if none is marked
+    pub fn marked_or_selected(&self) -> Vec<PathBuf> {
+        let files = match self.has_marks() {
+            true => self.marked_files().collect(),
+            false => self.selected_file().into_iter().collect::<Vec<_>>(),
+        };
+        files.iter().map(|pdf_file| pdf_file.path.clone()).collect()
+    }
+
+    pub fn clear_marks(&mut self) {
+        self.marks.clear();
+    }
+
+    /// marks or unmarks rows with the mark keys, returns false for other keys
+    fn mark(&mut self, key: Key) -> bool {
+        let paths = self.row_paths();
+        let rows = paths.iter().map(PathBuf::as_path).collect::<Vec<_>>();
+        let key_config = &self.key_config;
+        if key == key_config.toggle_mark {
+            let path = match rows.get(self.selection) {
+                Some(path) => path,
+                None => return false,
+            };
+            self.marks.toggle(path);
+            // the next row follows so that rows are marked by pressing the key again
+            self.selection = cmp::min(self.selection + 1, rows.len() - 1);
+        } else if key == key_config.visual_mark {
+            self.marks.toggle_visual(self.selection, &rows);
+        } else if key == key_config.mark_all {
+            self.marks.toggle_all(&rows);
+        } else if key == key_config.invert_marks {
+            self.marks.invert(&rows);
+        } else if key == key_config.exit_popup && (!self.marks.is_empty() || self.marks.is_visual())
+        {
+            self.marks.clear();
+        } else {
+            return false;
+        }
+        true
+    }
+
+    pub fn list_status(&self) -> ListStatus {
+        ListStatus {
+            // counted among the files, the folders are not
+            selected: self.selected_file().map(|_| {
+                self.rows[..self.selection]
+                    .iter()
+                    .filter(|row| matches!(row, Row::File { .. }))
+                    .count()
+            }),
+            total: self.pdf_files.len(),
+            filtered: self.filtered_indices.len(),
+            sort_order: self.sort_order,
+            filter: self.filter.clone(),
+            marked: self.marked_files().count(),
+            visual: self.marks.is_visual(),
+            status_filter: self.status_filter,
+        }
+    }
+
+    fn apply_filter(&mut self) {
+        let now = Utc::now();
+        self.filtered_indices = self
+            .pdf_files
+            .iter()
+            .enumerate()
+            .filter(|(_, file)| self.query.matches(file, self.records.get(&file.path)))
+            .filter(|(_, file)| {
+                self.status_filter
+                    .is_none_or(|status| self.readings.get(&file.path).status == status)
+            })
+            .filter(|(_, file)| {
+                self.collection_filter
+                    .as_ref()
+                    .is_none_or(|(_, paths)| paths.contains(&file.path))
+            })
+            .filter(|(_, file)| {
+                self.saved_search
+                    .as_ref()
+                    .is_none_or(|(_, query)| query.matches(file, self.records.get(&file.path)))
+            })
+            .filter(|(_, file)| {
+                self.recent
+                    .is_none_or(|recent| recent.includes(self.records.get(&file.path), now))
+            })
+            .map(|(idx, _)| idx)
+            .collect();
+        if let Some(recent) = self.recent {
+            let records = &self.records;
+            let pdf_files = &self.pdf_files;
+            self.filtered_indices.sort_by_key(|&idx| {
+                Reverse(
+                    records
+                        .get(&pdf_files[idx].path)
+                        .and_then(|record| recent.time(record)),
+                )
+            });
+        }
+        self.update_rows();
+    }
+
+    /// lays out the filtered files in the tree of their folders
+    fn update_rows(&mut self) {
+        if self.recent.is_some() {
+            self.rows = self
+                .filtered_indices
+                .iter()
+                .map(|&idx| Row::File { idx, depth: 0 })
+                .collect();
+            self.selection = cmp::min(self.selection, self.rows.len().saturating_sub(1));
+            return;
+        }
+        let filtered = !self.filter.trim().is_empty()
+            || self.status_filter.is_some()
+            || self.collection_filter.is_some()
+            || self.saved_search.is_some();
+        self.rows = self
+            .folders
+            .rows(&self.dir, &self.pdf_files, &self.filtered_indices, filtered);
+        self.selection = cmp::min(self.selection, self.rows.len().saturating_sub(1));
+    }
+
+    /// expands or collapses the selected folder with the folder keys, collapsing from a
+    /// file or a collapsed folder selects the folder it is in, returns false for other keys
+    fn fold(&mut self, key: Key) -> bool {
+        let selected = self.selected_folder().map(Path::to_path_buf);
+        if key == self.key_config.expand_folder {
+            match selected {
+                Some(folder) => self.folders.expand(&folder),
+                None => return false,
+            }
+        } else if key == self.key_config.collapse_folder {
+            match selected {
+                Some(folder) if self.folders.is_expanded(&folder) => self.folders.collapse(&folder),
+                _ => {
+                    let depth = match self.rows.get(self.selection) {
+                        Some(row) if row.depth() > 0 => row.depth(),
+                        _ => return false,
+                    };
+                    // the folder of a row is the closest row above it which is less deep
+                    let parent = self.rows[..self.selection]
+                        .iter()
+                        .rposition(|row| row.depth() < depth);
+                    if let Some(parent) = parent {
+                        self.selection = parent;
+                    }
+                    return true;
+                }
+            }
+        } else {
+            return false;
+        }
+        self.update_rows();
+        true
     }
 
     fn move_selection(&mut self, scroll: ScrollType) -> anyhow::Result<bool> {
@@ -55,7 +469,7 @@ impl ManagedPdfListComponent {
             ScrollType::Up => self.selection.saturating_sub(speed_int),
             ScrollType::Down => self.selection.saturating_add(speed_int),
         };
-        let selection_max = self.pdf_files.len().saturating_sub(1);
+        let selection_max = self.rows.len().saturating_sub(1);
         if selection_max < new_selection {
             return Ok(false);
         }
@@ -73,19 +487,81 @@ impl DrawableComponent for ManagedPdfListComponent {
         area: Rect,
         focused: bool,
     ) -> anyhow::Result<()> {
+        let dim = Style::default().fg(Color::DarkGray);
+        let now = Utc::now();
         let items: Vec<_> = self
-            .pdf_files
+            .rows
             .iter()
-            .map(|file| {
-                ListItem::new(Spans::from(vec![Span::styled(
-                    file.file_name.clone(),
-                    Style::default(),
-                )]))
+            .map(|row| {
+                let (path, depth) = match row {
+                    Row::Folder { path, depth, .. } => (self.dir.join(path), *depth),
+                    Row::File { idx, depth } => (self.pdf_files[*idx].path.clone(), *depth),
+                };
+                let name_style = match self.is_marked(&path) {
+                    true => Marks::style(),
+                    false => Style::default(),
+                };
+                let mut spans = self.marks.marker(&path).into_iter().collect::<Vec<_>>();
+                spans.push(Span::raw("  ".repeat(depth)));
+                let file = match row {
+                    Row::Folder { path, count, .. } => {
+                        let icon = match self.folders.is_expanded(path) {
+                            true => "▾",
+                            false => "▸",
+                        };
+                        let name = path.file_name().unwrap_or_default().to_string_lossy();
+                        spans.push(Span::raw(format!("{} ", icon)));
+                        spans.push(Span::styled(format!("{}/", name), name_style));
+                        spans.push(Span::styled(format!("  {}", count), dim));
+                        return ListItem::new(Spans::from(spans));
+                    }
+                    Row::File { idx, .. } => &self.pdf_files[*idx],
+                };
+                let reading = self.readings.get(&file.path);
+                spans.push(Span::raw(format!("{} ", reading.status.icon())));
+                // the recent lists are flat, the folder tells the files apart
+                let folder = folder::of_file(&self.dir, &file.path);
+                if self.recent.is_some() && !folder.as_os_str().is_empty() {
+                    spans.push(Span::styled(format!("{}/", folder.display()), dim));
+                }
+                spans.push(Span::styled(file.file_name.clone(), name_style));
+                if let Some(pages) = file.info.pages {
+                    spans.push(Span::styled(format!("  {}p", pages), dim));
+                }
+                if let Some(progress) = reading.progress(file.info.pages) {
+                    spans.push(Span::styled(format!("  {}", progress), dim));
+                }
+                if reading.rating.is_some() {
+                    spans.push(Span::styled(
+                        format!("  {}", reading.stars()),
+                        Style::default().fg(Color::Yellow),
+                    ));
+                }
+                let time = self
+                    .recent
+                    .and_then(|recent| recent.time(self.records.get(&file.path)?));
+                if let Some(time) = time {
+                    spans.push(Span::styled(format!("  {}", relative_time(time, now)), dim));
+                }
+                ListItem::new(Spans::from(spans))
             })
             .collect();
 
-        // TODO: unmanaged directories should be multiple
-        let title = format!("{} {}", "Managed", "[~/papers]");
+        // the breadcrumb of the folder the selection is in
+        let mut title = format!("{} [{}", "Managed", tilde_path(&self.dir));
+        for part in self.current_folder().iter() {
+            title.push_str(&format!(" › {}", part.to_string_lossy()));
+        }
+        title.push(']');
+        if let Some((collection, _)) = &self.collection_filter {
+            title.push_str(&format!(" in {}", collection));
+        }
+        if let Some((name, _)) = &self.saved_search {
+            title.push_str(&format!(" matching {}", name));
+        }
+        if let Some(recent) = self.recent {
+            title.push_str(&format!(" {}", recent.title()));
+        }
 
         let list_state_idx = Some(self.selection);
         self.list_state.select(list_state_idx);
@@ -110,6 +586,11 @@ impl DrawableComponent for ManagedPdfListComponent {
             );
 
         f.render_stateful_widget(list, area, &mut self.list_state);
+
+        // recalculated on every draw so that the max top follows the current area height
+        let visual_height = usize::from(area.height.saturating_sub(2));
+        self.scroll
+            .update(self.selection, self.rows.len(), visual_height);
         self.scroll.draw(f, area);
 
         Ok(())
@@ -117,7 +598,32 @@ impl DrawableComponent for ManagedPdfListComponent {
 }
 
 impl Component for ManagedPdfListComponent {
-    fn commands(&self) {}
+    fn commands(&self, out: &mut Vec<CommandInfo>, _force_all: bool) -> CommandBlocking {
+        out.push(CommandInfo::new(command::scroll(&self.key_config)));
+        out.push(CommandInfo::new(command::sort(&self.key_config)));
+        out.push(CommandInfo::new(command::edit_citation(&self.key_config)));
+        out.push(CommandInfo::new(command::fetch_metadata(&self.key_config)));
+        out.push(CommandInfo::new(command::reading_status(&self.key_config)));
+        out.push(CommandInfo::new(command::read_page(&self.key_config)));
+        out.push(CommandInfo::new(command::rate(&self.key_config)));
+        out.push(CommandInfo::new(command::filter_status(&self.key_config)));
+        out.push(CommandInfo::new(command::open_pdf(&self.key_config)));
+        out.push(CommandInfo::new(command::recent_lists(&self.key_config)));
+        out.push(CommandInfo::new(command::add_to_collection(
+            &self.key_config,
+        )));
+        out.push(CommandInfo::new(command::toggle_folder(&self.key_config)));
+        out.push(CommandInfo::new(command::new_folder(&self.key_config)));
+        out.push(CommandInfo::new(command::rename_folder(&self.key_config)));
+        out.push(CommandInfo::new(command::organize(&self.key_config)));
+        out.push(CommandInfo::new(command::toggle_mark(&self.key_config)));
+        out.push(CommandInfo::new(command::visual_mark(&self.key_config)));
+        out.push(CommandInfo::new(command::mark_all(&self.key_config)));
+        out.push(CommandInfo::new(command::tag_files(&self.key_config)));
+        out.push(CommandInfo::new(command::move_files(&self.key_config)));
+        out.push(CommandInfo::new(command::delete_files(&self.key_config)));
+        CommandBlocking::PassingOn
+    }
 
     fn event(&mut self, key: Key) -> anyhow::Result<EventState> {
         let selection_changed = if key == self.key_config.scroll_down {
@@ -127,6 +633,14 @@ impl Component for ManagedPdfListComponent {
         } else {
             false
         };
+        if selection_changed && self.marks.is_visual() {
+            let paths = self.row_paths();
+            let rows = paths.iter().map(PathBuf::as_path).collect::<Vec<_>>();
+            self.marks.extend_visual(self.selection, &rows);
+        }
+        if !selection_changed && (self.fold(key) || self.mark(key)) {
+            return Ok(EventState::Consumed);
+        }
 
         match selection_changed {
             true => Ok(EventState::Consumed),
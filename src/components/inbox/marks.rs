@@ -0,0 +1,158 @@
+use std::{
+    cmp,
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use tui::{
+    style::{Color, Modifier, Style},
+    text::Span,
+};
+
+use crate::domain::pdf_file::PdfFile;
+
+/// the paths of the listed files, in display order
+pub fn rows<'a>(pdf_files: &'a [PdfFile], indices: &[usize]) -> Vec<&'a Path> {
+    indices
+        .iter()
+        .map(|&idx| pdf_files[idx].path.as_path())
+        .collect()
+}
+
+/// The files marked in a list, which the bulk operations work on.
+///
+/// in visual mode the rows between the anchor and the selection are marked on top of
+/// the marks made before it started
+#[derive(Default)]
+pub struct Marks {
+    marked: HashSet<PathBuf>,
+    /// the row the visual range started at and the marks before it
+    visual: Option<(usize, HashSet<PathBuf>)>,
+}
+
+impl Marks {
+    /// the style of the marker and name of a marked row
+    pub fn style() -> Style {
+        Style::default()
+            .fg(Color::LightYellow)
+            .add_modifier(Modifier::BOLD)
+    }
+
+    /// the marker column of a row, empty while nothing is marked
+    pub fn marker(&self, path: &Path) -> Option<Span<'static>> {
+        if self.is_empty() && !self.is_visual() {
+            return None;
+        }
+        Some(match self.is_marked(path) {
+            true => Span::styled("* ", Self::style()),
+            false => Span::raw("  "),
+        })
+    }
+
+    pub fn is_marked(&self, path: &Path) -> bool {
+        self.marked.contains(path)
+    }
+
+    pub fn len(&self) -> usize {
+        self.marked.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.marked.is_empty()
+    }
+
+    pub fn is_visual(&self) -> bool {
+        self.visual.is_some()
+    }
+
+    pub fn toggle(&mut self, path: &Path) {
+        if !self.marked.remove(path) {
+            self.marked.insert(path.to_path_buf());
+        }
+    }
+
+    /// starts a visual range at `row`, which is marked, or ends the current one
+    pub fn toggle_visual(&mut self, row: usize, rows: &[&Path]) {
+        if self.visual.take().is_none() {
+            self.visual = Some((row, self.marked.clone()));
+            self.extend_visual(row, rows);
+        }
+    }
+
+    /// marks the rows between the anchor of the visual range and `row`
+    pub fn extend_visual(&mut self, row: usize, rows: &[&Path]) {
+        let (anchor, before) = match &self.visual {
+            Some(visual) => visual,
+            None => return,
+        };
+        let range = cmp::min(*anchor, row)..=cmp::max(*anchor, row);
+        self.marked = before.clone();
+        self.marked.extend(
+            rows.iter()
+                .skip(*range.start())
+                .take(range.count())
+                .map(|path| path.to_path_buf()),
+        );
+    }
+
+    /// marks every row, or unmarks them if they all are
+    pub fn toggle_all(&mut self, rows: &[&Path]) {
+        self.visual = None;
+        if rows.iter().all(|path| self.marked.contains(*path)) {
+            for path in rows {
+                self.marked.remove(*path);
+            }
+        } else {
+            self.marked
+                .extend(rows.iter().map(|path| path.to_path_buf()));
+        }
+    }
+
+    /// marks the unmarked rows and unmarks the marked ones
+    pub fn invert(&mut self, rows: &[&Path]) {
+        self.visual = None;
+        for path in rows {
+            self.toggle(path);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.visual = None;
+        self.marked.clear();
+    }
+
+    /// forgets the marks of the files which are no longer loaded
+    pub fn retain(&mut self, loaded: &HashSet<&Path>) {
+        self.visual = None;
+        self.marked.retain(|path| loaded.contains(path.as_path()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Marks;
+    use std::path::Path;
+
+    #[test]
+    fn test_visual_range_keeps_previous_marks() {
+        let rows = ["a.pdf", "b.pdf", "c.pdf", "d.pdf"].map(Path::new);
+        let mut marks = Marks::default();
+        marks.toggle(rows[0]);
+
+        marks.toggle_visual(3, &rows);
+        marks.extend_visual(2, &rows);
+        assert_eq!(marks.len(), 3);
+        marks.extend_visual(3, &rows);
+        marks.toggle_visual(3, &rows);
+        assert!(!marks.is_visual());
+        assert!(marks.is_marked(rows[0]) && !marks.is_marked(rows[1]));
+
+        marks.invert(&rows);
+        assert!(marks.is_marked(rows[1]) && marks.is_marked(rows[2]));
+        assert_eq!(marks.len(), 2);
+        marks.toggle_all(&rows);
+        assert_eq!(marks.len(), 4);
+        marks.toggle_all(&rows);
+        assert!(marks.is_empty());
+    }
+}
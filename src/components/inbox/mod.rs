@@ -1,31 +1,46 @@
+pub mod collection_tree;
+pub mod folders;
 pub mod managed_pdf_list;
+pub mod marks;
 pub mod pdf_detail;
 pub mod pdf_file_loader;
+pub mod saved_searches;
 pub mod searchbar;
 pub mod unmanaged_pdf_list;
 
+pub use collection_tree::CollectionTreeComponent;
 pub use managed_pdf_list::ManagedPdfListComponent;
 pub use pdf_detail::PdfDetailComponent;
 pub use pdf_file_loader::PdfFileLoader;
+pub use saved_searches::SavedSearchesComponent;
 pub use searchbar::SearchbarComponent;
 pub use unmanaged_pdf_list::UnmanagedPdfListComponent;
 
-use std::path::Path;
+use std::{collections::HashMap, path::PathBuf};
 use tui::{
     backend::Backend,
-    layout::{Alignment, Constraint, Direction, Layout, Rect},
-    style::{Color, Style},
-    text::{Span, Spans},
-    widgets::{Block, BorderType, Borders, Cell, Paragraph, Row, Table},
+    layout::{Constraint, Direction, Layout, Rect},
     Frame,
 };
 
-use crate::components::{Component, DrawableComponent, EventState};
+use crate::components::{
+    command::{self, CommandInfo},
+    status_bar::ListStatus,
+    CommandBlocking, Component, DrawableComponent, EventState,
+};
+use crate::config::Config;
+use crate::domain::{history::Recent, pdf_file::PdfFile, pdf_info::PdfInfo};
+use crate::error::Error;
 use crate::inputs::key::Key;
 use crate::key_config::KeyConfig;
 
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub enum InboxFocus {
     Searchbar,
+    /// collections filtering the managed list
+    CollectionTree,
+    /// saved searches filtering the managed list
+    SavedSearches,
     /// ~/paper
     ManagedPdfList,
     /// e.g. Downloads/, Documents/
@@ -35,42 +50,102 @@ pub enum InboxFocus {
 
 pub struct InboxComponent {
     pub searchbar: SearchbarComponent,
+    pub collection_tree: CollectionTreeComponent,
+    pub saved_searches: SavedSearchesComponent,
     pub managed_pdf_list: ManagedPdfListComponent,
     pub unmanaged_pdf_list: UnmanagedPdfListComponent,
     pub pdf_detail: PdfDetailComponent,
     pub focus: InboxFocus,
+    /// the last focused list, whose selection is shown in the detail pane
+    pub list_focus: InboxFocus,
     key_config: KeyConfig,
 }
 
 impl InboxComponent {
-    pub fn new(key_config: KeyConfig) -> Self {
+    pub fn new(key_config: KeyConfig, config: &Config) -> Self {
         Self {
             searchbar: SearchbarComponent::new(key_config.clone()),
-            managed_pdf_list: ManagedPdfListComponent::new(key_config.clone()),
-            unmanaged_pdf_list: UnmanagedPdfListComponent::new(key_config.clone()),
-            pdf_detail: PdfDetailComponent::new(key_config.clone()),
+            collection_tree: CollectionTreeComponent::new(key_config.clone()),
+            saved_searches: SavedSearchesComponent::new(key_config.clone()),
+            managed_pdf_list: ManagedPdfListComponent::new(
+                key_config.clone(),
+                config.managed_dir.clone(),
+            ),
+            unmanaged_pdf_list: UnmanagedPdfListComponent::new(
+                key_config.clone(),
+                config.unmanaged_dir.clone(),
+            ),
+            pdf_detail: PdfDetailComponent::new(key_config.clone(), config.preview.graphics),
             focus: InboxFocus::ManagedPdfList,
+            list_focus: InboxFocus::ManagedPdfList,
             key_config,
         }
     }
 
-    pub async fn update(&mut self) -> anyhow::Result<()> {
-        // TODO: refactor
-        let managed_file_path = Path::new("/Users/unvalley/papers");
-        let managed_pdf_files = self.managed_pdf_list.load_files(managed_file_path);
+    /// reloads both lists.
+    ///
+    /// a directory which cannot be read results in an empty list, the returned errors
+    /// describe the directories and files which could not be loaded
+    pub async fn update(&mut self) -> Vec<Error> {
+        let mut errors = Vec::new();
+
+        let managed_pdf_files = self.managed_pdf_list.load_files();
         match managed_pdf_files {
-            Ok(pdf_files) => self.managed_pdf_list.update(pdf_files),
-            Err(_) => todo!(),
+            Ok((pdf_files, folders)) => self.managed_pdf_list.update(pdf_files, folders),
+            Err(err) => {
+                self.managed_pdf_list.update(Vec::new(), Vec::new());
+                errors.push(err);
+            }
         }
+        errors.extend(self.managed_pdf_list.take_load_errors());
 
-        let unmanaged_file_path = Path::new("/Users/unvalley/Downloads");
-        let unmanaged_pdf_files = self.unmanaged_pdf_list.load_files(unmanaged_file_path);
+        let unmanaged_pdf_files = self.unmanaged_pdf_list.load_files();
         match unmanaged_pdf_files {
             Ok(pdf_files) => self.unmanaged_pdf_list.update(pdf_files),
-            Err(_) => todo!(),
+            Err(err) => {
+                self.unmanaged_pdf_list.update(Vec::new());
+                errors.push(err);
+            }
         }
+        errors.extend(self.unmanaged_pdf_list.take_load_errors());
 
-        Ok(())
+        errors
+    }
+
+    /// the files of both lists
+    pub fn pdf_files(&self) -> impl Iterator<Item = &PdfFile> {
+        self.managed_pdf_list
+            .pdf_files
+            .iter()
+            .chain(&self.unmanaged_pdf_list.pdf_files)
+    }
+
+    /// sets the info read from the files of both lists in the background
+    pub fn set_info(&mut self, infos: &HashMap<PathBuf, PdfInfo>) {
+        self.managed_pdf_list.set_info(infos);
+        self.unmanaged_pdf_list.set_info(infos);
+    }
+
+    pub fn is_searching(&self) -> bool {
+        matches!(self.focus, InboxFocus::Searchbar)
+    }
+
+    /// status of the list the user is currently working with
+    pub fn list_status(&self) -> ListStatus {
+        match self.list_focus {
+            InboxFocus::UnmanagedPdfList => self.unmanaged_pdf_list.list_status(),
+            _ => self.managed_pdf_list.list_status(),
+        }
+    }
+
+    /// filters the lists by the searchbar query, an invalid query keeps the last valid
+    /// filter while it is being fixed
+    fn apply_filter(&mut self) {
+        if let Ok(query) = self.searchbar.parsed() {
+            let filter = self.searchbar.query();
+            self.managed_pdf_list.set_filter(filter, query);
+            self.unmanaged_pdf_list.set_filter(filter, query);
+        }
     }
 }
 
@@ -83,17 +158,32 @@ impl DrawableComponent for InboxComponent {
     ) -> anyhow::Result<()> {
         let main_layout = Layout::default()
             .direction(Direction::Vertical)
-            .constraints([Constraint::Length(3), Constraint::Length(5)])
+            .constraints([
+                Constraint::Length(self.searchbar.height()),
+                Constraint::Length(5),
+            ])
             .split(area);
 
         let inbox_layout = Layout::default()
             .direction(Direction::Horizontal)
-            .constraints([Constraint::Percentage(70), Constraint::Percentage(30)].as_ref())
+            .constraints(
+                [
+                    Constraint::Percentage(20),
+                    Constraint::Percentage(50),
+                    Constraint::Percentage(30),
+                ]
+                .as_ref(),
+            )
             .split(main_layout[1]);
 
         let list_layout = Layout::default()
             .direction(Direction::Vertical)
             .constraints(vec![Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(inbox_layout[1]);
+
+        let sidebar_layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![Constraint::Percentage(60), Constraint::Percentage(40)])
             .split(inbox_layout[0]);
 
         self.searchbar.draw(
@@ -102,6 +192,16 @@ impl DrawableComponent for InboxComponent {
             focused && matches!(self.focus, InboxFocus::Searchbar),
         )?;
 
+        self.collection_tree.draw(
+            f,
+            sidebar_layout[0],
+            focused && matches!(self.focus, InboxFocus::CollectionTree),
+        )?;
+        self.saved_searches.draw(
+            f,
+            sidebar_layout[1],
+            focused && matches!(self.focus, InboxFocus::SavedSearches),
+        )?;
         self.managed_pdf_list.draw(
             f,
             list_layout[0],
@@ -115,7 +215,7 @@ impl DrawableComponent for InboxComponent {
 
         self.pdf_detail.draw(
             f,
-            inbox_layout[1],
+            inbox_layout[2],
             focused && matches!(self.focus, InboxFocus::PdfDetail),
         )?;
 
@@ -124,31 +224,124 @@ impl DrawableComponent for InboxComponent {
 }
 
 impl Component for InboxComponent {
-    fn commands(&self) {}
+    fn commands(&self, out: &mut Vec<CommandInfo>, force_all: bool) -> CommandBlocking {
+        if !force_all && self.is_searching() {
+            self.searchbar.commands(out, force_all);
+            return CommandBlocking::Blocking;
+        }
+
+        out.push(CommandInfo::new(command::move_focus(&self.key_config)));
+        out.push(CommandInfo::new(command::search(&self.key_config)));
+        out.push(CommandInfo::new(command::reload(&self.key_config)));
+        out.push(CommandInfo::new(command::export_catalogue(
+            &self.key_config,
+        )));
+        out.push(CommandInfo::new(command::toggle_preview(&self.key_config)));
+        if force_all || self.pdf_detail.is_previewing() {
+            out.push(CommandInfo::new(command::preview_page(&self.key_config)));
+        }
+
+        if force_all || matches!(self.focus, InboxFocus::Searchbar) {
+            self.searchbar.commands(out, force_all);
+        }
+        if force_all || matches!(self.focus, InboxFocus::CollectionTree) {
+            self.collection_tree.commands(out, force_all);
+        }
+        if force_all || matches!(self.focus, InboxFocus::SavedSearches) {
+            self.saved_searches.commands(out, force_all);
+        }
+        if force_all || matches!(self.focus, InboxFocus::ManagedPdfList) {
+            self.managed_pdf_list.commands(out, force_all);
+        }
+        if force_all || matches!(self.focus, InboxFocus::UnmanagedPdfList) {
+            self.unmanaged_pdf_list.commands(out, force_all);
+        }
+        if force_all || matches!(self.focus, InboxFocus::PdfDetail) {
+            self.pdf_detail.commands(out, force_all);
+        }
+        CommandBlocking::PassingOn
+    }
 
     fn event(&mut self, key: Key) -> anyhow::Result<EventState> {
-        match key {
-            Key::Up => {
-                // focus to paper
-                self.focus = InboxFocus::ManagedPdfList;
-                return Ok(EventState::Consumed);
+        if self.is_searching() {
+            // every key goes to the searchbar until the filter is applied or cleared
+            self.searchbar.event(key)?;
+            let applied = key == self.key_config.enter && self.searchbar.parsed().is_ok();
+            if applied || key == self.key_config.exit_popup {
+                self.focus = self.list_focus;
             }
-            Key::Down => {
-                // focus to existing
-                self.focus = InboxFocus::UnmanagedPdfList;
-                return Ok(EventState::Consumed);
+            self.apply_filter();
+            return Ok(EventState::Consumed);
+        }
+        if key == self.key_config.search {
+            self.focus = InboxFocus::Searchbar;
+            return Ok(EventState::Consumed);
+        }
+        if key == self.key_config.sort {
+            match self.list_focus {
+                InboxFocus::UnmanagedPdfList => self.unmanaged_pdf_list.toggle_sort(),
+                _ => self.managed_pdf_list.toggle_sort(),
             }
-            Key::Right => {
-                // detailにfocus
-                self.focus = InboxFocus::PdfDetail;
+            return Ok(EventState::Consumed);
+        }
+        if key == self.key_config.filter_status && self.list_focus == InboxFocus::ManagedPdfList {
+            self.managed_pdf_list.cycle_status_filter();
+            return Ok(EventState::Consumed);
+        }
+        let recent = if key == self.key_config.recently_added {
+            Some(Recent::Added)
+        } else if key == self.key_config.recently_opened {
+            Some(Recent::Opened)
+        } else {
+            None
+        };
+        if let Some(recent) = recent {
+            self.managed_pdf_list.toggle_recent(recent);
+            self.focus = InboxFocus::ManagedPdfList;
+            self.list_focus = self.focus;
+            return Ok(EventState::Consumed);
+        }
+        let in_sidebar = matches!(
+            self.focus,
+            InboxFocus::CollectionTree | InboxFocus::SavedSearches
+        );
+        if key == self.key_config.focus_up {
+            if in_sidebar {
+                self.focus = InboxFocus::CollectionTree;
                 return Ok(EventState::Consumed);
             }
-            Key::Left => {
-                // detailからどちらかにfocus
-                self.focus = InboxFocus::ManagedPdfList;
+            // focus to paper
+            self.focus = InboxFocus::ManagedPdfList;
+            self.list_focus = self.focus;
+            return Ok(EventState::Consumed);
+        }
+        if key == self.key_config.focus_down {
+            if in_sidebar {
+                self.focus = InboxFocus::SavedSearches;
                 return Ok(EventState::Consumed);
             }
-            _ => Ok(EventState::NotConsumed),
+            // focus to existing
+            self.focus = InboxFocus::UnmanagedPdfList;
+            self.list_focus = self.focus;
+            return Ok(EventState::Consumed);
+        }
+        if key == self.key_config.focus_right {
+            // detailにfocus, the sidebar goes back to the last list first
+            self.focus = match self.focus {
+                InboxFocus::CollectionTree | InboxFocus::SavedSearches => self.list_focus,
+                _ => InboxFocus::PdfDetail,
+            };
+            return Ok(EventState::Consumed);
+        }
+        if key == self.key_config.focus_left {
+            // detailからどちらかにfocus, the lists go to the sidebar
+            self.focus = match self.focus {
+                InboxFocus::PdfDetail => self.list_focus,
+                InboxFocus::SavedSearches => InboxFocus::SavedSearches,
+                _ => InboxFocus::CollectionTree,
+            };
+            return Ok(EventState::Consumed);
         }
+        Ok(EventState::NotConsumed)
     }
 }
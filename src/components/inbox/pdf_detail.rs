@@ -1,28 +1,359 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use tiny_skia::Pixmap;
 use tui::{
     backend::Backend,
-    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    layout::{Constraint, Rect},
     style::{Color, Style},
-    text::{Span, Spans},
-    widgets::{Block, BorderType, Borders, Cell, Paragraph, Row, Table},
+    text::Span,
+    widgets::{Block, BorderType, Borders, Cell, Paragraph, Row, Table, Wrap},
     Frame,
 };
 
+use chrono::Local;
+
 use crate::{
-    components::{Component, DrawableComponent, EventState},
+    components::{
+        command::{self, CommandInfo},
+        CommandBlocking, Component, DrawableComponent, EventState,
+    },
+    domain::{library_store::DocumentRecord, pdf_file::PdfFile},
+    error::Result,
+    graphics::{self, GraphicsProtocol, HalfBlocks, Placement},
     inputs::key::Key,
     key_config::KeyConfig,
 };
 
+/// pages whose text is kept, so that going back to a page does not extract it again
+const MAX_CACHED_PAGES: usize = 64;
+/// rendered first pages kept in memory, they are also cached on disk
+const MAX_CACHED_THUMBNAILS: usize = 16;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum DetailView {
+    Info,
+    /// the text of a page of the file
+    Preview,
+    /// a picture of the first page
+    Page,
+}
+
+struct Thumbnail {
+    pixmap: Pixmap,
+    /// tells the terminal's copy of the image apart from the others
+    id: u64,
+}
+
 pub struct PdfDetailComponent {
+    pdf_file: Option<PdfFile>,
+    /// `None` for the files outside the library
+    record: Option<DocumentRecord>,
+    view: DetailView,
+    /// the previewed page, counted from 1
+    page: u32,
+    /// lines of the preview scrolled past
+    scroll: u16,
+    /// the text of the pages extracted so far, or why it could not be extracted
+    texts: HashMap<(PathBuf, u32), std::result::Result<String, String>>,
+    graphics: GraphicsProtocol,
+    /// the rendered first pages, or why they could not be rendered
+    thumbnails: HashMap<PathBuf, std::result::Result<Thumbnail, String>>,
+    next_thumbnail_id: u64,
+    /// where the terminal draws the thumbnail, with the kitty and sixel protocols
+    placement: Option<Placement>,
     key_config: KeyConfig,
 }
 
 impl PdfDetailComponent {
-    pub fn new(key_config: KeyConfig) -> Self {
+    pub fn new(key_config: KeyConfig, graphics: GraphicsProtocol) -> Self {
         Self {
+            pdf_file: None,
+            record: None,
+            view: DetailView::Info,
+            page: 1,
+            scroll: 0,
+            texts: HashMap::new(),
+            graphics,
+            thumbnails: HashMap::new(),
+            next_thumbnail_id: 0,
+            placement: None,
             key_config: key_config.clone(),
         }
     }
+
+    /// sets the file shown in the pane, called by the app before drawing
+    pub fn set_file(&mut self, pdf_file: Option<PdfFile>, record: Option<DocumentRecord>) {
+        let previous = self.pdf_file.as_ref().map(|f| (&f.path, f.modified));
+        let current = pdf_file.as_ref().map(|f| (&f.path, f.modified));
+        if previous != current {
+            // the text of a file which has changed is extracted again
+            if let (Some((old, _)), Some((new, _))) = (previous, current) {
+                if old == new {
+                    self.texts.retain(|(path, _), _| path != new);
+                    self.thumbnails.remove(new);
+                }
+            }
+            self.page = 1;
+            self.scroll = 0;
+        }
+        self.pdf_file = pdf_file;
+        self.record = record;
+    }
+
+    pub fn is_previewing(&self) -> bool {
+        self.view == DetailView::Preview
+    }
+
+    /// the page the preview shows but whose text has not been extracted yet
+    pub fn missing_page(&self) -> Option<(PathBuf, u32)> {
+        let pdf_file = self.pdf_file.as_ref().filter(|_| self.is_previewing())?;
+        let key = (pdf_file.path.clone(), self.page);
+        (!self.texts.contains_key(&key)).then_some(key)
+    }
+
+    /// stores the extracted text of a page, shown if it is the previewed one
+    pub fn set_page_text(&mut self, path: PathBuf, page: u32, text: Result<String>) {
+        if self.texts.len() >= MAX_CACHED_PAGES {
+            self.texts.clear();
+        }
+        let text = text
+            .map(|text| clean_text(&text))
+            .map_err(|err| err.to_string());
+        self.texts.insert((path, page), text);
+    }
+
+    /// the first page shown but not rendered yet
+    pub fn missing_thumbnail(&self) -> Option<&PdfFile> {
+        self.pdf_file
+            .as_ref()
+            .filter(|f| self.view == DetailView::Page && !self.thumbnails.contains_key(&f.path))
+    }
+
+    /// stores the rendered first page of a file, shown if it is the selected one
+    pub fn set_thumbnail(&mut self, path: PathBuf, pixmap: Result<Pixmap>) {
+        if self.thumbnails.len() >= MAX_CACHED_THUMBNAILS {
+            self.thumbnails.clear();
+        }
+        self.next_thumbnail_id += 1;
+        let thumbnail = pixmap
+            .map(|pixmap| Thumbnail {
+                pixmap,
+                id: self.next_thumbnail_id,
+            })
+            .map_err(|err| err.to_string());
+        self.thumbnails.insert(path, thumbnail);
+    }
+
+    /// the thumbnail the terminal draws over the pane, as placed by the last draw
+    pub fn placed_image(&self) -> Option<(Placement, &Pixmap)> {
+        let placement = self.placement.filter(|_| self.view == DetailView::Page)?;
+        let pdf_file = self.pdf_file.as_ref()?;
+        match self.thumbnails.get(&pdf_file.path) {
+            Some(Ok(thumbnail)) if thumbnail.id == placement.image => {
+                Some((placement, &thumbnail.pixmap))
+            }
+            _ => None,
+        }
+    }
+
+    /// info, then the text preview, then the first page unless graphics are off
+    fn toggle_preview(&mut self) {
+        self.view = match self.view {
+            DetailView::Info => DetailView::Preview,
+            DetailView::Preview if self.graphics != GraphicsProtocol::Off => DetailView::Page,
+            DetailView::Preview | DetailView::Page => DetailView::Info,
+        };
+    }
+
+    /// moves the preview to the next or previous page, within the known page count
+    fn turn_page(&mut self, forward: bool) {
+        let pages = self.pdf_file.as_ref().and_then(|f| f.info.pages);
+        let page = if forward {
+            self.page.saturating_add(1)
+        } else {
+            self.page.saturating_sub(1).max(1)
+        };
+        if pages.is_none_or(|pages| page <= pages) && page != self.page {
+            self.page = page;
+            self.scroll = 0;
+        }
+    }
+
+    fn scroll_preview(&mut self, down: bool) {
+        let lines = self
+            .pdf_file
+            .as_ref()
+            .and_then(|f| self.texts.get(&(f.path.clone(), self.page)))
+            .and_then(|text| text.as_ref().ok())
+            .map_or(0, |text| text.lines().count());
+        self.scroll = if down {
+            self.scroll
+                .saturating_add(1)
+                .min(u16::try_from(lines.saturating_sub(1)).unwrap_or(u16::MAX))
+        } else {
+            self.scroll.saturating_sub(1)
+        };
+    }
+
+    fn rows(&self) -> Vec<(&'static str, String)> {
+        let pdf_file = match &self.pdf_file {
+            Some(pdf_file) => pdf_file,
+            None => return Vec::new(),
+        };
+        let mut rows = vec![
+            ("Name", pdf_file.file_name.clone()),
+            ("Size", format!("{} bytes", pdf_file.size)),
+        ];
+        if let Some(modified) = pdf_file.modified {
+            rows.push((
+                "Modified",
+                modified
+                    .with_timezone(&Local)
+                    .format("%Y-%m-%d %H:%M")
+                    .to_string(),
+            ));
+        }
+        let info = &pdf_file.info;
+        if let Some(pages) = info.pages {
+            rows.push(("Pages", pages.to_string()));
+        }
+        if let Some(version) = &info.version {
+            rows.push(("Version", format!("PDF {}", version)));
+        }
+        if let Some(producer) = &info.producer {
+            rows.push(("Producer", producer.clone()));
+        }
+        let flags = info.flags();
+        if !flags.is_empty() {
+            rows.push(("Flags", flags.join(", ")));
+        }
+        let record = match &self.record {
+            Some(record) => record,
+            None => return rows,
+        };
+        let reading = &record.reading;
+        rows.push((
+            "Status",
+            format!("{} {}", reading.status.icon(), reading.status),
+        ));
+        if let Some(progress) = reading.progress(info.pages) {
+            rows.push(("Progress", progress));
+        }
+        if reading.rating.is_some() {
+            rows.push(("Rating", reading.stars()));
+        }
+        if !record.collections.is_empty() {
+            let collections = record.collections.iter().map(|c| c.to_string());
+            rows.push(("Collections", collections.collect::<Vec<_>>().join(", ")));
+        }
+        match &record.identifiers {
+            Some(identifiers) => {
+                let none = || "-".to_string();
+                rows.push(("DOI", identifiers.doi.clone().unwrap_or_else(none)));
+                rows.push(("arXiv", identifiers.arxiv.clone().unwrap_or_else(none)));
+                rows.push(("ISBN", identifiers.isbn.clone().unwrap_or_else(none)));
+            }
+            None => rows.push(("Identifiers", "scanning…".to_string())),
+        }
+        if let Some(citation) = record.citation.as_ref().filter(|c| !c.title.is_empty()) {
+            rows.push(("Title", citation.title.clone()));
+        }
+        rows
+    }
+}
+
+/// the extracted text without control characters and with runs of blank lines shortened,
+/// the extractor puts a blank line between most text blocks
+pub fn clean_text(text: &str) -> String {
+    let mut cleaned = String::new();
+    let mut blank = true;
+    for line in text.lines() {
+        let line = line
+            .trim_end()
+            .chars()
+            .map(|c| if c.is_control() { ' ' } else { c })
+            .collect::<String>();
+        if line.is_empty() {
+            if !blank {
+                cleaned.push('\n');
+            }
+            blank = true;
+        } else {
+            cleaned.push_str(&line);
+            cleaned.push('\n');
+            blank = false;
+        }
+    }
+    cleaned.trim_end().to_string()
+}
+
+impl PdfDetailComponent {
+    fn draw_preview<B: Backend>(&self, f: &mut Frame<B>, area: Rect, block: Block) {
+        let pdf_file = match &self.pdf_file {
+            Some(pdf_file) => pdf_file,
+            None => {
+                f.render_widget(block.title("Preview"), area);
+                return;
+            }
+        };
+        let pages = pdf_file
+            .info
+            .pages
+            .map_or_else(|| "?".to_string(), |pages| pages.to_string());
+        let block = block.title(format!("Preview [{}/{}]", self.page, pages));
+
+        let note = |text: &str, color| {
+            Paragraph::new(Span::styled(text.to_string(), Style::default().fg(color)))
+                .wrap(Wrap { trim: false })
+        };
+        let paragraph = match self.texts.get(&(pdf_file.path.clone(), self.page)) {
+            None => note("extracting…", Color::Gray),
+            Some(Ok(text)) if text.is_empty() => {
+                note("no text on this page, it may be scanned", Color::Gray)
+            }
+            Some(Ok(text)) => Paragraph::new(text.as_str())
+                .wrap(Wrap { trim: false })
+                .scroll((self.scroll, 0)),
+            Some(Err(reason)) => note(reason, Color::LightRed),
+        };
+        f.render_widget(paragraph.block(block), area);
+    }
+}
+
+impl PdfDetailComponent {
+    fn draw_page<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect, block: Block) {
+        let inner = block.inner(area);
+        f.render_widget(block.title("Page 1"), area);
+        let pdf_file = match &self.pdf_file {
+            Some(pdf_file) => pdf_file,
+            None => return,
+        };
+        let note = |text: &str, color| {
+            Paragraph::new(Span::styled(text.to_string(), Style::default().fg(color)))
+                .wrap(Wrap { trim: false })
+        };
+        let thumbnail = match self.thumbnails.get(&pdf_file.path) {
+            None => return f.render_widget(note("rendering…", Color::Gray), inner),
+            Some(Err(reason)) => return f.render_widget(note(reason, Color::LightRed), inner),
+            Some(Ok(thumbnail)) => thumbnail,
+        };
+        let pixmap = &thumbnail.pixmap;
+        if self.graphics.is_overlay() {
+            // the cells stay blank, the terminal draws the image over them
+            let area = graphics::fit(
+                pixmap.width(),
+                pixmap.height(),
+                inner,
+                graphics::cell_size(),
+            );
+            self.placement = Some(Placement {
+                area,
+                image: thumbnail.id,
+            });
+        } else {
+            f.render_widget(HalfBlocks::new(pixmap), inner);
+        }
+    }
 }
 
 impl DrawableComponent for PdfDetailComponent {
@@ -32,26 +363,43 @@ impl DrawableComponent for PdfDetailComponent {
         area: Rect,
         focused: bool,
     ) -> anyhow::Result<()> {
-        let key_style = Style::default().fg(Color::LightCyan);
-
-        let mut rows = vec![];
-        let row = Row::new(vec![Cell::from(Span::styled("".to_string(), key_style))]);
-        rows.push(row);
-
         let border_style = if focused {
             Style::default().fg(Color::LightGreen)
         } else {
             Style::default().fg(Color::Gray)
         };
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Plain)
+            .border_style(border_style);
+        self.placement = None;
+        match self.view {
+            DetailView::Info => {}
+            DetailView::Preview => {
+                self.draw_preview(f, area, block);
+                return Ok(());
+            }
+            DetailView::Page => {
+                self.draw_page(f, area, block);
+                return Ok(());
+            }
+        }
+
+        let key_style = Style::default().fg(Color::LightCyan);
+
+        let rows = self
+            .rows()
+            .into_iter()
+            .map(|(key, value)| {
+                Row::new(vec![
+                    Cell::from(Span::styled(key, key_style)),
+                    Cell::from(value),
+                ])
+            })
+            .collect::<Vec<_>>();
 
         let table = Table::new(rows)
-            .block(
-                Block::default()
-                    .borders(Borders::ALL)
-                    .border_type(BorderType::Plain)
-                    .border_style(border_style)
-                    .title("Detail"),
-            )
+            .block(block.title("Detail"))
             .widths(&[Constraint::Length(11), Constraint::Min(20)])
             .column_spacing(1);
 
@@ -61,9 +409,29 @@ impl DrawableComponent for PdfDetailComponent {
 }
 
 impl Component for PdfDetailComponent {
-    fn commands(&self) {}
+    fn commands(&self, out: &mut Vec<CommandInfo>, force_all: bool) -> CommandBlocking {
+        if force_all || self.is_previewing() {
+            out.push(CommandInfo::new(command::scroll(&self.key_config)));
+        }
+        CommandBlocking::PassingOn
+    }
 
+    /// the preview keys, the scroll keys only reach the pane while it is focused
     fn event(&mut self, key: Key) -> anyhow::Result<EventState> {
+        if key == self.key_config.toggle_preview {
+            self.toggle_preview();
+            return Ok(EventState::Consumed);
+        }
+        if !self.is_previewing() {
+            return Ok(EventState::NotConsumed);
+        }
+        if key == self.key_config.next_page || key == self.key_config.previous_page {
+            self.turn_page(key == self.key_config.next_page);
+        } else if key == self.key_config.scroll_down || key == self.key_config.scroll_up {
+            self.scroll_preview(key == self.key_config.scroll_down);
+        } else {
+            return Ok(EventState::NotConsumed);
+        }
         Ok(EventState::Consumed)
     }
 }
@@ -1,8 +1,26 @@
-use std::{fs, path::Path};
+use chrono::{DateTime, Utc};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
 
-use crate::domain::pdf_file::PdfFile;
+use crate::{
+    domain::{
+        pdf_file::{check_header, PdfFile},
+        pdf_info::{PdfInfo, PdfInfoCache},
+    },
+    error::{Error, Result},
+};
 
-pub struct PdfFileLoader;
+pub struct PdfFileLoader {
+    /// errors of the entries skipped by the last `load_files`
+    errors: Vec<Error>,
+    /// the folders found by the last `load_tree`, relative to its directory
+    folders: Vec<PathBuf>,
+    /// so that reloading a directory does not parse unchanged files again, `None` if the
+    /// info is left to the caller, see `without_info`
+    info_cache: Option<PdfInfoCache>,
+}
 
 impl Default for PdfFileLoader {
     fn default() -> Self {
@@ -12,42 +30,197 @@ impl Default for PdfFileLoader {
 
 impl PdfFileLoader {
     pub fn new() -> Self {
-        Self {}
+        Self {
+            errors: Vec::new(),
+            folders: Vec::new(),
+            info_cache: Some(PdfInfoCache::default()),
+        }
     }
 
-    fn is_pdf(&mut self, entry: &fs::DirEntry) -> bool {
-        entry
-            .file_name()
-            .to_str()
-            .map(|s| s.ends_with(".pdf"))
-            .unwrap_or(false)
+    /// a loader which reads the info of the files through `info_cache`, e.g. the cache
+    /// saved by a previous run, see `save_info`
+    pub fn with_info(info_cache: PdfInfoCache) -> Self {
+        Self {
+            info_cache: Some(info_cache),
+            ..Self::new()
+        }
     }
 
-    pub fn load_files(&mut self, path: &Path) -> anyhow::Result<Vec<PdfFile>> {
+    /// a loader which leaves the info of the files at its default, for a caller which
+    /// reads it in the background
+    pub fn without_info() -> Self {
+        Self {
+            info_cache: None,
+            ..Self::new()
+        }
+    }
+
+    fn is_pdf(&self, entry: &fs::DirEntry) -> bool {
+        Path::new(&entry.file_name())
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("pdf"))
+    }
+
+    /// loads the pdf files directly under `path`.
+    ///
+    /// fails only if the directory itself cannot be read, entries which cannot be
+    /// loaded are skipped and can be retrieved with `take_errors`
+    pub fn load_files(&mut self, path: &Path) -> Result<Vec<PdfFile>> {
+        self.errors.clear();
         let mut result = Vec::new();
-        // for entry in WalkDir::new(path).into_iter().filter_map(|entry| entry.ok()) {
-        for entry in fs::read_dir(path)? {
-            let e = entry?;
+        self.load_dir(path, None, &mut result)?;
+        Ok(result)
+    }
+
+    /// loads the pdf files under `path` and in the folders nested in it, hidden folders
+    /// are skipped.
+    ///
+    /// fails like `load_files`, the folders can be retrieved with `take_folders`
+    pub fn load_tree(&mut self, path: &Path) -> Result<Vec<PdfFile>> {
+        self.errors.clear();
+        self.folders.clear();
+        let mut result = Vec::new();
+        self.load_dir(path, Some(path), &mut result)?;
+        self.folders.sort();
+        Ok(result)
+    }
+
+    /// the folders found by the last `load_tree`, sorted
+    pub fn take_folders(&mut self) -> Vec<PathBuf> {
+        std::mem::take(&mut self.folders)
+    }
+
+    /// loads the pdf files of `dir`, and of its folders if `root`, the directory the
+    /// tree is loaded from, is given
+    fn load_dir(
+        &mut self,
+        dir: &Path,
+        root: Option<&Path>,
+        result: &mut Vec<PdfFile>,
+    ) -> Result<()> {
+        let entries = fs::read_dir(dir).map_err(|err| Error::reading_dir(dir, err))?;
+        for entry in entries {
+            let e = match entry {
+                Ok(e) => e,
+                Err(err) => {
+                    self.skip(Error::reading_dir(dir, err));
+                    continue;
+                }
+            };
+            let is_dir = e.file_type().map(|file_type| file_type.is_dir());
+            if let (Some(root), Ok(true)) = (root, is_dir) {
+                let path = e.path();
+                if e.file_name().to_string_lossy().starts_with('.') {
+                    continue;
+                }
+                if let Ok(folder) = path.strip_prefix(root) {
+                    self.folders.push(folder.to_path_buf());
+                }
+                // a folder which cannot be read is skipped like a broken file
+                if let Err(err) = self.load_dir(&path, Some(root), result) {
+                    self.skip(err);
+                }
+                continue;
+            }
             if !self.is_pdf(&e) {
                 continue;
             }
-            let pdf_file = self.to_pdf_file(e);
-            result.push(pdf_file)
+            match self.load_file(&e.path()) {
+                Ok(pdf_file) => result.push(pdf_file),
+                Err(err) => self.skip(err),
+            }
         }
-        Ok(result)
+        Ok(())
+    }
+
+    /// saves the info read since the cache was opened, see `PdfInfoCache::save`
+    pub fn save_info(&mut self) -> Result<()> {
+        match &mut self.info_cache {
+            Some(info_cache) => info_cache.save(),
+            None => Ok(()),
+        }
+    }
+
+    pub fn take_errors(&mut self) -> Vec<Error> {
+        std::mem::take(&mut self.errors)
+    }
+
+    fn skip(&mut self, err: Error) {
+        log::warn!("skipped entry: {}", err);
+        self.errors.push(err);
     }
 
-    fn to_pdf_file(&mut self, entry: fs::DirEntry) -> PdfFile {
-        let file_name = entry
+    /// loads a single pdf file, failing if it cannot be read or has no pdf header
+    pub fn load_file(&mut self, path: &Path) -> Result<PdfFile> {
+        let file_name = path
             .file_name()
-            .into_string()
-            .unwrap_or_else(|_| "Invalid file name".to_string());
-        let path = entry.path();
-        let metadata = fs::symlink_metadata(&path);
-
-        match metadata {
-            Ok(metadata) => PdfFile { file_name },
-            Err(_) => todo!(),
+            .and_then(|name| name.to_str())
+            .unwrap_or("Invalid file name")
+            .to_string();
+        let metadata = fs::metadata(path).map_err(|err| Error::reading_file(path, err))?;
+        check_header(path)?;
+
+        let mut pdf_file = PdfFile {
+            file_name,
+            path: path.to_path_buf(),
+            size: metadata.len(),
+            created: metadata.created().ok().map(DateTime::<Utc>::from),
+            modified: metadata.modified().ok().map(DateTime::<Utc>::from),
+            info: PdfInfo::default(),
+        };
+        if let Some(info_cache) = &mut self.info_cache {
+            pdf_file.info = info_cache.get(&pdf_file);
         }
+        Ok(pdf_file)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PdfFileLoader;
+    use crate::error::Error;
+    use std::{fs, path::PathBuf};
+
+    #[test]
+    fn test_load_files_skips_broken_pdf() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("ok.pdf"), b"%PDF-1.7\n%%EOF").unwrap();
+        fs::write(dir.path().join("broken.pdf"), b"<html></html>").unwrap();
+        fs::write(dir.path().join("notes.txt"), b"").unwrap();
+
+        let mut loader = PdfFileLoader::new();
+        let files = loader.load_files(dir.path()).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].file_name, "ok.pdf");
+
+        let errors = loader.take_errors();
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(errors[0], Error::BrokenPdf { .. }));
+    }
+
+    #[test]
+    fn test_load_tree_finds_nested_files_and_folders() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("ml/nlp")).unwrap();
+        fs::create_dir_all(dir.path().join(".trash")).unwrap();
+        fs::create_dir_all(dir.path().join("empty")).unwrap();
+        fs::write(dir.path().join("ml/nlp/a.pdf"), b"%PDF-1.7\n%%EOF").unwrap();
+        fs::write(dir.path().join(".trash/b.pdf"), b"%PDF-1.7\n%%EOF").unwrap();
+
+        let mut loader = PdfFileLoader::new();
+        let files = loader.load_tree(dir.path()).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].path, dir.path().join("ml/nlp/a.pdf"));
+        assert_eq!(
+            loader.take_folders(),
+            ["empty", "ml", "ml/nlp"].map(PathBuf::from)
+        );
+    }
+
+    #[test]
+    fn test_load_files_missing_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let result = PdfFileLoader::new().load_files(&dir.path().join("missing"));
+        assert!(matches!(result, Err(Error::UnreadableDirectory { .. })));
     }
 }
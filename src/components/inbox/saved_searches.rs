@@ -0,0 +1,142 @@
+use std::cmp;
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, BorderType, Borders, List, ListItem, ListState},
+    Frame,
+};
+
+use crate::{
+    components::{
+        command::{self, CommandInfo},
+        utils::vertical_scroll::VerticalScroll,
+        CommandBlocking, Component, DrawableComponent, EventState, ScrollType,
+    },
+    inputs::key::Key,
+    key_config::KeyConfig,
+};
+
+/// A saved search and the number of managed files matching it
+pub struct SavedSearchEntry {
+    pub name: String,
+    pub query: String,
+    pub count: usize,
+}
+
+/// The saved searches, whose selection filters the managed list.
+///
+/// the first row stands for no saved search
+pub struct SavedSearchesComponent {
+    entries: Vec<SavedSearchEntry>,
+    /// 0 selects no saved search, then the entries
+    selection: usize,
+    list_state: ListState,
+    scroll: VerticalScroll,
+    key_config: KeyConfig,
+}
+
+impl SavedSearchesComponent {
+    pub fn new(key_config: KeyConfig) -> Self {
+        Self {
+            entries: Vec::new(),
+            selection: 0,
+            list_state: ListState::default(),
+            scroll: VerticalScroll::new(),
+            key_config,
+        }
+    }
+
+    /// sets the saved searches, the selected one stays selected unless it is gone
+    pub fn set_searches(&mut self, entries: Vec<SavedSearchEntry>) {
+        let selected = self.selected().map(|entry| entry.name.clone());
+        self.entries = entries;
+        self.selection = selected
+            .and_then(|selected| self.entries.iter().position(|e| e.name == selected))
+            .map_or(0, |idx| idx + 1);
+    }
+
+    /// the selected saved search, `None` for the first row
+    pub fn selected(&self) -> Option<&SavedSearchEntry> {
+        let idx = self.selection.checked_sub(1)?;
+        self.entries.get(idx)
+    }
+
+    fn move_selection(&mut self, scroll: ScrollType) -> bool {
+        let new_selection = match scroll {
+            ScrollType::Up => self.selection.saturating_sub(1),
+            ScrollType::Down => cmp::min(self.selection + 1, self.entries.len()),
+        };
+        let changed = new_selection != self.selection;
+        self.selection = new_selection;
+        changed
+    }
+}
+
+impl DrawableComponent for SavedSearchesComponent {
+    fn draw<B: Backend>(
+        &mut self,
+        f: &mut Frame<B>,
+        area: Rect,
+        focused: bool,
+    ) -> anyhow::Result<()> {
+        let dim = Style::default().fg(Color::DarkGray);
+        let none = ListItem::new(Span::styled("No saved search", dim));
+        let items = std::iter::once(none)
+            .chain(self.entries.iter().map(|entry| {
+                ListItem::new(Spans::from(vec![
+                    Span::raw(entry.name.clone()),
+                    Span::styled(format!("  {}", entry.count), dim),
+                ]))
+            }))
+            .collect::<Vec<_>>();
+
+        self.list_state.select(Some(self.selection));
+        let list = List::new(items)
+            .highlight_style(
+                Style::default()
+                    .bg(Color::Cyan)
+                    .fg(Color::White)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Plain)
+                    .border_style(if focused {
+                        Style::default().fg(Color::Cyan)
+                    } else {
+                        Style::default().fg(Color::Gray)
+                    })
+                    .title("Searches"),
+            );
+        f.render_stateful_widget(list, area, &mut self.list_state);
+
+        let visual_height = usize::from(area.height.saturating_sub(2));
+        self.scroll
+            .update(self.selection, self.entries.len() + 1, visual_height);
+        self.scroll.draw(f, area);
+        Ok(())
+    }
+}
+
+impl Component for SavedSearchesComponent {
+    fn commands(&self, out: &mut Vec<CommandInfo>, _force_all: bool) -> CommandBlocking {
+        out.push(CommandInfo::new(command::scroll(&self.key_config)));
+        out.push(CommandInfo::new(command::save_search(&self.key_config)));
+        out.push(CommandInfo::new(command::delete_search(&self.key_config)));
+        CommandBlocking::PassingOn
+    }
+
+    fn event(&mut self, key: Key) -> anyhow::Result<EventState> {
+        let changed = if key == self.key_config.scroll_down {
+            self.move_selection(ScrollType::Down)
+        } else if key == self.key_config.scroll_up {
+            self.move_selection(ScrollType::Up)
+        } else {
+            false
+        };
+        Ok(changed.into())
+    }
+}
@@ -1,28 +1,54 @@
 use tui::{
     backend::Backend,
-    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    layout::{Alignment, Rect},
     style::{Color, Style},
     text::{Span, Spans},
-    widgets::{Block, BorderType, Borders, Cell, Paragraph, Row, Table},
+    widgets::{Block, BorderType, Borders, Paragraph},
     Frame,
 };
 
 use crate::{
-    components::{Component, DrawableComponent, EventState},
+    components::{
+        command::{self, CommandInfo},
+        CommandBlocking, Component, DrawableComponent, EventState,
+    },
+    domain::query::Query,
+    error::{Error, Result},
     inputs::key::Key,
     key_config::KeyConfig,
 };
 
 pub struct SearchbarComponent {
+    query: String,
+    /// `query` parsed after every key, the error is shown under it
+    parsed: Result<Query>,
     key_config: KeyConfig,
 }
 
 impl SearchbarComponent {
     pub fn new(key_config: KeyConfig) -> Self {
         Self {
+            query: String::new(),
+            parsed: Ok(Query::default()),
             key_config: key_config.clone(),
         }
     }
+
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    pub fn parsed(&self) -> std::result::Result<&Query, &Error> {
+        self.parsed.as_ref()
+    }
+
+    /// rows of the searchbar, with the syntax error of the query if there is one
+    pub fn height(&self) -> u16 {
+        match self.parsed {
+            Ok(_) => 3,
+            Err(_) => 4,
+        }
+    }
 }
 
 impl DrawableComponent for SearchbarComponent {
@@ -32,26 +58,69 @@ impl DrawableComponent for SearchbarComponent {
         area: Rect,
         focused: bool,
     ) -> anyhow::Result<()> {
-        let body = Paragraph::new(vec![Spans::from(Span::raw("Search"))])
+        let text = if self.query.is_empty() && !focused {
+            Span::styled("Search", Style::default().fg(Color::DarkGray))
+        } else {
+            Span::raw(self.query.clone())
+        };
+
+        let mut lines = vec![Spans::from(text)];
+        if let Err(err) = &self.parsed {
+            // point at the mistake under the query
+            let line = match err {
+                Error::InvalidQuery { column, reason } => {
+                    format!("{}^ {}", " ".repeat(*column), reason)
+                }
+                err => err.to_string(),
+            };
+            lines.push(Spans::from(Span::styled(
+                line,
+                Style::default().fg(Color::LightRed),
+            )));
+        }
+
+        let body = Paragraph::new(lines)
             .style(Style::default().fg(Color::LightCyan))
             .alignment(Alignment::Left)
             .block(
                 Block::default()
                     .borders(Borders::ALL)
-                    .style(Style::default().fg(Color::White))
+                    .style(if focused {
+                        Style::default().fg(Color::LightCyan)
+                    } else {
+                        Style::default().fg(Color::White)
+                    })
                     .border_type(BorderType::Plain),
             );
 
         f.render_widget(body, area);
 
+        if focused {
+            // place the cursor after the query, inside the border
+            let cursor_x = area.x + 1 + self.query.chars().count() as u16;
+            f.set_cursor(cursor_x.min(area.right().saturating_sub(2)), area.y + 1);
+        }
+
         Ok(())
     }
 }
 
 impl Component for SearchbarComponent {
-    fn commands(&self) {}
+    fn commands(&self, out: &mut Vec<CommandInfo>, _force_all: bool) -> CommandBlocking {
+        out.push(CommandInfo::new(command::submit_search(&self.key_config)));
+        CommandBlocking::PassingOn
+    }
 
     fn event(&mut self, key: Key) -> anyhow::Result<EventState> {
+        match key {
+            Key::Char(c) => self.query.push(c),
+            Key::Backspace => {
+                self.query.pop();
+            }
+            _ if key == self.key_config.exit_popup => self.query.clear(),
+            _ => return Ok(EventState::NotConsumed),
+        }
+        self.parsed = Query::parse(&self.query);
         Ok(EventState::Consumed)
     }
 }
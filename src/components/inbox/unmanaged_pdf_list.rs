@@ -1,4 +1,4 @@
-use std::{cmp, path::Path};
+use std::{cmp, collections::HashMap, path::PathBuf};
 use tui::{
     backend::Backend,
     layout::Rect,
@@ -10,43 +10,198 @@ use tui::{
 
 use crate::{
     components::{
-        utils::{scrollbar::draw_scrollbar, vertical_scroll::VerticalScroll},
-        Component, DrawableComponent, EventState, ScrollType,
+        command::{self, CommandInfo},
+        status_bar::ListStatus,
+        utils::vertical_scroll::VerticalScroll,
+        CommandBlocking, Component, DrawableComponent, EventState, ScrollType,
     },
-    domain::pdf_file::PdfFile,
+    config::tilde_path,
+    domain::{
+        pdf_file::PdfFile, pdf_info::PdfInfo, query::Query, reading::Readings,
+        sort_order::SortOrder,
+    },
+    error::{Error, Result},
     inputs::key::Key,
     key_config::KeyConfig,
 };
 
-use super::pdf_file_loader::PdfFileLoader;
+use super::{
+    marks::{self, Marks},
+    pdf_file_loader::PdfFileLoader,
+};
 
 pub struct UnmanagedPdfListComponent {
     pub pdf_files: Vec<PdfFile>,
+    /// indices into `pdf_files` matching `filter`, in display order
+    filtered_indices: Vec<usize>,
+    filter: String,
+    query: Query,
+    sort_order: SortOrder,
+    /// directory the files are loaded from
+    dir: PathBuf,
     pdf_file_loader: PdfFileLoader,
     list_state: ListState,
     selection: usize,
+    /// the files the bulk operations work on
+    marks: Marks,
     scroll: VerticalScroll,
     key_config: KeyConfig,
 }
 
 impl UnmanagedPdfListComponent {
-    pub fn new(key_config: KeyConfig) -> Self {
+    pub fn new(key_config: KeyConfig, dir: PathBuf) -> Self {
         Self {
             pdf_files: Vec::new(),
-            pdf_file_loader: PdfFileLoader::new(),
+            filtered_indices: Vec::new(),
+            filter: String::new(),
+            query: Query::default(),
+            sort_order: SortOrder::default(),
+            dir,
+            pdf_file_loader: PdfFileLoader::without_info(),
             list_state: ListState::default(),
             selection: 0,
+            marks: Marks::default(),
             scroll: VerticalScroll::new(),
             key_config: key_config.clone(),
         }
     }
 
-    pub fn load_files(&mut self, path: &Path) -> anyhow::Result<Vec<PdfFile>> {
-        self.pdf_file_loader.load_files(path)
+    pub fn load_files(&mut self) -> Result<Vec<PdfFile>> {
+        self.pdf_file_loader.load_files(&self.dir)
+    }
+
+    /// errors of the files skipped by the last `load_files`
+    pub fn take_load_errors(&mut self) -> Vec<Error> {
+        self.pdf_file_loader.take_errors()
     }
 
     pub fn update(&mut self, pdf_files: Vec<PdfFile>) {
         self.pdf_files = pdf_files;
+        let loaded = self
+            .pdf_files
+            .iter()
+            .map(|pdf_file| pdf_file.path.as_path())
+            .collect();
+        self.marks.retain(&loaded);
+        self.sort_order
+            .sort(&mut self.pdf_files, &Readings::default());
+        self.apply_filter();
+    }
+
+    /// sets the info read from the files in the background, the list is filtered again as
+    /// queries may use it
+    pub fn set_info(&mut self, infos: &HashMap<PathBuf, PdfInfo>) {
+        let mut changed = false;
+        for pdf_file in &mut self.pdf_files {
+            if let Some(info) = infos.get(&pdf_file.path) {
+                pdf_file.info = info.clone();
+                changed = true;
+            }
+        }
+        if changed {
+            self.apply_filter();
+        }
+    }
+
+    /// lists the files matching `query`, parsed from `filter`
+    pub fn set_filter(&mut self, filter: &str, query: &Query) {
+        if self.filter == filter {
+            return;
+        }
+        self.filter = filter.to_string();
+        self.query = query.clone();
+        self.apply_filter();
+    }
+
+    pub fn toggle_sort(&mut self) {
+        self.sort_order = self.sort_order.next(false);
+        self.sort_order
+            .sort(&mut self.pdf_files, &Readings::default());
+        self.apply_filter();
+    }
+
+    pub fn selected_file(&self) -> Option<&PdfFile> {
+        self.filtered_indices
+            .get(self.selection)
+            .map(|&idx| &self.pdf_files[idx])
+    }
+
+    pub fn has_marks(&self) -> bool {
+        !self.marks.is_empty()
+    }
+
+    /// the marked files, including the ones filtered out, in display order
+    pub fn marked_files(&self) -> impl Iterator<Item = &PdfFile> {
+        self.pdf_files
+            .iter()
+            .filter(|pdf_file| self.marks.is_marked(&pdf_file.path))
+    }
+
+    /// the paths of the marked files, or of the selected file if none is marked
+    pub fn marked_or_selected(&self) -> Vec<PathBuf> {
+        let files = match self.has_marks() {
+            true => self.marked_files().collect(),
+            false => self.selected_file().into_iter().collect::<Vec<_>>(),
+        };
+        files.iter().map(|pdf_file| pdf_file.path.clone()).collect()
+    }
+
+    pub fn clear_marks(&mut self) {
+        self.marks.clear();
+    }
+
+    /// marks or unmarks rows with the mark keys, returns false for other keys
+    fn mark(&mut self, key: Key) -> bool {
+        let rows = marks::rows(&self.pdf_files, &self.filtered_indices);
+        let key_config = &self.key_config;
+        if key == key_config.toggle_mark {
+            let path = match rows.get(self.selection) {
+                Some(path) => path,
+                None => return false,
+            };
+            self.marks.toggle(path);
+            // the next row follows so that rows are marked by pressing the key again
+            self.selection = cmp::min(self.selection + 1, rows.len() - 1);
+        } else if key == key_config.visual_mark {
+            self.marks.toggle_visual(self.selection, &rows);
+        } else if key == key_config.mark_all {
+            self.marks.toggle_all(&rows);
+        } else if key == key_config.invert_marks {
+            self.marks.invert(&rows);
+        } else if key == key_config.exit_popup && (!self.marks.is_empty() || self.marks.is_visual())
+        {
+            self.marks.clear();
+        } else {
+            return false;
+        }
+        true
+    }
+
+    pub fn list_status(&self) -> ListStatus {
+        ListStatus {
+            selected: self.selected_file().map(|_| self.selection),
+            total: self.pdf_files.len(),
+            filtered: self.filtered_indices.len(),
+            sort_order: self.sort_order,
+            filter: self.filter.clone(),
+            marked: self.marks.len(),
+            visual: self.marks.is_visual(),
+            status_filter: None,
+        }
+    }
+
+    fn apply_filter(&mut self) {
+        self.filtered_indices = self
+            .pdf_files
+            .iter()
+            .enumerate()
+            .filter(|(_, file)| self.query.matches(file, None))
+            .map(|(idx, _)| idx)
+            .collect();
+        self.selection = cmp::min(
+            self.selection,
+            self.filtered_indices.len().saturating_sub(1),
+        );
     }
 
     fn move_selection(&mut self, scroll: ScrollType) -> anyhow::Result<bool> {
@@ -55,7 +210,7 @@ impl UnmanagedPdfListComponent {
             ScrollType::Up => self.selection.saturating_sub(speed_int),
             ScrollType::Down => self.selection.saturating_add(speed_int),
         };
-        let selection_max = self.pdf_files.len().saturating_sub(1);
+        let selection_max = self.filtered_indices.len().saturating_sub(1);
         if selection_max < new_selection {
             return Ok(false);
         }
@@ -74,18 +229,32 @@ impl DrawableComponent for UnmanagedPdfListComponent {
         focused: bool,
     ) -> anyhow::Result<()> {
         let items: Vec<_> = self
-            .pdf_files
+            .filtered_indices
             .iter()
+            .map(|&idx| &self.pdf_files[idx])
             .map(|file| {
-                ListItem::new(Spans::from(vec![Span::styled(
-                    file.file_name.clone(),
-                    Style::default(),
-                )]))
+                let name_style = match self.marks.is_marked(&file.path) {
+                    true => Marks::style(),
+                    false => Style::default(),
+                };
+                let mut spans = self
+                    .marks
+                    .marker(&file.path)
+                    .into_iter()
+                    .collect::<Vec<_>>();
+                spans.push(Span::styled(file.file_name.clone(), name_style));
+                if let Some(pages) = file.info.pages {
+                    spans.push(Span::styled(
+                        format!("  {}p", pages),
+                        Style::default().fg(Color::DarkGray),
+                    ));
+                }
+                ListItem::new(Spans::from(spans))
             })
             .collect();
 
         // TODO: unmanaged directories should be multiple
-        let title = format!("{} {}", "Unmanaged", "[~/Downloads]");
+        let title = format!("{} [{}]", "Unmanaged", tilde_path(&self.dir));
 
         let list_state_idx = Some(self.selection);
         self.list_state.select(list_state_idx);
@@ -110,6 +279,11 @@ impl DrawableComponent for UnmanagedPdfListComponent {
             );
 
         f.render_stateful_widget(list, area, &mut self.list_state);
+
+        // recalculated on every draw so that the max top follows the current area height
+        let visual_height = usize::from(area.height.saturating_sub(2));
+        self.scroll
+            .update(self.selection, self.filtered_indices.len(), visual_height);
         self.scroll.draw(f, area);
 
         Ok(())
@@ -117,7 +291,20 @@ impl DrawableComponent for UnmanagedPdfListComponent {
 }
 
 impl Component for UnmanagedPdfListComponent {
-    fn commands(&self) {}
+    fn commands(&self, out: &mut Vec<CommandInfo>, _force_all: bool) -> CommandBlocking {
+        out.push(CommandInfo::new(command::scroll(&self.key_config)));
+        out.push(CommandInfo::new(command::sort(&self.key_config)));
+        out.push(CommandInfo::new(command::import_pdf(&self.key_config)));
+        out.push(CommandInfo::new(command::open_pdf(&self.key_config)));
+        out.push(CommandInfo::new(command::import_all(&self.key_config)));
+        out.push(CommandInfo::new(command::triage(&self.key_config)));
+        out.push(CommandInfo::new(command::toggle_mark(&self.key_config)));
+        out.push(CommandInfo::new(command::visual_mark(&self.key_config)));
+        out.push(CommandInfo::new(command::mark_all(&self.key_config)));
+        out.push(CommandInfo::new(command::move_files(&self.key_config)));
+        out.push(CommandInfo::new(command::delete_files(&self.key_config)));
+        CommandBlocking::PassingOn
+    }
 
     fn event(&mut self, key: Key) -> anyhow::Result<EventState> {
         // key_config対応
@@ -128,6 +315,13 @@ impl Component for UnmanagedPdfListComponent {
         } else {
             false
         };
+        if selection_changed && self.marks.is_visual() {
+            let rows = marks::rows(&self.pdf_files, &self.filtered_indices);
+            self.marks.extend_visual(self.selection, &rows);
+        }
+        if !selection_changed && self.mark(key) {
+            return Ok(EventState::Consumed);
+        }
 
         match selection_changed {
             true => Ok(EventState::Consumed),
@@ -0,0 +1,134 @@
+use log::LevelFilter;
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    style::{Color, Style},
+    widgets::{Block, BorderType, Borders},
+    Frame,
+};
+use tui_logger::{TuiLoggerLevelOutput, TuiLoggerWidget, TuiWidgetState};
+
+use super::{
+    command::{self, CommandInfo},
+    CommandBlocking, Component, DrawableComponent, EventState,
+};
+use crate::{inputs::key::Key, key_config::KeyConfig};
+
+const LEVELS: [LevelFilter; 5] = [
+    LevelFilter::Error,
+    LevelFilter::Warn,
+    LevelFilter::Info,
+    LevelFilter::Debug,
+    LevelFilter::Trace,
+];
+
+/// Toggleable pane rendering the records of tui-logger
+pub struct LogComponent {
+    visible: bool,
+    display_level: LevelFilter,
+    state: TuiWidgetState,
+    key_config: KeyConfig,
+}
+
+impl LogComponent {
+    pub fn new(key_config: KeyConfig, display_level: LevelFilter) -> Self {
+        // records above the recorded level never reach the pane
+        let display_level = display_level.clamp(LevelFilter::Error, LevelFilter::Trace);
+        Self {
+            visible: false,
+            display_level,
+            state: TuiWidgetState::new().set_default_display_level(display_level),
+            key_config,
+        }
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    fn change_level(&mut self, more_verbose: bool) {
+        let idx = LEVELS
+            .iter()
+            .position(|&level| level == self.display_level)
+            .unwrap_or(0);
+        let idx = if more_verbose {
+            (idx + 1).min(LEVELS.len() - 1)
+        } else {
+            idx.saturating_sub(1)
+        };
+        self.display_level = LEVELS[idx];
+        self.state = TuiWidgetState::new().set_default_display_level(self.display_level);
+    }
+}
+
+impl DrawableComponent for LogComponent {
+    fn draw<B: Backend>(
+        &mut self,
+        f: &mut Frame<B>,
+        area: Rect,
+        focused: bool,
+    ) -> anyhow::Result<()> {
+        if !self.visible {
+            return Ok(());
+        }
+
+        let mut widget = TuiLoggerWidget::default()
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Plain)
+                    .border_style(if focused {
+                        Style::default().fg(Color::Yellow)
+                    } else {
+                        Style::default().fg(Color::Gray)
+                    })
+                    .title(format!("Log [{}]", self.display_level)),
+            )
+            .style_error(Style::default().fg(Color::Red))
+            .style_warn(Style::default().fg(Color::Yellow))
+            .style_info(Style::default().fg(Color::Cyan))
+            .style_debug(Style::default().fg(Color::Green))
+            .style_trace(Style::default().fg(Color::Magenta))
+            .output_level(Some(TuiLoggerLevelOutput::Abbreviated))
+            .output_target(false)
+            .output_file(false)
+            .output_line(false);
+        widget.state(&self.state);
+        f.render_widget(widget, area);
+
+        Ok(())
+    }
+}
+
+impl Component for LogComponent {
+    fn commands(&self, out: &mut Vec<CommandInfo>, _force_all: bool) -> CommandBlocking {
+        out.push(CommandInfo::new(command::toggle_log(&self.key_config)));
+        if self.visible {
+            out.push(CommandInfo::new(command::log_level(&self.key_config)));
+        }
+        CommandBlocking::PassingOn
+    }
+
+    fn event(&mut self, key: Key) -> anyhow::Result<EventState> {
+        if key == self.key_config.toggle_log {
+            self.toggle();
+            return Ok(EventState::Consumed);
+        }
+        if !self.visible {
+            return Ok(EventState::NotConsumed);
+        }
+        if key == self.key_config.log_level_up {
+            self.change_level(true);
+            return Ok(EventState::Consumed);
+        }
+        if key == self.key_config.log_level_down {
+            self.change_level(false);
+            return Ok(EventState::Consumed);
+        }
+        Ok(EventState::NotConsumed)
+    }
+}
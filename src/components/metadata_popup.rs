@@ -0,0 +1,152 @@
+use std::path::PathBuf;
+use tui::backend::Backend;
+use tui::layout::Rect;
+use tui::style::{Color, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, BorderType, Borders, Clear, Paragraph, Wrap};
+use tui::Frame;
+
+use super::{
+    citation_popup::CitationRequest,
+    command::{self, CommandInfo},
+    utils::popup::centered_rect,
+    CommandBlocking, Component, DrawableComponent, EventState,
+};
+use crate::{
+    domain::{citation::Citation, metadata::FieldChange},
+    inputs::key::Key,
+    key_config::KeyConfig,
+};
+
+const LABEL_WIDTH: usize = 9;
+
+/// Shows how fetched metadata changes the citation of a managed pdf file before it is saved
+pub struct MetadataPopup {
+    /// the file and its citation with the fetched metadata applied
+    pending: Option<(PathBuf, Citation)>,
+    title: String,
+    changes: Vec<FieldChange>,
+    request: Option<CitationRequest>,
+    key_config: KeyConfig,
+}
+
+impl MetadataPopup {
+    pub fn new(key_config: KeyConfig) -> Self {
+        Self {
+            pending: None,
+            title: String::new(),
+            changes: Vec::new(),
+            request: None,
+            key_config,
+        }
+    }
+
+    /// `title` names the file and the service, `changes` lead to `citation`
+    pub fn open(
+        &mut self,
+        path: PathBuf,
+        title: String,
+        changes: Vec<FieldChange>,
+        citation: Citation,
+    ) {
+        self.pending = Some((path, citation));
+        self.title = title;
+        self.changes = changes;
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// the citation accepted by the last event, if any
+    pub fn take_request(&mut self) -> Option<CitationRequest> {
+        self.request.take()
+    }
+}
+
+impl DrawableComponent for MetadataPopup {
+    fn draw<B: Backend>(
+        &mut self,
+        f: &mut Frame<B>,
+        _area: Rect,
+        _focused: bool,
+    ) -> anyhow::Result<()> {
+        if !self.is_visible() {
+            return Ok(());
+        }
+
+        let area = centered_rect(70, 50, f.size());
+        f.render_widget(Clear, area);
+
+        let label_style = Style::default().fg(Color::LightCyan);
+        let mut text = Vec::new();
+        for change in &self.changes {
+            let label = format!("{:width$}", change.field, width = LABEL_WIDTH);
+            if !change.old.is_empty() {
+                text.push(Spans::from(vec![
+                    Span::styled(label.clone(), label_style),
+                    Span::styled(
+                        format!("- {}", change.old),
+                        Style::default().fg(Color::LightRed),
+                    ),
+                ]));
+            }
+            let label = if change.old.is_empty() {
+                label
+            } else {
+                " ".repeat(LABEL_WIDTH)
+            };
+            text.push(Spans::from(vec![
+                Span::styled(label, label_style),
+                Span::styled(
+                    format!("+ {}", change.new),
+                    Style::default().fg(Color::LightGreen),
+                ),
+            ]));
+        }
+        text.push(Spans::from(""));
+        text.push(Spans::from(Span::styled(
+            command::apply_metadata(&self.key_config).name,
+            Style::default().fg(Color::Gray),
+        )));
+
+        let body = Paragraph::new(text).wrap(Wrap { trim: false }).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Plain)
+                .border_style(Style::default().fg(Color::Magenta))
+                .title(self.title.clone()),
+        );
+        f.render_widget(body, area);
+        Ok(())
+    }
+}
+
+impl Component for MetadataPopup {
+    fn commands(&self, out: &mut Vec<CommandInfo>, _force_all: bool) -> CommandBlocking {
+        if !self.is_visible() {
+            return CommandBlocking::PassingOn;
+        }
+        out.push(CommandInfo::new(command::apply_metadata(&self.key_config)));
+        CommandBlocking::Blocking
+    }
+
+    fn event(&mut self, key: Key) -> anyhow::Result<EventState> {
+        if !self.is_visible() {
+            return Ok(EventState::NotConsumed);
+        }
+
+        if key == self.key_config.exit_popup {
+            self.pending = None;
+        } else if key == self.key_config.enter {
+            if let Some((path, citation)) = self.pending.take() {
+                self.request = Some(CitationRequest { path, citation });
+            }
+        }
+        Ok(EventState::Consumed)
+    }
+
+    fn focused(&self) -> bool {
+        self.is_visible()
+    }
+}
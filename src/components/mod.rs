@@ -1,12 +1,31 @@
+pub mod batch_import_popup;
+pub mod bib_import_popup;
+pub mod bibliography_popup;
+pub mod bulk_popup;
+pub mod citation_popup;
+pub mod collection_popup;
 pub mod command;
+pub mod confirm_popup;
+pub mod error_popup;
+pub mod folder_popup;
+pub mod help;
 pub mod inbox;
+pub mod log;
+pub mod metadata_popup;
+pub mod organize_popup;
 pub mod pdf_import_popup;
+pub mod popups;
+pub mod save_search_popup;
+pub mod status_bar;
+pub mod triage_popup;
 pub mod utils;
 
 use tui::{backend::Backend, layout::Rect, Frame};
 
 use crate::inputs::key::Key;
 
+use self::command::CommandInfo;
+
 pub trait DrawableComponent {
     fn draw<B: Backend>(
         &mut self,
@@ -17,7 +36,9 @@ pub trait DrawableComponent {
 }
 
 pub trait Component {
-    fn commands(&self);
+    /// collects the commands available in the current context into `out`.
+    /// if `force_all` is true, commands of unfocused children are collected as well
+    fn commands(&self, out: &mut Vec<CommandInfo>, force_all: bool) -> CommandBlocking;
     fn event(&mut self, key: Key) -> anyhow::Result<EventState>;
     fn focused(&self) -> bool {
         false
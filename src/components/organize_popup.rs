@@ -0,0 +1,257 @@
+use std::path::{Path, PathBuf};
+use tui::backend::Backend;
+use tui::layout::{Constraint, Direction, Layout, Rect};
+use tui::style::{Color, Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, BorderType, Borders, Clear, List, ListItem, ListState, Paragraph};
+use tui::Frame;
+
+use super::{
+    command::{self, CommandInfo},
+    utils::{popup::centered_rect, vertical_scroll::VerticalScroll},
+    CommandBlocking, Component, DrawableComponent, EventState,
+};
+use crate::{
+    domain::{
+        library_store::LibraryStore,
+        organize::{OrganizePlan, PlannedMove},
+    },
+    error::Result,
+    inputs::key::Key,
+    key_config::KeyConfig,
+};
+
+/// A step confirmed by the user, performed by the app
+pub enum OrganizeRequest {
+    /// move the planned documents, then call `show_results`
+    Execute(OrganizePlan),
+}
+
+enum Stage {
+    Closed,
+    /// reviewing the planned moves before they are made
+    Review(OrganizePlan),
+    /// the old path of each moved document with its new path or why it failed
+    Results(Vec<(PathBuf, std::result::Result<PathBuf, String>)>),
+}
+
+/// Files the whole library by the organize policy of the config: shows the planned
+/// moves, then the result of each document
+pub struct OrganizePopup {
+    stage: Stage,
+    /// the paths are shown relative to it
+    managed_dir: PathBuf,
+    request: Option<OrganizeRequest>,
+    list_state: ListState,
+    selection: usize,
+    scroll: VerticalScroll,
+    key_config: KeyConfig,
+}
+
+impl OrganizePopup {
+    pub fn new(key_config: KeyConfig, managed_dir: PathBuf) -> Self {
+        Self {
+            stage: Stage::Closed,
+            managed_dir,
+            request: None,
+            list_state: ListState::default(),
+            selection: 0,
+            scroll: VerticalScroll::new(),
+            key_config,
+        }
+    }
+
+    pub fn review(&mut self, plan: OrganizePlan) {
+        self.selection = 0;
+        self.stage = Stage::Review(plan);
+    }
+
+    /// shows the results of the plan requested by `OrganizeRequest::Execute`
+    pub fn show_results(&mut self, results: Vec<(PathBuf, Result<PathBuf>)>) {
+        self.selection = 0;
+        let results = results
+            .into_iter()
+            .map(|(src, result)| (src, result.map_err(|err| err.to_string())))
+            .collect();
+        self.stage = Stage::Results(results);
+    }
+
+    pub fn is_visible(&self) -> bool {
+        !matches!(self.stage, Stage::Closed)
+    }
+
+    /// the step confirmed by the last event, if any
+    pub fn take_request(&mut self) -> Option<OrganizeRequest> {
+        self.request.take()
+    }
+
+    fn len(&self) -> usize {
+        match &self.stage {
+            Stage::Review(plan) => plan.files.len(),
+            Stage::Results(results) => results.len(),
+            Stage::Closed => 0,
+        }
+    }
+
+    fn relative(&self, path: &Path) -> String {
+        LibraryStore::key_for(&self.managed_dir, path).unwrap_or_else(|| path.display().to_string())
+    }
+
+    fn plan_items(&self, plan: &OrganizePlan) -> Vec<ListItem<'static>> {
+        let dim = Style::default().fg(Color::DarkGray);
+        plan.files
+            .iter()
+            .map(|(src, action)| {
+                let spans = match action {
+                    PlannedMove::Move(dest) => vec![
+                        Span::styled("→ ", Style::default().fg(Color::LightGreen)),
+                        Span::raw(format!("{} → {}", self.relative(src), self.relative(dest))),
+                    ],
+                    PlannedMove::Stay(reason) => vec![
+                        Span::styled("– ", dim),
+                        Span::raw(self.relative(src)),
+                        Span::styled(format!("  stays: {}", reason), dim),
+                    ],
+                };
+                ListItem::new(Spans::from(spans))
+            })
+            .collect()
+    }
+
+    fn result_items(
+        &self,
+        results: &[(PathBuf, std::result::Result<PathBuf, String>)],
+    ) -> Vec<ListItem<'static>> {
+        results
+            .iter()
+            .map(|(src, result)| {
+                let spans = match result {
+                    Ok(dest) => vec![
+                        Span::styled("✓ ", Style::default().fg(Color::LightGreen)),
+                        Span::raw(format!("{} → {}", self.relative(src), self.relative(dest))),
+                    ],
+                    Err(err) => vec![
+                        Span::styled("✗ ", Style::default().fg(Color::LightRed)),
+                        Span::raw(format!("{}: {}", self.relative(src), err)),
+                    ],
+                };
+                ListItem::new(Spans::from(spans))
+            })
+            .collect()
+    }
+}
+
+impl DrawableComponent for OrganizePopup {
+    fn draw<B: Backend>(
+        &mut self,
+        f: &mut Frame<B>,
+        _area: Rect,
+        _focused: bool,
+    ) -> anyhow::Result<()> {
+        let (title, items, help) = match &self.stage {
+            Stage::Closed => return Ok(()),
+            Stage::Review(plan) if plan.files.is_empty() => (
+                "Organize".to_string(),
+                vec![ListItem::new("Every document is in its folder")],
+                command::exit_popup(&self.key_config).name,
+            ),
+            Stage::Review(plan) => {
+                let moves = plan.moves().count();
+                let title = format!(
+                    "Organize: {} to move, {} staying",
+                    moves,
+                    plan.files.len() - moves
+                );
+                (
+                    title,
+                    self.plan_items(plan),
+                    command::execute_organize(&self.key_config).name,
+                )
+            }
+            Stage::Results(results) => {
+                let moved = results.iter().filter(|(_, result)| result.is_ok()).count();
+                let title = format!("Moved {} of {} documents", moved, results.len());
+                (
+                    title,
+                    self.result_items(results),
+                    command::exit_popup(&self.key_config).name,
+                )
+            }
+        };
+
+        let area = centered_rect(80, 70, f.size());
+        f.render_widget(Clear, area);
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Plain)
+            .border_style(Style::default().fg(Color::Magenta))
+            .title(title);
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Length(1)].as_ref())
+            .split(inner);
+
+        let len = items.len();
+        self.list_state.select(Some(self.selection));
+        let list = List::new(items).highlight_style(Style::default().add_modifier(Modifier::BOLD));
+        f.render_stateful_widget(list, chunks[0], &mut self.list_state);
+
+        self.scroll
+            .update(self.selection, len, usize::from(chunks[0].height));
+        self.scroll.draw(f, area);
+
+        f.render_widget(
+            Paragraph::new(Span::styled(help, Style::default().fg(Color::Gray))),
+            chunks[1],
+        );
+        Ok(())
+    }
+}
+
+impl Component for OrganizePopup {
+    fn commands(&self, out: &mut Vec<CommandInfo>, _force_all: bool) -> CommandBlocking {
+        match self.stage {
+            Stage::Closed => return CommandBlocking::PassingOn,
+            Stage::Review(_) => {
+                out.push(CommandInfo::new(command::scroll(&self.key_config)));
+                out.push(CommandInfo::new(command::execute_organize(
+                    &self.key_config,
+                )));
+            }
+            Stage::Results(_) => {
+                out.push(CommandInfo::new(command::scroll(&self.key_config)));
+                out.push(CommandInfo::new(command::exit_popup(&self.key_config)));
+            }
+        }
+        CommandBlocking::Blocking
+    }
+
+    fn event(&mut self, key: Key) -> anyhow::Result<EventState> {
+        if !self.is_visible() {
+            return Ok(EventState::NotConsumed);
+        }
+        if key == self.key_config.exit_popup {
+            self.stage = Stage::Closed;
+        } else if key == self.key_config.scroll_down {
+            self.selection = (self.selection + 1).min(self.len().saturating_sub(1));
+        } else if key == self.key_config.scroll_up {
+            self.selection = self.selection.saturating_sub(1);
+        } else if key == self.key_config.enter {
+            if let Stage::Review(plan) = &mut self.stage {
+                if plan.moves().next().is_some() {
+                    let plan = std::mem::take(plan);
+                    self.stage = Stage::Closed;
+                    self.request = Some(OrganizeRequest::Execute(plan));
+                }
+            }
+        }
+        Ok(EventState::Consumed)
+    }
+
+    fn focused(&self) -> bool {
+        self.is_visible()
+    }
+}
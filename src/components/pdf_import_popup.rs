@@ -1,36 +1,269 @@
+use std::path::{Path, PathBuf};
 use tui::backend::Backend;
 use tui::layout::Rect;
+use tui::style::{Color, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, BorderType, Borders, Clear, Paragraph};
 use tui::Frame;
 
-use super::{Component, DrawableComponent, EventState};
-use crate::inputs::key::Key;
+use super::{
+    command::{self, CommandInfo},
+    utils::popup::centered_rect,
+    CommandBlocking, Component, DrawableComponent, EventState,
+};
+use crate::{
+    domain::{identifiers::Identifiers, pdf_file::PdfFile},
+    inputs::key::Key,
+    key_config::KeyConfig,
+};
 
-pub struct PdfImportPopup {}
+/// An import confirmed by the user, performed by the app
+pub struct ImportRequest {
+    pub src: PathBuf,
+    /// relative to the managed directory, which is the empty path
+    pub folder: PathBuf,
+    pub name: String,
+    /// the identifiers scanned after the popup was opened, `None` if the scan failed or
+    /// was not done yet
+    pub identifiers: Option<Identifiers>,
+}
+
+/// What the app found out about the file to import
+#[derive(Default)]
+pub struct ImportHints {
+    pub identifiers: Option<Identifiers>,
+    /// the managed file with the same identifiers, with the identifier they share
+    pub duplicate_of: Option<String>,
+}
+
+/// Asks for the name of an unmanaged pdf file before it is moved into the library
+pub struct PdfImportPopup {
+    src: Option<PathBuf>,
+    name: String,
+    /// the folders of the managed directory the file can be imported into, the first is
+    /// the managed directory itself
+    folders: Vec<PathBuf>,
+    /// the chosen one of `folders`
+    folder: usize,
+    /// `None` while the app scans the file
+    hints: Option<ImportHints>,
+    request: Option<ImportRequest>,
+    key_config: KeyConfig,
+}
 
 impl PdfImportPopup {
-    pub fn new() -> Self {
-        Self {}
+    pub fn new(key_config: KeyConfig) -> Self {
+        Self {
+            src: None,
+            name: String::new(),
+            folders: Vec::new(),
+            folder: 0,
+            hints: None,
+            request: None,
+            key_config,
+        }
     }
 
-    pub fn open(&mut self) -> anyhow::Result<()> {
+    /// asks where to import `pdf_file`, into `folder` unless another one of `folders` is
+    /// chosen. the file can be imported before the hints of its scan are set
+    pub fn open(
+        &mut self,
+        pdf_file: &PdfFile,
+        folders: Vec<PathBuf>,
+        folder: &Path,
+    ) -> anyhow::Result<()> {
+        self.src = Some(pdf_file.path.clone());
+        self.name = pdf_file.file_name.clone();
+        self.folders = std::iter::once(PathBuf::new()).chain(folders).collect();
+        self.folder = self
+            .folders
+            .iter()
+            .position(|f| f == folder)
+            .unwrap_or_default();
+        self.hints = None;
         Ok(())
     }
+
+    /// whether the popup waits for the scan of the file at `path`
+    pub fn is_scanning(&self, path: &Path) -> bool {
+        self.src.as_deref() == Some(path) && self.hints.is_none()
+    }
+
+    /// sets what the app found out about the file to import
+    pub fn set_hints(&mut self, hints: ImportHints) {
+        self.hints = Some(hints);
+    }
+
+    fn folder(&self) -> &Path {
+        self.folders
+            .get(self.folder)
+            .map_or_else(|| Path::new(""), PathBuf::as_path)
+    }
+
+    fn suggested_name(&self) -> Option<String> {
+        self.hints
+            .as_ref()?
+            .identifiers
+            .as_ref()
+            .and_then(Identifiers::suggested_file_name)
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.src.is_some()
+    }
+
+    /// the import confirmed by the last event, if any
+    pub fn take_request(&mut self) -> Option<ImportRequest> {
+        self.request.take()
+    }
+
+    fn close(&mut self) {
+        self.src = None;
+        self.name.clear();
+        self.folders.clear();
+        self.hints = None;
+    }
 }
 
 impl DrawableComponent for PdfImportPopup {
     fn draw<B: Backend>(
         &mut self,
         f: &mut Frame<B>,
-        area: Rect,
-        focused: bool,
+        _area: Rect,
+        _focused: bool,
     ) -> anyhow::Result<()> {
+        let src = match &self.src {
+            Some(src) => src,
+            None => return Ok(()),
+        };
+
+        let area = centered_rect(60, 30, f.size());
+        f.render_widget(Clear, area);
+
+        let label_style = Style::default().fg(Color::LightCyan);
+        let hint_style = Style::default().fg(Color::Gray);
+        let mut text = vec![
+            Spans::from(vec![
+                Span::styled("From: ", label_style),
+                Span::raw(src.display().to_string()),
+            ]),
+            Spans::from(vec![
+                Span::styled("Name: ", label_style),
+                Span::raw(self.name.clone()),
+            ]),
+            Spans::from(vec![
+                Span::styled("Into: ", label_style),
+                Span::raw(match self.folder().as_os_str().is_empty() {
+                    true => "the library".to_string(),
+                    false => format!("{}/", self.folder().display()),
+                }),
+                Span::styled(
+                    format!("  {}/{}", self.folder + 1, self.folders.len()),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ]),
+            Spans::from(""),
+        ];
+        match &self.hints {
+            None => text.push(Spans::from(Span::styled(
+                "Scanning for identifiers…",
+                Style::default().fg(Color::DarkGray),
+            ))),
+            Some(ImportHints {
+                duplicate_of: Some(duplicate_of),
+                ..
+            }) => text.push(Spans::from(Span::styled(
+                format!("Already in the library: {}", duplicate_of),
+                Style::default().fg(Color::LightRed),
+            ))),
+            Some(_) => {}
+        }
+        if let Some(suggested_name) = self.suggested_name() {
+            text.push(Spans::from(Span::styled(
+                format!(
+                    "{}: {}",
+                    command::use_suggested_name(&self.key_config).name,
+                    suggested_name
+                ),
+                hint_style,
+            )));
+        }
+        if self.folders.len() > 1 {
+            text.push(Spans::from(Span::styled(
+                command::choose_folder(&self.key_config).name,
+                hint_style,
+            )));
+        }
+        text.push(Spans::from(Span::styled(
+            command::confirm_import(&self.key_config).name,
+            hint_style,
+        )));
+        let body = Paragraph::new(text).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Plain)
+                .border_style(Style::default().fg(Color::Magenta))
+                .title("Import"),
+        );
+        f.render_widget(body, area);
+
+        let cursor_x = area.x + 1 + "Name: ".len() as u16 + self.name.chars().count() as u16;
+        f.set_cursor(cursor_x.min(area.right().saturating_sub(2)), area.y + 2);
+
         Ok(())
     }
 }
 
 impl Component for PdfImportPopup {
-    fn commands(&self) {}
+    fn commands(&self, out: &mut Vec<CommandInfo>, _force_all: bool) -> CommandBlocking {
+        if !self.is_visible() {
+            return CommandBlocking::PassingOn;
+        }
+        out.push(CommandInfo::new(command::confirm_import(&self.key_config)));
+        if self.folders.len() > 1 {
+            out.push(CommandInfo::new(command::choose_folder(&self.key_config)));
+        }
+        if self.suggested_name().is_some() {
+            out.push(CommandInfo::new(command::use_suggested_name(
+                &self.key_config,
+            )));
+        }
+        CommandBlocking::Blocking
+    }
+
     fn event(&mut self, key: Key) -> anyhow::Result<EventState> {
-        Ok(EventState::NotConsumed)
+        if !self.is_visible() {
+            return Ok(EventState::NotConsumed);
+        }
+
+        if key == self.key_config.exit_popup {
+            self.close();
+        } else if key == self.key_config.enter {
+            if let Some(src) = self.src.take() {
+                self.request = Some(ImportRequest {
+                    src,
+                    folder: self.folder().to_path_buf(),
+                    name: std::mem::take(&mut self.name),
+                    identifiers: self.hints.take().and_then(|hints| hints.identifiers),
+                });
+            }
+        } else if key == self.key_config.focus_down && !self.folders.is_empty() {
+            self.folder = (self.folder + 1) % self.folders.len();
+        } else if key == self.key_config.focus_up && !self.folders.is_empty() {
+            self.folder = (self.folder + self.folders.len() - 1) % self.folders.len();
+        } else if key == Key::Tab {
+            if let Some(suggested_name) = self.suggested_name() {
+                self.name = suggested_name;
+            }
+        } else if let Key::Char(c) = key {
+            self.name.push(c);
+        } else if key == Key::Backspace {
+            self.name.pop();
+        }
+        Ok(EventState::Consumed)
+    }
+
+    fn focused(&self) -> bool {
+        self.is_visible()
     }
 }
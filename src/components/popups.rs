@@ -0,0 +1,191 @@
+use std::path::PathBuf;
+
+use tui::{backend::Backend, Frame};
+
+use super::{
+    batch_import_popup::{BatchImportPopup, BatchImportRequest},
+    bib_import_popup::{BibImportPopup, BibImportRequest},
+    bibliography_popup::{BibliographyPopup, BibliographyRequest},
+    bulk_popup::{BulkPopup, BulkRequest},
+    citation_popup::{CitationPopup, CitationRequest},
+    collection_popup::{CollectionPopup, CollectionRequest},
+    command::CommandInfo,
+    confirm_popup::{ConfirmPopup, ConfirmRequest},
+    folder_popup::{FolderPopup, FolderRequest},
+    metadata_popup::MetadataPopup,
+    organize_popup::{OrganizePopup, OrganizeRequest},
+    pdf_import_popup::{ImportRequest, PdfImportPopup},
+    save_search_popup::{SaveSearchPopup, SaveSearchRequest},
+    triage_popup::{TriagePopup, TriageRequest},
+    CommandBlocking, Component, DrawableComponent, EventState,
+};
+use crate::{inputs::key::Key, key_config::KeyConfig};
+
+/// What the user confirmed in one of the popups, carried out by the app
+pub enum PopupRequest {
+    Import(ImportRequest),
+    /// from the citation editor or the fetched metadata
+    Citation(CitationRequest),
+    BibImport(BibImportRequest),
+    Bibliography(BibliographyRequest),
+    Collection(CollectionRequest),
+    SaveSearch(SaveSearchRequest),
+    Bulk(BulkRequest),
+    BatchImport(BatchImportRequest),
+    Folder(FolderRequest),
+    Organize(OrganizeRequest),
+    Triage(TriageRequest),
+    Confirm(ConfirmRequest),
+}
+
+/// The popups drawn over the inbox which ask the user for a request, at most one of them
+/// is open at a time.
+///
+/// the help and the error popups are not among them, they ask for nothing
+pub struct Popups {
+    pub pdf_import: PdfImportPopup,
+    pub citation: CitationPopup,
+    pub metadata: MetadataPopup,
+    pub bib_import: BibImportPopup,
+    pub bibliography: BibliographyPopup,
+    pub collection: CollectionPopup,
+    pub save_search: SaveSearchPopup,
+    pub bulk: BulkPopup,
+    pub batch_import: BatchImportPopup,
+    pub folder: FolderPopup,
+    pub organize: OrganizePopup,
+    pub triage: TriagePopup,
+    pub confirm: ConfirmPopup,
+}
+
+impl Popups {
+    pub fn new(key_config: &KeyConfig, managed_dir: PathBuf) -> Self {
+        Self {
+            pdf_import: PdfImportPopup::new(key_config.clone()),
+            citation: CitationPopup::new(key_config.clone()),
+            metadata: MetadataPopup::new(key_config.clone()),
+            bib_import: BibImportPopup::new(key_config.clone()),
+            bibliography: BibliographyPopup::new(key_config.clone()),
+            collection: CollectionPopup::new(key_config.clone()),
+            save_search: SaveSearchPopup::new(key_config.clone()),
+            bulk: BulkPopup::new(key_config.clone()),
+            batch_import: BatchImportPopup::new(key_config.clone(), managed_dir.clone()),
+            folder: FolderPopup::new(key_config.clone()),
+            organize: OrganizePopup::new(key_config.clone(), managed_dir),
+            triage: TriagePopup::new(key_config.clone()),
+            confirm: ConfirmPopup::new(key_config.clone()),
+        }
+    }
+
+    /// the popups in the order they take events
+    fn components(&self) -> [&dyn Component; 13] {
+        [
+            &self.pdf_import,
+            &self.citation,
+            &self.metadata,
+            &self.bib_import,
+            &self.bibliography,
+            &self.collection,
+            &self.save_search,
+            &self.bulk,
+            &self.batch_import,
+            &self.folder,
+            &self.organize,
+            &self.triage,
+            &self.confirm,
+        ]
+    }
+
+    fn components_mut(&mut self) -> [&mut dyn Component; 13] {
+        [
+            &mut self.pdf_import,
+            &mut self.citation,
+            &mut self.metadata,
+            &mut self.bib_import,
+            &mut self.bibliography,
+            &mut self.collection,
+            &mut self.save_search,
+            &mut self.bulk,
+            &mut self.batch_import,
+            &mut self.folder,
+            &mut self.organize,
+            &mut self.triage,
+            &mut self.confirm,
+        ]
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.components().iter().any(|popup| popup.focused())
+    }
+
+    /// draws the open popup over the whole frame
+    pub fn draw<B: Backend>(&mut self, f: &mut Frame<B>) -> anyhow::Result<()> {
+        let size = f.size();
+        self.pdf_import.draw(f, size, false)?;
+        self.citation.draw(f, size, false)?;
+        self.metadata.draw(f, size, false)?;
+        self.bib_import.draw(f, size, false)?;
+        self.bibliography.draw(f, size, false)?;
+        self.collection.draw(f, size, false)?;
+        self.save_search.draw(f, size, false)?;
+        self.bulk.draw(f, size, false)?;
+        self.batch_import.draw(f, size, false)?;
+        self.folder.draw(f, size, false)?;
+        self.organize.draw(f, size, false)?;
+        self.triage.draw(f, size, false)?;
+        self.confirm.draw(f, size, false)?;
+        Ok(())
+    }
+
+    /// the commands of the open popup, which block the commands of the panes below
+    pub fn commands(&self, out: &mut Vec<CommandInfo>) -> CommandBlocking {
+        for popup in self.components() {
+            if popup.commands(out, false) == CommandBlocking::Blocking {
+                return CommandBlocking::Blocking;
+            }
+        }
+        CommandBlocking::PassingOn
+    }
+
+    /// passes `key` to the open popup, the request it confirmed is taken with
+    /// `take_request`
+    pub fn event(&mut self, key: Key) -> anyhow::Result<EventState> {
+        for popup in self.components_mut() {
+            if popup.event(key)?.is_consumed() {
+                return Ok(EventState::Consumed);
+            }
+        }
+        Ok(EventState::NotConsumed)
+    }
+
+    /// the request confirmed by the last event, if any
+    pub fn take_request(&mut self) -> Option<PopupRequest> {
+        self.pdf_import
+            .take_request()
+            .map(PopupRequest::Import)
+            .or_else(|| self.citation.take_request().map(PopupRequest::Citation))
+            .or_else(|| self.metadata.take_request().map(PopupRequest::Citation))
+            .or_else(|| self.bib_import.take_request().map(PopupRequest::BibImport))
+            .or_else(|| {
+                self.bibliography
+                    .take_request()
+                    .map(PopupRequest::Bibliography)
+            })
+            .or_else(|| self.collection.take_request().map(PopupRequest::Collection))
+            .or_else(|| {
+                self.save_search
+                    .take_request()
+                    .map(PopupRequest::SaveSearch)
+            })
+            .or_else(|| self.bulk.take_request().map(PopupRequest::Bulk))
+            .or_else(|| {
+                self.batch_import
+                    .take_request()
+                    .map(PopupRequest::BatchImport)
+            })
+            .or_else(|| self.folder.take_request().map(PopupRequest::Folder))
+            .or_else(|| self.organize.take_request().map(PopupRequest::Organize))
+            .or_else(|| self.triage.take_request().map(PopupRequest::Triage))
+            .or_else(|| self.confirm.take_request().map(PopupRequest::Confirm))
+    }
+}
@@ -0,0 +1,157 @@
+use tui::backend::Backend;
+use tui::layout::Rect;
+use tui::style::{Color, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, BorderType, Borders, Clear, Paragraph};
+use tui::Frame;
+
+use super::{
+    command::{self, CommandInfo},
+    utils::popup::centered_rect,
+    CommandBlocking, Component, DrawableComponent, EventState,
+};
+use crate::{inputs::key::Key, key_config::KeyConfig};
+
+const LABEL_WIDTH: usize = 7;
+
+/// A query to save under a name, saved by the app
+pub struct SaveSearchRequest {
+    pub name: String,
+    pub query: String,
+}
+
+/// Asks the name a searchbar query is saved under
+pub struct SaveSearchPopup {
+    /// the query to save, `None` while closed
+    query: Option<String>,
+    name: String,
+    message: Option<String>,
+    request: Option<SaveSearchRequest>,
+    key_config: KeyConfig,
+}
+
+impl SaveSearchPopup {
+    pub fn new(key_config: KeyConfig) -> Self {
+        Self {
+            query: None,
+            name: String::new(),
+            message: None,
+            request: None,
+            key_config,
+        }
+    }
+
+    pub fn open(&mut self, query: String) {
+        self.query = Some(query);
+        self.name.clear();
+        self.message = None;
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.query.is_some()
+    }
+
+    /// the search saved by the last event, if any
+    pub fn take_request(&mut self) -> Option<SaveSearchRequest> {
+        self.request.take()
+    }
+}
+
+impl DrawableComponent for SaveSearchPopup {
+    fn draw<B: Backend>(
+        &mut self,
+        f: &mut Frame<B>,
+        _area: Rect,
+        _focused: bool,
+    ) -> anyhow::Result<()> {
+        let query = match &self.query {
+            Some(query) => query,
+            None => return Ok(()),
+        };
+
+        let area = centered_rect(50, 20, f.size());
+        f.render_widget(Clear, area);
+
+        let label_style = Style::default().fg(Color::LightCyan);
+        let label = |label: &str| {
+            Span::styled(
+                format!("{:width$}", label, width = LABEL_WIDTH),
+                label_style,
+            )
+        };
+        let mut text = vec![
+            Spans::from(vec![label("Name:"), Span::raw(self.name.clone())]),
+            Spans::from(vec![
+                label("Query:"),
+                Span::styled(query.clone(), Style::default().fg(Color::DarkGray)),
+            ]),
+            Spans::from(""),
+        ];
+        if let Some(message) = &self.message {
+            text.push(Spans::from(Span::styled(
+                message.clone(),
+                Style::default().fg(Color::LightRed),
+            )));
+        }
+        text.push(Spans::from(Span::styled(
+            command::save_search_name(&self.key_config).name,
+            Style::default().fg(Color::Gray),
+        )));
+
+        let body = Paragraph::new(text).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Plain)
+                .border_style(Style::default().fg(Color::Magenta))
+                .title("Save search"),
+        );
+        f.render_widget(body, area);
+
+        let cursor_x = area.x + 1 + (LABEL_WIDTH + self.name.chars().count()) as u16;
+        f.set_cursor(cursor_x.min(area.right().saturating_sub(2)), area.y + 1);
+        Ok(())
+    }
+}
+
+impl Component for SaveSearchPopup {
+    fn commands(&self, out: &mut Vec<CommandInfo>, _force_all: bool) -> CommandBlocking {
+        if !self.is_visible() {
+            return CommandBlocking::PassingOn;
+        }
+        out.push(CommandInfo::new(command::save_search_name(
+            &self.key_config,
+        )));
+        CommandBlocking::Blocking
+    }
+
+    fn event(&mut self, key: Key) -> anyhow::Result<EventState> {
+        if !self.is_visible() {
+            return Ok(EventState::NotConsumed);
+        }
+
+        if key == self.key_config.exit_popup {
+            self.query = None;
+        } else if key == self.key_config.enter {
+            let name = self.name.trim();
+            if name.is_empty() {
+                self.message = Some("the name cannot be empty".to_string());
+            } else if let Some(query) = self.query.take() {
+                self.request = Some(SaveSearchRequest {
+                    name: name.to_string(),
+                    query,
+                });
+            }
+        } else if let Key::Char(c) = key {
+            self.name.push(c);
+            self.message = None;
+        } else if key == Key::Backspace {
+            self.name.pop();
+            self.message = None;
+        }
+        Ok(EventState::Consumed)
+    }
+
+    fn focused(&self) -> bool {
+        self.is_visible()
+    }
+}
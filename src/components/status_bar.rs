@@ -0,0 +1,163 @@
+use std::time::{Duration, Instant};
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::Paragraph,
+    Frame,
+};
+
+use super::{command::CommandInfo, inbox::marks::Marks, DrawableComponent};
+use crate::domain::{reading::ReadingStatus, sort_order::SortOrder};
+
+/// how long a message stays in the status bar
+const MESSAGE_TTL: Duration = Duration::from_secs(5);
+
+/// summary of the list the user is working with
+pub struct ListStatus {
+    pub selected: Option<usize>,
+    pub total: usize,
+    pub filtered: usize,
+    pub sort_order: SortOrder,
+    pub filter: String,
+    /// the number of files marked for the bulk operations
+    pub marked: usize,
+    /// whether the rows passed by the selection are marked
+    pub visual: bool,
+    /// only the files with this reading status are listed
+    pub status_filter: Option<ReadingStatus>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum MessageKind {
+    Info,
+    Error,
+}
+
+struct Message {
+    kind: MessageKind,
+    text: String,
+    expires_at: Instant,
+}
+
+/// Footer showing the commands of the focused pane, the list status and transient messages
+#[derive(Default)]
+pub struct StatusBarComponent {
+    cmds: Vec<CommandInfo>,
+    list_status: Option<ListStatus>,
+    message: Option<Message>,
+}
+
+impl StatusBarComponent {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_cmds(&mut self, cmds: Vec<CommandInfo>) {
+        self.cmds = cmds;
+    }
+
+    pub fn set_list_status(&mut self, list_status: ListStatus) {
+        self.list_status = Some(list_status);
+    }
+
+    pub fn show_info(&mut self, text: impl Into<String>) {
+        self.show_message(MessageKind::Info, text.into());
+    }
+
+    pub fn show_error(&mut self, text: impl Into<String>) {
+        self.show_message(MessageKind::Error, text.into());
+    }
+
+    fn show_message(&mut self, kind: MessageKind, text: String) {
+        self.message = Some(Message {
+            kind,
+            text,
+            expires_at: Instant::now() + MESSAGE_TTL,
+        });
+    }
+
+    /// drops the current message once it has expired, returns true if it did
+    pub fn tick(&mut self) -> bool {
+        match &self.message {
+            Some(message) if message.expires_at <= Instant::now() => {
+                self.message = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn commands_line(&self) -> Spans<'_> {
+        let key_style = Style::default().fg(Color::Gray);
+        let mut spans = Vec::new();
+        for (i, cmd) in self.cmds.iter().enumerate() {
+            if i > 0 {
+                spans.push(Span::styled(" | ", Style::default().fg(Color::DarkGray)));
+            }
+            spans.push(Span::styled(cmd.text.name.clone(), key_style));
+        }
+        Spans::from(spans)
+    }
+
+    fn status_line(&self) -> Spans<'_> {
+        let mut spans = Vec::new();
+
+        if let Some(status) = &self.list_status {
+            let selected = status.selected.map_or(0, |selection| selection + 1);
+            let mut counts = format!("{}/{}", selected, status.filtered);
+            if status.filtered != status.total {
+                counts.push_str(&format!(" (of {})", status.total));
+            }
+            spans.push(Span::styled(
+                counts,
+                Style::default().add_modifier(Modifier::BOLD),
+            ));
+            spans.push(Span::raw(format!("  sort: {}", status.sort_order)));
+            if !status.filter.is_empty() {
+                spans.push(Span::raw(format!("  filter: {}", status.filter)));
+            }
+            if let Some(status_filter) = status.status_filter {
+                spans.push(Span::raw(format!("  status: {}", status_filter)));
+            }
+            if status.marked > 0 || status.visual {
+                let mut marked = format!("  {} marked", status.marked);
+                if status.visual {
+                    marked.push_str(" (visual)");
+                }
+                spans.push(Span::styled(marked, Marks::style()));
+            }
+        }
+
+        if let Some(message) = &self.message {
+            let style = match message.kind {
+                MessageKind::Info => Style::default().fg(Color::LightGreen),
+                MessageKind::Error => Style::default().fg(Color::LightRed),
+            };
+            spans.push(Span::raw("  "));
+            spans.push(Span::styled(message.text.clone(), style));
+        }
+
+        Spans::from(spans)
+    }
+}
+
+impl DrawableComponent for StatusBarComponent {
+    fn draw<B: Backend>(
+        &mut self,
+        f: &mut Frame<B>,
+        area: Rect,
+        _focused: bool,
+    ) -> anyhow::Result<()> {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Length(1)].as_ref())
+            .split(area);
+
+        f.render_widget(Paragraph::new(self.commands_line()), chunks[0]);
+        f.render_widget(Paragraph::new(self.status_line()), chunks[1]);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,432 @@
+use chrono::Local;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+use tui::backend::Backend;
+use tui::layout::{Constraint, Direction, Layout, Rect};
+use tui::style::{Color, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, BorderType, Borders, Clear, Paragraph, Wrap};
+use tui::Frame;
+
+use super::{
+    command::{self, CommandInfo},
+    inbox::pdf_detail::clean_text,
+    pdf_import_popup::ImportHints,
+    utils::popup::centered_rect,
+    CommandBlocking, Component, DrawableComponent, EventState,
+};
+use crate::{
+    domain::{
+        identifiers::Identifiers,
+        pdf_file::PdfFile,
+        pdf_info::PdfInfo,
+        triage::{Outcome, TriageQueue},
+    },
+    error::Result,
+    inputs::key::Key,
+    key_config::KeyConfig,
+};
+
+/// An action on the current file chosen by the user, performed by the app which then
+/// calls `advance` unless the file stays current
+pub enum TriageRequest {
+    Import {
+        src: PathBuf,
+        name: String,
+        /// the identifiers scanned for the file, `None` if the scan failed
+        identifiers: Option<Identifiers>,
+        tags: Vec<String>,
+    },
+    Skip(PathBuf),
+    Trash(PathBuf),
+    /// the file stays current
+    Open(PathBuf),
+}
+
+/// Goes through the unmanaged files one at a time, with the facts and the text of the
+/// first page of each, to import, skip or trash it with a single key
+pub struct TriagePopup {
+    /// `None` while closed
+    queue: Option<TriageQueue>,
+    /// the files of the queue
+    pdf_files: HashMap<PathBuf, PdfFile>,
+    /// of the current file with the name it is imported under, once the app scanned it
+    hints: Option<(PathBuf, ImportHints, String)>,
+    /// the cleaned text of the first page of the current file, or why it failed
+    preview: Option<(PathBuf, std::result::Result<String, String>)>,
+    /// the tags being typed to import the current file with, `None` while not typing
+    tags: Option<String>,
+    request: Option<TriageRequest>,
+    key_config: KeyConfig,
+}
+
+impl TriagePopup {
+    pub fn new(key_config: KeyConfig) -> Self {
+        Self {
+            queue: None,
+            pdf_files: HashMap::new(),
+            hints: None,
+            preview: None,
+            tags: None,
+            request: None,
+            key_config,
+        }
+    }
+
+    /// goes through `queue`, whose files are among `pdf_files`
+    pub fn open(&mut self, queue: TriageQueue, pdf_files: Vec<PdfFile>) {
+        self.pdf_files = pdf_files
+            .into_iter()
+            .map(|pdf_file| (pdf_file.path.clone(), pdf_file))
+            .collect();
+        self.queue = Some(queue);
+        self.hints = None;
+        self.preview = None;
+        self.tags = None;
+    }
+
+    /// sets the info read from the files in the background since the triage was opened
+    pub fn set_info(&mut self, infos: &HashMap<PathBuf, PdfInfo>) {
+        for (path, info) in infos {
+            if let Some(pdf_file) = self.pdf_files.get_mut(path) {
+                pdf_file.info = info.clone();
+            }
+        }
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.queue.is_some()
+    }
+
+    /// the step chosen by the last event, if any
+    pub fn take_request(&mut self) -> Option<TriageRequest> {
+        self.request.take()
+    }
+
+    /// moves on to the next file once the app performed the requested step
+    pub fn advance(&mut self, outcome: Outcome) {
+        if let Some(queue) = &mut self.queue {
+            queue.advance(outcome);
+        }
+        self.tags = None;
+    }
+
+    fn current(&self) -> Option<&PdfFile> {
+        let path = self.queue.as_ref()?.current()?;
+        self.pdf_files.get(path)
+    }
+
+    /// the current file if the app has not scanned it yet
+    pub fn missing_hints(&self) -> Option<PathBuf> {
+        let path = &self.current()?.path;
+        match &self.hints {
+            Some((scanned, ..)) if scanned == path => None,
+            _ => Some(path.clone()),
+        }
+    }
+
+    /// sets what the app found out about the file at `path`, which is imported as `name`
+    pub fn set_hints(&mut self, path: PathBuf, hints: ImportHints, name: String) {
+        self.hints = Some((path, hints, name));
+    }
+
+    /// the first page of the current file if its text has not been extracted yet
+    pub fn missing_preview(&self) -> Option<(PathBuf, u32)> {
+        let path = &self.current()?.path;
+        match &self.preview {
+            Some((extracted, _)) if extracted == path => None,
+            _ => Some((path.clone(), 1)),
+        }
+    }
+
+    /// stores the text of a page if it is the missing preview, returns whether it was
+    pub fn set_preview(&mut self, path: &Path, page: u32, text: &Result<String>) -> bool {
+        if page != 1
+            || self
+                .missing_preview()
+                .is_none_or(|(missing, _)| missing != path)
+        {
+            return false;
+        }
+        let text = match text {
+            Ok(text) => Ok(clean_text(text)),
+            Err(err) => Err(err.to_string()),
+        };
+        self.preview = Some((path.to_path_buf(), text));
+        true
+    }
+
+    fn current_hints(&self) -> Option<(&ImportHints, &str)> {
+        let path = &self.current()?.path;
+        self.hints
+            .as_ref()
+            .filter(|(scanned, ..)| scanned == path)
+            .map(|(_, hints, name)| (hints, name.as_str()))
+    }
+
+    /// the name the current file is imported under, its own until it is scanned
+    fn import_name(&self, pdf_file: &PdfFile) -> String {
+        self.current_hints()
+            .map_or_else(|| pdf_file.file_name.clone(), |(_, name)| name.to_string())
+    }
+
+    fn import_request(&self, tags: Vec<String>) -> Option<TriageRequest> {
+        let pdf_file = self.current()?;
+        Some(TriageRequest::Import {
+            src: pdf_file.path.clone(),
+            name: self.import_name(pdf_file),
+            identifiers: self
+                .current_hints()
+                .and_then(|(hints, _)| hints.identifiers.clone()),
+            tags,
+        })
+    }
+
+    fn close(&mut self) {
+        self.queue = None;
+        self.pdf_files.clear();
+        self.hints = None;
+        self.preview = None;
+        self.tags = None;
+    }
+
+    fn rows(&self, pdf_file: &PdfFile) -> Vec<Spans<'static>> {
+        let label_style = Style::default().fg(Color::LightCyan);
+        let row = |label: &str, value: String| {
+            Spans::from(vec![
+                Span::styled(format!("{:11}", label), label_style),
+                Span::raw(value),
+            ])
+        };
+        let mut rows = vec![
+            row("Name", pdf_file.file_name.clone()),
+            row("Size", format!("{} bytes", pdf_file.size)),
+        ];
+        if let Some(modified) = pdf_file.modified {
+            let modified = modified.with_timezone(&Local).format("%Y-%m-%d %H:%M");
+            rows.push(row("Modified", modified.to_string()));
+        }
+        let info = &pdf_file.info;
+        if let Some(pages) = info.pages {
+            rows.push(row("Pages", pages.to_string()));
+        }
+        if let Some(producer) = &info.producer {
+            rows.push(row("Producer", producer.clone()));
+        }
+        let (hints, name) = match self.current_hints() {
+            Some(hints) => hints,
+            None => {
+                rows.push(row("Identifiers", "scanning…".to_string()));
+                return rows;
+            }
+        };
+        if let Some(identifiers) = &hints.identifiers {
+            let ids = [
+                ("DOI", &identifiers.doi),
+                ("arXiv", &identifiers.arxiv),
+                ("ISBN", &identifiers.isbn),
+            ];
+            for (label, id) in ids {
+                if let Some(id) = id {
+                    rows.push(row(label, id.clone()));
+                }
+            }
+        }
+        rows.push(Spans::from(""));
+        rows.push(row("Import as", name.to_string()));
+        if let Some(duplicate_of) = &hints.duplicate_of {
+            rows.push(Spans::from(Span::styled(
+                format!("Already in the library: {}", duplicate_of),
+                Style::default().fg(Color::LightRed),
+            )));
+        }
+        rows
+    }
+}
+
+impl DrawableComponent for TriagePopup {
+    fn draw<B: Backend>(
+        &mut self,
+        f: &mut Frame<B>,
+        _area: Rect,
+        _focused: bool,
+    ) -> anyhow::Result<()> {
+        let queue = match &self.queue {
+            Some(queue) => queue,
+            None => return Ok(()),
+        };
+        let tally = queue.tally();
+        let (position, len) = queue.progress();
+        let title = format!(
+            "Triage {}/{}: {} imported, {} trashed, {} skipped",
+            position, len, tally.imported, tally.trashed, tally.skipped
+        );
+
+        let area = centered_rect(90, 90, f.size());
+        f.render_widget(Clear, area);
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Plain)
+            .border_style(Style::default().fg(Color::Magenta))
+            .title(title);
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Length(1)].as_ref())
+            .split(inner);
+        let help_style = Style::default().fg(Color::Gray);
+
+        let pdf_file = match self.current() {
+            Some(pdf_file) => pdf_file,
+            None => {
+                let done = Paragraph::new(vec![
+                    Spans::from("Every file has been dealt with"),
+                    Spans::from(""),
+                    Spans::from(format!(
+                        "{} imported, {} trashed, {} skipped for the next time",
+                        tally.imported, tally.trashed, tally.skipped
+                    )),
+                ]);
+                f.render_widget(done, chunks[0]);
+                let help = command::exit_popup(&self.key_config).name;
+                f.render_widget(Paragraph::new(Span::styled(help, help_style)), chunks[1]);
+                return Ok(());
+            }
+        };
+
+        let panes = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(40), Constraint::Percentage(60)].as_ref())
+            .split(chunks[0]);
+        let info = Paragraph::new(self.rows(pdf_file))
+            .block(Block::default().borders(Borders::RIGHT))
+            .wrap(Wrap { trim: false });
+        f.render_widget(info, panes[0]);
+
+        let preview = match &self.preview {
+            Some((path, Ok(text))) if *path == pdf_file.path => {
+                Paragraph::new(text.clone()).wrap(Wrap { trim: false })
+            }
+            Some((path, Err(err))) if *path == pdf_file.path => Paragraph::new(Span::styled(
+                format!("No text: {}", err),
+                Style::default().fg(Color::LightRed),
+            )),
+            _ => Paragraph::new(Span::styled(
+                "Extracting the first page…",
+                Style::default().fg(Color::DarkGray),
+            )),
+        };
+        let preview_area = Rect {
+            x: panes[1].x + 1,
+            width: panes[1].width.saturating_sub(1),
+            ..panes[1]
+        };
+        f.render_widget(preview, preview_area);
+
+        match &self.tags {
+            Some(tags) => {
+                let label = "Tags: ";
+                let line = Spans::from(vec![
+                    Span::styled(label, Style::default().fg(Color::LightCyan)),
+                    Span::raw(tags.clone()),
+                    Span::styled(
+                        format!("  {}", command::save_triage_tags(&self.key_config).name),
+                        help_style,
+                    ),
+                ]);
+                f.render_widget(Paragraph::new(line), chunks[1]);
+                let cursor_x = chunks[1].x + (label.len() + tags.chars().count()) as u16;
+                f.set_cursor(cursor_x.min(chunks[1].right()), chunks[1].y);
+            }
+            None => {
+                let help = format!(
+                    "{} | {}",
+                    command::triage_actions(&self.key_config).name,
+                    command::exit_popup(&self.key_config).name
+                );
+                f.render_widget(Paragraph::new(Span::styled(help, help_style)), chunks[1]);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Component for TriagePopup {
+    fn commands(&self, out: &mut Vec<CommandInfo>, _force_all: bool) -> CommandBlocking {
+        if !self.is_visible() {
+            return CommandBlocking::PassingOn;
+        }
+        if self.tags.is_some() {
+            out.push(CommandInfo::new(command::save_triage_tags(
+                &self.key_config,
+            )));
+        } else {
+            if self.current().is_some() {
+                out.push(CommandInfo::new(command::triage_actions(&self.key_config)));
+            }
+            out.push(CommandInfo::new(command::exit_popup(&self.key_config)));
+        }
+        CommandBlocking::Blocking
+    }
+
+    fn event(&mut self, key: Key) -> anyhow::Result<EventState> {
+        if !self.is_visible() {
+            return Ok(EventState::NotConsumed);
+        }
+        if let Some(tags) = &mut self.tags {
+            if key == self.key_config.exit_popup {
+                self.tags = None;
+            } else if key == self.key_config.enter {
+                // separated by commas or spaces
+                let tags = tags
+                    .split([',', ' '])
+                    .filter(|tag| !tag.is_empty())
+                    .map(str::to_string)
+                    .collect();
+                self.request = self.import_request(tags);
+            } else {
+                match key {
+                    Key::Char(c) => tags.push(c),
+                    Key::Backspace => {
+                        tags.pop();
+                    }
+                    _ => {}
+                }
+            }
+            return Ok(EventState::Consumed);
+        }
+        if key == self.key_config.exit_popup {
+            self.close();
+            return Ok(EventState::Consumed);
+        }
+        let path = match self.current() {
+            Some(pdf_file) => pdf_file.path.clone(),
+            None => {
+                if key == self.key_config.enter {
+                    self.close();
+                }
+                return Ok(EventState::Consumed);
+            }
+        };
+        if key == self.key_config.import {
+            self.request = self.import_request(Vec::new());
+        } else if key == self.key_config.tag_files {
+            self.tags = Some(String::new());
+        } else if key == self.key_config.skip_file {
+            self.request = Some(TriageRequest::Skip(path));
+        } else if key == self.key_config.delete_files {
+            self.request = Some(TriageRequest::Trash(path));
+        } else if key == self.key_config.open_pdf {
+            self.request = Some(TriageRequest::Open(path));
+        }
+        Ok(EventState::Consumed)
+    }
+
+    fn focused(&self) -> bool {
+        self.is_visible()
+    }
+}
@@ -1,2 +1,3 @@
+pub mod popup;
 pub mod scrollbar;
 pub mod vertical_scroll;
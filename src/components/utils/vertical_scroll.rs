@@ -3,6 +3,7 @@ use crate::components::{utils::scrollbar::draw_scrollbar, ScrollType};
 use std::cell::Cell;
 use tui::{backend::Backend, layout::Rect, Frame};
 
+#[derive(Default)]
 pub struct VerticalScroll {
     top: Cell<usize>,
     max_top: Cell<usize>,
@@ -33,7 +34,6 @@ impl VerticalScroll {
             ScrollType::Up => old.saturating_sub(1),
             // ScrollType::Home => 0,
             // ScrollType::End => max,
-            _ => old,
         };
 
         let new_scroll_top = new_scroll_top.clamp(0, max);
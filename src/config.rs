@@ -0,0 +1,333 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use anyhow::Context;
+use log::LevelFilter;
+use serde::Deserialize;
+
+use crate::domain::{
+    batch_import::ImportRule,
+    export::{ExportField, ExportFormat},
+    organize::FolderPolicy,
+};
+use crate::error::Result;
+use crate::graphics::GraphicsProtocol;
+
+const APP_DIR: &str = "pdfstore";
+
+/// User configuration, read from `$XDG_CONFIG_HOME/pdfstore/config.toml`.
+///
+/// Every field is optional in the file, and some can be overridden by environment variables.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// the library, overridden by `PDFSTORE_MANAGED_DIR`
+    pub managed_dir: PathBuf,
+    /// where new pdfs are picked up from, overridden by `PDFSTORE_UNMANAGED_DIR`
+    pub unmanaged_dir: PathBuf,
+    /// tags, notes and other records of the library, overridden by `PDFSTORE_LIBRARY_FILE`
+    pub library_file: PathBuf,
+    pub log: LogConfig,
+    pub export: ExportConfig,
+    pub import: ImportConfig,
+    pub organize: OrganizeConfig,
+    pub metadata: MetadataConfig,
+    pub preview: PreviewConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let home = dirs::home_dir().unwrap_or_default();
+        Self {
+            managed_dir: home.join("papers"),
+            unmanaged_dir: dirs::download_dir().unwrap_or_else(|| home.join("Downloads")),
+            library_file: dirs::data_dir()
+                .unwrap_or_else(|| home.join(".local/share"))
+                .join(APP_DIR)
+                .join("library.json"),
+            log: LogConfig::default(),
+            export: ExportConfig::default(),
+            import: ImportConfig::default(),
+            organize: OrganizeConfig::default(),
+            metadata: MetadataConfig::default(),
+            preview: PreviewConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct LogConfig {
+    /// overridden by `PDFSTORE_LOG`
+    pub level: LevelFilter,
+    /// also write the log to `pdfstore.log` in the state directory,
+    /// overridden by `PDFSTORE_LOG_FILE`
+    pub file: bool,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            level: LevelFilter::Info,
+            file: false,
+        }
+    }
+}
+
+/// Defaults of the export command, the cli can override them
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ExportConfig {
+    pub format: ExportFormat,
+    /// exported columns, in this order
+    pub fields: Vec<ExportField>,
+    /// where the terminal UI writes its exports
+    pub dir: PathBuf,
+}
+
+impl Default for ExportConfig {
+    fn default() -> Self {
+        Self {
+            format: ExportFormat::default(),
+            fields: ExportField::ALL.to_vec(),
+            dir: dirs::home_dir().unwrap_or_default(),
+        }
+    }
+}
+
+/// How the batch import files the pdfs of the unmanaged directory
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ImportConfig {
+    /// tried in order, the files no rule applies to go to the managed directory
+    pub rules: Vec<ImportRule>,
+    /// leave the files with the DOI, arXiv id or ISBN of a managed document
+    pub skip_duplicates: bool,
+}
+
+impl Default for ImportConfig {
+    fn default() -> Self {
+        Self {
+            rules: Vec::new(),
+            skip_duplicates: true,
+        }
+    }
+}
+
+/// How the documents are filed into the folders of the library by their metadata
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct OrganizeConfig {
+    /// template of the folder of each document such as `{year}/{venue}`, see
+    /// `FolderPolicy::parse`. the library is not organized without it
+    pub folder: Option<String>,
+    /// file the documents imported into the library itself right away
+    pub on_import: bool,
+}
+
+impl Default for OrganizeConfig {
+    fn default() -> Self {
+        Self {
+            folder: None,
+            on_import: true,
+        }
+    }
+}
+
+impl OrganizeConfig {
+    /// the policy of the config, `None` if there is none
+    pub fn policy(&self) -> Option<Result<FolderPolicy>> {
+        self.folder.as_deref().map(FolderPolicy::parse)
+    }
+}
+
+/// Services which resolve a doi or an arXiv id into bibliographic metadata
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct MetadataConfig {
+    /// serves `/works/<doi>` like Crossref, overridden by `PDFSTORE_CROSSREF_URL`
+    pub crossref_url: String,
+    /// serves `/api/query?id_list=<id>` like arXiv, overridden by `PDFSTORE_ARXIV_URL`
+    pub arxiv_url: String,
+    /// responses already fetched, which are not fetched again
+    pub cache_file: PathBuf,
+    /// seconds to wait for a response
+    pub timeout: u64,
+}
+
+impl Default for MetadataConfig {
+    fn default() -> Self {
+        Self {
+            crossref_url: "https://api.crossref.org".to_string(),
+            arxiv_url: "https://export.arxiv.org".to_string(),
+            cache_file: dirs::cache_dir()
+                .unwrap_or_else(|| dirs::home_dir().unwrap_or_default().join(".cache"))
+                .join(APP_DIR)
+                .join("metadata.json"),
+            timeout: 10,
+        }
+    }
+}
+
+/// The picture of the first page in the detail pane
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct PreviewConfig {
+    /// how the page is drawn, `off` hides it, overridden by `PDFSTORE_GRAPHICS`
+    pub graphics: GraphicsProtocol,
+    /// rendered pages, which are not rendered again
+    pub thumbnail_dir: PathBuf,
+    /// the page counts and the other info read from the files, which are not read again
+    pub info_file: PathBuf,
+}
+
+impl Default for PreviewConfig {
+    fn default() -> Self {
+        Self {
+            graphics: GraphicsProtocol::default(),
+            thumbnail_dir: dirs::cache_dir()
+                .unwrap_or_else(|| dirs::home_dir().unwrap_or_default().join(".cache"))
+                .join(APP_DIR)
+                .join("thumbnails"),
+            info_file: dirs::cache_dir()
+                .unwrap_or_else(|| dirs::home_dir().unwrap_or_default().join(".cache"))
+                .join(APP_DIR)
+                .join("info.json"),
+        }
+    }
+}
+
+impl Config {
+    /// loads the config file if it exists and applies the environment overrides
+    pub fn load() -> anyhow::Result<Self> {
+        let mut config = match Self::path() {
+            Some(path) if path.exists() => Self::from_file(&path)?,
+            _ => Self::default(),
+        };
+        config.apply_env()?;
+        config.managed_dir = expand_home(&config.managed_dir);
+        config.unmanaged_dir = expand_home(&config.unmanaged_dir);
+        config.library_file = expand_home(&config.library_file);
+        config.export.dir = expand_home(&config.export.dir);
+        config.metadata.cache_file = expand_home(&config.metadata.cache_file);
+        config.preview.thumbnail_dir = expand_home(&config.preview.thumbnail_dir);
+        config.preview.info_file = expand_home(&config.preview.info_file);
+        Ok(config)
+    }
+
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join(APP_DIR).join("config.toml"))
+    }
+
+    fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("failed to read config {}", path.display()))?;
+        toml::from_str(&content)
+            .with_context(|| format!("failed to parse config {}", path.display()))
+    }
+
+    fn apply_env(&mut self) -> anyhow::Result<()> {
+        if let Some(dir) = env::var_os("PDFSTORE_MANAGED_DIR") {
+            self.managed_dir = PathBuf::from(dir);
+        }
+        if let Some(dir) = env::var_os("PDFSTORE_UNMANAGED_DIR") {
+            self.unmanaged_dir = PathBuf::from(dir);
+        }
+        if let Some(file) = env::var_os("PDFSTORE_LIBRARY_FILE") {
+            self.library_file = PathBuf::from(file);
+        }
+        if let Ok(url) = env::var("PDFSTORE_CROSSREF_URL") {
+            self.metadata.crossref_url = url;
+        }
+        if let Ok(url) = env::var("PDFSTORE_ARXIV_URL") {
+            self.metadata.arxiv_url = url;
+        }
+        if let Ok(graphics) = env::var("PDFSTORE_GRAPHICS") {
+            self.preview.graphics = GraphicsProtocol::from_str(&graphics)
+                .map_err(anyhow::Error::msg)
+                .context("invalid PDFSTORE_GRAPHICS")?;
+        }
+        if let Ok(level) = env::var("PDFSTORE_LOG") {
+            self.log.level = LevelFilter::from_str(&level)
+                .with_context(|| format!("invalid PDFSTORE_LOG level: {}", level))?;
+        }
+        if let Ok(file) = env::var("PDFSTORE_LOG_FILE") {
+            self.log.file = !matches!(file.as_str(), "" | "0" | "false");
+        }
+        Ok(())
+    }
+}
+
+/// directory for state which should persist between runs but is not worth a backup, e.g. logs.
+///
+/// `$XDG_STATE_HOME/pdfstore`, or the local data directory on platforms without one
+pub fn state_dir() -> Option<PathBuf> {
+    dirs::state_dir()
+        .or_else(dirs::data_local_dir)
+        .map(|dir| dir.join(APP_DIR))
+}
+
+/// replaces a leading `~` with the home directory
+pub fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), dirs::home_dir()) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// formats `path` for display, with the home directory shortened to `~`
+pub fn tilde_path(path: &Path) -> String {
+    match dirs::home_dir().and_then(|home| path.strip_prefix(home).ok().map(Path::to_path_buf)) {
+        Some(rest) if rest.as_os_str().is_empty() => "~".to_string(),
+        Some(rest) => format!("~/{}", rest.display()),
+        None => path.display().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Config;
+    use crate::domain::export::{ExportField, ExportFormat};
+    use log::LevelFilter;
+
+    #[test]
+    fn test_parse_partial_config() {
+        let content =
+            "[log]\nlevel = \"debug\"\n[export]\nformat = \"csv\"\nfields = [\"path\", \"tags\"]\n";
+        let config: Config = toml::from_str(content).unwrap();
+        assert_eq!(config.log.level, LevelFilter::Debug);
+        assert!(!config.log.file);
+        assert_eq!(config.export.format, ExportFormat::Csv);
+        assert_eq!(config.export.fields, [ExportField::Path, ExportField::Tags]);
+    }
+
+    #[test]
+    fn test_parse_empty_config() {
+        let config: Config = toml::from_str("").unwrap();
+        assert_eq!(config.log.level, LevelFilter::Info);
+        assert!(config.managed_dir.ends_with("papers"));
+    }
+
+    #[test]
+    fn test_parse_import_rules() {
+        let content = "[import]\nskip_duplicates = false\n[[import.rules]]\npattern = \"arXiv*.pdf\"\ndir = \"arxiv\"\nname = \"{arxiv}\"\n[[import.rules]]\npattern = \"invoice*\"\nskip = true\n";
+        let config: Config = toml::from_str(content).unwrap();
+        assert!(!config.import.skip_duplicates);
+        assert_eq!(config.import.rules.len(), 2);
+        assert_eq!(config.import.rules[0].name.as_deref(), Some("{arxiv}"));
+        assert!(config.import.rules[1].skip);
+        assert!(config.organize.policy().is_none());
+        assert!(config.organize.on_import);
+    }
+
+    #[test]
+    fn test_parse_organize_policy() {
+        let content = "[organize]\nfolder = \"{year}/{venue}\"\non_import = false\n";
+        let config: Config = toml::from_str(content).unwrap();
+        assert!(!config.organize.on_import);
+        assert!(matches!(config.organize.policy(), Some(Ok(_))));
+    }
+}
@@ -0,0 +1,498 @@
+use std::path::{Component, Path, PathBuf};
+
+use serde::Deserialize;
+
+use super::{
+    identifiers::{safe_file_stem, Identifiers},
+    importer::{import_to, validate_name},
+    library_store::LibraryStore,
+};
+use crate::error::Result;
+
+/// A rule of the batch import, the first rule which applies to a file decides where
+/// it goes
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ImportRule {
+    /// matched against the file name ignoring case, `*` matches any characters and `?`
+    /// a single one
+    pub pattern: String,
+    /// directory of the library the files go to, relative to the managed directory
+    pub dir: Option<PathBuf>,
+    /// template of the new file name such as `{arxiv}`, see `render`
+    pub name: Option<String>,
+    /// tags given to the imported files
+    pub tags: Vec<String>,
+    /// leave the matching files where they are
+    pub skip: bool,
+}
+
+/// What the batch import does with a file
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlannedAction {
+    Import {
+        dest: PathBuf,
+        tags: Vec<String>,
+    },
+    /// the file stays where it is, for this reason
+    Skip(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct PlannedFile {
+    pub src: PathBuf,
+    /// the index of the rule which decided, `None` if no rule applied
+    pub rule: Option<usize>,
+    pub identifiers: Option<Identifiers>,
+    pub action: PlannedAction,
+}
+
+/// The dry run of a batch import, which is executed once reviewed
+#[derive(Debug, Clone, Default)]
+pub struct ImportPlan {
+    pub files: Vec<PlannedFile>,
+}
+
+enum TemplateError {
+    /// the template needs a value the file does not have, the rule does not apply
+    Missing,
+    Unknown(String),
+}
+
+impl ImportPlan {
+    /// decides what to do with each scanned file, given with the identifiers printed in
+    /// it.
+    ///
+    /// a rule whose template needs an identifier the file does not have does not
+    /// apply, the files no rule applies to are imported into the managed directory
+    /// under their names
+    pub fn new(
+        scanned: Vec<(PathBuf, Option<Identifiers>)>,
+        rules: &[ImportRule],
+        skip_duplicates: bool,
+        managed_dir: &Path,
+        library: &LibraryStore,
+    ) -> Self {
+        let mut plan = Self::default();
+        for (src, identifiers) in scanned {
+            let (rule, action) = plan_file(&src, identifiers.as_ref(), rules, managed_dir);
+            let action = match action {
+                PlannedAction::Import { dest, .. } if dest.exists() => {
+                    PlannedAction::Skip(format!("{} already exists", display(managed_dir, &dest)))
+                }
+                PlannedAction::Import { dest, tags } => {
+                    match plan.conflict(&dest, identifiers.as_ref(), skip_duplicates, library) {
+                        Some(reason) => PlannedAction::Skip(reason),
+                        None => PlannedAction::Import { dest, tags },
+                    }
+                }
+                skip => skip,
+            };
+            plan.files.push(PlannedFile {
+                src,
+                rule,
+                identifiers,
+                action,
+            });
+        }
+        plan
+    }
+
+    /// why a file cannot be imported to `dest` next to the files planned so far
+    fn conflict(
+        &self,
+        dest: &Path,
+        identifiers: Option<&Identifiers>,
+        skip_duplicates: bool,
+        library: &LibraryStore,
+    ) -> Option<String> {
+        let name = |path: &Path| {
+            path.file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string()
+        };
+        if let Some(identifiers) = identifiers.filter(|_| skip_duplicates) {
+            if let Some((key, shared)) = library.find_duplicate(identifiers, None) {
+                return Some(format!("duplicate of {} (same {})", key, shared));
+            }
+            let duplicate = self.imports().find_map(|planned| {
+                let shared = planned.identifiers.as_ref()?.shared_with(identifiers)?;
+                Some(format!(
+                    "duplicate of {} (same {})",
+                    name(&planned.src),
+                    shared
+                ))
+            });
+            if duplicate.is_some() {
+                return duplicate;
+            }
+        }
+        self.imports().find_map(|planned| match &planned.action {
+            PlannedAction::Import { dest: other, .. } if other == dest => {
+                Some(format!("{} goes there too", name(&planned.src)))
+            }
+            _ => None,
+        })
+    }
+
+    /// the files which are imported
+    pub fn imports(&self) -> impl Iterator<Item = &PlannedFile> {
+        self.files
+            .iter()
+            .filter(|planned| matches!(planned.action, PlannedAction::Import { .. }))
+    }
+
+    /// imports the planned files and records their identifiers and tags, a file which
+    /// fails does not stop the others.
+    ///
+    /// returns the new path of each imported file or why it failed, `save` the store to
+    /// keep the records
+    pub fn execute(
+        &self,
+        managed_dir: &Path,
+        library: &mut LibraryStore,
+    ) -> Vec<(PathBuf, Result<PathBuf>)> {
+        let mut results = Vec::new();
+        for planned in &self.files {
+            let (dest, tags) = match &planned.action {
+                PlannedAction::Import { dest, tags } => (dest, tags),
+                PlannedAction::Skip(_) => continue,
+            };
+            let result = import_to(&planned.src, dest).map(|()| dest.clone());
+            if let Some(key) = result
+                .as_ref()
+                .ok()
+                .and_then(|dest| LibraryStore::key_for(managed_dir, dest))
+            {
+                let record = library.record_mut(&key);
+                if planned.identifiers.is_some() {
+                    record.identifiers = planned.identifiers.clone();
+                }
+                for tag in tags {
+                    if !record.tags.contains(tag) {
+                        record.tags.push(tag.clone());
+                    }
+                }
+            }
+            results.push((planned.src.clone(), result));
+        }
+        results
+    }
+}
+
+/// the rule deciding about `src` and what it does
+fn plan_file(
+    src: &Path,
+    identifiers: Option<&Identifiers>,
+    rules: &[ImportRule],
+    managed_dir: &Path,
+) -> (Option<usize>, PlannedAction) {
+    let file_name = src.file_name().unwrap_or_default().to_string_lossy();
+    for (idx, rule) in rules.iter().enumerate() {
+        if !glob_match(&rule.pattern, &file_name) {
+            continue;
+        }
+        if rule.skip {
+            return (
+                Some(idx),
+                PlannedAction::Skip("matched a skip rule".to_string()),
+            );
+        }
+        let name = match &rule.name {
+            Some(template) => match render(template, src, identifiers) {
+                Ok(name) => name,
+                Err(TemplateError::Missing) => continue,
+                Err(TemplateError::Unknown(placeholder)) => {
+                    let reason = format!("rule {}: unknown {{{}}}", idx + 1, placeholder);
+                    return (Some(idx), PlannedAction::Skip(reason));
+                }
+            },
+            None => file_name.to_string(),
+        };
+        let action = match destination(managed_dir, rule.dir.as_deref(), name) {
+            Ok(dest) => PlannedAction::Import {
+                dest,
+                tags: rule.tags.clone(),
+            },
+            Err(reason) => PlannedAction::Skip(format!("rule {}: {}", idx + 1, reason)),
+        };
+        return (Some(idx), action);
+    }
+    match destination(managed_dir, None, file_name.to_string()) {
+        Ok(dest) => (
+            None,
+            PlannedAction::Import {
+                dest,
+                tags: Vec::new(),
+            },
+        ),
+        Err(reason) => (None, PlannedAction::Skip(reason)),
+    }
+}
+
+fn destination(
+    managed_dir: &Path,
+    dir: Option<&Path>,
+    mut name: String,
+) -> std::result::Result<PathBuf, String> {
+    if !name.to_lowercase().ends_with(".pdf") {
+        name.push_str(".pdf");
+    }
+    validate_name(&name).map_err(|err| err.to_string())?;
+    let dir = dir.unwrap_or_else(|| Path::new(""));
+    if !dir
+        .components()
+        .all(|part| matches!(part, Component::Normal(_)))
+    {
+        return Err(format!("{} is not inside the library", dir.display()));
+    }
+    Ok(managed_dir.join(dir).join(name))
+}
+
+/// fills in the placeholders of a file name template: `{name}` the current name without
+/// its extension, `{doi}`, `{arxiv}` and `{isbn}` the identifiers printed in the file,
+/// and `{id}` the first of them with its kind, e.g. `arxiv-2101.00001`
+fn render(
+    template: &str,
+    src: &Path,
+    identifiers: Option<&Identifiers>,
+) -> std::result::Result<String, TemplateError> {
+    let mut rendered = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+        let end = match rest[start..].find('}') {
+            Some(end) => start + end,
+            None => return Err(TemplateError::Unknown(rest[start + 1..].to_string())),
+        };
+        let placeholder = &rest[start + 1..end];
+        let identifier = |get: fn(&Identifiers) -> &Option<String>| {
+            identifiers.and_then(|identifiers| get(identifiers).as_deref().map(safe_file_stem))
+        };
+        let value = match placeholder {
+            "name" => src
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string()),
+            "doi" => identifier(|identifiers| &identifiers.doi),
+            "arxiv" => identifier(|identifiers| &identifiers.arxiv),
+            "isbn" => identifier(|identifiers| &identifiers.isbn),
+            "id" => identifiers
+                .and_then(Identifiers::suggested_file_name)
+                .map(|name| name.trim_end_matches(".pdf").to_string()),
+            _ => return Err(TemplateError::Unknown(placeholder.to_string())),
+        };
+        rendered.push_str(&value.ok_or(TemplateError::Missing)?);
+        rest = &rest[end + 1..];
+    }
+    rendered.push_str(rest);
+    Ok(rendered)
+}
+
+/// matches `name` against a pattern of `*` and `?` wildcards, ignoring case
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.to_lowercase().chars().collect::<Vec<_>>();
+    let name = name.to_lowercase().chars().collect::<Vec<_>>();
+    // the position after the last `*` and the name position it was tried with
+    let (mut p, mut n) = (0, 0);
+    let mut star = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p + 1, n));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((after, tried)) => {
+                    p = after;
+                    n = tried + 1;
+                    star = Some((after, tried + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// `path` relative to the managed directory, for the reasons of skipped files
+fn display(managed_dir: &Path, path: &Path) -> String {
+    LibraryStore::key_for(managed_dir, path).unwrap_or_else(|| path.display().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{glob_match, ImportPlan, ImportRule, PlannedAction};
+    use crate::{
+        domain::{
+            identifiers::Identifiers,
+            library_store::LibraryStore,
+            testing::{library, write_pdf, ReadOnly},
+        },
+        error::Error,
+    };
+    use std::{fs, path::PathBuf};
+    use tempfile::TempDir;
+
+    /// a download directory with the files `names`, a managed directory and an empty
+    /// library
+    fn downloads(names: &[&str]) -> (TempDir, PathBuf, Vec<PathBuf>, LibraryStore) {
+        let (dir, managed, library) = library(&[]);
+        let paths = names
+            .iter()
+            .map(|name| {
+                let path = dir.path().join("downloads").join(name);
+                write_pdf(&path);
+                path
+            })
+            .collect();
+        (dir, managed, paths, library)
+    }
+
+    fn arxiv(id: &str) -> Option<Identifiers> {
+        Some(Identifiers {
+            arxiv: Some(id.to_string()),
+            ..Identifiers::default()
+        })
+    }
+
+    fn rules() -> [ImportRule; 2] {
+        [
+            ImportRule {
+                pattern: "arxiv*.pdf".to_string(),
+                dir: Some(PathBuf::from("arxiv")),
+                name: Some("{arxiv}".to_string()),
+                tags: vec!["preprint".to_string()],
+                ..ImportRule::default()
+            },
+            ImportRule {
+                pattern: "invoice*".to_string(),
+                skip: true,
+                ..ImportRule::default()
+            },
+        ]
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("arXiv*.pdf", "ARXIV-2101.00001.pdf"));
+        assert!(glob_match("*.p?f", "a.b.pdf"));
+        assert!(!glob_match("arxiv*", "paper.pdf"));
+    }
+
+    #[test]
+    fn test_plan_follows_the_rules() {
+        let (_dir, managed, paths, library) =
+            downloads(&["arxiv-a.pdf", "arxiv-unknown.pdf", "invoice.pdf"]);
+        let scanned = vec![
+            (paths[0].clone(), arxiv("2101.00001")),
+            (paths[1].clone(), None),
+            (paths[2].clone(), None),
+        ];
+        let plan = ImportPlan::new(scanned, &rules(), true, &managed, &library);
+        let actions = plan
+            .files
+            .iter()
+            .map(|planned| (planned.rule, &planned.action))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            actions[0],
+            (
+                Some(0),
+                &PlannedAction::Import {
+                    dest: managed.join("arxiv/2101.00001.pdf"),
+                    tags: vec!["preprint".to_string()],
+                }
+            )
+        );
+        // the name falls back to the file name without the identifier it needs
+        assert!(
+            matches!(actions[1], (None, PlannedAction::Import { dest, .. }) if *dest == managed.join("arxiv-unknown.pdf"))
+        );
+        assert_eq!(
+            actions[2],
+            (Some(1), &PlannedAction::Skip("matched a skip rule".into()))
+        );
+    }
+
+    #[test]
+    fn test_plan_skips_duplicates_and_existing_files() {
+        let (_dir, managed, paths, library) = downloads(&["arxiv-a.pdf", "arxiv-copy.pdf"]);
+        fs::create_dir_all(managed.join("arxiv")).unwrap();
+        fs::write(managed.join("arxiv/2101.00002.pdf"), "%PDF-1.4").unwrap();
+        let scanned = vec![
+            (paths[0].clone(), arxiv("2101.00001")),
+            (paths[1].clone(), arxiv("2101.00001")),
+        ];
+        let plan = ImportPlan::new(scanned, &rules(), true, &managed, &library);
+        assert_eq!(
+            plan.files[1].action,
+            PlannedAction::Skip("duplicate of arxiv-a.pdf (same arXiv 2101.00001)".into())
+        );
+
+        let scanned = vec![(paths[0].clone(), arxiv("2101.00002"))];
+        let plan = ImportPlan::new(scanned, &rules(), true, &managed, &library);
+        assert_eq!(
+            plan.files[0].action,
+            PlannedAction::Skip("arxiv/2101.00002.pdf already exists".into())
+        );
+    }
+
+    #[test]
+    fn test_execute_imports_and_tags() {
+        let (_dir, managed, paths, mut library) = downloads(&["arxiv-a.pdf", "invoice.pdf"]);
+        let scanned = vec![
+            (paths[0].clone(), arxiv("2101.00001")),
+            (paths[1].clone(), None),
+        ];
+        let plan = ImportPlan::new(scanned, &rules(), true, &managed, &library);
+        let results = plan.execute(&managed, &mut library);
+        assert_eq!(results.len(), 1);
+        assert!(results.iter().all(|(_, result)| result.is_ok()));
+        let record = library.record("arxiv/2101.00001.pdf").unwrap();
+        assert_eq!(record.tags, vec!["preprint"]);
+        assert_eq!(record.identifiers, arxiv("2101.00001"));
+        assert!(paths[1].exists());
+    }
+
+    #[test]
+    fn test_execute_never_overwrites() {
+        let (_dir, managed, paths, mut library) = downloads(&["arxiv-a.pdf"]);
+        let scanned = vec![(paths[0].clone(), arxiv("2101.00001"))];
+        let plan = ImportPlan::new(scanned, &rules(), true, &managed, &library);
+        // created after the plan was reviewed
+        fs::create_dir_all(managed.join("arxiv")).unwrap();
+        fs::write(managed.join("arxiv/2101.00001.pdf"), "other").unwrap();
+
+        let results = plan.execute(&managed, &mut library);
+        assert!(matches!(
+            results.as_slice(),
+            [(_, Err(Error::AlreadyExists { .. }))]
+        ));
+        assert!(paths[0].exists());
+        assert!(library.record("arxiv/2101.00001.pdf").is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_execute_into_read_only_library() {
+        let (_dir, managed, paths, mut library) = downloads(&["paper.pdf"]);
+        let Some(_read_only) = ReadOnly::new(&managed) else {
+            return;
+        };
+        let scanned = vec![(paths[0].clone(), None)];
+        let plan = ImportPlan::new(scanned, &rules(), true, &managed, &library);
+
+        let results = plan.execute(&managed, &mut library);
+        assert!(matches!(
+            results.as_slice(),
+            [(_, Err(Error::PermissionDenied { .. }))]
+        ));
+        assert!(paths[0].exists());
+        assert!(library.record("paper.pdf").is_none());
+    }
+}
@@ -0,0 +1,261 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use crate::domain::{
+    bibtex::{normalize_doi, BibtexEntry},
+    citation::Citation,
+    library_store::LibraryStore,
+    pdf_file::PdfFile,
+};
+
+/// how similar two titles must be to be matched, see `title_similarity`
+const TITLE_THRESHOLD: f64 = 0.85;
+
+/// Why a BibTeX entry was matched to a pdf file
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MatchReason {
+    /// the entry's `file` field points to the file
+    File,
+    /// the entry has the doi of the file's citation, or the doi printed in the file
+    Doi,
+    /// the titles are similar, with this similarity
+    Title(f64),
+}
+
+impl fmt::Display for MatchReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::File => write!(f, "file"),
+            Self::Doi => write!(f, "doi"),
+            Self::Title(similarity) => write!(f, "title {:.0}%", similarity * 100.0),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BibMatch {
+    pub entry: BibtexEntry,
+    pub path: PathBuf,
+    pub reason: MatchReason,
+}
+
+/// The result of matching the entries of a BibTeX file to the library
+#[derive(Debug, Clone, Default)]
+pub struct BibImport {
+    pub matched: Vec<BibMatch>,
+    pub unmatched: Vec<BibtexEntry>,
+}
+
+impl BibImport {
+    /// matches `entries` to `pdf_files`, by file field, then doi, then title.
+    ///
+    /// A file is matched to one entry at most. Relative paths of the `file` field are
+    /// resolved against `bib_dir`; a file which has been moved into the library is still
+    /// found by its file name if no other file of the library has the same name.
+    pub fn match_entries(
+        entries: Vec<BibtexEntry>,
+        bib_dir: &Path,
+        managed_dir: &Path,
+        pdf_files: &[PdfFile],
+        store: &LibraryStore,
+    ) -> Self {
+        let records = pdf_files
+            .iter()
+            .map(|pdf_file| {
+                LibraryStore::key_for(managed_dir, &pdf_file.path)
+                    .and_then(|key| store.record(&key))
+            })
+            .collect::<Vec<_>>();
+        let citations = records
+            .iter()
+            .zip(pdf_files)
+            .map(|(record, pdf_file)| {
+                record
+                    .and_then(|record| record.citation.clone())
+                    .unwrap_or_else(|| Citation::from_file(pdf_file))
+            })
+            .collect::<Vec<_>>();
+        // the doi of the citation, else the one printed in the file
+        let dois = records
+            .iter()
+            .zip(&citations)
+            .map(|(record, citation)| {
+                if !citation.doi.is_empty() {
+                    return Some(normalize_doi(&citation.doi));
+                }
+                (*record)?.identifiers.as_ref()?.doi.clone()
+            })
+            .collect::<Vec<_>>();
+
+        let mut by_name = HashMap::<&str, Vec<usize>>::new();
+        for (idx, pdf_file) in pdf_files.iter().enumerate() {
+            by_name.entry(&pdf_file.file_name).or_default().push(idx);
+        }
+        let find_file = |file: &Path| {
+            let file = bib_dir.join(file);
+            if let Some(idx) = pdf_files.iter().position(|pdf_file| pdf_file.path == file) {
+                return Some(idx);
+            }
+            let name = file.file_name()?.to_str()?;
+            match by_name.get(name).map(Vec::as_slice) {
+                Some([idx]) => Some(*idx),
+                _ => None,
+            }
+        };
+
+        let mut taken = HashSet::new();
+        let mut found = vec![None; entries.len()];
+        for (entry, found) in entries.iter().zip(&mut found) {
+            let idx = entry.files().iter().find_map(|file| find_file(file));
+            if let Some(idx) = idx.filter(|idx| taken.insert(*idx)) {
+                *found = Some((idx, MatchReason::File));
+            }
+        }
+        for (entry, found) in entries.iter().zip(&mut found) {
+            let doi = match (&found, entry.doi()) {
+                (None, Some(doi)) => doi,
+                _ => continue,
+            };
+            let idx = dois.iter().enumerate().position(|(idx, file_doi)| {
+                !taken.contains(&idx) && file_doi.as_ref() == Some(&doi)
+            });
+            if let Some(idx) = idx {
+                taken.insert(idx);
+                *found = Some((idx, MatchReason::Doi));
+            }
+        }
+        for (entry, found) in entries.iter().zip(&mut found) {
+            if found.is_some() {
+                continue;
+            }
+            let title = entry.to_citation().title;
+            let best = citations
+                .iter()
+                .enumerate()
+                .filter(|(idx, _)| !taken.contains(idx))
+                .map(|(idx, citation)| (idx, title_similarity(&title, &citation.title)))
+                .max_by(|a, b| a.1.total_cmp(&b.1));
+            if let Some((idx, similarity)) = best.filter(|(_, s)| *s >= TITLE_THRESHOLD) {
+                taken.insert(idx);
+                *found = Some((idx, MatchReason::Title(similarity)));
+            }
+        }
+
+        let mut import = Self::default();
+        for (entry, found) in entries.into_iter().zip(found) {
+            match found {
+                Some((idx, reason)) => import.matched.push(BibMatch {
+                    entry,
+                    path: pdf_files[idx].path.clone(),
+                    reason,
+                }),
+                None => import.unmatched.push(entry),
+            }
+        }
+        import
+    }
+
+    /// attaches the metadata of the matched entries to the citation records, the fields
+    /// missing from an entry are kept.
+    ///
+    /// returns the number of records written, `save` the store to keep them
+    pub fn apply(&self, managed_dir: &Path, store: &mut LibraryStore) -> usize {
+        let mut count = 0;
+        for matched in &self.matched {
+            if let Some(key) = LibraryStore::key_for(managed_dir, &matched.path) {
+                store
+                    .record_mut(&key)
+                    .citation
+                    .get_or_insert_with(Citation::default)
+                    .update_from(matched.entry.to_citation());
+                count += 1;
+            }
+        }
+        count
+    }
+}
+
+/// Sørensen–Dice coefficient of the character bigrams of the normalized titles, from 0 to 1.
+///
+/// Tolerates case, punctuation, separators of file names and small typos.
+pub fn title_similarity(a: &str, b: &str) -> f64 {
+    let bigrams = |s: &str| {
+        let normalized = s
+            .chars()
+            .filter(|c| c.is_alphanumeric())
+            .flat_map(char::to_lowercase)
+            .collect::<Vec<_>>();
+        normalized
+            .windows(2)
+            .map(|w| (w[0], w[1]))
+            .collect::<Vec<_>>()
+    };
+    let (a, mut b) = (bigrams(a), bigrams(b));
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    let total = a.len() + b.len();
+    let mut common = 0;
+    for bigram in &a {
+        if let Some(pos) = b.iter().position(|other| other == bigram) {
+            b.swap_remove(pos);
+            common += 1;
+        }
+    }
+    2.0 * common as f64 / total as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{title_similarity, BibImport, MatchReason};
+    use crate::domain::{
+        bibtex::parse, library_store::LibraryStore, pdf_file::PdfFile, pdf_info::PdfInfo,
+    };
+    use std::path::{Path, PathBuf};
+
+    fn pdf_file(name: &str) -> PdfFile {
+        PdfFile {
+            file_name: name.to_string(),
+            path: PathBuf::from("/papers").join(name),
+            size: 0,
+            created: None,
+            modified: None,
+            info: PdfInfo::default(),
+        }
+    }
+
+    #[test]
+    fn test_match_entries() {
+        let (entries, _) = parse(
+            r#"
+@article{a, title = {Something else}, file = {:/zotero/storage/attention.pdf:PDF}}
+@article{b, title = {Deep Residual Learning for Image Recognition}}
+@article{c, title = {Never seen}}
+"#,
+        );
+        let files = [
+            pdf_file("attention.pdf"),
+            pdf_file("deep_residual_learning.pdf"),
+        ];
+        let dir = tempfile::tempdir().unwrap();
+        let store = LibraryStore::open(dir.path().join("library.json")).unwrap();
+
+        let import = BibImport::match_entries(
+            entries,
+            Path::new("/bib"),
+            Path::new("/papers"),
+            &files,
+            &store,
+        );
+        assert_eq!(import.matched.len(), 1);
+        assert_eq!(import.matched[0].entry.citekey, "a");
+        assert_eq!(import.matched[0].reason, MatchReason::File);
+        assert_eq!(import.unmatched.len(), 2);
+
+        assert!(title_similarity("Attention is all you need", "attention_is_all_you_need") > 0.99);
+        assert!(title_similarity("Attention is all you need", "Attention is all you neeed") > 0.85);
+    }
+}
@@ -0,0 +1,477 @@
+use std::{collections::BTreeMap, iter::Peekable, path::PathBuf, str::Chars};
+
+use crate::domain::citation::Citation;
+
+/// An entry of a BibTeX file, with lowercase field names and the outer braces or quotes
+/// of the values removed
+#[derive(Debug, Clone, PartialEq)]
+pub struct BibtexEntry {
+    pub entry_type: String,
+    pub citekey: String,
+    pub fields: BTreeMap<String, String>,
+}
+
+impl BibtexEntry {
+    pub fn field(&self, name: &str) -> Option<&str> {
+        self.fields
+            .get(name)
+            .map(String::as_str)
+            .filter(|value| !value.is_empty())
+    }
+
+    /// the files of the `file` field as written by Zotero, JabRef or Mendeley,
+    /// e.g. `Full Text:/papers/a.pdf:application/pdf;:b.pdf:PDF`
+    pub fn files(&self) -> Vec<PathBuf> {
+        let field = match self.field("file") {
+            Some(field) => field,
+            None => return Vec::new(),
+        };
+        field
+            .split(';')
+            .filter_map(|file| {
+                // `\:` escapes the colon of windows drive letters
+                let file = file.replace("\\:", "\u{0}");
+                let parts = file.split(':').collect::<Vec<_>>();
+                let path = match parts.as_slice() {
+                    [path] => path.to_string(),
+                    [_, path] => path.to_string(),
+                    [_, path @ .., _] => path.join(":"),
+                    [] => return None,
+                };
+                let path = path.replace('\u{0}', ":");
+                let path = path.trim();
+                (!path.is_empty()).then(|| PathBuf::from(path))
+            })
+            .collect()
+    }
+
+    pub fn doi(&self) -> Option<String> {
+        self.field("doi").map(normalize_doi)
+    }
+
+    pub fn to_citation(&self) -> Citation {
+        let text = |name: &str| self.field(name).map(latex_text).unwrap_or_default();
+        Citation {
+            citekey: self.citekey.clone(),
+            authors: self
+                .field("author")
+                .map(|authors| {
+                    authors
+                        .split(" and ")
+                        .map(latex_text)
+                        .filter(|author| !author.is_empty())
+                        .collect()
+                })
+                .unwrap_or_default(),
+            title: text("title"),
+            year: self
+                .field("year")
+                .and_then(|year| year.trim().parse().ok())
+                .or_else(|| {
+                    // biblatex writes `date = {2020-05-01}`
+                    self.field("date")
+                        .and_then(|date| date.get(..4))
+                        .and_then(|year| year.parse().ok())
+                }),
+            venue: self
+                .field("journal")
+                .or_else(|| self.field("journaltitle"))
+                .or_else(|| self.field("booktitle"))
+                .map(latex_text)
+                .unwrap_or_default(),
+            doi: self.doi().unwrap_or_default(),
+        }
+    }
+}
+
+/// lowercases a doi and removes the resolver prefix, so that dois can be compared
+pub fn normalize_doi(doi: &str) -> String {
+    let doi = doi.trim().to_lowercase();
+    [
+        "https://doi.org/",
+        "http://doi.org/",
+        "https://dx.doi.org/",
+        "doi:",
+    ]
+    .iter()
+    .find_map(|prefix| doi.strip_prefix(prefix))
+    .unwrap_or(&doi)
+    .trim()
+    .to_string()
+}
+
+/// LaTeX accents: the command, its combining mark, and the letters with a precomposed
+/// form, other letters get the combining mark
+const ACCENTS: [(&str, char, &str, &str); 13] = [
+    ("`", '\u{300}', "aeiouAEIOU", "àèìòùÀÈÌÒÙ"),
+    (
+        "'",
+        '\u{301}',
+        "aeiouyAEIOUYcnszCNSZ",
+        "áéíóúýÁÉÍÓÚÝćńśźĆŃŚŹ",
+    ),
+    ("^", '\u{302}', "aeiouAEIOU", "âêîôûÂÊÎÔÛ"),
+    ("\"", '\u{308}', "aeiouyAEIOUY", "äëïöüÿÄËÏÖÜŸ"),
+    ("~", '\u{303}', "anoANO", "ãñõÃÑÕ"),
+    ("=", '\u{304}', "aeiouAEIOU", "āēīōūĀĒĪŌŪ"),
+    (".", '\u{307}', "ezEIZ", "ėżĖİŻ"),
+    ("u", '\u{306}', "agAG", "ăğĂĞ"),
+    ("v", '\u{30c}', "cenrszCENRSZ", "čěňřšžČĚŇŘŠŽ"),
+    ("H", '\u{30b}', "ouOU", "őűŐŰ"),
+    ("c", '\u{327}', "cstCST", "çşţÇŞŢ"),
+    ("k", '\u{328}', "aeAE", "ąęĄĘ"),
+    ("r", '\u{30a}', "auAU", "åůÅŮ"),
+];
+
+/// LaTeX commands for letters, e.g. `\ss`
+const LETTERS: [(&str, char); 13] = [
+    ("ss", 'ß'),
+    ("o", 'ø'),
+    ("O", 'Ø'),
+    ("ae", 'æ'),
+    ("AE", 'Æ'),
+    ("oe", 'œ'),
+    ("OE", 'Œ'),
+    ("aa", 'å'),
+    ("AA", 'Å'),
+    ("l", 'ł'),
+    ("L", 'Ł'),
+    ("i", 'ı'),
+    ("j", 'ȷ'),
+];
+
+/// the text of a value: accents such as `{\"o}` and escaped characters such as `\&`
+/// decoded, the braces protecting the case of words removed, e.g. `{BERT}: pre-training`,
+/// and the whitespace collapsed
+fn latex_text(s: &str) -> String {
+    let mut text = String::new();
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' | '}' => {}
+            '\\' => command(&mut chars, &mut text),
+            c => text.push(c),
+        }
+    }
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// decodes the command after a `\` into `text`, unknown commands are kept as they are
+/// unless they format an argument, e.g. `\emph{new}`
+fn command(chars: &mut Peekable<Chars>, text: &mut String) {
+    let name = command_name(chars);
+    if let Some((_, mark, letters, composed)) = ACCENTS.iter().find(|(accent, ..)| *accent == name)
+    {
+        // `\v s` is written with a space, unlike `\"o`
+        if name.chars().all(|c| c.is_ascii_alphabetic()) {
+            while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        }
+        let argument = accent_argument(chars);
+        let mut argument = argument.chars();
+        match argument.next() {
+            Some(letter) => match letters.chars().position(|c| c == letter) {
+                Some(idx) => text.extend(composed.chars().nth(idx)),
+                None => text.extend([letter, *mark]),
+            },
+            // `\~{}` is the character itself
+            None if name.len() == 1 => text.push_str(&name),
+            None => {}
+        }
+        text.extend(argument);
+    } else if let Some((_, letter)) = LETTERS.iter().find(|(command, _)| *command == name) {
+        // the space ending `\ss ` is not part of the text
+        chars.next_if_eq(&' ');
+        text.push(*letter);
+    } else if name.len() == 1 && "&%$#_{} ".contains(&name) {
+        text.push_str(&name);
+    } else if chars.peek() != Some(&'{') {
+        text.push('\\');
+        text.push_str(&name);
+    }
+}
+
+/// a command is a run of letters or a single other character
+fn command_name(chars: &mut Peekable<Chars>) -> String {
+    let mut name = String::new();
+    while let Some(c) = chars.next_if(char::is_ascii_alphabetic) {
+        name.push(c);
+    }
+    if name.is_empty() {
+        name.extend(chars.next());
+    }
+    name
+}
+
+/// the letter an accent is put on, `o` of `\"o` and `\"{o}`, `i` of `\"{\i}`
+fn accent_argument(chars: &mut Peekable<Chars>) -> String {
+    let argument = if chars.next_if_eq(&'{').is_some() {
+        let mut argument = String::new();
+        while let Some(c) = chars.next_if(|c| *c != '}') {
+            argument.push(c);
+        }
+        chars.next();
+        argument
+    } else if chars.next_if_eq(&'\\').is_some() {
+        format!("\\{}", command_name(chars))
+    } else {
+        chars.next().map(String::from).unwrap_or_default()
+    };
+    // the accent replaces the dot of a dotless letter
+    match argument.trim() {
+        "\\i" => "i".to_string(),
+        "\\j" => "j".to_string(),
+        argument => argument.to_string(),
+    }
+}
+
+/// parses the entries of a BibTeX file.
+///
+/// `@comment`, `@preamble` and `@string` are skipped, as are entries which cannot be
+/// parsed: their errors are returned with the line they start on
+pub fn parse(input: &str) -> (Vec<BibtexEntry>, Vec<String>) {
+    let mut entries = Vec::new();
+    let mut errors = Vec::new();
+    let mut parser = Parser { input, pos: 0 };
+    // the line of the entry, counted up to `counted` as the parser moves on
+    let (mut line, mut counted) = (1, 0);
+
+    while let Some(start) = parser.input[parser.pos..].find('@') {
+        let at = parser.pos + start;
+        line += input[counted..at].bytes().filter(|b| *b == b'\n').count();
+        counted = at;
+        parser.pos = at + 1;
+        match parser.entry() {
+            Ok(Some(entry)) => entries.push(entry),
+            Ok(None) => {}
+            Err(reason) => {
+                errors.push(format!("line {}: {}", line, reason));
+                parser.skip_entry(at);
+            }
+        }
+    }
+    (entries, errors)
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.input[self.pos..]
+    }
+
+    /// moves to the next `@` starting a line after the malformed entry at `at`, so that
+    /// an `@` within the entry is not taken for the next one
+    fn skip_entry(&mut self, at: usize) {
+        let rest = &self.input[at..];
+        let next = rest
+            .match_indices('\n')
+            .map(|(idx, _)| idx + 1)
+            .find(|idx| {
+                rest[*idx..]
+                    .trim_start_matches([' ', '\t'])
+                    .starts_with('@')
+            });
+        self.pos = next.map_or(self.input.len(), |idx| at + idx);
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        if self.rest().starts_with(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// a name, citekey or bare value, up to a delimiter
+    fn word(&mut self) -> &'a str {
+        self.skip_whitespace();
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| c.is_whitespace() || "{}(),=\"#@".contains(c))
+            .unwrap_or(rest.len());
+        self.pos += len;
+        &rest[..len]
+    }
+
+    /// parses an entry after its `@`, `None` for the kinds which are not entries
+    fn entry(&mut self) -> Result<Option<BibtexEntry>, String> {
+        let entry_type = self.word().to_lowercase();
+        if entry_type.is_empty() {
+            return Err("missing entry type".to_string());
+        }
+        let close = if self.eat('{') {
+            '}'
+        } else if self.eat('(') {
+            ')'
+        } else {
+            return Err(format!("expected {{ after @{}", entry_type));
+        };
+        if matches!(entry_type.as_str(), "comment" | "preamble" | "string") {
+            self.pos -= 1;
+            self.braced(close)?;
+            return Ok(None);
+        }
+
+        let citekey = self.word().to_string();
+        let mut fields = BTreeMap::new();
+        loop {
+            if self.eat(close) {
+                break;
+            }
+            if !self.eat(',') && !fields.is_empty() {
+                return Err(format!("expected , or {} in {}", close, citekey));
+            }
+            if self.eat(close) {
+                break;
+            }
+            let name = self.word().to_lowercase();
+            if name.is_empty() || !self.eat('=') {
+                return Err(format!("expected a field in {}", citekey));
+            }
+            let value = self.value()?;
+            fields.insert(name, value);
+        }
+        Ok(Some(BibtexEntry {
+            entry_type,
+            citekey,
+            fields,
+        }))
+    }
+
+    /// a value made of `{...}`, `"..."` or bare parts joined by `#`
+    fn value(&mut self) -> Result<String, String> {
+        let mut value = String::new();
+        loop {
+            self.skip_whitespace();
+            if self.rest().starts_with('{') {
+                value.push_str(self.braced('}')?);
+            } else if self.eat('"') {
+                let len = self
+                    .rest()
+                    .find('"')
+                    .ok_or_else(|| "unterminated quoted value".to_string())?;
+                value.push_str(&self.rest()[..len]);
+                self.pos += len + 1;
+            } else {
+                let word = self.word();
+                if word.is_empty() {
+                    return Err("missing value".to_string());
+                }
+                value.push_str(word);
+            }
+            if !self.eat('#') {
+                return Ok(value);
+            }
+        }
+    }
+
+    /// the content between a `{` at the current position and its matching `close`
+    fn braced(&mut self, close: char) -> Result<&'a str, String> {
+        let rest = self.rest();
+        let open = if close == ')' { '(' } else { '{' };
+        let mut depth = 0;
+        for (idx, c) in rest.char_indices() {
+            if c == open {
+                depth += 1;
+            } else if c == close {
+                depth -= 1;
+                if depth == 0 {
+                    self.pos += idx + 1;
+                    return Ok(&rest[1..idx]);
+                }
+            }
+        }
+        Err("unbalanced braces".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{latex_text, parse};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_parse() {
+        let input = r#"
+@comment{exported by Zotero}
+@article{turing1936,
+  title = {On {Computable} Numbers},
+  author = "Turing, Alan",
+  year = 1936,
+  doi = {https://doi.org/10.1112/PLMS/s2-42.1.230},
+  file = {Full Text:/papers/turing.pdf:application/pdf;:C\:/papers/t2.pdf:PDF},
+}
+"#;
+        let (entries, errors) = parse(input);
+        assert_eq!(entries.len(), 1);
+        assert!(errors.is_empty());
+
+        let entry = &entries[0];
+        assert_eq!(entry.citekey, "turing1936");
+        assert_eq!(
+            entry.files(),
+            [
+                PathBuf::from("/papers/turing.pdf"),
+                PathBuf::from("C:/papers/t2.pdf")
+            ]
+        );
+        let citation = entry.to_citation();
+        assert_eq!(citation.title, "On Computable Numbers");
+        assert_eq!(citation.authors, ["Turing, Alan"]);
+        assert_eq!(citation.year, Some(1936));
+        assert_eq!(citation.doi, "10.1112/plms/s2-42.1.230");
+    }
+
+    #[test]
+    fn test_errors_are_reported_with_their_line() {
+        let input = "@misc{a, title = {A}}\n\n@misc{b, title = {B} year = 1}\n@misc{c, title = {C}}\n@{d}\n";
+        let (entries, errors) = parse(input);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(
+            errors,
+            ["line 3: expected , or } in b", "line 5: missing entry type"]
+        );
+    }
+
+    #[test]
+    fn test_parsing_resumes_at_the_next_entry() {
+        // the @ of the address is not taken for an entry, the next line is
+        let input = "@misc{broken, note = {mail me@example.com} title = {no comma}\n  \
+                     and more@text\n@article{ok, title = {Fine}}\n@misc{unclosed, title = {x}";
+        let (entries, errors) = parse(input);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].citekey, "ok");
+        assert_eq!(errors.len(), 2);
+        assert!(errors[1].starts_with("line 4: "));
+    }
+
+    #[test]
+    fn test_latex_accents_are_decoded() {
+        assert_eq!(
+            latex_text(r#"Sch{\"o}nheit der {\'E}l\`{e}ves \v{s}a \v s \ss{} {\"\i} \c c"#),
+            "Schönheit der Élèves ša š ß ï ç"
+        );
+        // letters without a precomposed form get a combining mark
+        assert_eq!(latex_text(r"\~{x}"), "x\u{303}");
+        assert_eq!(
+            latex_text(r"Smith \& Co. \~{} 50\% \emph{new} {\o}re \LaTeX"),
+            r"Smith & Co. ~ 50% new øre \LaTeX"
+        );
+
+        let (entries, _) = parse(r#"@misc{g, author = {G{\"o}del, Kurt and Erd\H{o}s, Paul}}"#);
+        assert_eq!(
+            entries[0].to_citation().authors,
+            ["Gödel, Kurt", "Erdős, Paul"]
+        );
+    }
+}
@@ -0,0 +1,237 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use super::{
+    importer::{self, move_file, Importer},
+    library_store::LibraryStore,
+};
+use crate::error::{Error, Result};
+
+/// What an operation on several files did: the number of files it changed and the
+/// errors of the others, which do not stop it
+#[derive(Debug, Default)]
+pub struct BulkReport {
+    pub done: usize,
+    /// the new paths of the imported files
+    pub imported: Vec<PathBuf>,
+    pub errors: Vec<Error>,
+}
+
+impl BulkReport {
+    fn add(&mut self, result: Result<()>) {
+        match result {
+            Ok(()) => self.done += 1,
+            Err(err) => self.errors.push(err),
+        }
+    }
+}
+
+/// imports the files into the managed directory under their current names
+pub fn import(paths: &[PathBuf], importer: &Importer) -> BulkReport {
+    let mut report = BulkReport::default();
+    for path in paths {
+        match importer.import(path, None) {
+            Ok(dest) => {
+                report.done += 1;
+                report.imported.push(dest);
+            }
+            Err(err) => report.errors.push(err),
+        }
+    }
+    report
+}
+
+/// moves the files to the trash of the system, the records of the managed ones are
+/// forgotten
+pub fn delete(paths: &[PathBuf], managed_dir: &Path, library: &mut LibraryStore) -> BulkReport {
+    let mut report = BulkReport::default();
+    for path in paths {
+        let result = importer::trash(path);
+        if result.is_ok() {
+            if let Some(key) = LibraryStore::key_for(managed_dir, path) {
+                library.remove_record(&key);
+            }
+        }
+        report.add(result);
+    }
+    report
+}
+
+/// moves the files into `dir`, which is created if needed.
+///
+/// the records follow the files moved within the managed directory, the records of the
+/// files moved out of it are forgotten. existing files are never overwritten
+pub fn move_to(
+    paths: &[PathBuf],
+    dir: &Path,
+    managed_dir: &Path,
+    library: &mut LibraryStore,
+) -> BulkReport {
+    let mut report = BulkReport::default();
+    if let Err(err) = fs::create_dir_all(dir) {
+        report.errors.push(Error::writing_file(dir, err));
+        return report;
+    }
+    for path in paths {
+        let dest = match path.file_name() {
+            Some(file_name) => dir.join(file_name),
+            None => continue,
+        };
+        if dest == *path {
+            continue;
+        }
+        report.add(move_one(path, &dest, managed_dir, library));
+    }
+    report
+}
+
+/// moves `src` to `dest` with its record, existing files are never overwritten
+pub(super) fn move_one(
+    src: &Path,
+    dest: &Path,
+    managed_dir: &Path,
+    library: &mut LibraryStore,
+) -> Result<()> {
+    move_file(src, dest)?;
+    if let Some(from) = LibraryStore::key_for(managed_dir, src) {
+        match LibraryStore::key_for(managed_dir, dest) {
+            Some(to) => library.rename_record(&from, &to),
+            None => {
+                library.remove_record(&from);
+            }
+        }
+    }
+    log::info!("moved {} to {}", src.display(), dest.display());
+    Ok(())
+}
+
+/// adds `tag` to the managed files, or removes it from them if it starts with `-`.
+///
+/// the files which already have the tag or do not have it are left alone
+pub fn tag(
+    paths: &[PathBuf],
+    tag: &str,
+    managed_dir: &Path,
+    library: &mut LibraryStore,
+) -> BulkReport {
+    let (tag, remove) = match tag.strip_prefix('-') {
+        Some(tag) => (tag, true),
+        None => (tag, false),
+    };
+    let mut report = BulkReport::default();
+    for key in paths
+        .iter()
+        .filter_map(|path| LibraryStore::key_for(managed_dir, path))
+    {
+        let tags = &mut library.record_mut(&key).tags;
+        let position = tags.iter().position(|t| t == tag);
+        match (position, remove) {
+            (Some(idx), true) => {
+                tags.remove(idx);
+            }
+            (None, false) => tags.push(tag.to_string()),
+            _ => continue,
+        }
+        report.done += 1;
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{delete, move_to, tag};
+    use crate::{
+        domain::testing::{library, ReadOnly},
+        error::Error,
+    };
+    use std::fs;
+
+    #[test]
+    fn test_tag_and_untag() {
+        let (_dir, managed, mut library) = library(&["a.pdf", "b.pdf"]);
+        let paths = [managed.join("a.pdf"), managed.join("b.pdf")];
+        assert_eq!(tag(&paths, "ml", &managed, &mut library).done, 2);
+        assert_eq!(tag(&paths, "ml", &managed, &mut library).done, 0);
+        assert_eq!(tag(&paths[..1], "-ml", &managed, &mut library).done, 1);
+        assert!(library.record("a.pdf").unwrap().tags.is_empty());
+        assert_eq!(library.record("b.pdf").unwrap().tags, ["ml"]);
+    }
+
+    #[test]
+    fn test_move_keeps_records() {
+        let (_dir, managed, mut library) = library(&["a.pdf", "b.pdf"]);
+        let paths = [managed.join("a.pdf"), managed.join("b.pdf")];
+        library.record_mut("a.pdf").tags.push("ml".to_string());
+
+        let report = move_to(&paths, &managed.join("ml"), &managed, &mut library);
+        assert_eq!(report.done, 2);
+        assert!(library.record("a.pdf").is_none());
+        assert_eq!(library.record("ml/a.pdf").unwrap().tags, ["ml"]);
+        // a file without a record moves without one
+        assert!(library.record("ml/b.pdf").is_none());
+        assert!(managed.join("ml/b.pdf").exists());
+    }
+
+    #[test]
+    fn test_move_never_overwrites() {
+        let (_dir, managed, mut library) = library(&["a.pdf", "b.pdf"]);
+        let paths = [managed.join("a.pdf"), managed.join("b.pdf")];
+        library.record_mut("a.pdf");
+        fs::create_dir_all(managed.join("ml")).unwrap();
+        fs::write(managed.join("ml/a.pdf"), "other").unwrap();
+
+        let report = move_to(&paths, &managed.join("ml"), &managed, &mut library);
+        assert_eq!(report.done, 1);
+        assert!(matches!(
+            report.errors.as_slice(),
+            [Error::AlreadyExists { path }] if *path == managed.join("ml/a.pdf")
+        ));
+        assert!(paths[0].exists());
+        assert_eq!(fs::read(managed.join("ml/a.pdf")).unwrap(), b"other");
+        assert!(library.record("a.pdf").is_some());
+    }
+
+    #[test]
+    fn test_move_out_of_the_library_forgets_records() {
+        let (dir, managed, mut library) = library(&["a.pdf"]);
+        library.record_mut("a.pdf");
+        let paths = [managed.join("a.pdf")];
+        let report = move_to(&paths, &dir.path().join("out"), &managed, &mut library);
+        assert_eq!(report.done, 1);
+        assert!(library.record("a.pdf").is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_move_into_read_only_directory_keeps_records() {
+        let (_dir, managed, mut library) = library(&["a.pdf"]);
+        library.record_mut("a.pdf");
+        let ro = managed.join("ro");
+        fs::create_dir_all(&ro).unwrap();
+        let Some(_read_only) = ReadOnly::new(&ro) else {
+            return;
+        };
+
+        let paths = [managed.join("a.pdf")];
+        let report = move_to(&paths, &ro, &managed, &mut library);
+        assert_eq!(report.done, 0);
+        assert!(matches!(
+            report.errors.as_slice(),
+            [Error::PermissionDenied { .. }]
+        ));
+        assert!(paths[0].exists());
+        assert!(library.record("a.pdf").is_some());
+    }
+
+    #[test]
+    fn test_delete_missing_file_keeps_record() {
+        let (_dir, managed, mut library) = library(&[]);
+        library.record_mut("gone.pdf");
+        let report = delete(&[managed.join("gone.pdf")], &managed, &mut library);
+        assert_eq!((report.done, report.errors.len()), (0, 1));
+        assert!(matches!(report.errors[0], Error::TrashFailed { .. }));
+        assert!(library.record("gone.pdf").is_some());
+    }
+}
@@ -0,0 +1,436 @@
+use std::{
+    collections::HashSet,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::domain::{library_store::LibraryStore, pdf_file::PdfFile};
+
+/// words skipped when the citekey takes the first word of the title
+const STOP_WORDS: [&str; 12] = [
+    "a", "an", "and", "at", "for", "from", "in", "of", "on", "the", "to", "with",
+];
+
+/// Bibliographic record of a managed document
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Citation {
+    /// set by the user, empty when the citekey is generated
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub citekey: String,
+    /// as written by the user, either "Given Family" or "Family, Given"
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub authors: Vec<String>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub year: Option<i32>,
+    /// journal or conference
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub venue: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub doi: String,
+}
+
+impl Citation {
+    /// the record of a document nobody has described yet, titled after its file
+    pub fn from_file(pdf_file: &PdfFile) -> Self {
+        let title = Path::new(&pdf_file.file_name)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().replace(['_', '-'], " "))
+            .unwrap_or_default();
+        Self {
+            title,
+            ..Self::default()
+        }
+    }
+
+    /// `<family name of the first author><year><first title word>`, e.g. `lovelace1843sketch`
+    pub fn generated_citekey(&self) -> String {
+        let author = self
+            .first_author()
+            .map(key_part)
+            .filter(|part| !part.is_empty())
+            .unwrap_or_else(|| "anon".to_string());
+        let year = self.year.map(|year| year.to_string()).unwrap_or_default();
+        let word = self
+            .title
+            .split_whitespace()
+            .map(key_part)
+            .find(|word| !word.is_empty() && !STOP_WORDS.contains(&word.as_str()))
+            .unwrap_or_default();
+        format!("{}{}{}", author, year, word)
+    }
+
+    /// the family name of the first author
+    pub fn first_author(&self) -> Option<&str> {
+        self.authors
+            .first()
+            .map(|author| split_name(author).0)
+            .filter(|family| !family.is_empty())
+    }
+
+    /// takes the non-empty fields of `other`, keeping the others
+    pub fn update_from(&mut self, other: Citation) {
+        let take = |field: &mut String, value: String| {
+            if !value.is_empty() {
+                *field = value;
+            }
+        };
+        take(&mut self.citekey, other.citekey);
+        take(&mut self.title, other.title);
+        take(&mut self.venue, other.venue);
+        take(&mut self.doi, other.doi);
+        if !other.authors.is_empty() {
+            self.authors = other.authors;
+        }
+        self.year = other.year.or(self.year);
+    }
+
+    fn entry_type(&self) -> &'static str {
+        if self.venue.is_empty() {
+            "misc"
+        } else {
+            "article"
+        }
+    }
+}
+
+/// lowercase ascii letters and digits of `s`
+fn key_part(s: &str) -> String {
+    s.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// splits an author into family and given names
+fn split_name(author: &str) -> (&str, &str) {
+    let author = author.trim();
+    match author.split_once(',') {
+        Some((family, given)) => (family.trim(), given.trim()),
+        None => match author.rsplit_once(' ') {
+            Some((given, family)) => (family.trim(), given.trim()),
+            None => (author, ""),
+        },
+    }
+}
+
+/// Output formats of the bibliography
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BibliographyFormat {
+    Bibtex,
+    CslJson,
+}
+
+impl BibliographyFormat {
+    pub const ALL: [Self; 2] = [Self::Bibtex, Self::CslJson];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Bibtex => "BibTeX",
+            Self::CslJson => "CSL-JSON",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Bibtex => "bib",
+            Self::CslJson => "json",
+        }
+    }
+}
+
+impl FromStr for BibliographyFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bibtex" => Ok(Self::Bibtex),
+            "csl-json" => Ok(Self::CslJson),
+            _ => Err(format!(
+                "unknown bibliography format \"{}\", use bibtex or csl-json",
+                s
+            )),
+        }
+    }
+}
+
+/// A citation with its final citekey
+#[derive(Debug, Clone)]
+pub struct BibEntry {
+    pub path: PathBuf,
+    pub citekey: String,
+    pub citation: Citation,
+}
+
+/// the citation of every file of the library, with unique citekeys.
+///
+/// Citekeys are resolved over the whole library in path order, so the citekey of a
+/// document does not depend on which documents are exported. Citekeys set by the user
+/// win over generated ones; a citekey which is already taken gets a suffix `a`, `b`, ...
+pub fn bibliography(
+    managed_dir: &Path,
+    pdf_files: &[PdfFile],
+    store: &LibraryStore,
+) -> Vec<BibEntry> {
+    let mut entries = pdf_files
+        .iter()
+        .map(|pdf_file| {
+            let key = LibraryStore::key_for(managed_dir, &pdf_file.path).unwrap_or_default();
+            let citation = store
+                .record(&key)
+                .and_then(|record| record.citation.clone())
+                .unwrap_or_else(|| Citation::from_file(pdf_file));
+            (key, pdf_file.path.clone(), citation)
+        })
+        .collect::<Vec<_>>();
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut taken = HashSet::new();
+    let mut citekeys = vec![String::new(); entries.len()];
+    let by_user = |idx: &usize| !entries[*idx].2.citekey.is_empty();
+    let user_first = (0..entries.len())
+        .filter(by_user)
+        .chain((0..entries.len()).filter(|idx| !by_user(idx)));
+    for idx in user_first {
+        let citation = &entries[idx].2;
+        let base = if citation.citekey.is_empty() {
+            citation.generated_citekey()
+        } else {
+            citation.citekey.clone()
+        };
+        let citekey = (0..)
+            .map(|n| format!("{}{}", base, suffix(n)))
+            .find(|citekey| !taken.contains(citekey))
+            .unwrap_or(base);
+        taken.insert(citekey.clone());
+        citekeys[idx] = citekey;
+    }
+
+    entries
+        .into_iter()
+        .zip(citekeys)
+        .map(|((_, path, citation), citekey)| BibEntry {
+            path,
+            citekey,
+            citation,
+        })
+        .collect()
+}
+
+/// "", "a", ..., "z", "aa", "ab", ...
+fn suffix(mut n: usize) -> String {
+    let mut suffix = Vec::new();
+    while n > 0 {
+        n -= 1;
+        suffix.push(b'a' + (n % 26) as u8);
+        n /= 26;
+    }
+    suffix.reverse();
+    String::from_utf8(suffix).unwrap_or_default()
+}
+
+/// writes `entries` in the given format, returns the number of entries written
+pub fn write_bibliography<W: Write>(
+    out: &mut W,
+    entries: &[BibEntry],
+    format: BibliographyFormat,
+) -> io::Result<usize> {
+    match format {
+        BibliographyFormat::Bibtex => write_bibtex(out, entries)?,
+        BibliographyFormat::CslJson => write_csl_json(out, entries)?,
+    }
+    out.flush()?;
+    Ok(entries.len())
+}
+
+fn write_bibtex<W: Write>(out: &mut W, entries: &[BibEntry]) -> io::Result<()> {
+    for (i, entry) in entries.iter().enumerate() {
+        if i > 0 {
+            writeln!(out)?;
+        }
+        let citation = &entry.citation;
+        writeln!(out, "@{}{{{},", citation.entry_type(), entry.citekey)?;
+        if !citation.authors.is_empty() {
+            let authors = citation.authors.iter().map(|author| {
+                let (family, given) = split_name(author);
+                if given.is_empty() {
+                    family.to_string()
+                } else {
+                    format!("{}, {}", family, given)
+                }
+            });
+            let authors = authors.collect::<Vec<_>>().join(" and ");
+            writeln!(out, "  author = {{{}}},", latex_escape(&authors))?;
+        }
+        if !citation.title.is_empty() {
+            writeln!(out, "  title = {{{}}},", latex_escape(&citation.title))?;
+        }
+        if let Some(year) = citation.year {
+            writeln!(out, "  year = {{{}}},", year)?;
+        }
+        if !citation.venue.is_empty() {
+            writeln!(out, "  journal = {{{}}},", latex_escape(&citation.venue))?;
+        }
+        if !citation.doi.is_empty() {
+            writeln!(out, "  doi = {{{}}},", citation.doi)?;
+        }
+        writeln!(out, "  file = {{{}}},", entry.path.display())?;
+        writeln!(out, "}}")?;
+    }
+    Ok(())
+}
+
+/// escapes the characters which are special in LaTeX text. Balanced braces are kept, as
+/// they protect the case of words such as `{BERT}`, unbalanced ones are escaped
+fn latex_escape(s: &str) -> String {
+    let chars = s.chars().collect::<Vec<_>>();
+    let mut balanced = vec![false; chars.len()];
+    let mut open = Vec::new();
+    for (i, c) in chars.iter().enumerate() {
+        match c {
+            '{' => open.push(i),
+            '}' => {
+                if let Some(start) = open.pop() {
+                    balanced[start] = true;
+                    balanced[i] = true;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut escaped = String::with_capacity(s.len());
+    for (c, balanced) in chars.into_iter().zip(balanced) {
+        match c {
+            '&' | '%' | '$' | '#' | '_' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '{' | '}' if !balanced => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\\' => escaped.push_str("\\textbackslash{}"),
+            '~' => escaped.push_str("\\textasciitilde{}"),
+            '^' => escaped.push_str("\\textasciicircum{}"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn write_csl_json<W: Write>(out: &mut W, entries: &[BibEntry]) -> io::Result<()> {
+    let items = entries
+        .iter()
+        .map(|entry| {
+            let citation = &entry.citation;
+            let mut item = json!({
+                "id": entry.citekey,
+                "type": if citation.venue.is_empty() { "document" } else { "article-journal" },
+            });
+            if !citation.authors.is_empty() {
+                let authors = citation
+                    .authors
+                    .iter()
+                    .map(|author| match split_name(author) {
+                        (family, "") => json!({ "literal": family }),
+                        (family, given) => json!({ "family": family, "given": given }),
+                    });
+                item["author"] = authors.collect();
+            }
+            if !citation.title.is_empty() {
+                item["title"] = citation.title.clone().into();
+            }
+            if let Some(year) = citation.year {
+                item["issued"] = json!({ "date-parts": [[year]] });
+            }
+            if !citation.venue.is_empty() {
+                item["container-title"] = citation.venue.clone().into();
+            }
+            if !citation.doi.is_empty() {
+                item["DOI"] = citation.doi.clone().into();
+            }
+            item
+        })
+        .collect::<Vec<_>>();
+    serde_json::to_writer_pretty(&mut *out, &items)?;
+    writeln!(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{bibliography, latex_escape, Citation};
+    use crate::domain::{library_store::LibraryStore, pdf_file::PdfFile, pdf_info::PdfInfo};
+    use std::path::{Path, PathBuf};
+
+    fn pdf_file(name: &str) -> PdfFile {
+        PdfFile {
+            file_name: name.to_string(),
+            path: PathBuf::from("/papers").join(name),
+            size: 0,
+            created: None,
+            modified: None,
+            info: PdfInfo::default(),
+        }
+    }
+
+    #[test]
+    fn test_generated_citekey() {
+        let citation = Citation {
+            authors: vec!["Ada Lovelace".to_string(), "Babbage, Charles".to_string()],
+            title: "A Sketch of the Analytical Engine".to_string(),
+            year: Some(1843),
+            ..Citation::default()
+        };
+        assert_eq!(citation.generated_citekey(), "lovelace1843sketch");
+        assert_eq!(Citation::default().generated_citekey(), "anon");
+    }
+
+    #[test]
+    fn test_citekey_conflicts() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = LibraryStore::open(dir.path().join("library.json")).unwrap();
+        let citation = Citation {
+            authors: vec!["Turing, Alan".to_string()],
+            year: Some(1936),
+            title: "On computable numbers".to_string(),
+            ..Citation::default()
+        };
+        for key in ["a.pdf", "b.pdf"] {
+            store.record_mut(key).citation = Some(citation.clone());
+        }
+        // set by the user, wins over the generated citekey of a.pdf
+        store.record_mut("c.pdf").citation = Some(Citation {
+            citekey: "turing1936computable".to_string(),
+            ..Citation::default()
+        });
+
+        let files = [pdf_file("c.pdf"), pdf_file("a.pdf"), pdf_file("b.pdf")];
+        let citekeys = bibliography(Path::new("/papers"), &files, &store)
+            .into_iter()
+            .map(|entry| entry.citekey)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            citekeys,
+            [
+                "turing1936computablea",
+                "turing1936computableb",
+                "turing1936computable"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_latex_escape() {
+        assert_eq!(
+            latex_escape("{BERT}: 50% of C:\\Users\\~x^2 {unbalanced"),
+            "{BERT}: 50\\% of C:\\textbackslash{}Users\\textbackslash{}\\textasciitilde{}x\\textasciicircum{}2 \\{unbalanced"
+        );
+        assert_eq!(latex_escape("a} {b}"), "a\\} {b}");
+    }
+}
@@ -0,0 +1,95 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// A collection of the tree, as the names from the top level down,
+/// e.g. `Thesis > Chapter 2 > Related work`.
+///
+/// Collections only group documents, a document can be in several of them
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CollectionPath(Vec<String>);
+
+impl CollectionPath {
+    /// separates the names of nested collections when they are typed or shown
+    pub const SEPARATOR: &'static str = " > ";
+
+    /// parses `Thesis > Chapter 2`, `None` if a name is empty
+    pub fn parse(s: &str) -> Option<Self> {
+        let names = s
+            .split(Self::SEPARATOR.trim())
+            .map(|name| name.trim().to_string())
+            .collect::<Vec<_>>();
+        if names.iter().any(String::is_empty) {
+            return None;
+        }
+        Some(Self(names))
+    }
+
+    /// the collection `relative` nested in this one
+    pub fn join(&self, relative: &Self) -> Self {
+        Self(self.0.iter().chain(&relative.0).cloned().collect())
+    }
+
+    /// the last name
+    pub fn name(&self) -> &str {
+        self.0.last().map_or("", String::as_str)
+    }
+
+    /// 0 for a top level collection
+    pub fn depth(&self) -> usize {
+        self.0.len().saturating_sub(1)
+    }
+
+    /// this collection and the collections it is nested in, the top level one first
+    pub fn ancestors(&self) -> impl Iterator<Item = Self> + '_ {
+        (1..=self.0.len()).map(|len| Self(self.0[..len].to_vec()))
+    }
+
+    /// whether `other` is this collection or nested in it
+    pub fn contains(&self, other: &Self) -> bool {
+        other.0.starts_with(&self.0)
+    }
+}
+
+impl fmt::Display for CollectionPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.join(Self::SEPARATOR))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CollectionPath;
+
+    fn path(s: &str) -> CollectionPath {
+        CollectionPath::parse(s).unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        let nested = path("Thesis >Chapter 2 > Related work");
+        assert_eq!(nested.to_string(), "Thesis > Chapter 2 > Related work");
+        assert_eq!((nested.name(), nested.depth()), ("Related work", 2));
+        assert!(CollectionPath::parse("Thesis > ").is_none());
+        assert!(CollectionPath::parse("").is_none());
+    }
+
+    #[test]
+    fn test_contains() {
+        let thesis = path("Thesis");
+        let nested = path("Thesis > Chapter 2 > Related work");
+        assert!(thesis.contains(&nested));
+        assert!(!nested.contains(&thesis));
+        // a sibling whose name starts the same is not nested
+        assert!(!thesis.contains(&path("Thesis drafts")));
+    }
+
+    #[test]
+    fn test_ancestors_and_join() {
+        let thesis = path("Thesis");
+        let nested = path("Thesis > Chapter 2 > Related work");
+        assert_eq!(nested.ancestors().next(), Some(thesis.clone()));
+        assert_eq!(thesis.join(&path("Chapter 2 > Related work")), nested);
+    }
+}
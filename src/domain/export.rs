@@ -0,0 +1,284 @@
+use std::{fmt, io::Write, path::Path, str::FromStr};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::Deserialize;
+
+use crate::domain::{
+    library_store::{DocumentRecord, LibraryStore},
+    pdf_file::PdfFile,
+};
+
+/// File formats of the catalogue export
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub enum ExportFormat {
+    /// one JSON object per line
+    #[default]
+    #[serde(rename = "jsonl")]
+    JsonLines,
+    /// comma separated values with a header row
+    #[serde(rename = "csv")]
+    Csv,
+}
+
+impl ExportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::JsonLines => "jsonl",
+            Self::Csv => "csv",
+        }
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "jsonl" => Ok(Self::JsonLines),
+            "csv" => Ok(Self::Csv),
+            _ => Err(format!("unknown export format \"{}\", use jsonl or csv", s)),
+        }
+    }
+}
+
+/// Columns of the catalogue export.
+///
+/// The names are the stable schema: they are the JSON keys and the CSV header, in this order.
+/// New fields are only ever appended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportField {
+    Path,
+    FileName,
+    Size,
+    Created,
+    Modified,
+    Tags,
+    Notes,
+}
+
+impl ExportField {
+    pub const ALL: [Self; 7] = [
+        Self::Path,
+        Self::FileName,
+        Self::Size,
+        Self::Created,
+        Self::Modified,
+        Self::Tags,
+        Self::Notes,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Path => "path",
+            Self::FileName => "file_name",
+            Self::Size => "size",
+            Self::Created => "created",
+            Self::Modified => "modified",
+            Self::Tags => "tags",
+            Self::Notes => "notes",
+        }
+    }
+}
+
+impl fmt::Display for ExportField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for ExportField {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|field| field.name() == s)
+            .ok_or_else(|| {
+                let names = Self::ALL.map(Self::name).join(", ");
+                format!("unknown export field \"{}\", use one of {}", s, names)
+            })
+    }
+}
+
+/// A managed pdf file with what the library knows about it
+pub struct ExportRow<'a> {
+    pub pdf_file: &'a PdfFile,
+    pub record: Option<&'a DocumentRecord>,
+}
+
+impl<'a> ExportRow<'a> {
+    /// joins the managed files with their records in the library
+    pub fn catalogue(
+        managed_dir: &Path,
+        pdf_files: &'a [PdfFile],
+        store: &'a LibraryStore,
+    ) -> Vec<Self> {
+        pdf_files
+            .iter()
+            .map(|pdf_file| ExportRow {
+                pdf_file,
+                record: LibraryStore::key_for(managed_dir, &pdf_file.path)
+                    .and_then(|key| store.record(&key)),
+            })
+            .collect()
+    }
+}
+
+/// A single exported value, formatted by each format in its own way
+enum Value<'a> {
+    Null,
+    Number(u64),
+    Text(String),
+    List(&'a [String]),
+}
+
+impl<'a> ExportRow<'a> {
+    fn value(&self, field: ExportField) -> Value<'a> {
+        let date = |date: Option<DateTime<Utc>>| match date {
+            Some(date) => Value::Text(date.to_rfc3339_opts(SecondsFormat::Secs, true)),
+            None => Value::Null,
+        };
+        match field {
+            ExportField::Path => Value::Text(self.pdf_file.path.display().to_string()),
+            ExportField::FileName => Value::Text(self.pdf_file.file_name.clone()),
+            ExportField::Size => Value::Number(self.pdf_file.size),
+            ExportField::Created => date(self.pdf_file.created),
+            ExportField::Modified => date(self.pdf_file.modified),
+            ExportField::Tags => Value::List(self.record.map_or(&[], |r| r.tags.as_slice())),
+            ExportField::Notes => match self.record {
+                Some(record) if !record.notes.is_empty() => Value::Text(record.notes.clone()),
+                _ => Value::Null,
+            },
+        }
+    }
+}
+
+/// writes `rows` with the given columns, returns the number of rows written
+pub fn export<W: Write>(
+    out: &mut W,
+    rows: &[ExportRow<'_>],
+    fields: &[ExportField],
+    format: ExportFormat,
+) -> std::io::Result<usize> {
+    match format {
+        ExportFormat::JsonLines => write_json_lines(out, rows, fields)?,
+        ExportFormat::Csv => write_csv(out, rows, fields)?,
+    }
+    out.flush()?;
+    Ok(rows.len())
+}
+
+fn write_json_lines<W: Write>(
+    out: &mut W,
+    rows: &[ExportRow<'_>],
+    fields: &[ExportField],
+) -> std::io::Result<()> {
+    for row in rows {
+        // written by hand to keep the keys in schema order
+        let mut line = String::from("{");
+        for (i, &field) in fields.iter().enumerate() {
+            if i > 0 {
+                line.push(',');
+            }
+            let value = match row.value(field) {
+                Value::Null => serde_json::Value::Null,
+                Value::Number(n) => n.into(),
+                Value::Text(s) => s.into(),
+                Value::List(list) => list.into(),
+            };
+            line.push_str(&format!("\"{}\":{}", field.name(), value));
+        }
+        line.push('}');
+        writeln!(out, "{}", line)?;
+    }
+    Ok(())
+}
+
+fn write_csv<W: Write>(
+    out: &mut W,
+    rows: &[ExportRow<'_>],
+    fields: &[ExportField],
+) -> std::io::Result<()> {
+    let header = fields.iter().map(|field| field.name()).collect::<Vec<_>>();
+    writeln!(out, "{}", header.join(","))?;
+
+    for row in rows {
+        let cells = fields
+            .iter()
+            .map(|&field| match row.value(field) {
+                Value::Null => String::new(),
+                Value::Number(n) => n.to_string(),
+                Value::Text(s) => csv_escape(&s),
+                Value::List(list) => csv_escape(&list.join(";")),
+            })
+            .collect::<Vec<_>>();
+        writeln!(out, "{}", cells.join(","))?;
+    }
+    Ok(())
+}
+
+/// quotes a cell if it contains a separator, a quote or a line break (RFC 4180)
+fn csv_escape(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{export, ExportField, ExportFormat, ExportRow};
+    use crate::domain::{library_store::DocumentRecord, pdf_file::PdfFile, pdf_info::PdfInfo};
+    use std::path::PathBuf;
+
+    fn pdf_file() -> PdfFile {
+        PdfFile {
+            file_name: "a, b.pdf".to_string(),
+            path: PathBuf::from("/papers/a, b.pdf"),
+            size: 42,
+            created: None,
+            modified: None,
+            info: PdfInfo::default(),
+        }
+    }
+
+    #[test]
+    fn test_export_json_lines() {
+        let file = pdf_file();
+        let record = DocumentRecord {
+            tags: vec!["ml".to_string(), "survey".to_string()],
+            notes: "read \"twice\"".to_string(),
+            ..DocumentRecord::default()
+        };
+        let rows = [ExportRow {
+            pdf_file: &file,
+            record: Some(&record),
+        }];
+        let mut out = Vec::new();
+        export(&mut out, &rows, &ExportField::ALL, ExportFormat::JsonLines).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "{\"path\":\"/papers/a, b.pdf\",\"file_name\":\"a, b.pdf\",\"size\":42,\
+             \"created\":null,\"modified\":null,\"tags\":[\"ml\",\"survey\"],\
+             \"notes\":\"read \\\"twice\\\"\"}\n"
+        );
+    }
+
+    #[test]
+    fn test_export_csv_selected_fields() {
+        let file = pdf_file();
+        let rows = [ExportRow {
+            pdf_file: &file,
+            record: None,
+        }];
+        let fields = [ExportField::FileName, ExportField::Size, ExportField::Tags];
+        let mut out = Vec::new();
+        export(&mut out, &rows, &fields, ExportFormat::Csv).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "file_name,size,tags\n\"a, b.pdf\",42,\n"
+        );
+    }
+}
@@ -0,0 +1,199 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use super::{importer::validate_name, library_store::LibraryStore};
+use crate::error::{Error, Result};
+
+/// parses a folder typed by the user, relative to the managed directory, e.g.
+/// `theses/2021`.
+///
+/// an empty input is the managed directory itself
+pub fn parse(input: &str) -> Result<PathBuf> {
+    let mut folder = PathBuf::new();
+    for part in input
+        .split('/')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+    {
+        validate_name(part)?;
+        folder.push(part);
+    }
+    Ok(folder)
+}
+
+/// the folder of `path` relative to the managed directory, empty for its own files
+pub fn of_file<'a>(managed_dir: &Path, path: &'a Path) -> &'a Path {
+    path.parent()
+        .and_then(|parent| parent.strip_prefix(managed_dir).ok())
+        .unwrap_or_else(|| Path::new(""))
+}
+
+/// creates `folder` and the folders it is nested in inside the managed directory
+pub fn create(managed_dir: &Path, folder: &Path) -> Result<PathBuf> {
+    let path = managed_dir.join(folder);
+    if path.exists() {
+        return Err(Error::AlreadyExists { path });
+    }
+    fs::create_dir_all(&path).map_err(|err| Error::writing_file(&path, err))?;
+    log::info!("created the folder {}", path.display());
+    Ok(path)
+}
+
+/// moves or renames the folder `from` to `to`, both relative to the managed directory,
+/// and the records of the files in it with it.
+///
+/// returns the number of records which followed their files, existing folders are never
+/// overwritten
+pub fn move_folder(
+    managed_dir: &Path,
+    from: &Path,
+    to: &Path,
+    library: &mut LibraryStore,
+) -> Result<usize> {
+    let invalid = |reason: &str| Error::InvalidName {
+        name: to.display().to_string(),
+        reason: reason.to_string(),
+    };
+    if from.as_os_str().is_empty() || to.as_os_str().is_empty() {
+        return Err(invalid("the library itself cannot be moved"));
+    }
+    if to.starts_with(from) {
+        return Err(invalid("a folder cannot be moved into itself"));
+    }
+    let (src, dest) = (managed_dir.join(from), managed_dir.join(to));
+    if dest.exists() {
+        return Err(Error::AlreadyExists { path: dest });
+    }
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent).map_err(|err| Error::writing_file(parent, err))?;
+    }
+    fs::rename(&src, &dest).map_err(|err| Error::writing_file(&src, err))?;
+    log::info!("moved the folder {} to {}", src.display(), dest.display());
+
+    match (
+        LibraryStore::key_for(managed_dir, &src),
+        LibraryStore::key_for(managed_dir, &dest),
+    ) {
+        (Some(from), Some(to)) => Ok(library.rename_folder(&from, &to)),
+        _ => Ok(0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{move_folder, parse};
+    use crate::{
+        domain::{
+            library_store::LibraryStore,
+            testing::{library, ReadOnly},
+        },
+        error::Error,
+    };
+    use std::{
+        fs,
+        path::{Path, PathBuf},
+    };
+    use tempfile::TempDir;
+
+    /// a managed directory with `ml/nlp/a.pdf` tagged `read`, and a record of `mlx/b.pdf`
+    fn ml_folder() -> (TempDir, PathBuf, LibraryStore) {
+        let (dir, managed, mut library) = library(&["ml/nlp/a.pdf"]);
+        library
+            .record_mut("ml/nlp/a.pdf")
+            .tags
+            .push("read".to_string());
+        library.record_mut("mlx/b.pdf");
+        (dir, managed, library)
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse(" archive / ml ").unwrap(), Path::new("archive/ml"));
+        assert!(matches!(parse("a/../b"), Err(Error::InvalidName { .. })));
+    }
+
+    #[test]
+    fn test_move_folder_moves_records() {
+        let (_dir, managed, mut library) = ml_folder();
+        let to = Path::new("archive/ml");
+        let moved = move_folder(&managed, Path::new("ml"), to, &mut library).unwrap();
+        assert_eq!(moved, 1);
+        assert!(managed.join("archive/ml/nlp/a.pdf").exists());
+        assert_eq!(
+            library.record("archive/ml/nlp/a.pdf").unwrap().tags,
+            ["read"]
+        );
+        // a folder whose name starts the same is not moved
+        assert!(library.record("mlx/b.pdf").is_some());
+    }
+
+    #[test]
+    fn test_move_folder_without_records() {
+        let (_dir, managed, mut library) = ml_folder();
+        fs::create_dir_all(managed.join("empty")).unwrap();
+        let moved = move_folder(
+            &managed,
+            Path::new("empty"),
+            Path::new("other"),
+            &mut library,
+        );
+        assert_eq!(moved.unwrap(), 0);
+        assert!(managed.join("other").is_dir());
+    }
+
+    #[test]
+    fn test_move_folder_into_itself_fails() {
+        let (_dir, managed, mut library) = ml_folder();
+        let result = move_folder(
+            &managed,
+            Path::new("ml"),
+            Path::new("ml/inner"),
+            &mut library,
+        );
+        assert!(matches!(result, Err(Error::InvalidName { .. })));
+        assert!(managed.join("ml/nlp/a.pdf").exists());
+    }
+
+    #[test]
+    fn test_move_folder_onto_existing_fails() {
+        let (_dir, managed, mut library) = ml_folder();
+        fs::create_dir_all(managed.join("archive")).unwrap();
+        let result = move_folder(
+            &managed,
+            Path::new("ml"),
+            Path::new("archive"),
+            &mut library,
+        );
+        assert!(matches!(result, Err(Error::AlreadyExists { .. })));
+        assert!(library.record("ml/nlp/a.pdf").is_some());
+    }
+
+    #[test]
+    fn test_move_missing_folder_fails() {
+        let (_dir, managed, mut library) = ml_folder();
+        let result = move_folder(
+            &managed,
+            Path::new("gone"),
+            Path::new("other"),
+            &mut library,
+        );
+        assert!(matches!(result, Err(Error::UnwritableFile { .. })));
+        assert!(library.record("mlx/b.pdf").is_some());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_move_folder_into_read_only_folder_keeps_records() {
+        let (_dir, managed, mut library) = ml_folder();
+        fs::create_dir_all(managed.join("ro")).unwrap();
+        let Some(_read_only) = ReadOnly::new(&managed.join("ro")) else {
+            return;
+        };
+        let result = move_folder(&managed, Path::new("ml"), Path::new("ro/ml"), &mut library);
+        assert!(matches!(result, Err(Error::PermissionDenied { .. })));
+        assert!(managed.join("ml/nlp/a.pdf").exists());
+        assert!(library.record("ml/nlp/a.pdf").is_some());
+    }
+}
@@ -0,0 +1,109 @@
+use chrono::{DateTime, Duration, Utc};
+
+use super::library_store::DocumentRecord;
+
+/// The virtual lists of the documents by what happened to them lately, the newest first
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Recent {
+    /// imported in the last 7 days
+    Added,
+    /// opened in the last 30 days
+    Opened,
+}
+
+impl Recent {
+    pub fn title(self) -> &'static str {
+        match self {
+            Self::Added => "recently added",
+            Self::Opened => "recently opened",
+        }
+    }
+
+    /// when the document was imported, or last opened
+    pub fn time(self, record: &DocumentRecord) -> Option<DateTime<Utc>> {
+        match self {
+            Self::Added => record.imported,
+            Self::Opened => record.opened,
+        }
+    }
+
+    /// how far back the list goes
+    fn window(self) -> Duration {
+        match self {
+            Self::Added => Duration::days(7),
+            Self::Opened => Duration::days(30),
+        }
+    }
+
+    /// whether the document belongs to the list at `now`
+    pub fn includes(self, record: Option<&DocumentRecord>, now: DateTime<Utc>) -> bool {
+        record
+            .and_then(|record| self.time(record))
+            .is_some_and(|time| now - time <= self.window())
+    }
+}
+
+/// how long before `now` the `time` was, e.g. `2 hours ago`, in the largest unit
+pub fn relative_time(time: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let elapsed = now - time;
+    let ago = |count: i64, unit: &str| match count {
+        1 => format!("1 {} ago", unit),
+        _ => format!("{} {}s ago", count, unit),
+    };
+    if elapsed < Duration::minutes(1) {
+        "just now".to_string()
+    } else if elapsed < Duration::hours(1) {
+        ago(elapsed.num_minutes(), "minute")
+    } else if elapsed < Duration::days(1) {
+        ago(elapsed.num_hours(), "hour")
+    } else if elapsed < Duration::days(2) {
+        "yesterday".to_string()
+    } else if elapsed < Duration::weeks(2) {
+        ago(elapsed.num_days(), "day")
+    } else if elapsed < Duration::days(60) {
+        ago(elapsed.num_weeks(), "week")
+    } else {
+        time.format("%Y-%m-%d").to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{relative_time, Recent};
+    use crate::domain::library_store::DocumentRecord;
+    use chrono::{DateTime, Duration, TimeZone, Utc};
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 10, 19, 12, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_relative_time() {
+        let before = |duration| relative_time(now() - duration, now());
+        assert_eq!(before(Duration::seconds(20)), "just now");
+        assert_eq!(before(Duration::minutes(1)), "1 minute ago");
+        assert_eq!(before(Duration::minutes(150)), "2 hours ago");
+        assert_eq!(before(Duration::hours(30)), "yesterday");
+        assert_eq!(before(Duration::days(5)), "5 days ago");
+        assert_eq!(before(Duration::days(20)), "2 weeks ago");
+        assert_eq!(before(Duration::days(100)), "2026-07-11");
+    }
+
+    #[test]
+    fn test_recent_window() {
+        let record = DocumentRecord {
+            imported: Some(now() - Duration::days(8)),
+            opened: Some(now() - Duration::days(8)),
+            ..DocumentRecord::default()
+        };
+        assert!(!Recent::Added.includes(Some(&record), now()));
+        assert!(Recent::Opened.includes(Some(&record), now()));
+    }
+
+    #[test]
+    fn test_recent_without_record_or_time() {
+        assert!(!Recent::Opened.includes(None, now()));
+        let record = DocumentRecord::default();
+        assert!(!Recent::Added.includes(Some(&record), now()));
+    }
+}
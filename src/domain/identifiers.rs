@@ -0,0 +1,195 @@
+use std::{fmt, sync::OnceLock};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::domain::bibtex::normalize_doi;
+
+/// the pages scanned for identifiers, they are printed on the first page or the cover
+pub const SCANNED_PAGES: u32 = 2;
+
+/// Identifiers printed in a document
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Identifiers {
+    /// lowercase, without resolver prefix
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub doi: Option<String>,
+    /// without version, e.g. `2101.00001` or `hep-th/9901001`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub arxiv: Option<String>,
+    /// digits only, with a valid check digit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub isbn: Option<String>,
+}
+
+/// An identifier shared by two documents
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SharedIdentifier {
+    Doi(String),
+    Arxiv(String),
+    Isbn(String),
+}
+
+impl fmt::Display for SharedIdentifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Doi(doi) => write!(f, "doi {}", doi),
+            Self::Arxiv(id) => write!(f, "arXiv {}", id),
+            Self::Isbn(isbn) => write!(f, "isbn {}", isbn),
+        }
+    }
+}
+
+fn doi_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r#"\b10\.\d{4,9}/[^\s"<>]+"#).unwrap())
+}
+
+fn arxiv_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| {
+        Regex::new(r"(?i)arxiv:\s*(\d{4}\.\d{4,5}|[a-z-]+(?:\.[a-z]{2})?/\d{7})(?:v\d+)?").unwrap()
+    })
+}
+
+fn isbn_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"(?i)ISBN(?:-1[03])?:?\s*([\dX][\dX -]{8,16}[\dX])").unwrap())
+}
+
+impl Identifiers {
+    /// finds the first doi, arXiv id and isbn of `text`
+    pub fn scan(text: &str) -> Self {
+        let doi = doi_regex()
+            .find(text)
+            .map(|doi| normalize_doi(trim_doi(doi.as_str())));
+        let arxiv = arxiv_regex()
+            .captures(text)
+            .map(|captures| captures[1].to_lowercase());
+        let isbn = isbn_regex().captures_iter(text).find_map(|captures| {
+            let isbn = captures[1]
+                .chars()
+                .filter(|c| !matches!(c, ' ' | '-'))
+                .map(|c| c.to_ascii_uppercase())
+                .collect::<String>();
+            is_valid_isbn(&isbn).then_some(isbn)
+        });
+        Self { doi, arxiv, isbn }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.doi.is_none() && self.arxiv.is_none() && self.isbn.is_none()
+    }
+
+    /// the first identifier both documents have, strongest first
+    pub fn shared_with(&self, other: &Self) -> Option<SharedIdentifier> {
+        let same = |a: &Option<String>, b: &Option<String>| match (a, b) {
+            (Some(a), Some(b)) if a == b => Some(a.clone()),
+            _ => None,
+        };
+        same(&self.doi, &other.doi)
+            .map(SharedIdentifier::Doi)
+            .or_else(|| same(&self.arxiv, &other.arxiv).map(SharedIdentifier::Arxiv))
+            .or_else(|| same(&self.isbn, &other.isbn).map(SharedIdentifier::Isbn))
+    }
+
+    /// a file name derived from the identifiers, e.g. `arxiv-2101.00001.pdf`
+    pub fn suggested_file_name(&self) -> Option<String> {
+        let stem = if let Some(arxiv) = &self.arxiv {
+            format!("arxiv-{}", arxiv)
+        } else if let Some(doi) = &self.doi {
+            format!("doi-{}", doi)
+        } else {
+            format!("isbn-{}", self.isbn.as_ref()?)
+        };
+        Some(format!("{}.pdf", safe_file_stem(&stem)))
+    }
+}
+
+/// replaces the characters which are awkward in a file name, such as the `/` of a doi
+pub fn safe_file_stem(stem: &str) -> String {
+    stem.chars()
+        .map(|c| match c {
+            c if c.is_ascii_alphanumeric() || matches!(c, '.' | '-') => c,
+            _ => '_',
+        })
+        .collect()
+}
+
+/// removes the punctuation which ends the sentence around a doi
+fn trim_doi(doi: &str) -> &str {
+    let mut doi = doi.trim_end_matches(['.', ',', ';', ':', ']', '}']);
+    // a closing parenthesis belongs to the doi only if it closes one of the doi
+    while doi.ends_with(')') && doi.matches('(').count() < doi.matches(')').count() {
+        doi = doi[..doi.len() - 1].trim_end_matches(['.', ',', ';', ':']);
+    }
+    doi
+}
+
+fn is_valid_isbn(isbn: &str) -> bool {
+    let last = isbn.len().saturating_sub(1);
+    // an X stands for 10, and only as the check digit of an isbn-10
+    let digits = isbn
+        .chars()
+        .enumerate()
+        .map(|(i, c)| match c {
+            'X' if i == last && isbn.len() == 10 => Some(10),
+            _ => c.to_digit(10),
+        })
+        .collect::<Option<Vec<_>>>();
+    match digits.as_deref() {
+        Some(digits) if digits.len() == 10 => {
+            let sum: u32 = digits
+                .iter()
+                .enumerate()
+                .map(|(i, d)| (10 - i as u32) * d)
+                .sum();
+            sum.is_multiple_of(11)
+        }
+        Some(digits) if digits.len() == 13 => {
+            let sum: u32 = digits
+                .iter()
+                .enumerate()
+                .map(|(i, d)| if i % 2 == 0 { *d } else { 3 * d })
+                .sum();
+            sum.is_multiple_of(10)
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_valid_isbn, Identifiers};
+
+    #[test]
+    fn test_scan() {
+        let text = "Proc. (2019), https://doi.org/10.1145/3292500.3330701.\n\
+                    arXiv:1810.04805v2 [cs.CL] 24 May 2019\n\
+                    ISBN 0-306-40615-2, ISBN 978-0-306-40615-7";
+        let identifiers = Identifiers::scan(text);
+        assert_eq!(identifiers.doi.as_deref(), Some("10.1145/3292500.3330701"));
+        assert_eq!(identifiers.arxiv.as_deref(), Some("1810.04805"));
+        assert_eq!(identifiers.isbn.as_deref(), Some("0306406152"));
+        assert_eq!(
+            identifiers.suggested_file_name().as_deref(),
+            Some("arxiv-1810.04805.pdf")
+        );
+
+        let text = "(see 10.1016/S0140-6736(20)30183-5)";
+        assert_eq!(
+            Identifiers::scan(text).doi.as_deref(),
+            Some("10.1016/s0140-6736(20)30183-5")
+        );
+        assert!(Identifiers::scan("ISBN 0-306-40615-3").isbn.is_none());
+    }
+
+    #[test]
+    fn test_isbn_check_digit_x_only_last() {
+        assert!(is_valid_isbn("080442957X"));
+        assert!(Identifiers::scan("ISBN 0-8044-2957-x").isbn.is_some());
+        assert!(!is_valid_isbn("08044295X7"));
+        assert!(!is_valid_isbn("X804429570"));
+        assert!(!is_valid_isbn("978030640615X"));
+    }
+}
@@ -0,0 +1,314 @@
+use std::{
+    collections::HashSet,
+    fs::{self, File},
+    io::Read,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::library_store::LibraryStore;
+use crate::error::{Error, Result};
+
+/// FNV-1a, stable between runs unlike `DefaultHasher`
+pub struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Fnv1a {
+    pub fn write(&mut self, bytes: impl IntoIterator<Item = u8>) {
+        for byte in bytes {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(0x100_0000_01b3);
+        }
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// What identifies the file of a document whatever its name: the hash of its content,
+/// with its inode as a hint
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileIdentity {
+    /// FNV-1a of the content, in hex
+    pub hash: String,
+    pub size: u64,
+    /// seconds since the epoch, the file is hashed again once it changes
+    pub modified: u64,
+    /// kept by renames and moves within a file system, on unix only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inode: Option<u64>,
+}
+
+impl FileIdentity {
+    /// hashes the file at `path`
+    pub fn of(path: &Path) -> Result<Self> {
+        let metadata = fs::metadata(path).map_err(|err| Error::reading_file(path, err))?;
+        let mut file = File::open(path).map_err(|err| Error::reading_file(path, err))?;
+        let mut hasher = Fnv1a::default();
+        let mut buf = vec![0; 64 * 1024];
+        loop {
+            let read = file
+                .read(&mut buf)
+                .map_err(|err| Error::reading_file(path, err))?;
+            if read == 0 {
+                break;
+            }
+            hasher.write(buf[..read].iter().copied());
+        }
+        Ok(Self {
+            hash: format!("{:016x}", hasher.finish()),
+            size: metadata.len(),
+            modified: modified(&metadata),
+            inode: inode(&metadata),
+        })
+    }
+
+    /// whether the file with `metadata` is the one hashed, unchanged, judging by its
+    /// inode, size and time without reading it
+    pub fn is_current(&self, metadata: &fs::Metadata) -> bool {
+        self.inode.is_some()
+            && self.inode == inode(metadata)
+            && self.size == metadata.len()
+            && self.modified == modified(metadata)
+    }
+}
+
+fn modified(metadata: &fs::Metadata) -> u64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |since| since.as_secs())
+}
+
+#[cfg(unix)]
+fn inode(metadata: &fs::Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(metadata.ino())
+}
+
+#[cfg(not(unix))]
+fn inode(_metadata: &fs::Metadata) -> Option<u64> {
+    None
+}
+
+/// the files among `paths` to hash as their record has no identity or they changed
+pub fn stale(paths: &[PathBuf], managed_dir: &Path, library: &LibraryStore) -> Vec<PathBuf> {
+    paths
+        .iter()
+        .filter(|path| {
+            let identity = LibraryStore::key_for(managed_dir, path)
+                .and_then(|key| library.record(&key)?.identity.clone());
+            match (identity, fs::metadata(path)) {
+                (Some(identity), Ok(metadata)) => !identity.is_current(&metadata),
+                (None, Ok(_)) => true,
+                (_, Err(_)) => false,
+            }
+        })
+        .cloned()
+        .collect()
+}
+
+/// What reconciling the records with the files of the library found
+#[derive(Debug, Default)]
+pub struct Reconciliation {
+    /// the records which followed their file renamed or moved outside pdfstore, by their
+    /// old and new keys
+    pub moved: Vec<(String, String)>,
+    /// the records without a file, e.g. deleted or moved out of the library, which hold
+    /// more than what was found in the file
+    pub orphans: Vec<String>,
+    /// the records without a file which held only what was found in it
+    pub forgotten: Vec<String>,
+}
+
+/// moves the records whose file is not among `paths`, the files of the library, to the
+/// file with the same content.
+///
+/// The files are matched by the identities hashed into their records, so every listed
+/// file should be hashed first. A record can only take over the record of a file which
+/// holds nothing else. Records which are not matched are orphans if they hold user
+/// data, else they are removed. `save` the store to keep the changes.
+pub fn reconcile(
+    paths: &[PathBuf],
+    managed_dir: &Path,
+    library: &mut LibraryStore,
+) -> Reconciliation {
+    let mut reconciliation = Reconciliation::default();
+    let listed = paths
+        .iter()
+        .filter_map(|path| LibraryStore::key_for(managed_dir, path))
+        .collect::<HashSet<_>>();
+    let missing = library
+        .keys()
+        .filter(|key| !listed.contains(*key))
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+    if missing.is_empty() {
+        return reconciliation;
+    }
+
+    // the files the missing records may belong to, with a record holding only their
+    // identity and what was found in them
+    let mut untracked = listed
+        .into_iter()
+        .filter_map(|key| {
+            let record = library.record(&key)?;
+            let identity = record.identity.clone()?;
+            (!record.has_user_data()).then_some((key, identity))
+        })
+        .collect::<Vec<_>>();
+    untracked.sort_by(|a, b| a.0.cmp(&b.0));
+
+    for key in missing {
+        let record = match library.record(&key) {
+            Some(record) => record,
+            None => continue,
+        };
+        let found = record.identity.as_ref().and_then(|identity| {
+            let same = |idx: &usize| {
+                let other = &untracked[*idx].1;
+                other.hash == identity.hash && other.size == identity.size
+            };
+            // a rename keeps the inode, the file with the same one wins over a copy
+            let mut candidates = (0..untracked.len()).filter(same).collect::<Vec<_>>();
+            candidates.sort_by_key(|idx| {
+                identity.inode.is_none() || untracked[*idx].1.inode != identity.inode
+            });
+            candidates.first().copied()
+        });
+        match found.map(|idx| untracked.remove(idx)) {
+            Some((to, identity)) => {
+                log::info!("the file of {} was moved to {}", key, to);
+                library.rename_record(&key, &to);
+                // of a copy, the inode changed
+                library.record_mut(&to).identity = Some(identity);
+                reconciliation.moved.push((key, to));
+            }
+            None if record.has_user_data() => reconciliation.orphans.push(key),
+            None => {
+                library.remove_record(&key);
+                reconciliation.forgotten.push(key);
+            }
+        }
+    }
+    reconciliation
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{reconcile, stale, FileIdentity};
+    use crate::domain::{identifiers::Identifiers, library_store::LibraryStore, testing::library};
+    use chrono::Utc;
+    use std::{
+        fs,
+        path::{Path, PathBuf},
+    };
+
+    /// writes the file `name` and stores its identity, as the hashing worker does
+    fn add(managed: &Path, library: &mut LibraryStore, name: &str, content: &str) {
+        let path = managed.join(name);
+        fs::write(&path, content).unwrap();
+        hash(managed, library, name);
+    }
+
+    fn hash(managed: &Path, library: &mut LibraryStore, name: &str) {
+        library.record_mut(name).identity = Some(FileIdentity::of(&managed.join(name)).unwrap());
+    }
+
+    fn listed(managed: &Path, names: &[&str]) -> Vec<PathBuf> {
+        names.iter().map(|name| managed.join(name)).collect()
+    }
+
+    #[test]
+    fn test_reconcile_follows_a_renamed_file() {
+        let (_dir, managed, mut library) = library(&[]);
+        add(&managed, &mut library, "a.pdf", "%PDF-1.4 a");
+        library.record_mut("a.pdf").tags.push("ml".to_string());
+
+        fs::create_dir_all(managed.join("ml")).unwrap();
+        fs::rename(managed.join("a.pdf"), managed.join("ml/renamed.pdf")).unwrap();
+        hash(&managed, &mut library, "ml/renamed.pdf");
+        let found = reconcile(
+            &listed(&managed, &["ml/renamed.pdf"]),
+            &managed,
+            &mut library,
+        );
+        assert_eq!(
+            found.moved,
+            [("a.pdf".to_string(), "ml/renamed.pdf".to_string())]
+        );
+        assert_eq!(library.record("ml/renamed.pdf").unwrap().tags, ["ml"]);
+        assert!(library.record("a.pdf").is_none());
+    }
+
+    #[test]
+    fn test_reconcile_prefers_the_renamed_file_to_a_copy() {
+        let (_dir, managed, mut library) = library(&[]);
+        add(&managed, &mut library, "a.pdf", "%PDF-1.4 a");
+        library.record_mut("a.pdf").notes = "read".to_string();
+
+        fs::copy(managed.join("a.pdf"), managed.join("b.pdf")).unwrap();
+        fs::rename(managed.join("a.pdf"), managed.join("c.pdf")).unwrap();
+        hash(&managed, &mut library, "b.pdf");
+        hash(&managed, &mut library, "c.pdf");
+        let paths = listed(&managed, &["b.pdf", "c.pdf"]);
+        reconcile(&paths, &managed, &mut library);
+        assert_eq!(library.record("c.pdf").unwrap().notes, "read");
+        assert!(!library.record("b.pdf").unwrap().has_user_data());
+        // the record took the identity of its file
+        assert!(stale(&paths, &managed, &library).is_empty());
+    }
+
+    #[test]
+    fn test_reconcile_does_not_take_over_a_record_with_user_data() {
+        let (_dir, managed, mut library) = library(&[]);
+        add(&managed, &mut library, "a.pdf", "%PDF-1.4 a");
+        library.record_mut("a.pdf").notes = "mine".to_string();
+        add(&managed, &mut library, "b.pdf", "%PDF-1.4 a");
+        library.record_mut("b.pdf").notes = "theirs".to_string();
+        fs::remove_file(managed.join("a.pdf")).unwrap();
+
+        let found = reconcile(&listed(&managed, &["b.pdf"]), &managed, &mut library);
+        assert!(found.moved.is_empty());
+        assert_eq!(found.orphans, ["a.pdf"]);
+        assert_eq!(library.record("b.pdf").unwrap().notes, "theirs");
+    }
+
+    #[test]
+    fn test_reconcile_keeps_only_the_records_with_user_data() {
+        let (_dir, managed, mut library) = library(&[]);
+        library.record_mut("noted.pdf").notes = "read".to_string();
+        library.record_mut("imported.pdf").imported = Some(Utc::now());
+        library.record_mut("opened.pdf").opened = Some(Utc::now());
+        library.record_mut("scanned.pdf").identifiers = Some(Identifiers::default());
+
+        let found = reconcile(&[], &managed, &mut library);
+        assert_eq!(found.orphans, ["imported.pdf", "noted.pdf", "opened.pdf"]);
+        assert_eq!(found.forgotten, ["scanned.pdf"]);
+        assert!(library.record("scanned.pdf").is_none());
+    }
+
+    #[test]
+    fn test_stale_files_are_the_unhashed_and_changed_ones() {
+        let (_dir, managed, mut library) = library(&[]);
+        add(&managed, &mut library, "a.pdf", "%PDF-1.4 a");
+        add(&managed, &mut library, "b.pdf", "%PDF-1.4 b");
+        fs::write(managed.join("new.pdf"), "%PDF-1.4 n").unwrap();
+        fs::write(managed.join("b.pdf"), "%PDF-1.4 longer").unwrap();
+
+        let paths = listed(&managed, &["a.pdf", "b.pdf", "new.pdf", "missing.pdf"]);
+        assert_eq!(
+            stale(&paths, &managed, &library),
+            listed(&managed, &["b.pdf", "new.pdf"])
+        );
+    }
+}
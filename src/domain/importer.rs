@@ -0,0 +1,235 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use super::pdf_file::check_header;
+use crate::error::{Error, Result};
+
+/// Moves pdf files into the managed directory
+pub struct Importer {
+    managed_dir: PathBuf,
+}
+
+impl Importer {
+    pub fn new(managed_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            managed_dir: managed_dir.into(),
+        }
+    }
+
+    /// the path `src` would be imported to in `folder` of the managed directory, `name`
+    /// defaults to the current file name
+    pub fn destination(&self, src: &Path, folder: &Path, name: Option<&str>) -> Result<PathBuf> {
+        let name = match name {
+            Some(name) => name.trim().to_string(),
+            None => src
+                .file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or_default()
+                .to_string(),
+        };
+        validate_name(&name)?;
+
+        let mut file_name = name;
+        if !file_name.to_lowercase().ends_with(".pdf") {
+            file_name.push_str(".pdf");
+        }
+        Ok(self.managed_dir.join(folder).join(file_name))
+    }
+
+    /// moves `src` into the managed directory and returns its new path.
+    ///
+    /// existing files are never overwritten
+    pub fn import(&self, src: &Path, name: Option<&str>) -> Result<PathBuf> {
+        self.import_into(src, Path::new(""), name)
+    }
+
+    /// moves `src` into `folder` of the managed directory, which is created if needed, and
+    /// returns its new path
+    pub fn import_into(&self, src: &Path, folder: &Path, name: Option<&str>) -> Result<PathBuf> {
+        let dest = self.destination(src, folder, name)?;
+        import_to(src, &dest)?;
+        Ok(dest)
+    }
+}
+
+/// moves the pdf `src` to `dest`, creating its directory, existing files are never
+/// overwritten
+pub fn import_to(src: &Path, dest: &Path) -> Result<()> {
+    check_header(src)?;
+    if let Some(dir) = dest.parent() {
+        fs::create_dir_all(dir).map_err(|err| Error::writing_file(dir, err))?;
+    }
+    move_file(src, dest)?;
+    log::info!("imported {} to {}", src.display(), dest.display());
+    Ok(())
+}
+
+/// moves `src` to `dest`, copying it when they are on different file systems, e.g. from
+/// a mounted Downloads. An existing `dest` is never overwritten
+pub fn move_file(src: &Path, dest: &Path) -> Result<()> {
+    // checked again right before the rename, which would replace a file created since
+    if dest.exists() {
+        return Err(Error::AlreadyExists {
+            path: dest.to_path_buf(),
+        });
+    }
+    match fs::rename(src, dest) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == io::ErrorKind::CrossesDevices => {
+            copy_file(src, dest)?;
+            fs::remove_file(src).map_err(|err| Error::removing_file(src, err))
+        }
+        Err(err) => Err(Error::writing_file(dest, err)),
+    }
+}
+
+/// moves the file at `path` to the trash of the system, where it can be restored from
+pub fn trash(path: &Path) -> Result<()> {
+    trash::delete(path).map_err(|err| Error::TrashFailed {
+        path: path.to_path_buf(),
+        reason: err.to_string(),
+    })?;
+    log::info!("moved {} to the trash", path.display());
+    Ok(())
+}
+
+/// copies `src` to `dest`, which is created only if it does not exist yet. A partial copy
+/// is removed
+fn copy_file(src: &Path, dest: &Path) -> Result<()> {
+    let mut from = fs::File::open(src).map_err(|err| Error::reading_file(src, err))?;
+    let mut to = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(dest)
+        .map_err(|err| match err.kind() {
+            io::ErrorKind::AlreadyExists => Error::AlreadyExists {
+                path: dest.to_path_buf(),
+            },
+            _ => Error::writing_file(dest, err),
+        })?;
+    let copied = io::copy(&mut from, &mut to)
+        .and_then(|_| to.sync_all())
+        .and_then(|()| fs::set_permissions(dest, from.metadata()?.permissions()));
+    if let Err(err) = copied {
+        drop(to);
+        if let Err(err) = fs::remove_file(dest) {
+            log::warn!(
+                "failed to remove the partial copy {}: {}",
+                dest.display(),
+                err
+            );
+        }
+        return Err(Error::writing_file(dest, err));
+    }
+    Ok(())
+}
+
+/// checks that `name` can be the name of a file of the library
+pub fn validate_name(name: &str) -> Result<()> {
+    let reason = if name.is_empty() {
+        "the name is empty"
+    } else if name.contains('/') || name.contains('\\') {
+        "the name must not contain a path separator"
+    } else if name.starts_with('.') {
+        "the name must not start with a dot"
+    } else {
+        return Ok(());
+    };
+    Err(Error::InvalidName {
+        name: name.to_string(),
+        reason: reason.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{copy_file, move_file, trash, Importer};
+    use crate::error::Error;
+    use std::fs;
+
+    #[test]
+    fn test_import_renames_and_appends_extension() {
+        let downloads = tempfile::tempdir().unwrap();
+        let papers = tempfile::tempdir().unwrap();
+        let src = downloads.path().join("2201.00001.pdf");
+        fs::write(&src, b"%PDF-1.5\n").unwrap();
+
+        let importer = Importer::new(papers.path());
+        let dest = importer.import(&src, Some("attention")).unwrap();
+
+        assert_eq!(dest, papers.path().join("attention.pdf"));
+        assert!(dest.exists());
+        assert!(!src.exists());
+    }
+
+    #[test]
+    fn test_import_never_overwrites() {
+        let downloads = tempfile::tempdir().unwrap();
+        let papers = tempfile::tempdir().unwrap();
+        let src = downloads.path().join("paper.pdf");
+        fs::write(&src, b"%PDF-1.5\n").unwrap();
+        fs::write(papers.path().join("paper.pdf"), b"%PDF-1.4\n").unwrap();
+
+        let result = Importer::new(papers.path()).import(&src, None);
+        assert!(matches!(result, Err(Error::AlreadyExists { .. })));
+        assert!(src.exists());
+    }
+
+    #[test]
+    fn test_destination_rejects_separators() {
+        let importer = Importer::new("/papers");
+        let result = importer.destination("a.pdf".as_ref(), "".as_ref(), Some("../a.pdf"));
+        assert!(matches!(result, Err(Error::InvalidName { .. })));
+    }
+
+    #[test]
+    fn test_move_file_never_overwrites() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("a.pdf");
+        let dest = dir.path().join("b.pdf");
+        fs::write(&src, b"%PDF-1.5\n").unwrap();
+        fs::write(&dest, b"%PDF-1.4\n").unwrap();
+
+        let result = move_file(&src, &dest);
+        assert!(matches!(result, Err(Error::AlreadyExists { .. })));
+        assert_eq!(fs::read(&dest).unwrap(), b"%PDF-1.4\n");
+        assert!(src.exists());
+    }
+
+    #[test]
+    fn test_copy_file_keeps_an_existing_destination() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("a.pdf");
+        let dest = dir.path().join("b.pdf");
+        fs::write(&src, b"%PDF-1.5\n").unwrap();
+        fs::write(&dest, b"%PDF-1.4\n").unwrap();
+
+        let result = copy_file(&src, &dest);
+        assert!(matches!(result, Err(Error::AlreadyExists { .. })));
+        assert_eq!(fs::read(&dest).unwrap(), b"%PDF-1.4\n");
+    }
+
+    #[test]
+    fn test_copy_file_leaves_no_partial_destination() {
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("b.pdf");
+
+        // a directory opens but cannot be read from
+        let result = copy_file(dir.path(), &dest);
+        assert!(result.is_err());
+        assert!(!dest.exists());
+
+        fs::write(dir.path().join("a.pdf"), b"%PDF-1.5\n").unwrap();
+        copy_file(&dir.path().join("a.pdf"), &dest).unwrap();
+        assert_eq!(fs::read(&dest).unwrap(), b"%PDF-1.5\n");
+    }
+
+    #[test]
+    fn test_trash_missing_file_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let result = trash(&dir.path().join("gone.pdf"));
+        assert!(matches!(result, Err(Error::TrashFailed { .. })));
+    }
+}
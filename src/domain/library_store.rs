@@ -0,0 +1,501 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    domain::{
+        citation::Citation,
+        collection::CollectionPath,
+        identifiers::{Identifiers, SharedIdentifier},
+        identity::FileIdentity,
+        reading::Reading,
+    },
+    error::{Error, Result},
+};
+
+/// What pdfstore knows about a managed document beyond the file itself
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DocumentRecord {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub notes: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub citation: Option<Citation>,
+    /// found in the text of the document, `None` until the document has been scanned
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identifiers: Option<Identifiers>,
+    #[serde(default, skip_serializing_if = "Reading::is_default")]
+    pub reading: Reading,
+    /// the collections the document is in, which are in the store's tree
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub collections: Vec<CollectionPath>,
+    /// of the file, to find the record again once the file is renamed or moved outside
+    /// pdfstore. `None` until the file has been hashed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity: Option<FileIdentity>,
+    /// when the document was imported into the library, for the recently added list
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub imported: Option<DateTime<Utc>>,
+    /// when the document was last opened from pdfstore, for the recently opened list
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub opened: Option<DateTime<Utc>>,
+}
+
+impl DocumentRecord {
+    /// whether the record holds more than what pdfstore found in the file itself, and
+    /// would be lost with the file
+    pub fn has_user_data(&self) -> bool {
+        !self.tags.is_empty()
+            || !self.notes.is_empty()
+            || self.citation.is_some()
+            || !self.reading.is_default()
+            || !self.collections.is_empty()
+            || self.imported.is_some()
+            || self.opened.is_some()
+    }
+}
+
+/// Persistent records of the managed documents, stored as JSON.
+///
+/// Documents are keyed by their path relative to the managed directory. Another
+/// pdfstore, e.g. `pdfstore import` while the terminal UI runs, may save the same file:
+/// its changes are merged on saving, see `save`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct LibraryStore {
+    #[serde(skip)]
+    path: PathBuf,
+    /// set when the file could not be opened, so that it is not overwritten
+    #[serde(skip)]
+    read_only: bool,
+    /// the content of the file when it was last opened or saved, the base of the merge
+    #[serde(skip)]
+    saved: String,
+    #[serde(default)]
+    documents: BTreeMap<String, DocumentRecord>,
+    /// every collection of the tree, including the empty ones
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    collections: BTreeSet<CollectionPath>,
+    /// queries saved under a name, evaluated again whenever they are shown
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    searches: BTreeMap<String, String>,
+    /// the unmanaged files skipped while triaging, left out of the next sessions
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    triage_skipped: BTreeSet<PathBuf>,
+}
+
+impl LibraryStore {
+    /// opens the store at `path`, a missing file is an empty store
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let saved = read_if_exists(&path)?;
+        let mut store = parse(&path, &saved)?;
+        store.path = path;
+        store.saved = saved;
+        Ok(store)
+    }
+
+    /// an empty store for the file at `path` which could not be opened, never saved
+    pub fn read_only(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            read_only: true,
+            ..Self::default()
+        }
+    }
+
+    /// an empty store not backed by any file, for the tests
+    #[cfg(test)]
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    /// writes the store, replacing the previous file atomically.
+    ///
+    /// If the file changed since it was opened or saved, the changes are merged first:
+    /// the records, collections, searches and skipped files changed only there are
+    /// taken from the file, the ones changed here win.
+    pub fn save(&mut self) -> Result<()> {
+        if self.read_only {
+            return Err(Error::CorruptLibrary {
+                path: self.path.clone(),
+                reason: "changes are not saved as the library could not be opened".to_string(),
+            });
+        }
+        let current = read_if_exists(&self.path)?;
+        if current != self.saved {
+            log::info!("merging the changes saved to {}", self.path.display());
+            let base = parse(&self.path, &self.saved)?;
+            let theirs = parse(&self.path, &current)?;
+            self.merge(base, theirs);
+        }
+
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).map_err(|err| Error::writing_file(dir, err))?;
+        }
+        let content = serde_json::to_string_pretty(self).map_err(io::Error::from)?;
+        let tmp = self.path.with_extension("json.tmp");
+        fs::write(&tmp, &content).map_err(|err| Error::writing_file(&tmp, err))?;
+        fs::rename(&tmp, &self.path).map_err(|err| Error::writing_file(&self.path, err))?;
+        self.saved = content;
+        Ok(())
+    }
+
+    /// takes the changes from `base` to `theirs` which were not made here too
+    fn merge(&mut self, base: Self, theirs: Self) {
+        merge_map(&mut self.documents, base.documents, theirs.documents);
+        merge_set(&mut self.collections, base.collections, theirs.collections);
+        merge_map(&mut self.searches, base.searches, theirs.searches);
+        merge_set(
+            &mut self.triage_skipped,
+            base.triage_skipped,
+            theirs.triage_skipped,
+        );
+    }
+
+    /// the key of the document at `path`, if it is inside `managed_dir`
+    pub fn key_for(managed_dir: &Path, path: &Path) -> Option<String> {
+        let relative = path.strip_prefix(managed_dir).ok()?;
+        let parts = relative
+            .components()
+            .map(|part| part.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>();
+        Some(parts.join("/"))
+    }
+
+    /// the keys of every document, in order
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.documents.keys().map(String::as_str)
+    }
+
+    pub fn record(&self, key: &str) -> Option<&DocumentRecord> {
+        self.documents.get(key)
+    }
+
+    pub fn record_mut(&mut self, key: &str) -> &mut DocumentRecord {
+        self.documents.entry(key.to_string()).or_default()
+    }
+
+    /// records that the documents at `paths` were just imported, for the recently added
+    /// list
+    pub fn record_imports(&mut self, managed_dir: &Path, paths: &[PathBuf]) {
+        let now = Utc::now();
+        for path in paths {
+            if let Some(key) = Self::key_for(managed_dir, path) {
+                self.record_mut(&key).imported = Some(now);
+            }
+        }
+    }
+
+    /// forgets the document, e.g. after its file was deleted
+    pub fn remove_record(&mut self, key: &str) -> Option<DocumentRecord> {
+        self.documents.remove(key)
+    }
+
+    /// moves the record of a document whose file moved within the managed directory
+    pub fn rename_record(&mut self, from: &str, to: &str) {
+        if let Some(record) = self.documents.remove(from) {
+            self.documents.insert(to.to_string(), record);
+        }
+    }
+
+    /// moves the records of the documents in the folder `from`, whose files moved to the
+    /// folder `to`, returns how many moved
+    pub fn rename_folder(&mut self, from: &str, to: &str) -> usize {
+        let prefix = format!("{}/", from);
+        let keys = self
+            .documents
+            .keys()
+            .filter(|key| key.starts_with(&prefix))
+            .cloned()
+            .collect::<Vec<_>>();
+        for key in &keys {
+            let moved = format!("{}/{}", to, &key[prefix.len()..]);
+            self.rename_record(key, &moved);
+        }
+        keys.len()
+    }
+
+    /// the collections in tree order, each followed by the ones nested in it
+    pub fn collections(&self) -> impl Iterator<Item = &CollectionPath> {
+        self.collections.iter()
+    }
+
+    /// adds `collection` and the collections it is nested in to the tree
+    pub fn add_collection(&mut self, collection: &CollectionPath) {
+        self.collections.extend(collection.ancestors());
+    }
+
+    /// removes `collection` and the ones nested in it, the documents are kept
+    pub fn remove_collection(&mut self, collection: &CollectionPath) {
+        self.collections.retain(|c| !collection.contains(c));
+        for record in self.documents.values_mut() {
+            record.collections.retain(|c| !collection.contains(c));
+        }
+    }
+
+    /// adds the document to `collection`, which is added to the tree if it is new
+    pub fn add_to_collection(&mut self, key: &str, collection: &CollectionPath) {
+        self.add_collection(collection);
+        let collections = &mut self.record_mut(key).collections;
+        if !collections.contains(collection) {
+            collections.push(collection.clone());
+            collections.sort();
+        }
+    }
+
+    /// adds the document to `collection` or removes it, returns whether it is in it now
+    pub fn toggle_collection(&mut self, key: &str, collection: &CollectionPath) -> bool {
+        let collections = &mut self.record_mut(key).collections;
+        match collections.iter().position(|c| c == collection) {
+            Some(idx) => {
+                collections.remove(idx);
+                false
+            }
+            None => {
+                self.add_to_collection(key, collection);
+                true
+            }
+        }
+    }
+
+    /// the keys of the documents in `collection` or in a collection nested in it
+    pub fn keys_in_collection<'a>(
+        &'a self,
+        collection: &'a CollectionPath,
+    ) -> impl Iterator<Item = &'a str> {
+        self.documents
+            .iter()
+            .filter(|(_, record)| record.collections.iter().any(|c| collection.contains(c)))
+            .map(|(key, _)| key.as_str())
+    }
+
+    /// the saved searches with their queries, by name
+    pub fn searches(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.searches
+            .iter()
+            .map(|(name, query)| (name.as_str(), query.as_str()))
+    }
+
+    /// saves `query` under `name`, replacing a saved search of the same name
+    pub fn save_search(&mut self, name: &str, query: &str) {
+        self.searches.insert(name.to_string(), query.to_string());
+    }
+
+    pub fn remove_search(&mut self, name: &str) {
+        self.searches.remove(name);
+    }
+
+    /// the unmanaged files skipped while triaging
+    pub fn triage_skipped(&self) -> &BTreeSet<PathBuf> {
+        &self.triage_skipped
+    }
+
+    /// leaves the unmanaged file out of the next triage sessions, or takes it back in
+    pub fn set_triage_skipped(&mut self, path: &Path, skipped: bool) {
+        match skipped {
+            true => self.triage_skipped.insert(path.to_path_buf()),
+            false => self.triage_skipped.remove(path),
+        };
+    }
+
+    /// forgets the skipped files which are gone, returns whether any was
+    pub fn retain_triage_skipped(&mut self, existing: &[PathBuf]) -> bool {
+        let before = self.triage_skipped.len();
+        self.triage_skipped.retain(|path| existing.contains(path));
+        self.triage_skipped.len() != before
+    }
+
+    /// another document with one of the same identifiers, with the shared identifier
+    pub fn find_duplicate(
+        &self,
+        identifiers: &Identifiers,
+        except_key: Option<&str>,
+    ) -> Option<(&str, SharedIdentifier)> {
+        self.documents
+            .iter()
+            .filter(|(key, _)| Some(key.as_str()) != except_key)
+            .find_map(|(key, record)| {
+                let shared = record.identifiers.as_ref()?.shared_with(identifiers)?;
+                Some((key.as_str(), shared))
+            })
+    }
+}
+
+/// the content of the file at `path`, empty if there is none
+fn read_if_exists(path: &Path) -> Result<String> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(content),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(String::new()),
+        Err(err) => Err(Error::reading_file(path, err)),
+    }
+}
+
+/// the store saved as `content` at `path`, empty content is an empty store
+fn parse(path: &Path, content: &str) -> Result<LibraryStore> {
+    if content.is_empty() {
+        return Ok(LibraryStore::default());
+    }
+    serde_json::from_str(content).map_err(|err| Error::CorruptLibrary {
+        path: path.to_path_buf(),
+        reason: err.to_string(),
+    })
+}
+
+/// applies to `ours` the entries which changed from `base` to `theirs` only
+fn merge_map<K: Ord, V: PartialEq>(
+    ours: &mut BTreeMap<K, V>,
+    mut base: BTreeMap<K, V>,
+    theirs: BTreeMap<K, V>,
+) {
+    for (key, value) in theirs {
+        let base = base.remove(&key);
+        if base.as_ref() != Some(&value) && ours.get(&key) == base.as_ref() {
+            ours.insert(key, value);
+        }
+    }
+    // removed there
+    for (key, value) in base {
+        if ours.get(&key) == Some(&value) {
+            ours.remove(&key);
+        }
+    }
+}
+
+/// applies to `ours` the items added or removed from `base` to `theirs` only
+fn merge_set<T: Ord>(ours: &mut BTreeSet<T>, base: BTreeSet<T>, mut theirs: BTreeSet<T>) {
+    for item in base {
+        if !theirs.remove(&item) {
+            ours.remove(&item);
+        }
+    }
+    ours.append(&mut theirs);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LibraryStore;
+    use crate::domain::{
+        collection::CollectionPath,
+        identifiers::{Identifiers, SharedIdentifier},
+    };
+    use std::{fs, path::Path};
+
+    #[test]
+    fn test_save_and_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("library.json");
+
+        let mut store = LibraryStore::open(&path).unwrap();
+        store.record_mut("a.pdf").tags.push("ml".to_string());
+        store.save().unwrap();
+
+        let store = LibraryStore::open(&path).unwrap();
+        assert_eq!(store.record("a.pdf").unwrap().tags, vec!["ml"]);
+        assert!(store.record("b.pdf").is_none());
+    }
+
+    #[test]
+    fn test_save_merges_the_changes_saved_meanwhile() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("library.json");
+        let mut store = LibraryStore::open(&path).unwrap();
+        store.record_mut("old.pdf").notes = "kept".to_string();
+        store.record_mut("both.pdf").notes = "base".to_string();
+        store.save_search("recent", "year:2024");
+        store.save().unwrap();
+
+        // the terminal UI and `pdfstore import` opened the same file
+        let mut ui = LibraryStore::open(&path).unwrap();
+        let mut cli = LibraryStore::open(&path).unwrap();
+        cli.record_mut("new.pdf").tags.push("imported".to_string());
+        cli.record_mut("both.pdf").notes = "cli".to_string();
+        cli.remove_record("old.pdf");
+        cli.save().unwrap();
+
+        ui.record_mut("both.pdf").notes = "ui".to_string();
+        ui.remove_search("recent");
+        ui.save().unwrap();
+
+        let store = LibraryStore::open(&path).unwrap();
+        assert_eq!(store.keys().collect::<Vec<_>>(), ["both.pdf", "new.pdf"]);
+        // changed on both sides, the last save wins
+        assert_eq!(store.record("both.pdf").unwrap().notes, "ui");
+        assert_eq!(store.searches().count(), 0);
+    }
+
+    #[test]
+    fn test_save_keeps_a_file_corrupted_meanwhile() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("library.json");
+        let mut store = LibraryStore::open(&path).unwrap();
+        store.record_mut("a.pdf").notes = "a".to_string();
+        store.save().unwrap();
+
+        fs::write(&path, "{").unwrap();
+        store.record_mut("a.pdf").notes = "b".to_string();
+        assert!(store.save().is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{");
+    }
+
+    #[test]
+    fn test_read_only_store_is_not_saved() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("library.json");
+        let mut store = LibraryStore::read_only(&path);
+        store.record_mut("a.pdf").notes = "a".to_string();
+        assert!(store.save().is_err());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_collections() {
+        let mut store = LibraryStore::in_memory();
+        let thesis = CollectionPath::parse("Thesis").unwrap();
+        let related = CollectionPath::parse("Thesis > Related work").unwrap();
+        assert!(store.toggle_collection("a.pdf", &related));
+        assert!(store.toggle_collection("b.pdf", &thesis));
+        assert_eq!(store.collections().collect::<Vec<_>>(), [&thesis, &related]);
+        assert_eq!(
+            store.keys_in_collection(&thesis).collect::<Vec<_>>(),
+            ["a.pdf", "b.pdf"]
+        );
+        assert!(!store.toggle_collection("b.pdf", &thesis));
+
+        store.remove_collection(&thesis);
+        assert_eq!(store.collections().count(), 0);
+        assert!(store.record("a.pdf").unwrap().collections.is_empty());
+    }
+
+    #[test]
+    fn test_key_for() {
+        let root = Path::new("/papers");
+        assert_eq!(
+            LibraryStore::key_for(root, Path::new("/papers/ml/a.pdf")).as_deref(),
+            Some("ml/a.pdf")
+        );
+        assert_eq!(LibraryStore::key_for(root, Path::new("/tmp/a.pdf")), None);
+    }
+
+    #[test]
+    fn test_find_duplicate() {
+        let mut store = LibraryStore::in_memory();
+        let identifiers = Identifiers {
+            arxiv: Some("1810.04805".to_string()),
+            ..Identifiers::default()
+        };
+        store.record_mut("a.pdf").identifiers = Some(identifiers.clone());
+        store.record_mut("b.pdf").identifiers = Some(Identifiers::default());
+
+        assert_eq!(
+            store.find_duplicate(&identifiers, None),
+            Some(("a.pdf", SharedIdentifier::Arxiv("1810.04805".to_string())))
+        );
+        assert_eq!(store.find_duplicate(&identifiers, Some("a.pdf")), None);
+    }
+}
@@ -0,0 +1,537 @@
+use std::{
+    collections::BTreeMap,
+    fmt, fs, io,
+    path::PathBuf,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    config::MetadataConfig,
+    domain::{citation::Citation, library_store::DocumentRecord},
+    error::{Error, Result},
+};
+
+/// An identifier the metadata of a document can be looked up by
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Lookup {
+    Doi(String),
+    Arxiv(String),
+}
+
+impl Lookup {
+    /// the doi of the citation, else the doi or arXiv id printed in the document
+    pub fn for_record(record: &DocumentRecord) -> Option<Self> {
+        let citation_doi = record
+            .citation
+            .as_ref()
+            .map(|citation| citation.doi.clone())
+            .filter(|doi| !doi.is_empty());
+        let identifiers = record.identifiers.as_ref();
+        citation_doi
+            .or_else(|| identifiers?.doi.clone())
+            .map(Self::Doi)
+            .or_else(|| identifiers?.arxiv.clone().map(Self::Arxiv))
+    }
+}
+
+impl fmt::Display for Lookup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Doi(doi) => write!(f, "doi {}", doi),
+            Self::Arxiv(id) => write!(f, "arXiv {}", id),
+        }
+    }
+}
+
+/// Resolves identifiers into bibliographic metadata
+pub trait MetadataResolver {
+    /// the citation of the document, `None` if the service does not know the identifier.
+    ///
+    /// the citekey of the returned citation is empty
+    fn resolve(&self, lookup: &Lookup) -> Result<Option<Citation>>;
+}
+
+/// how long an identifier the services did not know is not looked up again, it may be
+/// registered since
+const UNKNOWN_TTL: chrono::Duration = chrono::Duration::days(7);
+
+/// the citations resolved since the cache was last written which make it written again,
+/// the others are written after `SAVE_INTERVAL` or once the resolver is dropped
+const SAVE_EVERY: usize = 20;
+const SAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Resolves dois with the Crossref REST API and arXiv ids with the arXiv API.
+///
+/// The resolved citations are cached on disk so that a document is looked up once and
+/// its metadata can be fetched again offline. The unknown identifiers are cached for
+/// `UNKNOWN_TTL`, the failed requests are not cached.
+pub struct HttpResolver {
+    crossref_url: String,
+    arxiv_url: String,
+    agent: ureq::Agent,
+    cache: Mutex<CitationCache>,
+}
+
+impl HttpResolver {
+    pub fn new(config: &MetadataConfig) -> Self {
+        let agent = ureq::AgentBuilder::new()
+            .timeout(Duration::from_secs(config.timeout))
+            .user_agent(concat!("pdfstore/", env!("CARGO_PKG_VERSION")))
+            .build();
+        Self {
+            crossref_url: config.crossref_url.trim_end_matches('/').to_string(),
+            arxiv_url: config.arxiv_url.trim_end_matches('/').to_string(),
+            agent,
+            cache: Mutex::new(CitationCache::open(config.cache_file.clone())),
+        }
+    }
+
+    /// the body of the response to `url`, `None` if the service answered 404
+    fn get(&self, url: &str) -> Result<Option<String>> {
+        let unavailable = |reason: String| Error::MetadataUnavailable {
+            url: url.to_string(),
+            reason,
+        };
+        let response = match self.agent.get(url).call() {
+            Ok(response) => Some(
+                response
+                    .into_string()
+                    .map_err(|err| unavailable(err.to_string()))?,
+            ),
+            Err(ureq::Error::Status(404, _)) => None,
+            Err(ureq::Error::Status(status, _)) => {
+                return Err(unavailable(format!("status {}", status)))
+            }
+            Err(ureq::Error::Transport(err)) => {
+                let reason = match err.message() {
+                    Some(message) => format!("{}: {}", err.kind(), message),
+                    None => err.kind().to_string(),
+                };
+                return Err(unavailable(reason));
+            }
+        };
+        Ok(response)
+    }
+
+    /// the citation at `url` from the service, `None` if it does not know the identifier
+    fn fetch(&self, lookup: &Lookup, url: &str) -> Result<Option<Citation>> {
+        let body = match self.get(url)? {
+            Some(body) => body,
+            None => return Ok(None),
+        };
+        match lookup {
+            Lookup::Doi(_) => {
+                parse_crossref(&body)
+                    .map(Some)
+                    .ok_or_else(|| Error::MetadataUnavailable {
+                        url: url.to_string(),
+                        reason: "unexpected response".to_string(),
+                    })
+            }
+            Lookup::Arxiv(_) => Ok(parse_arxiv(&body)),
+        }
+    }
+}
+
+impl MetadataResolver for HttpResolver {
+    fn resolve(&self, lookup: &Lookup) -> Result<Option<Citation>> {
+        let url = match lookup {
+            Lookup::Doi(doi) => format!("{}/works/{}", self.crossref_url, encode_path(doi)),
+            Lookup::Arxiv(id) => {
+                format!("{}/api/query?id_list={}", self.arxiv_url, encode_path(id))
+            }
+        };
+        let mut cache = self.cache.lock().unwrap_or_else(|err| err.into_inner());
+        if let Some(citation) = cache.get(&url) {
+            log::debug!("metadata cache hit: {}", url);
+            return Ok(citation);
+        }
+        let citation = self.fetch(lookup, &url)?;
+        cache.insert(url, citation.clone());
+        Ok(citation)
+    }
+}
+
+/// A citation resolved by a metadata service, `None` for an unknown identifier
+#[derive(Serialize, Deserialize)]
+struct CachedCitation {
+    citation: Option<Citation>,
+    fetched: DateTime<Utc>,
+}
+
+/// The citations resolved by the metadata services keyed by url
+#[derive(Default, Serialize, Deserialize)]
+struct CitationCache {
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(default)]
+    citations: BTreeMap<String, CachedCitation>,
+    /// the citations inserted since the cache was last written
+    #[serde(skip)]
+    unsaved: usize,
+    #[serde(skip)]
+    saved_at: Option<Instant>,
+}
+
+impl CitationCache {
+    /// a cache which cannot be read is started over, it only saves requests
+    fn open(path: PathBuf) -> Self {
+        let mut cache = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|err| {
+                log::warn!("ignored the metadata cache {}: {}", path.display(), err);
+                Self::default()
+            }),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Self::default(),
+            Err(err) => {
+                log::warn!("ignored the metadata cache {}: {}", path.display(), err);
+                Self::default()
+            }
+        };
+        cache.path = path;
+        cache
+    }
+
+    /// the cached citation at `url`, the unknown identifiers cached for `UNKNOWN_TTL`
+    fn get(&self, url: &str) -> Option<Option<Citation>> {
+        let cached = self.citations.get(url)?;
+        if cached.citation.is_none() && Utc::now() - cached.fetched >= UNKNOWN_TTL {
+            return None;
+        }
+        Some(cached.citation.clone())
+    }
+
+    /// caches the citation at `url`, written by batches
+    fn insert(&mut self, url: String, citation: Option<Citation>) {
+        let fetched = Utc::now();
+        self.citations
+            .insert(url, CachedCitation { citation, fetched });
+        self.unsaved += 1;
+        let interval_over = self
+            .saved_at
+            .is_none_or(|saved_at| saved_at.elapsed() >= SAVE_INTERVAL);
+        if self.unsaved >= SAVE_EVERY || interval_over {
+            self.flush();
+        }
+    }
+
+    /// writes the cache if citations were inserted since it was last written
+    fn flush(&mut self) {
+        if self.unsaved == 0 {
+            return;
+        }
+        match self.save() {
+            Ok(()) => self.unsaved = 0,
+            Err(err) => log::warn!("failed to save the metadata cache: {}", err),
+        }
+        self.saved_at = Some(Instant::now());
+    }
+
+    fn save(&self) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).map_err(|err| Error::writing_file(dir, err))?;
+        }
+        let content = serde_json::to_string(self).map_err(io::Error::from)?;
+        let tmp = self.path.with_extension("json.tmp");
+        fs::write(&tmp, content).map_err(|err| Error::writing_file(&tmp, err))?;
+        fs::rename(&tmp, &self.path).map_err(|err| Error::writing_file(&self.path, err))?;
+        Ok(())
+    }
+}
+
+impl Drop for CitationCache {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+/// percent-encodes `s` for a url path or query value, keeping the `/` of dois
+fn encode_path(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// the citation of a Crossref `/works/<doi>` response
+fn parse_crossref(body: &str) -> Option<Citation> {
+    let value = serde_json::from_str::<Value>(body).ok()?;
+    let work = value.get("message")?;
+    let first = |name: &str| {
+        work.get(name)
+            .and_then(|values| values.get(0))
+            .and_then(Value::as_str)
+            .map(collapse_whitespace)
+            .unwrap_or_default()
+    };
+    let authors = work
+        .get("author")
+        .and_then(Value::as_array)
+        .map(|authors| {
+            authors
+                .iter()
+                .filter_map(|author| {
+                    let family = author.get("family").and_then(Value::as_str);
+                    let given = author.get("given").and_then(Value::as_str);
+                    match (family, given) {
+                        (Some(family), Some(given)) => Some(format!("{}, {}", family, given)),
+                        (Some(family), None) => Some(family.to_string()),
+                        // organizations only have a name
+                        _ => author
+                            .get("name")
+                            .and_then(Value::as_str)
+                            .map(str::to_string),
+                    }
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let year = ["issued", "published-print", "published-online"]
+        .iter()
+        .find_map(|name| work.get(name)?.get("date-parts")?.get(0)?.get(0)?.as_i64())
+        .and_then(|year| i32::try_from(year).ok());
+    Some(Citation {
+        citekey: String::new(),
+        authors,
+        title: first("title"),
+        year,
+        venue: first("container-title"),
+        doi: work
+            .get("DOI")
+            .and_then(Value::as_str)
+            .map(str::to_lowercase)
+            .unwrap_or_default(),
+    })
+}
+
+/// the citation of the first entry of an arXiv API feed, `None` if it has no entry
+fn parse_arxiv(body: &str) -> Option<Citation> {
+    let start = body.find("<entry>")?;
+    let entry = &body[start
+        ..body[start..]
+            .find("</entry>")
+            .map_or(body.len(), |end| start + end)];
+    // unknown ids are answered with an entry describing the error
+    if xml_text(entry, "id").is_some_and(|id| id.contains("/api/errors")) {
+        return None;
+    }
+    let authors = entry
+        .split("<author>")
+        .skip(1)
+        .filter_map(|author| xml_text(author, "name"))
+        .collect();
+    Some(Citation {
+        citekey: String::new(),
+        authors,
+        title: xml_text(entry, "title").unwrap_or_default(),
+        year: xml_text(entry, "published")
+            .and_then(|date| date.get(..4).and_then(|year| year.parse().ok())),
+        venue: xml_text(entry, "arxiv:journal_ref").unwrap_or_default(),
+        doi: xml_text(entry, "arxiv:doi")
+            .map(|doi| doi.to_lowercase())
+            .unwrap_or_default(),
+    })
+}
+
+/// the text of the first `<tag>` element of `xml`, unescaped and on one line
+fn xml_text(xml: &str, tag: &str) -> Option<String> {
+    let open = format!("<{}", tag);
+    let start = xml.find(&open)?;
+    let content_start = start + xml[start..].find('>')? + 1;
+    let content_end = content_start + xml[content_start..].find(&format!("</{}>", tag))?;
+    let text = xml[content_start..content_end]
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&");
+    Some(collapse_whitespace(&text))
+}
+
+fn collapse_whitespace(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// A field of a citation changed by fetched metadata
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldChange {
+    pub field: &'static str,
+    pub old: String,
+    pub new: String,
+}
+
+/// the fields which differ between `old` and `new`, in the order of the citation popup
+pub fn changes(old: &Citation, new: &Citation) -> Vec<FieldChange> {
+    let year = |citation: &Citation| citation.year.map(|year| year.to_string());
+    [
+        ("citekey", old.citekey.clone(), new.citekey.clone()),
+        (
+            "authors",
+            old.authors.join(" and "),
+            new.authors.join(" and "),
+        ),
+        ("title", old.title.clone(), new.title.clone()),
+        (
+            "year",
+            year(old).unwrap_or_default(),
+            year(new).unwrap_or_default(),
+        ),
+        ("venue", old.venue.clone(), new.venue.clone()),
+        ("doi", old.doi.clone(), new.doi.clone()),
+    ]
+    .into_iter()
+    .filter(|(_, old, new)| old != new)
+    .map(|(field, old, new)| FieldChange { field, old, new })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        changes, parse_arxiv, CachedCitation, CitationCache, HttpResolver, Lookup,
+        MetadataResolver, UNKNOWN_TTL,
+    };
+    use crate::config::MetadataConfig;
+    use crate::domain::citation::Citation;
+    use chrono::Utc;
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    const CROSSREF: &str = r#"{"status":"ok","message":{"DOI":"10.1145/3292500.3330701",
+        "title":["Example  Title"],"container-title":["KDD"],
+        "author":[{"given":"Ada","family":"Lovelace"},{"name":"ACM"}],
+        "issued":{"date-parts":[[2019,7,25]]}}}"#;
+
+    /// serves `body` to a single request, then stops
+    fn serve_once(body: &'static str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 2 {
+                line.clear();
+            }
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .unwrap();
+        });
+        url
+    }
+
+    #[test]
+    fn test_resolve_crossref_and_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = MetadataConfig {
+            crossref_url: serve_once(CROSSREF),
+            cache_file: dir.path().join("metadata.json"),
+            ..MetadataConfig::default()
+        };
+        let lookup = Lookup::Doi("10.1145/3292500.3330701".to_string());
+
+        let citation = HttpResolver::new(&config)
+            .resolve(&lookup)
+            .unwrap()
+            .unwrap();
+        assert_eq!(citation.title, "Example Title");
+        assert_eq!(citation.authors, ["Lovelace, Ada", "ACM"]);
+        assert_eq!(citation.year, Some(2019));
+        assert_eq!(citation.venue, "KDD");
+
+        // the stand-in has stopped, the citation comes from the cache
+        let cached = HttpResolver::new(&config).resolve(&lookup).unwrap();
+        assert_eq!(cached, Some(citation));
+    }
+
+    #[test]
+    fn test_failed_requests_are_not_cached() {
+        let dir = tempfile::tempdir().unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let config = MetadataConfig {
+            crossref_url: format!("http://{}", listener.local_addr().unwrap()),
+            cache_file: dir.path().join("metadata.json"),
+            ..MetadataConfig::default()
+        };
+        drop(listener);
+        let lookup = Lookup::Doi("10.1145/3292500.3330701".to_string());
+
+        assert!(HttpResolver::new(&config).resolve(&lookup).is_err());
+        let cache = CitationCache::open(config.cache_file);
+        assert!(cache.citations.is_empty());
+    }
+
+    #[test]
+    fn test_cache_is_written_by_batches() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("metadata.json");
+        let mut cache = CitationCache::open(path.clone());
+        cache.insert("a".to_string(), None);
+        cache.insert("b".to_string(), None);
+        assert_eq!(CitationCache::open(path.clone()).citations.len(), 1);
+        drop(cache);
+        assert_eq!(CitationCache::open(path).citations.len(), 2);
+    }
+
+    #[test]
+    fn test_unknown_identifiers_expire() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut cache = CitationCache::open(dir.path().join("metadata.json"));
+        cache.insert("unknown".to_string(), None);
+        assert_eq!(cache.get("unknown"), Some(None));
+
+        let fetched = Utc::now() - UNKNOWN_TTL;
+        for (url, citation) in [("unknown", None), ("known", Some(Citation::default()))] {
+            let cached = CachedCitation { citation, fetched };
+            cache.citations.insert(url.to_string(), cached);
+        }
+        assert_eq!(cache.get("unknown"), None);
+        assert_eq!(cache.get("known"), Some(Some(Citation::default())));
+    }
+
+    #[test]
+    fn test_parse_arxiv_and_changes() {
+        let feed = "<feed><entry><id>http://arxiv.org/abs/1810.04805v2</id>\
+            <published>2018-10-11T00:00:00Z</published><title>BERT: Pre-training\n  of \
+            Deep Bidirectional Transformers</title><author><name>Jacob Devlin</name></author>\
+            <arxiv:doi>10.18653/V1/N19-1423</arxiv:doi></entry></feed>";
+        let fetched = parse_arxiv(feed).unwrap();
+        assert_eq!(
+            fetched.title,
+            "BERT: Pre-training of Deep Bidirectional Transformers"
+        );
+        assert_eq!(fetched.year, Some(2018));
+        assert_eq!(fetched.doi, "10.18653/v1/n19-1423");
+        assert!(
+            parse_arxiv("<feed><entry><id>http://arxiv.org/api/errors#x</id></entry>").is_none()
+        );
+
+        let old = Citation {
+            citekey: "devlin".to_string(),
+            title: fetched.title.clone(),
+            ..Citation::default()
+        };
+        let mut new = old.clone();
+        new.update_from(fetched);
+        let fields = changes(&old, &new)
+            .iter()
+            .map(|change| change.field)
+            .collect::<Vec<_>>();
+        assert_eq!(fields, ["authors", "year", "doi"]);
+    }
+}
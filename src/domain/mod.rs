@@ -1 +1,25 @@
+pub mod batch_import;
+pub mod bib_import;
+pub mod bibtex;
+pub mod bulk;
+pub mod citation;
+pub mod collection;
+pub mod export;
+pub mod folder;
+pub mod history;
+pub mod identifiers;
+pub mod identity;
+pub mod importer;
+pub mod library_store;
+pub mod metadata;
+pub mod organize;
 pub mod pdf_file;
+pub mod pdf_info;
+pub mod pdf_text;
+pub mod query;
+pub mod reading;
+pub mod sort_order;
+#[cfg(test)]
+mod testing;
+pub mod thumbnail;
+pub mod triage;
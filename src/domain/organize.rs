@@ -0,0 +1,405 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use super::{
+    bulk::move_one,
+    identifiers::safe_file_stem,
+    library_store::{DocumentRecord, LibraryStore},
+};
+use crate::error::{Error, Result};
+
+const PLACEHOLDERS: [&str; 4] = ["year", "venue", "author", "first_tag"];
+
+/// Where the documents of the library go by their metadata, e.g. `{year}/{venue}`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FolderPolicy {
+    /// the folders nested in each other, each a template
+    parts: Vec<String>,
+}
+
+impl FolderPolicy {
+    /// parses a template of folders separated by `/`. the placeholders are `{year}`,
+    /// `{venue}` and `{author}` of the citation, the family name of the first author, and
+    /// `{first_tag}`
+    pub fn parse(template: &str) -> Result<Self> {
+        let invalid = |reason: String| Error::InvalidName {
+            name: template.to_string(),
+            reason,
+        };
+        let parts = template
+            .split('/')
+            .map(str::trim)
+            .filter(|part| !part.is_empty())
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+        if parts.is_empty() {
+            return Err(invalid("the template is empty".to_string()));
+        }
+        for part in &parts {
+            if part == "." || part == ".." {
+                return Err(invalid(
+                    "the folders must stay inside the library".to_string(),
+                ));
+            }
+            let mut rest = part.as_str();
+            while let Some(start) = rest.find('{') {
+                let end = match rest[start..].find('}') {
+                    Some(end) => start + end,
+                    None => return Err(invalid(format!("unclosed {}", &rest[start..]))),
+                };
+                let placeholder = &rest[start + 1..end];
+                if !PLACEHOLDERS.contains(&placeholder) {
+                    return Err(invalid(format!("unknown {{{}}}", placeholder)));
+                }
+                rest = &rest[end + 1..];
+            }
+        }
+        Ok(Self { parts })
+    }
+
+    /// the folder of a document relative to the managed directory, or the placeholder its
+    /// record has no value for
+    pub fn folder(&self, record: Option<&DocumentRecord>) -> std::result::Result<PathBuf, String> {
+        let citation = record.and_then(|record| record.citation.as_ref());
+        let mut folder = PathBuf::new();
+        for part in &self.parts {
+            let mut rendered = String::new();
+            let mut rest = part.as_str();
+            while let Some(start) = rest.find('{') {
+                rendered.push_str(&rest[..start]);
+                // the templates are checked by `parse`
+                let end = match rest[start..].find('}') {
+                    Some(end) => start + end,
+                    None => break,
+                };
+                let placeholder = &rest[start + 1..end];
+                let value = match placeholder {
+                    "year" => citation.and_then(|c| c.year).map(|year| year.to_string()),
+                    "venue" => citation.map(|c| c.venue.clone()),
+                    "author" => citation.and_then(|c| c.first_author()).map(str::to_string),
+                    "first_tag" => record.and_then(|record| record.tags.first().cloned()),
+                    _ => None,
+                };
+                match value.as_deref().map(folder_name) {
+                    Some(value) if !value.is_empty() => rendered.push_str(&value),
+                    _ => return Err(placeholder.to_string()),
+                }
+                rest = &rest[end + 1..];
+            }
+            rendered.push_str(rest);
+            folder.push(folder_name(&rendered));
+        }
+        Ok(folder)
+    }
+}
+
+/// `value` as the name of a folder, its words joined by `_` without the characters
+/// awkward in a path, e.g. `Proc_KDD`
+fn folder_name(value: &str) -> String {
+    safe_file_stem(value)
+        .split(['_', '.'])
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+/// What organizing does with a document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlannedMove {
+    Move(PathBuf),
+    /// the document stays where it is, for this reason
+    Stay(String),
+}
+
+/// The preview of filing the documents by a `FolderPolicy`, which is executed once
+/// reviewed. the documents already in their folder are left out
+#[derive(Debug, Clone, Default)]
+pub struct OrganizePlan {
+    pub files: Vec<(PathBuf, PlannedMove)>,
+}
+
+impl OrganizePlan {
+    pub fn new(
+        policy: &FolderPolicy,
+        paths: impl IntoIterator<Item = PathBuf>,
+        managed_dir: &Path,
+        library: &LibraryStore,
+    ) -> Self {
+        let mut plan = Self::default();
+        for src in paths {
+            let key = match LibraryStore::key_for(managed_dir, &src) {
+                Some(key) => key,
+                None => continue,
+            };
+            let folder = match policy.folder(library.record(&key)) {
+                Ok(folder) => folder,
+                Err(placeholder) => {
+                    let reason = format!("no {}", placeholder);
+                    plan.files.push((src, PlannedMove::Stay(reason)));
+                    continue;
+                }
+            };
+            let dest = managed_dir
+                .join(folder)
+                .join(src.file_name().unwrap_or_default());
+            if dest == src {
+                continue;
+            }
+            let action = if dest.exists() {
+                PlannedMove::Stay(format!("{} already exists", display(managed_dir, &dest)))
+            } else if plan.moves().any(|(_, other)| other == dest) {
+                PlannedMove::Stay(format!(
+                    "another file goes to {}",
+                    display(managed_dir, &dest)
+                ))
+            } else {
+                PlannedMove::Move(dest)
+            };
+            plan.files.push((src, action));
+        }
+        plan
+    }
+
+    /// the documents which move and where to
+    pub fn moves(&self) -> impl Iterator<Item = (&Path, &Path)> {
+        self.files.iter().filter_map(|(src, action)| match action {
+            PlannedMove::Move(dest) => Some((src.as_path(), dest.as_path())),
+            PlannedMove::Stay(_) => None,
+        })
+    }
+
+    /// moves the documents with their records, a document which fails does not stop the
+    /// others. the folders left empty are removed.
+    ///
+    /// returns the new path of each moved document or why it failed, `save` the store to
+    /// keep the records
+    pub fn execute(
+        &self,
+        managed_dir: &Path,
+        library: &mut LibraryStore,
+    ) -> Vec<(PathBuf, Result<PathBuf>)> {
+        let mut results = Vec::new();
+        for (src, dest) in self.moves() {
+            let result = dest
+                .parent()
+                .map_or(Ok(()), |dir| {
+                    fs::create_dir_all(dir).map_err(|err| Error::writing_file(dir, err))
+                })
+                .and_then(|()| move_one(src, dest, managed_dir, library))
+                .map(|()| dest.to_path_buf());
+            if result.is_ok() {
+                remove_empty_folders(managed_dir, src);
+            }
+            results.push((src.to_path_buf(), result));
+        }
+        results
+    }
+}
+
+/// Where the documents just imported are once filed by a `FolderPolicy`
+#[derive(Debug, Default)]
+pub struct Filed {
+    /// the path of each document afterwards, in the order they were imported
+    pub paths: Vec<PathBuf>,
+    /// the documents left where they were imported and why, e.g. `no year` as a
+    /// document has no citation until its metadata is fetched
+    pub left: Vec<(PathBuf, String)>,
+}
+
+/// files the documents just imported by `policy`, except the ones imported into a folder
+/// of the library on purpose. a document which cannot be filed stays where it was
+/// imported, `save` the store to keep the records
+pub fn file_imported(
+    policy: &FolderPolicy,
+    imported: &[PathBuf],
+    managed_dir: &Path,
+    library: &mut LibraryStore,
+) -> Filed {
+    let in_root = imported
+        .iter()
+        .filter(|path| path.parent() == Some(managed_dir))
+        .cloned();
+    let plan = OrganizePlan::new(policy, in_root, managed_dir, library);
+    let mut filed = Filed::default();
+    for (src, action) in &plan.files {
+        if let PlannedMove::Stay(reason) = action {
+            filed.left.push((src.clone(), reason.clone()));
+        }
+    }
+    let mut moved = HashMap::new();
+    for (src, result) in plan.execute(managed_dir, library) {
+        match result {
+            Ok(dest) => {
+                moved.insert(src, dest);
+            }
+            Err(err) => filed.left.push((src, err.to_string())),
+        }
+    }
+    for (path, reason) in &filed.left {
+        log::info!("left {} in place: {}", path.display(), reason);
+    }
+    filed.paths = imported
+        .iter()
+        .map(|path| moved.get(path).unwrap_or(path).clone())
+        .collect();
+    filed
+}
+
+/// removes the folders of `path` inside the managed directory which are empty
+fn remove_empty_folders(managed_dir: &Path, path: &Path) {
+    for dir in path
+        .ancestors()
+        .skip(1)
+        .take_while(|dir| dir.starts_with(managed_dir) && *dir != managed_dir)
+    {
+        // fails once a folder is not empty
+        if fs::remove_dir(dir).is_err() {
+            break;
+        }
+        log::info!("removed the empty folder {}", dir.display());
+    }
+}
+
+/// `path` relative to the managed directory, for the reasons of documents which stay
+fn display(managed_dir: &Path, path: &Path) -> String {
+    LibraryStore::key_for(managed_dir, path).unwrap_or_else(|| path.display().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{file_imported, FolderPolicy, OrganizePlan, PlannedMove};
+    use crate::{
+        domain::{
+            citation::Citation,
+            importer::Importer,
+            testing::{library, write_pdf},
+        },
+        error::Error,
+    };
+    use std::fs;
+
+    fn citation(year: i32, venue: &str) -> Citation {
+        Citation {
+            year: Some(year),
+            venue: venue.to_string(),
+            ..Citation::default()
+        }
+    }
+
+    fn policy() -> FolderPolicy {
+        FolderPolicy::parse("{year}/{venue}").unwrap()
+    }
+
+    #[test]
+    fn test_parse_policy() {
+        assert!(FolderPolicy::parse("{year}/{first_tag}").is_ok());
+        assert!(matches!(
+            FolderPolicy::parse("{year}/{month}"),
+            Err(Error::InvalidName { .. })
+        ));
+    }
+
+    #[test]
+    fn test_plan_by_year_and_venue() {
+        let (_dir, managed, mut library) = library(&["a.pdf", "c.pdf"]);
+        library.record_mut("a.pdf").citation = Some(citation(2019, "Proc. KDD"));
+        library.record_mut("c.pdf").citation = Some(citation(2021, ""));
+
+        let paths = ["a.pdf", "c.pdf"].map(|name| managed.join(name));
+        let plan = OrganizePlan::new(&policy(), paths, &managed, &library);
+        assert_eq!(
+            plan.files[0].1,
+            PlannedMove::Move(managed.join("2019/Proc_KDD/a.pdf"))
+        );
+        assert_eq!(plan.files[1].1, PlannedMove::Stay("no venue".to_string()));
+    }
+
+    #[test]
+    fn test_plan_leaves_out_filed_documents() {
+        let (_dir, managed, mut library) = library(&["2019/Proc_KDD/a.pdf"]);
+        library.record_mut("2019/Proc_KDD/a.pdf").citation = Some(citation(2019, "Proc. KDD"));
+        let paths = [managed.join("2019/Proc_KDD/a.pdf")];
+        assert!(OrganizePlan::new(&policy(), paths, &managed, &library)
+            .files
+            .is_empty());
+    }
+
+    #[test]
+    fn test_document_without_record_stays() {
+        let (_dir, managed, library) = library(&["a.pdf"]);
+        let plan = OrganizePlan::new(&policy(), [managed.join("a.pdf")], &managed, &library);
+        assert_eq!(plan.files[0].1, PlannedMove::Stay("no year".to_string()));
+    }
+
+    #[test]
+    fn test_existing_destination_stays() {
+        let (_dir, managed, mut library) = library(&["a.pdf", "2020/NeurIPS/a.pdf"]);
+        library.record_mut("a.pdf").citation = Some(citation(2020, "NeurIPS"));
+        let plan = OrganizePlan::new(&policy(), [managed.join("a.pdf")], &managed, &library);
+        assert_eq!(
+            plan.files[0].1,
+            PlannedMove::Stay("2020/NeurIPS/a.pdf already exists".to_string())
+        );
+    }
+
+    #[test]
+    fn test_execute_moves_records_and_removes_empty_folders() {
+        let (_dir, managed, mut library) = library(&["old/b.pdf"]);
+        library.record_mut("old/b.pdf").citation = Some(citation(2020, "NeurIPS"));
+        library.record_mut("old/b.pdf").notes = "good".to_string();
+
+        let plan = OrganizePlan::new(&policy(), [managed.join("old/b.pdf")], &managed, &library);
+        let results = plan.execute(&managed, &mut library);
+        assert!(results.iter().all(|(_, result)| result.is_ok()));
+        assert!(managed.join("2020/NeurIPS/b.pdf").exists());
+        assert!(!managed.join("old").exists());
+        assert_eq!(library.record("2020/NeurIPS/b.pdf").unwrap().notes, "good");
+        assert!(library.record("old/b.pdf").is_none());
+    }
+
+    #[test]
+    fn test_execute_never_overwrites() {
+        let (_dir, managed, mut library) = library(&["a.pdf"]);
+        library.record_mut("a.pdf").citation = Some(citation(2020, "NeurIPS"));
+        let plan = OrganizePlan::new(&policy(), [managed.join("a.pdf")], &managed, &library);
+        // created after the plan was reviewed
+        fs::create_dir_all(managed.join("2020/NeurIPS")).unwrap();
+        fs::write(managed.join("2020/NeurIPS/a.pdf"), "other").unwrap();
+
+        let results = plan.execute(&managed, &mut library);
+        assert!(matches!(
+            results.as_slice(),
+            [(_, Err(Error::AlreadyExists { .. }))]
+        ));
+        assert!(managed.join("a.pdf").exists());
+        assert!(library.record("a.pdf").is_some());
+    }
+
+    #[test]
+    fn test_file_imported_reports_left_in_place() {
+        let (dir, managed, mut library) = library(&[]);
+        let src = dir.path().join("downloads");
+        for name in ["a.pdf", "b.pdf"] {
+            write_pdf(&src.join(name));
+        }
+        let importer = Importer::new(&managed);
+        let imported = ["a.pdf", "b.pdf"]
+            .map(|name| importer.import(&src.join(name), None).unwrap())
+            .to_vec();
+        library.record_imports(&managed, &imported);
+        library.record_mut("b.pdf").citation = Some(citation(2020, "NeurIPS"));
+
+        let filed = file_imported(&policy(), &imported, &managed, &mut library);
+        let a = managed.join("a.pdf");
+        assert_eq!(filed.left, vec![(a.clone(), "no year".to_string())]);
+        assert_eq!(
+            filed.paths,
+            vec![a.clone(), managed.join("2020/NeurIPS/b.pdf")]
+        );
+        assert!(a.exists());
+        assert!(library.record("a.pdf").unwrap().imported.is_some());
+    }
+}
@@ -1,5 +1,51 @@
+use std::{
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::{
+    domain::pdf_info::PdfInfo,
+    error::{Error, Result},
+};
+
+/// the header may be preceded by garbage, readers accept it within the first 1024 bytes
+const HEADER_SEARCH_LEN: u64 = 1024;
+
+#[derive(Debug, Clone, Serialize)]
 pub struct PdfFile {
     pub file_name: String,
-    // pub file_path: String,
-    // pub updated_at: String,
+    pub path: PathBuf,
+    /// size in bytes
+    pub size: u64,
+    /// not available on every platform and filesystem
+    pub created: Option<DateTime<Utc>>,
+    pub modified: Option<DateTime<Utc>>,
+    /// page count, version and other facts read from the file's structure
+    pub info: PdfInfo,
+}
+
+/// fails with `Error::BrokenPdf` if `path` does not start with a pdf header
+pub fn check_header(path: &Path) -> Result<()> {
+    let file = fs::File::open(path).map_err(|err| Error::reading_file(path, err))?;
+    let mut head = Vec::new();
+    file.take(HEADER_SEARCH_LEN)
+        .read_to_end(&mut head)
+        .map_err(|err| Error::reading_file(path, err))?;
+
+    if head.windows(5).any(|w| w == b"%PDF-") {
+        return Ok(());
+    }
+    let reason = if head.is_empty() {
+        "file is empty"
+    } else {
+        "missing %PDF- header"
+    };
+    Err(Error::BrokenPdf {
+        path: path.to_path_buf(),
+        reason: reason.to_string(),
+    })
 }
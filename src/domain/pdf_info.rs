@@ -0,0 +1,479 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use chrono::{DateTime, Utc};
+use lopdf::{decode_text_string, Dictionary, Document, Object};
+use regex::bytes::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    domain::{pdf_file::PdfFile, pdf_text},
+    error::{Error, Result},
+};
+
+/// the header and the first object of a linearized file are within the first kilobyte
+const HEAD_LEN: u64 = 1024;
+
+/// Facts read from the structure of a pdf file, without rendering it.
+///
+/// Reading is best effort: a malformed file gives the facts which could be read.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PdfInfo {
+    /// e.g. `1.7`, the version of the catalog wins over the header's if it is later
+    pub version: Option<String>,
+    pub pages: Option<u32>,
+    pub encrypted: bool,
+    /// optimized for display while downloading ("fast web view")
+    pub linearized: bool,
+    pub producer: Option<String>,
+    /// the file could not be parsed, only its header has been read
+    pub damaged: bool,
+}
+
+impl PdfInfo {
+    /// reads the info of the pdf at `path`, failing only if the file cannot be read
+    pub fn read(path: &Path) -> Result<Self> {
+        let mut head = Vec::new();
+        fs::File::open(path)
+            .and_then(|file| file.take(HEAD_LEN).read_to_end(&mut head))
+            .map_err(|err| Error::reading_file(path, err))?;
+
+        let mut info = Self {
+            version: header_version(&head),
+            linearized: is_linearized(&head),
+            ..Self::default()
+        };
+        let doc = match pdf_text::catching_panics(path, || {
+            Document::load(path).map_err(|err| err.to_string())
+        }) {
+            Ok(doc) => doc,
+            Err(err) => {
+                log::debug!("{}", err);
+                info.damaged = true;
+                return Ok(info);
+            }
+        };
+
+        info.encrypted = doc.trailer.has(b"Encrypt");
+        if let Ok(root) = doc.catalog() {
+            if let Ok(version) = root.get(b"Version").and_then(Object::as_name) {
+                let version = String::from_utf8_lossy(version).to_string();
+                if info.version.as_ref().is_none_or(|header| *header < version) {
+                    info.version = Some(version);
+                }
+            }
+            info.pages = page_count(&doc, root);
+        }
+        // the objects of an encrypted file are missing unless it opens without a password
+        info.producer = doc
+            .trailer
+            .get(b"Info")
+            .and_then(|info| dictionary(&doc, info))
+            .and_then(|info| info.get(b"Producer"))
+            .and_then(decode_text_string)
+            .ok()
+            .map(|producer| {
+                producer
+                    .trim_matches(|c: char| c.is_whitespace() || c == '\0' || c == '\u{feff}')
+                    .to_string()
+            })
+            .filter(|producer| !producer.is_empty());
+        Ok(info)
+    }
+
+    /// names of the flags which are set, e.g. `encrypted`
+    pub fn flags(&self) -> Vec<&'static str> {
+        [
+            (self.encrypted, "encrypted"),
+            (self.linearized, "linearized"),
+            (self.damaged, "damaged"),
+        ]
+        .iter()
+        .filter(|(set, _)| *set)
+        .map(|(_, flag)| *flag)
+        .collect()
+    }
+}
+
+/// The info of the files read before, kept while their size and modification time are
+/// unchanged, and saved so that a restart does not read every file again
+#[derive(Default, Serialize, Deserialize)]
+pub struct PdfInfoCache {
+    entries: HashMap<PathBuf, CachedInfo>,
+    /// where the cache is saved, not saved if empty
+    #[serde(skip)]
+    path: PathBuf,
+    /// whether there are entries which are not saved yet
+    #[serde(skip)]
+    changed: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedInfo {
+    size: u64,
+    modified: Option<DateTime<Utc>>,
+    info: PdfInfo,
+}
+
+impl PdfInfoCache {
+    /// the cache saved at `path`, empty if there is none or it cannot be read
+    pub fn open(path: &Path) -> Self {
+        let mut cache = match fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|err| {
+                log::warn!("ignored the info cache {}: {}", path.display(), err);
+                Self::default()
+            }),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Self::default(),
+            Err(err) => {
+                log::warn!("ignored the info cache {}: {}", path.display(), err);
+                Self::default()
+            }
+        };
+        cache.path = path.to_path_buf();
+        cache
+    }
+
+    /// the cached info of `pdf_file`, `None` if it has not been read or has changed since
+    pub fn cached(&self, pdf_file: &PdfFile) -> Option<PdfInfo> {
+        self.entries
+            .get(&pdf_file.path)
+            .filter(|cached| cached.size == pdf_file.size && cached.modified == pdf_file.modified)
+            .map(|cached| cached.info.clone())
+    }
+
+    pub fn insert(&mut self, pdf_file: &PdfFile, info: PdfInfo) {
+        let cached = CachedInfo {
+            size: pdf_file.size,
+            modified: pdf_file.modified,
+            info,
+        };
+        self.entries.insert(pdf_file.path.clone(), cached);
+        self.changed = true;
+    }
+
+    /// the info of `pdf_file`, read again only if the file has changed
+    pub fn get(&mut self, pdf_file: &PdfFile) -> PdfInfo {
+        if let Some(info) = self.cached(pdf_file) {
+            return info;
+        }
+        let info = read_or_default(&pdf_file.path);
+        self.insert(pdf_file, info.clone());
+        info
+    }
+
+    /// forgets the files which are not among `paths`, e.g. once they are deleted
+    pub fn retain(&mut self, paths: &HashSet<&Path>) {
+        let len = self.entries.len();
+        self.entries
+            .retain(|path, _| paths.contains(path.as_path()));
+        self.changed |= self.entries.len() != len;
+    }
+
+    /// saves the cache if it changed since it was opened or saved
+    pub fn save(&mut self) -> Result<()> {
+        if !self.changed || self.path.as_os_str().is_empty() {
+            return Ok(());
+        }
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).map_err(|err| Error::writing_file(dir, err))?;
+        }
+        let content = serde_json::to_string(self).map_err(io::Error::from)?;
+        let tmp = self.path.with_extension("json.tmp");
+        fs::write(&tmp, content).map_err(|err| Error::writing_file(&tmp, err))?;
+        fs::rename(&tmp, &self.path).map_err(|err| Error::writing_file(&self.path, err))?;
+        self.changed = false;
+        Ok(())
+    }
+}
+
+/// the info of the pdf at `path`, the default info if it cannot be read
+pub fn read_or_default(path: &Path) -> PdfInfo {
+    PdfInfo::read(path).unwrap_or_else(|err| {
+        log::warn!("{}", err);
+        PdfInfo::default()
+    })
+}
+
+fn header_version(head: &[u8]) -> Option<String> {
+    let start = find(head, b"%PDF-")? + 5;
+    let version = head[start..]
+        .iter()
+        .take_while(|b| b.is_ascii_digit() || **b == b'.')
+        .map(|b| *b as char)
+        .collect::<String>();
+    (!version.is_empty()).then_some(version)
+}
+
+/// whether the first object of the file is a linearization dictionary
+fn is_linearized(head: &[u8]) -> bool {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    // the header line and the binary comment which usually follows are skipped
+    let regex = REGEX.get_or_init(|| {
+        Regex::new(r"(?-u)^%PDF-[^\r\n]*[\r\n]+(?:%[^\r\n]*[\r\n]+)*\s*\d+\s+\d+\s+obj\s*<<[^>]*/Linearized\b")
+            .unwrap()
+    });
+    find(head, b"%PDF-").is_some_and(|start| regex.is_match(&head[start..]))
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+fn dictionary<'a>(doc: &'a Document, object: &'a Object) -> lopdf::Result<&'a Dictionary> {
+    doc.dereference(object)
+        .and_then(|(_, object)| object.as_dict())
+}
+
+/// the count of the page tree, else its pages counted one by one
+fn page_count(doc: &Document, root: &Dictionary) -> Option<u32> {
+    let count = root
+        .get(b"Pages")
+        .and_then(|pages| dictionary(doc, pages))
+        .and_then(|pages| pages.get(b"Count"))
+        .and_then(Object::as_i64)
+        .ok()
+        .and_then(|count| u32::try_from(count).ok());
+    count.or_else(|| match doc.page_iter().count() {
+        0 => None,
+        count => u32::try_from(count).ok(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PdfInfo, PdfInfoCache};
+    use crate::domain::pdf_file::PdfFile;
+    use flate2::{write::ZlibEncoder, Compression};
+    use std::{collections::HashSet, fs, io::Write, path::PathBuf};
+
+    /// writes `objects` after a header and returns the file with their offsets
+    fn body(header: &str, objects: &[String]) -> (Vec<u8>, Vec<usize>) {
+        let mut file = header.as_bytes().to_vec();
+        let mut offsets = Vec::new();
+        for (idx, object) in objects.iter().enumerate() {
+            offsets.push(file.len());
+            file.extend(format!("{} 0 obj\n{}\nendobj\n", idx + 1, object).as_bytes());
+        }
+        (file, offsets)
+    }
+
+    fn xref_table(first: usize, offsets: &[usize]) -> String {
+        let mut table = format!(
+            "xref\n0 1\n0000000000 65535 f \n{} {}\n",
+            first,
+            offsets.len()
+        );
+        for offset in offsets {
+            table.push_str(&format!("{:010} 00000 n \n", offset));
+        }
+        table
+    }
+
+    /// a pdf whose incremental update adds a page, a new producer and a later catalog
+    /// version, with the offset of its last cross-reference table
+    fn updated_pdf() -> (Vec<u8>, usize) {
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+            "<< /Type /Page /Parent 2 0 R >>".to_string(),
+            "<< /Producer (old) >>".to_string(),
+        ];
+        let (mut file, offsets) = body("%PDF-1.4\n%\u{e2}\u{e3}\n", &objects);
+        let xref = file.len();
+        file.extend(xref_table(1, &offsets).as_bytes());
+        file.extend(b"trailer\n<< /Size 5 /Root 1 0 R /Info 4 0 R >>\n");
+        file.extend(format!("startxref\n{}\n%%EOF\n", xref).as_bytes());
+
+        let mut update = Vec::new();
+        let mut add = |number: usize, object: &str| {
+            update.push((number, file.len()));
+            file.extend(format!("{} 0 obj\n{}\nendobj\n", number, object).as_bytes());
+        };
+        add(1, "<< /Type /Catalog /Pages 2 0 R /Version /1.7 >>");
+        add(2, "<< /Type /Pages /Kids [3 0 R 5 0 R] /Count 2 >>");
+        add(4, "<< /Producer <FEFF0070006400660073> >>");
+        add(5, "<< /Type /Page /Parent 2 0 R >>");
+        let prev = xref;
+        let xref = file.len();
+        file.extend(b"xref\n");
+        for (number, offset) in &update {
+            file.extend(format!("{} 1\n{:010} 00000 n \n", number, offset).as_bytes());
+        }
+        file.extend(
+            format!(
+                "trailer\n<< /Size 6 /Root 1 0 R /Info 4 0 R /Prev {} >>\nstartxref\n{}\n%%EOF\n",
+                prev, xref
+            )
+            .as_bytes(),
+        );
+        (file, xref)
+    }
+
+    /// an xref stream with the PNG up predictor, 1 is the linearization dictionary, 2 the
+    /// catalog and 4 the page tree in the object stream 3, 5 the xref stream
+    fn xref_stream_pdf(trailer: &str) -> Vec<u8> {
+        let members = "<< /Type /Catalog /Pages 4 0 R >> << /Type /Pages /Kids [] /Count 12 >>";
+        let objstm = format!("2 0 4 34 {}", members);
+        let objects = [
+            "<< /Linearized 1 /L 1000 >>".to_string(),
+            "<< /Type /Pages /Count 1 >>".to_string(),
+            format!(
+                "<< /Type /ObjStm /N 2 /First 9 /Length {} >>\nstream\n{}\nendstream",
+                objstm.len(),
+                objstm
+            ),
+        ];
+        let (mut file, offsets) = body("%PDF-1.5\n", &objects);
+        let xref = file.len();
+
+        // the types, offsets or object streams, and indexes, 1, 2 and 1 bytes wide
+        let rows: [[u8; 4]; 6] = [
+            [0, 0, 0, 0],
+            [1, (offsets[0] >> 8) as u8, offsets[0] as u8, 0],
+            [2, 0, 3, 0],
+            [1, (offsets[2] >> 8) as u8, offsets[2] as u8, 0],
+            [2, 0, 3, 1],
+            [1, (xref >> 8) as u8, xref as u8, 0],
+        ];
+        let mut predicted = Vec::new();
+        let mut previous = [0u8; 4];
+        for row in rows {
+            predicted.push(2);
+            predicted.extend(row.iter().zip(previous).map(|(b, up)| b.wrapping_sub(up)));
+            previous = row;
+        }
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&predicted).unwrap();
+        let data = encoder.finish().unwrap();
+        file.extend(
+            format!(
+                "5 0 obj\n<< /Type /XRef /Size 6 /W [1 2 1] /Root 2 0 R {} \
+                 /Filter /FlateDecode /DecodeParms << /Predictor 12 /Columns 4 >> \
+                 /Length {} >>\nstream\n",
+                trailer,
+                data.len()
+            )
+            .as_bytes(),
+        );
+        file.extend(&data);
+        file.extend(format!("\nendstream\nendobj\nstartxref\n{}\n%%EOF\n", xref).as_bytes());
+        file
+    }
+
+    fn read(file: &[u8]) -> PdfInfo {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.pdf");
+        fs::write(&path, file).unwrap();
+        PdfInfo::read(&path).unwrap()
+    }
+
+    #[test]
+    fn test_read_incremental_update() {
+        let (file, _) = updated_pdf();
+        let info = read(&file);
+        assert_eq!(info.version.as_deref(), Some("1.7"));
+        assert_eq!(info.pages, Some(2));
+        assert_eq!(info.producer.as_deref(), Some("pdfs"));
+        assert!(info.flags().is_empty());
+    }
+
+    #[test]
+    fn test_read_damaged_file_keeps_the_header() {
+        let (file, xref) = updated_pdf();
+        let broken = String::from_utf8_lossy(&file)
+            .replace(&format!("startxref\n{}\n", xref), "startxref\n999999\n");
+        let info = read(broken.as_bytes());
+        assert_eq!(info.flags(), ["damaged"]);
+        assert_eq!(info.version.as_deref(), Some("1.4"));
+        assert_eq!(info.pages, None);
+
+        let info = read(b"not a pdf");
+        assert!(info.damaged);
+        assert_eq!(info.version, None);
+    }
+
+    #[test]
+    fn test_read_missing_file_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(PdfInfo::read(&dir.path().join("missing.pdf")).is_err());
+    }
+
+    #[test]
+    fn test_read_xref_stream() {
+        let info = read(&xref_stream_pdf(""));
+        assert_eq!(info.version.as_deref(), Some("1.5"));
+        assert_eq!(info.flags(), ["linearized"]);
+        assert_eq!(info.pages, Some(12));
+    }
+
+    #[test]
+    fn test_read_encrypted_file() {
+        // the objects cannot be decrypted, only the trailer is known
+        let info = read(&xref_stream_pdf("/Encrypt << >>"));
+        assert!(info.encrypted);
+        assert_eq!(info.pages, None);
+        assert_eq!(info.producer, None);
+    }
+
+    #[test]
+    fn test_read_overflowing_xref_range() {
+        let info = read(&xref_stream_pdf("/Index [9223372036854775807 6]"));
+        assert!(info.damaged);
+        assert_eq!(info.version.as_deref(), Some("1.5"));
+    }
+
+    #[test]
+    fn test_info_cache_is_saved_and_pruned() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cache/info.json");
+        let pdf_file = |name: &str, size| PdfFile {
+            file_name: name.to_string(),
+            path: PathBuf::from("/papers").join(name),
+            size,
+            created: None,
+            modified: None,
+            info: PdfInfo::default(),
+        };
+        let info = PdfInfo {
+            pages: Some(12),
+            ..PdfInfo::default()
+        };
+
+        let mut cache = PdfInfoCache::open(&path);
+        cache.insert(&pdf_file("a.pdf", 100), info.clone());
+        cache.insert(&pdf_file("b.pdf", 100), info.clone());
+        cache.save().unwrap();
+
+        let mut cache = PdfInfoCache::open(&path);
+        assert_eq!(cache.cached(&pdf_file("a.pdf", 100)), Some(info.clone()));
+        // a file whose size changed is read again
+        assert_eq!(cache.cached(&pdf_file("a.pdf", 101)), None);
+
+        let a = pdf_file("a.pdf", 100);
+        cache.retain(&HashSet::from([a.path.as_path()]));
+        cache.save().unwrap();
+        let cache = PdfInfoCache::open(&path);
+        assert_eq!(cache.cached(&a), Some(info));
+        assert_eq!(cache.cached(&pdf_file("b.pdf", 100)), None);
+    }
+
+    #[test]
+    fn test_info_cache_ignores_a_corrupt_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("info.json");
+        fs::write(&path, "{").unwrap();
+        let mut cache = PdfInfoCache::open(&path);
+        assert!(cache.entries.is_empty());
+        // nothing changed, the file is left as it is
+        cache.save().unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{");
+    }
+}
@@ -0,0 +1,75 @@
+use std::{cell::Cell, ops::RangeInclusive, panic, path::Path};
+
+use pdf_extract::{output_doc_page, Document, PlainTextOutput};
+
+use crate::error::{Error, Result};
+
+thread_local! {
+    /// set while the text of a pdf is extracted, see `install_panic_hook`
+    static EXTRACTING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// logs the panics raised while extracting text instead of printing them over the terminal UI.
+///
+/// the pdf parser panics on some malformed files, these panics are caught by `first_pages` and `page`
+pub fn install_panic_hook() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        if EXTRACTING.with(Cell::get) {
+            log::warn!("pdf parser panicked: {}", info);
+        } else {
+            default_hook(info);
+        }
+    }));
+}
+
+/// the text of each of the first `pages` pages of the pdf at `path`
+pub fn first_pages(path: &Path, pages: u32) -> Result<Vec<String>> {
+    catching_panics(path, || extract(path, 1..=pages))
+}
+
+/// the text of the page `page` of the pdf at `path`, counted from 1
+pub fn page(path: &Path, page: u32) -> Result<String> {
+    let pages = catching_panics(path, || extract(path, page..=page))?;
+    pages.into_iter().next().ok_or_else(|| Error::BrokenPdf {
+        path: path.to_path_buf(),
+        reason: format!("there is no page {}", page),
+    })
+}
+
+/// runs `extract`, turning its error or a panic of the parser into `Error::BrokenPdf`
+pub(crate) fn catching_panics<T>(
+    path: &Path,
+    extract: impl FnOnce() -> std::result::Result<T, String> + panic::UnwindSafe,
+) -> Result<T> {
+    EXTRACTING.with(|extracting| extracting.set(true));
+    let result = panic::catch_unwind(extract);
+    EXTRACTING.with(|extracting| extracting.set(false));
+
+    let reason = match result {
+        Ok(Ok(text)) => return Ok(text),
+        Ok(Err(reason)) => reason,
+        Err(_) => "the pdf parser failed".to_string(),
+    };
+    Err(Error::BrokenPdf {
+        path: path.to_path_buf(),
+        reason,
+    })
+}
+
+/// the text of the pages in `pages` which exist
+fn extract(path: &Path, pages: RangeInclusive<u32>) -> std::result::Result<Vec<String>, String> {
+    let doc = Document::load(path).map_err(|err| err.to_string())?;
+    if doc.is_encrypted() {
+        return Err("the pdf is encrypted".to_string());
+    }
+    let count = doc.get_pages().len() as u32;
+    (*pages.start().max(&1)..=*pages.end().min(&count))
+        .map(|page| {
+            let mut text = String::new();
+            let mut output = PlainTextOutput::new(&mut text);
+            output_doc_page(&doc, &mut output, page).map_err(|err| format!("{:?}", err))?;
+            Ok(text)
+        })
+        .collect()
+}
@@ -0,0 +1,479 @@
+use std::ops::{Bound, RangeBounds};
+
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+
+use crate::{
+    domain::{library_store::DocumentRecord, pdf_file::PdfFile, reading::ReadingStatus},
+    error::{Error, Result},
+};
+
+/// the fields of `field:value` terms
+const FIELDS: [&str; 10] = [
+    "name", "title", "tag", "author", "year", "status", "size", "pages", "created", "modified",
+];
+
+/// A query typed in the searchbar or given to `pdfstore search`, e.g.
+/// `attention (tag:nlp OR author:vaswani) NOT status:read pages:<20 size:..2mb`.
+///
+/// Terms next to each other must all match, `NOT` binds tighter than `AND`, which binds
+/// tighter than `OR`. A word or a quoted phrase is part of the file name, a
+/// `field:value` term matches a fact of the file or of its record, and numbers and
+/// dates take `>`, `>=`, `<`, `<=` or an inclusive range `low..high`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    expr: Expr,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Term(Term),
+    Not(Box<Expr>),
+    /// matches if all match, so the empty query matches every file
+    And(Vec<Expr>),
+    /// matches if any matches
+    Or(Vec<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Term {
+    /// part of the file name, case insensitive like the other text terms
+    Name(String),
+    /// part of the title of the citation
+    Title(String),
+    Tag(String),
+    /// part of the name of one of the authors of the citation
+    Author(String),
+    Year(Range),
+    Status(ReadingStatus),
+    /// in bytes
+    Size(Range),
+    Pages(Range),
+    /// in days, see `days`
+    Created(Range),
+    Modified(Range),
+}
+
+/// the values a number or date term accepts
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Range {
+    low: Bound<i64>,
+    high: Bound<i64>,
+}
+
+impl Range {
+    /// parses `v`, `>v`, `>=v`, `<v`, `<=v`, `low..high`, `low..` or `..high`, with the
+    /// values parsed by `value`
+    fn parse(s: &str, value: ValueParser) -> std::result::Result<Self, String> {
+        let bound = |s: &str, bound: fn(i64) -> Bound<i64>| match s {
+            "" => Ok(Bound::Unbounded),
+            s => value(s).map(bound),
+        };
+        let (low, high) = if let Some(s) = s.strip_prefix(">=") {
+            (bound(s, Bound::Included)?, Bound::Unbounded)
+        } else if let Some(s) = s.strip_prefix("<=") {
+            (Bound::Unbounded, bound(s, Bound::Included)?)
+        } else if let Some(s) = s.strip_prefix('>') {
+            (bound(s, Bound::Excluded)?, Bound::Unbounded)
+        } else if let Some(s) = s.strip_prefix('<') {
+            (Bound::Unbounded, bound(s, Bound::Excluded)?)
+        } else if let Some((low, high)) = s.split_once("..") {
+            (bound(low, Bound::Included)?, bound(high, Bound::Included)?)
+        } else {
+            let value = value(s)?;
+            (Bound::Included(value), Bound::Included(value))
+        };
+        if low == Bound::Unbounded && high == Bound::Unbounded {
+            return Err("expected a value".to_string());
+        }
+        Ok(Self { low, high })
+    }
+
+    fn matches(&self, value: i64) -> bool {
+        (self.low, self.high).contains(&value)
+    }
+}
+
+/// parses the value of a number or date term, or tells why it cannot
+type ValueParser = fn(&str) -> std::result::Result<i64, String>;
+
+/// a count such as a year or a number of pages
+fn number(s: &str) -> std::result::Result<i64, String> {
+    s.parse()
+        .map_err(|_| format!("\"{}\" is not a whole number", s))
+}
+
+/// a size in bytes, with an optional `kb`, `mb` or `gb` unit, e.g. `1.5mb`
+fn size(s: &str) -> std::result::Result<i64, String> {
+    let lower = s.to_lowercase();
+    let digits = lower.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let unit = match &lower[digits.len()..] {
+        "" | "b" => 1.0,
+        "k" | "kb" => 1024.0,
+        "m" | "mb" => 1024.0 * 1024.0,
+        "g" | "gb" => 1024.0 * 1024.0 * 1024.0,
+        _ => return Err(format!("\"{}\" is not a size such as 500kb or 2mb", s)),
+    };
+    match digits.parse::<f64>() {
+        Ok(value) if value >= 0.0 => Ok((value * unit) as i64),
+        _ => Err(format!("\"{}\" is not a size such as 500kb or 2mb", s)),
+    }
+}
+
+/// a date as `YYYY-MM-DD`, in days since the common era
+fn date(s: &str) -> std::result::Result<i64, String> {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .map(days)
+        .map_err(|_| format!("\"{}\" is not a date such as 2024-01-31", s))
+}
+
+fn days(date: NaiveDate) -> i64 {
+    i64::from(date.num_days_from_ce())
+}
+
+fn days_of(time: Option<DateTime<Utc>>) -> Option<i64> {
+    time.map(|time| days(time.date_naive()))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// a word, a `field:value` term or a phrase
+    Text {
+        text: String,
+        /// whether it starts with a quote, so that it is neither a keyword nor a field
+        phrase: bool,
+        column: usize,
+    },
+    Open(usize),
+    Close(usize),
+}
+
+/// splits the query at whitespace and parentheses outside quotes, the columns count
+/// characters
+fn tokenize(query: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = query.chars().enumerate().peekable();
+    while let Some((column, c)) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '(' => tokens.push(Token::Open(column)),
+            ')' => tokens.push(Token::Close(column)),
+            _ => {
+                let mut text = String::new();
+                let mut next = Some((column, c));
+                while let Some((at, c)) = next {
+                    if c == '"' {
+                        loop {
+                            match chars.next() {
+                                Some((_, '"')) => break,
+                                Some((_, c)) => text.push(c),
+                                None => return Err(invalid(at, "the quote is not closed")),
+                            }
+                        }
+                    } else {
+                        text.push(c);
+                    }
+                    next = chars.next_if(|(_, c)| !c.is_whitespace() && *c != '(' && *c != ')');
+                }
+                tokens.push(Token::Text {
+                    text,
+                    phrase: c == '"',
+                    column,
+                });
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+fn invalid(column: usize, reason: impl Into<String>) -> Error {
+    Error::InvalidQuery {
+        column,
+        reason: reason.into(),
+    }
+}
+
+/// a recursive descent parser, one function per precedence level
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    /// the column after the query, where a missing term is reported
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    /// whether the next token is the keyword, which must not be quoted
+    fn at_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Text { text, phrase: false, .. }) if text == keyword)
+    }
+
+    fn column(&self) -> usize {
+        match self.peek() {
+            Some(Token::Text { column, .. } | Token::Open(column) | Token::Close(column)) => {
+                *column
+            }
+            None => self.end,
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr> {
+        let mut exprs = vec![self.and()?];
+        while self.at_keyword("OR") {
+            self.pos += 1;
+            exprs.push(self.and()?);
+        }
+        Ok(match exprs.len() {
+            1 => exprs.remove(0),
+            _ => Expr::Or(exprs),
+        })
+    }
+
+    fn and(&mut self) -> Result<Expr> {
+        let mut exprs = vec![self.unary()?];
+        loop {
+            if self.at_keyword("AND") {
+                self.pos += 1;
+            } else if self.at_keyword("OR") || matches!(self.peek(), None | Some(Token::Close(_))) {
+                break;
+            }
+            exprs.push(self.unary()?);
+        }
+        Ok(match exprs.len() {
+            1 => exprs.remove(0),
+            _ => Expr::And(exprs),
+        })
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        let column = self.column();
+        match self.peek().cloned() {
+            None => Err(invalid(column, "expected a term")),
+            Some(Token::Close(_)) => Err(invalid(column, "this parenthesis is not open")),
+            Some(Token::Open(_)) => {
+                self.pos += 1;
+                let expr = self.or()?;
+                match self.peek() {
+                    Some(Token::Close(_)) => {
+                        self.pos += 1;
+                        Ok(expr)
+                    }
+                    _ => Err(invalid(column, "this parenthesis is not closed")),
+                }
+            }
+            Some(Token::Text { text, phrase, .. }) => {
+                self.pos += 1;
+                if phrase {
+                    return Ok(Expr::Term(Term::Name(text.to_lowercase())));
+                }
+                match text.as_str() {
+                    "NOT" => Ok(Expr::Not(Box::new(self.unary()?))),
+                    "AND" | "OR" => {
+                        Err(invalid(column, format!("expected a term before {}", text)))
+                    }
+                    _ => Term::parse(&text, column).map(Expr::Term),
+                }
+            }
+        }
+    }
+}
+
+impl Query {
+    /// parses the query, failing with `Error::InvalidQuery` at the column of the mistake
+    pub fn parse(query: &str) -> Result<Self> {
+        let mut parser = Parser {
+            tokens: tokenize(query)?,
+            pos: 0,
+            end: query.chars().count(),
+        };
+        if parser.tokens.is_empty() {
+            return Ok(Self::default());
+        }
+        let expr = parser.or()?;
+        match parser.peek() {
+            None => Ok(Self { expr }),
+            Some(_) => Err(invalid(parser.column(), "this parenthesis is not open")),
+        }
+    }
+
+    /// a query matching no file, e.g. in place of a saved query which no longer parses
+    pub fn nothing() -> Self {
+        Self {
+            expr: Expr::Or(Vec::new()),
+        }
+    }
+
+    /// whether `file` matches the query, `record` is what the library knows about it
+    pub fn matches(&self, file: &PdfFile, record: Option<&DocumentRecord>) -> bool {
+        self.expr.matches(file, record)
+    }
+}
+
+impl Default for Query {
+    /// the empty query, matching every file
+    fn default() -> Self {
+        Self {
+            expr: Expr::And(Vec::new()),
+        }
+    }
+}
+
+impl Expr {
+    fn matches(&self, file: &PdfFile, record: Option<&DocumentRecord>) -> bool {
+        match self {
+            Self::Term(term) => term.matches(file, record),
+            Self::Not(expr) => !expr.matches(file, record),
+            Self::And(exprs) => exprs.iter().all(|expr| expr.matches(file, record)),
+            Self::Or(exprs) => exprs.iter().any(|expr| expr.matches(file, record)),
+        }
+    }
+}
+
+impl Term {
+    /// parses a word, which is a `field:value` term if it has a colon
+    fn parse(text: &str, column: usize) -> Result<Self> {
+        let (field, value) = match text.split_once(':') {
+            Some((field, value)) => (field.to_lowercase(), value),
+            None => return Ok(Self::Name(text.to_lowercase())),
+        };
+        if !FIELDS.contains(&field.as_str()) {
+            return Err(invalid(
+                column,
+                format!(
+                    "unknown field \"{}\", use {} or quote the text",
+                    field,
+                    FIELDS.join(", ")
+                ),
+            ));
+        }
+        // the column of the value, after `field:`
+        let column = column + field.chars().count() + 1;
+        if value.is_empty() {
+            return Err(invalid(
+                column,
+                format!("expected a value after {}:", field),
+            ));
+        }
+        let text = || value.to_lowercase();
+        let range = |parse: ValueParser| {
+            Range::parse(value, parse).map_err(|reason| invalid(column, reason))
+        };
+        Ok(match field.as_str() {
+            "name" => Self::Name(text()),
+            "title" => Self::Title(text()),
+            "tag" => Self::Tag(value.to_string()),
+            "author" => Self::Author(text()),
+            "year" => Self::Year(range(number)?),
+            "status" => Self::Status(value.parse().map_err(|reason| invalid(column, reason))?),
+            "size" => Self::Size(range(size)?),
+            "pages" => Self::Pages(range(number)?),
+            "created" => Self::Created(range(date)?),
+            _ => Self::Modified(range(date)?),
+        })
+    }
+
+    fn matches(&self, file: &PdfFile, record: Option<&DocumentRecord>) -> bool {
+        let citation = record.and_then(|record| record.citation.as_ref());
+        let contains = |haystack: &str, needle: &str| haystack.to_lowercase().contains(needle);
+        match self {
+            Self::Name(name) => contains(&file.file_name, name),
+            Self::Title(title) => citation.is_some_and(|citation| contains(&citation.title, title)),
+            Self::Tag(tag) => record.is_some_and(|record| record.tags.contains(tag)),
+            Self::Author(author) => citation
+                .is_some_and(|citation| citation.authors.iter().any(|name| contains(name, author))),
+            Self::Year(range) => citation
+                .and_then(|citation| citation.year)
+                .is_some_and(|year| range.matches(i64::from(year))),
+            Self::Status(status) => {
+                record
+                    .map(|record| record.reading.status)
+                    .unwrap_or_default()
+                    == *status
+            }
+            Self::Size(range) => i64::try_from(file.size).is_ok_and(|size| range.matches(size)),
+            Self::Pages(range) => file
+                .info
+                .pages
+                .is_some_and(|pages| range.matches(i64::from(pages))),
+            Self::Created(range) => days_of(file.created).is_some_and(|day| range.matches(day)),
+            Self::Modified(range) => days_of(file.modified).is_some_and(|day| range.matches(day)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Query;
+    use crate::{
+        domain::{
+            citation::Citation, library_store::DocumentRecord, pdf_file::PdfFile,
+            pdf_info::PdfInfo, reading::ReadingStatus,
+        },
+        error::Error,
+    };
+    use chrono::{TimeZone, Utc};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_matches() {
+        let file = PdfFile {
+            file_name: "Attention.pdf".to_string(),
+            path: PathBuf::from("/papers/Attention.pdf"),
+            size: 3 * 1024 * 1024,
+            created: None,
+            modified: Some(Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap()),
+            info: PdfInfo {
+                pages: Some(15),
+                ..PdfInfo::default()
+            },
+        };
+        let mut record = DocumentRecord {
+            tags: vec!["nlp".to_string()],
+            citation: Some(Citation {
+                authors: vec!["Vaswani, Ashish".to_string()],
+                title: "Attention Is All You Need".to_string(),
+                year: Some(2017),
+                ..Citation::default()
+            }),
+            ..DocumentRecord::default()
+        };
+        record.reading.status = ReadingStatus::Reading;
+
+        let matches = |query: &str| Query::parse(query).unwrap().matches(&file, Some(&record));
+        assert!(matches(""));
+        assert!(matches(
+            "atten tag:nlp author:VASWANI year:>2016 status:reading"
+        ));
+        assert!(matches("title:\"all you need\" year:2015..2017 pages:<=15"));
+        assert!(matches("size:>2mb size:..3.5MB modified:>=2024-03-01"));
+        assert!(matches("(tag:cv OR tag:nlp) AND NOT status:read"));
+        assert!(matches("tag:cv OR attention tag:nlp"));
+        assert!(!matches("tag:cv OR (attention tag:cv)"));
+        assert!(!matches("year:<2017"));
+        assert!(!matches("\"attention is\""));
+        assert!(!matches("created:2024-03-01"));
+
+        let unrecorded = Query::parse("status:unread NOT tag:nlp").unwrap();
+        assert!(unrecorded.matches(&file, None));
+        assert!(!Query::nothing().matches(&file, None));
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        let column = |query: &str| match Query::parse(query) {
+            Err(Error::InvalidQuery { column, .. }) => column,
+            other => panic!("{:?} for {}", other.map(|_| ()), query),
+        };
+        assert_eq!(column("year:recent"), 5);
+        assert_eq!(column("re:invent"), 0);
+        assert_eq!(column("tag:nlp AND"), 11);
+        assert_eq!(column("OR tag:nlp"), 0);
+        assert_eq!(column("(tag:nlp OR tag:cv"), 0);
+        assert_eq!(column("tag:nlp)"), 7);
+        assert_eq!(column("title:\"deep"), 6);
+        assert_eq!(column("size:>"), 5);
+        assert!(Query::parse("\"re:invent\" NOT (\"OR\")").is_ok());
+    }
+}
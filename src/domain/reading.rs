@@ -0,0 +1,216 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+
+/// the highest rating, in stars
+pub const MAX_RATING: u8 = 5;
+
+/// Where the reader is with a document
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReadingStatus {
+    #[default]
+    Unread,
+    Reading,
+    Read,
+    /// read, but worth another look
+    ToReview,
+}
+
+impl ReadingStatus {
+    pub const ALL: [Self; 4] = [Self::Unread, Self::Reading, Self::Read, Self::ToReview];
+
+    /// the status set after this one when cycling with the status key
+    pub fn next(self) -> Self {
+        match self {
+            Self::Unread => Self::Reading,
+            Self::Reading => Self::Read,
+            Self::Read => Self::ToReview,
+            Self::ToReview => Self::Unread,
+        }
+    }
+
+    pub fn is_unread(&self) -> bool {
+        *self == Self::Unread
+    }
+
+    /// shown in front of the file name in the managed list
+    pub fn icon(self) -> &'static str {
+        match self {
+            Self::Unread => "○",
+            Self::Reading => "◐",
+            Self::Read => "●",
+            Self::ToReview => "↻",
+        }
+    }
+
+    /// the position in the status sort order, documents being read come first
+    fn rank(self) -> u8 {
+        match self {
+            Self::Reading => 0,
+            Self::ToReview => 1,
+            Self::Unread => 2,
+            Self::Read => 3,
+        }
+    }
+}
+
+impl fmt::Display for ReadingStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let str = match self {
+            Self::Unread => "unread",
+            Self::Reading => "reading",
+            Self::Read => "read",
+            Self::ToReview => "to-review",
+        };
+        write!(f, "{}", str)
+    }
+}
+
+impl FromStr for ReadingStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|status| status.to_string() == s)
+            .ok_or_else(|| {
+                format!(
+                    "unknown status \"{}\", use unread, reading, read or to-review",
+                    s
+                )
+            })
+    }
+}
+
+/// The reading status, progress and rating of a managed document
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Reading {
+    #[serde(default, skip_serializing_if = "ReadingStatus::is_unread")]
+    pub status: ReadingStatus,
+    /// the last page read, counted from 1
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_page: Option<u32>,
+    /// from 1 to `MAX_RATING` stars
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rating: Option<u8>,
+}
+
+impl Reading {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// moves the last page read forward or back, within the page count if it is known.
+    ///
+    /// an unread document is being read once a page has been read
+    pub fn turn_page(&mut self, forward: bool, pages: Option<u32>) {
+        let page = match (self.last_page, forward) {
+            (None, true) => 1,
+            (None, false) => return,
+            (Some(page), true) => page.saturating_add(1),
+            (Some(page), false) => page - 1,
+        };
+        self.last_page = match pages {
+            Some(pages) if page > pages => Some(pages.max(1)),
+            _ => Some(page).filter(|page| *page > 0),
+        };
+        if self.last_page.is_some() && self.status.is_unread() {
+            self.status = ReadingStatus::Reading;
+        }
+    }
+
+    /// sets the rating, 0 clears it
+    pub fn rate(&mut self, stars: u8) {
+        self.rating = Some(stars.min(MAX_RATING)).filter(|stars| *stars > 0);
+    }
+
+    /// `★★★☆☆`, empty without a rating
+    pub fn stars(&self) -> String {
+        let rating = match self.rating {
+            Some(rating) => usize::from(rating.min(MAX_RATING)),
+            None => return String::new(),
+        };
+        format!(
+            "{}{}",
+            "★".repeat(rating),
+            "☆".repeat(usize::from(MAX_RATING) - rating)
+        )
+    }
+
+    /// `p. 12/30`, or `p. 12` when the page count is unknown
+    pub fn progress(&self, pages: Option<u32>) -> Option<String> {
+        let page = self.last_page?;
+        Some(match pages {
+            Some(pages) => format!("p. {}/{}", page, pages),
+            None => format!("p. {}", page),
+        })
+    }
+}
+
+/// The readings of the listed managed files, by path
+#[derive(Debug, Clone, Default)]
+pub struct Readings(HashMap<PathBuf, Reading>);
+
+impl Readings {
+    pub fn new(readings: HashMap<PathBuf, Reading>) -> Self {
+        Self(readings)
+    }
+
+    /// the reading of the file at `path`, the default for a file without one
+    pub fn get(&self, path: &Path) -> Reading {
+        self.0.get(path).copied().unwrap_or_default()
+    }
+
+    /// the sort key of the status order
+    pub(crate) fn status_rank(&self, path: &Path) -> u8 {
+        self.get(path).status.rank()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Reading, ReadingStatus};
+
+    #[test]
+    fn test_turn_page() {
+        let mut reading = Reading::default();
+        reading.turn_page(false, Some(3));
+        assert_eq!(reading, Reading::default());
+
+        reading.turn_page(true, Some(3));
+        assert_eq!(reading.last_page, Some(1));
+        assert_eq!(reading.status, ReadingStatus::Reading);
+        for _ in 0..5 {
+            reading.turn_page(true, Some(3));
+        }
+        assert_eq!(reading.progress(Some(3)).as_deref(), Some("p. 3/3"));
+        for _ in 0..3 {
+            reading.turn_page(false, Some(3));
+        }
+        assert_eq!(reading.last_page, None);
+        // the status is kept, going back does not unread a document
+        assert_eq!(reading.status, ReadingStatus::Reading);
+    }
+
+    #[test]
+    fn test_rating_and_serde() {
+        let mut reading = Reading::default();
+        reading.rate(4);
+        assert_eq!(reading.stars(), "★★★★☆");
+        reading.rate(0);
+        assert_eq!(reading.rating, None);
+
+        reading.status = ReadingStatus::ToReview;
+        assert_eq!(
+            serde_json::to_string(&reading).unwrap(),
+            r#"{"status":"to-review"}"#
+        );
+        assert_eq!("to-review".parse(), Ok(ReadingStatus::ToReview));
+    }
+}
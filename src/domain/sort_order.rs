@@ -0,0 +1,60 @@
+use std::{cmp::Reverse, fmt};
+
+use super::{pdf_file::PdfFile, reading::Readings};
+
+/// order in which pdf files are listed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortOrder {
+    #[default]
+    NameAsc,
+    NameDesc,
+    /// documents being read first, then to review, unread and read
+    Status,
+    /// the best rated first, unrated last
+    RatingDesc,
+}
+
+impl SortOrder {
+    /// the order used after this one when cycling through sort orders,
+    /// the orders by reading are only offered for the files of the library
+    pub fn next(self, with_reading: bool) -> Self {
+        match self {
+            Self::NameAsc => Self::NameDesc,
+            Self::NameDesc if with_reading => Self::Status,
+            Self::Status => Self::RatingDesc,
+            Self::NameDesc | Self::RatingDesc => Self::NameAsc,
+        }
+    }
+
+    /// sorts `pdf_files`, by name where the order does not tell them apart
+    pub fn sort(self, pdf_files: &mut [PdfFile], readings: &Readings) {
+        match self {
+            Self::NameAsc => pdf_files.sort_by(|a, b| a.file_name.cmp(&b.file_name)),
+            Self::NameDesc => pdf_files.sort_by(|a, b| b.file_name.cmp(&a.file_name)),
+            Self::Status => pdf_files.sort_by(|a, b| {
+                let key = |f: &PdfFile| readings.status_rank(&f.path);
+                key(a)
+                    .cmp(&key(b))
+                    .then_with(|| a.file_name.cmp(&b.file_name))
+            }),
+            Self::RatingDesc => pdf_files.sort_by(|a, b| {
+                let key = |f: &PdfFile| Reverse(readings.get(&f.path).rating);
+                key(a)
+                    .cmp(&key(b))
+                    .then_with(|| a.file_name.cmp(&b.file_name))
+            }),
+        }
+    }
+}
+
+impl fmt::Display for SortOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let str = match self {
+            Self::NameAsc => "name ↑",
+            Self::NameDesc => "name ↓",
+            Self::Status => "status",
+            Self::RatingDesc => "rating ↓",
+        };
+        write!(f, "{}", str)
+    }
+}
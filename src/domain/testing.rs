@@ -0,0 +1,58 @@
+//! Helpers shared by the tests of the domain
+
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use tempfile::TempDir;
+
+use crate::domain::library_store::LibraryStore;
+
+/// writes a minimal PDF at `path`, creating its folders
+pub fn write_pdf(path: &Path) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, "%PDF-1.4").unwrap();
+}
+
+/// a managed directory `papers` with the files `names`, which may be nested, and an
+/// empty library
+pub fn library(names: &[&str]) -> (TempDir, PathBuf, LibraryStore) {
+    let dir = tempfile::tempdir().unwrap();
+    let managed = dir.path().join("papers");
+    fs::create_dir_all(&managed).unwrap();
+    for name in names {
+        write_pdf(&managed.join(name));
+    }
+    (dir, managed, LibraryStore::in_memory())
+}
+
+/// A directory made read-only for a test, writable again once dropped so that its
+/// temporary directory can be removed
+#[cfg(unix)]
+pub struct ReadOnly(PathBuf);
+
+#[cfg(unix)]
+impl ReadOnly {
+    /// makes `dir` read-only, `None` if the permissions are not enforced, e.g. for root,
+    /// in which case the test has nothing to check
+    pub fn new(dir: &Path) -> Option<Self> {
+        fs::set_permissions(dir, fs::Permissions::from_mode(0o555)).unwrap();
+        let read_only = Self(dir.to_path_buf());
+        let probe = dir.join(".probe");
+        if fs::write(&probe, "").is_ok() {
+            let _ = fs::remove_file(probe);
+            return None;
+        }
+        Some(read_only)
+    }
+}
+
+#[cfg(unix)]
+impl Drop for ReadOnly {
+    fn drop(&mut self) {
+        let _ = fs::set_permissions(&self.0, fs::Permissions::from_mode(0o755));
+    }
+}
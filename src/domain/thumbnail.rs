@@ -0,0 +1,1257 @@
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    rc::Rc,
+    time::{Duration, SystemTime},
+};
+
+use lopdf::{content::Content, Dictionary, Document, Object, ObjectId};
+use tiny_skia::{
+    Color, FillRule, FilterQuality, Mask, Paint, PathBuilder, Pixmap, PixmapPaint, Rect, Stroke,
+    Transform,
+};
+
+use crate::{
+    domain::{identity::Fnv1a, pdf_file::PdfFile, pdf_text},
+    error::Result,
+};
+
+/// width in pixels of the rendered pages, the terminal scales them to the pane
+pub const THUMBNAIL_WIDTH: u32 = 480;
+/// changes the names of the cached thumbnails, bumped when the rendering changes
+const RENDERER_VERSION: u32 = 2;
+/// thumbnails kept in the cache, the least recently shown are removed first
+const MAX_THUMBNAILS: usize = 1000;
+/// a temporary file older than this was left behind by an interrupted render
+const STALE_TMP_AGE: Duration = Duration::from_secs(60 * 60);
+/// the letter size, for pages without a media box
+const DEFAULT_MEDIA_BOX: [f32; 4] = [0.0, 0.0, 612.0, 792.0];
+/// nested form xobjects which are drawn
+const MAX_FORM_DEPTH: usize = 8;
+/// nested indexed color spaces which are decoded, a broken file may nest one in itself
+const MAX_COLOR_SPACE_DEPTH: usize = 4;
+/// images with more pixels are drawn as a gray box instead of being decoded
+const MAX_IMAGE_PIXELS: u64 = 64 * 1024 * 1024;
+/// the rasterizer of poppler, which draws the text, used when it is installed
+const PDFTOPPM: &str = "pdftoppm";
+/// the built-in renderer draws text as bars, lighter than the text color
+const TEXT_OPACITY: f32 = 0.45;
+/// the height of the text bars relative to the font size, about the x-height
+const TEXT_HEIGHT: f32 = 0.5;
+/// a `TJ` adjustment wider than this, in thousandths of an em, separates words
+const WORD_GAP: f32 = 200.0;
+
+/// Rendered first pages, stored as png files named after the file's path, size and
+/// modification time, so a changed file is rendered again.
+///
+/// the modification time of a png is when it was last shown, the oldest are removed
+/// once there are more than `limit`
+pub struct ThumbnailCache {
+    dir: PathBuf,
+    limit: usize,
+}
+
+impl ThumbnailCache {
+    pub fn new(dir: &Path) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            limit: MAX_THUMBNAILS,
+        }
+    }
+
+    /// the cached thumbnail of `pdf_file`, rendered and cached if there is none
+    pub fn load_or_render(&self, pdf_file: &PdfFile) -> Result<Pixmap> {
+        let cached = self.path_of(pdf_file);
+        if let Ok(pixmap) = Pixmap::load_png(&cached) {
+            if let Err(err) = touch(&cached) {
+                log::debug!(
+                    "failed to touch the thumbnail {}: {}",
+                    cached.display(),
+                    err
+                );
+            }
+            return Ok(pixmap);
+        }
+        let pixmap = render_first_page(&pdf_file.path)?;
+        // the thumbnail is still shown if it cannot be cached
+        if let Err(err) = self.save(&cached, &pixmap) {
+            log::warn!(
+                "failed to cache the thumbnail {}: {}",
+                cached.display(),
+                err
+            );
+        } else if let Err(err) = self.prune() {
+            log::warn!(
+                "failed to prune the thumbnails in {}: {}",
+                self.dir.display(),
+                err
+            );
+        }
+        Ok(pixmap)
+    }
+
+    fn path_of(&self, pdf_file: &PdfFile) -> PathBuf {
+        self.dir.join(format!("{:016x}.png", cache_key(pdf_file)))
+    }
+
+    fn save(&self, path: &Path, pixmap: &Pixmap) -> io::Result<()> {
+        let png = pixmap.encode_png().map_err(io::Error::other)?;
+        fs::create_dir_all(&self.dir)?;
+        let tmp = path.with_extension("png.tmp");
+        fs::write(&tmp, png)?;
+        fs::rename(&tmp, path)
+    }
+
+    /// removes the least recently shown thumbnails beyond `limit`, and the temporary
+    /// files of interrupted renders
+    fn prune(&self) -> io::Result<()> {
+        let mut thumbnails = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            let modified = match entry.metadata().and_then(|metadata| metadata.modified()) {
+                Ok(modified) => modified,
+                // removed by another instance meanwhile
+                Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+                Err(err) => return Err(err),
+            };
+            if name.ends_with(".png.tmp") {
+                if modified.elapsed().is_ok_and(|age| age > STALE_TMP_AGE) {
+                    remove_if_exists(&entry.path())?;
+                }
+            } else if name.ends_with(".png") {
+                thumbnails.push((modified, entry.path()));
+            }
+        }
+        if thumbnails.len() <= self.limit {
+            return Ok(());
+        }
+        thumbnails.sort();
+        let excess = thumbnails.len() - self.limit;
+        for (_, path) in &thumbnails[..excess] {
+            remove_if_exists(path)?;
+        }
+        Ok(())
+    }
+}
+
+/// marks the thumbnail at `path` as shown now
+fn touch(path: &Path) -> io::Result<()> {
+    fs::File::options()
+        .write(true)
+        .open(path)?
+        .set_modified(SystemTime::now())
+}
+
+fn remove_if_exists(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
+
+/// FNV-1a of what identifies a rendering, stable between runs unlike `DefaultHasher`
+fn cache_key(pdf_file: &PdfFile) -> u64 {
+    let modified = pdf_file
+        .modified
+        .and_then(|modified| modified.timestamp_nanos_opt())
+        .unwrap_or(0);
+    let mut hasher = Fnv1a::default();
+    hasher.write(pdf_file.path.as_os_str().as_encoded_bytes().iter().copied());
+    hasher.write(pdf_file.size.to_le_bytes());
+    hasher.write(modified.to_le_bytes());
+    hasher.write(THUMBNAIL_WIDTH.to_le_bytes());
+    hasher.write(RENDERER_VERSION.to_le_bytes());
+    hasher.finish()
+}
+
+/// renders the first page of the pdf at `path`, `THUMBNAIL_WIDTH` pixels wide, with
+/// `pdftoppm` if it is installed, else with `draw_first_page`
+pub fn render_first_page(path: &Path) -> Result<Pixmap> {
+    match rasterize_first_page(path) {
+        Some(pixmap) => Ok(pixmap),
+        None => draw_first_page(path),
+    }
+}
+
+/// the first page rendered by `pdftoppm`, `None` if it is not installed or failed
+fn rasterize_first_page(path: &Path) -> Option<Pixmap> {
+    let width = THUMBNAIL_WIDTH.to_string();
+    // without an output root the page is written to stdout
+    let output = Command::new(PDFTOPPM)
+        .args(["-f", "1", "-l", "1", "-singlefile", "-png"])
+        .args(["-scale-to-x", &width, "-scale-to-y", "-1"])
+        .arg(path)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output();
+    let output = match output {
+        Ok(output) => output,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return None,
+        Err(err) => {
+            log::warn!("failed to run {}: {}", PDFTOPPM, err);
+            return None;
+        }
+    };
+    if !output.status.success() {
+        log::debug!("{} failed on {}", PDFTOPPM, path.display());
+        return None;
+    }
+    Pixmap::decode_png(&output.stdout)
+        .map_err(|err| log::warn!("{} wrote an invalid png: {}", PDFTOPPM, err))
+        .ok()
+}
+
+/// draws the first page of the pdf at `path`, `THUMBNAIL_WIDTH` pixels wide.
+///
+/// paths, images and clipping are drawn, text is drawn as bars where its words are
+fn draw_first_page(path: &Path) -> Result<Pixmap> {
+    pdf_text::catching_panics(path, || {
+        let doc = Document::load(path).map_err(|err| err.to_string())?;
+        if doc.is_encrypted() {
+            return Err("the pdf is encrypted".to_string());
+        }
+        let page_id = *doc
+            .get_pages()
+            .get(&1)
+            .ok_or_else(|| "the pdf has no pages".to_string())?;
+        render_page(&doc, page_id)
+    })
+}
+
+fn render_page(doc: &Document, page_id: ObjectId) -> std::result::Result<Pixmap, String> {
+    let page = doc.get_dictionary(page_id).map_err(|err| err.to_string())?;
+    let [x0, y0, x1, y1] = inherited(doc, page, b"CropBox")
+        .or_else(|| inherited(doc, page, b"MediaBox"))
+        .and_then(|rect| rect_of(doc, rect))
+        .unwrap_or(DEFAULT_MEDIA_BOX);
+    let rotate = inherited(doc, page, b"Rotate")
+        .and_then(|rotate| rotate.as_i64().ok())
+        .unwrap_or(0)
+        .rem_euclid(360);
+    let (width, height) = (x1 - x0, y1 - y0);
+    if width < 1.0 || height < 1.0 {
+        return Err("the page is empty".to_string());
+    }
+
+    // maps the page into pixels, with y downwards and the page rotated clockwise
+    let rotated = rotate == 90 || rotate == 270;
+    let scale = THUMBNAIL_WIDTH as f32 / if rotated { height } else { width };
+    let s = scale;
+    let device = match rotate {
+        90 => Transform::from_row(0.0, s, s, 0.0, -y0 * s, -x0 * s),
+        180 => Transform::from_row(-s, 0.0, 0.0, s, x1 * s, -y0 * s),
+        270 => Transform::from_row(0.0, -s, -s, 0.0, y1 * s, x1 * s),
+        _ => Transform::from_row(s, 0.0, 0.0, -s, -x0 * s, y1 * s),
+    };
+    let pixel_height = (if rotated { width } else { height } * scale).round() as u32;
+    let mut pixmap = Pixmap::new(THUMBNAIL_WIDTH, pixel_height.clamp(1, THUMBNAIL_WIDTH * 4))
+        .ok_or_else(|| "the page is too large".to_string())?;
+    pixmap.fill(Color::WHITE);
+
+    let resources = inherited(doc, page, b"Resources")
+        .and_then(|resources| deref(doc, resources).as_dict().ok())
+        .cloned()
+        .unwrap_or_default();
+    let content = doc
+        .get_page_content(page_id)
+        .map_err(|err| err.to_string())?;
+    let mut renderer = Renderer {
+        doc,
+        pixmap,
+        state: GraphicsState::new(device),
+        stack: Vec::new(),
+        path: PathBuilder::new(),
+        pending_clip: None,
+        fonts: HashMap::new(),
+    };
+    renderer.run(&content, &resources, 0);
+    Ok(renderer.pixmap)
+}
+
+/// a page attribute, looked up in the parents if the page does not set it
+fn inherited<'a>(doc: &'a Document, page: &'a Dictionary, key: &[u8]) -> Option<&'a Object> {
+    let mut node = page;
+    for _ in 0..32 {
+        if let Ok(value) = node.get(key) {
+            return Some(value);
+        }
+        node = node
+            .get(b"Parent")
+            .ok()
+            .and_then(|parent| deref(doc, parent).as_dict().ok())?;
+    }
+    None
+}
+
+fn deref<'a>(doc: &'a Document, object: &'a Object) -> &'a Object {
+    doc.dereference(object)
+        .map(|(_, object)| object)
+        .unwrap_or(&Object::Null)
+}
+
+fn number(object: &Object) -> Option<f32> {
+    object.as_float().ok()
+}
+
+fn rect_of(doc: &Document, object: &Object) -> Option<[f32; 4]> {
+    let values = deref(doc, object)
+        .as_array()
+        .ok()?
+        .iter()
+        .map(|value| number(deref(doc, value)))
+        .collect::<Option<Vec<_>>>()?;
+    match values[..] {
+        [a, b, c, d] => Some([a.min(c), b.min(d), a.max(c), b.max(d)]),
+        _ => None,
+    }
+}
+
+/// a pdf matrix `[a b c d e f]`
+fn matrix(operands: &[Object]) -> Option<Transform> {
+    let values = operands.iter().map(number).collect::<Option<Vec<_>>>()?;
+    match values[..] {
+        [a, b, c, d, e, f] => Some(Transform::from_row(a, b, c, d, e, f)),
+        _ => None,
+    }
+}
+
+/// a color from the components of the current color space, guessed from their number
+fn color(components: &[f32]) -> Color {
+    let channel = |value: f32| value.clamp(0.0, 1.0);
+    match *components {
+        [gray] => Color::from_rgba(channel(gray), channel(gray), channel(gray), 1.0),
+        [r, g, b] => Color::from_rgba(channel(r), channel(g), channel(b), 1.0),
+        [c, m, y, k] => Color::from_rgba(
+            (1.0 - channel(c)) * (1.0 - channel(k)),
+            (1.0 - channel(m)) * (1.0 - channel(k)),
+            (1.0 - channel(y)) * (1.0 - channel(k)),
+            1.0,
+        ),
+        // patterns and other spaces are drawn in gray
+        _ => Color::from_rgba(0.5, 0.5, 0.5, 1.0),
+    }
+    .unwrap_or(Color::BLACK)
+}
+
+#[derive(Clone)]
+struct TextState {
+    font: Option<Rc<FontMetrics>>,
+    size: f32,
+    char_spacing: f32,
+    word_spacing: f32,
+    /// `Tz` divided by 100
+    horizontal_scale: f32,
+    leading: f32,
+    rise: f32,
+    /// `Tr`, 3 and 7 draw nothing, e.g. the text over a scanned page
+    render_mode: i64,
+}
+
+#[derive(Clone)]
+struct GraphicsState {
+    /// maps user space into pixels
+    ctm: Transform,
+    fill: Color,
+    stroke: Color,
+    line_width: f32,
+    clip: Option<Rc<Mask>>,
+    text: TextState,
+}
+
+impl GraphicsState {
+    fn new(device: Transform) -> Self {
+        Self {
+            ctm: device,
+            fill: Color::BLACK,
+            stroke: Color::BLACK,
+            line_width: 1.0,
+            clip: None,
+            text: TextState {
+                font: None,
+                size: 0.0,
+                char_spacing: 0.0,
+                word_spacing: 0.0,
+                horizontal_scale: 1.0,
+                leading: 0.0,
+                rise: 0.0,
+                render_mode: 0,
+            },
+        }
+    }
+}
+
+/// The widths of the glyphs of a font, in thousandths of an em
+struct FontMetrics {
+    /// composite fonts use two byte codes
+    two_byte: bool,
+    first_char: u32,
+    widths: Vec<f32>,
+    /// widths of composite fonts by cid
+    cid_widths: HashMap<u32, f32>,
+    default_width: f32,
+    /// type 3 fonts are not in thousandths of an em, this converts their widths
+    scale: f32,
+}
+
+impl FontMetrics {
+    fn from_dict(doc: &Document, font: &Dictionary) -> Self {
+        let get = |key: &[u8]| font.get(key).ok().map(|value| deref(doc, value));
+        let subtype = get(b"Subtype").and_then(|s| s.as_name().ok());
+        let scale = match (subtype, get(b"FontMatrix").and_then(|m| m.as_array().ok())) {
+            (Some(b"Type3"), Some(m)) => m.first().and_then(number).unwrap_or(0.001) * 1000.0,
+            _ => 1.0,
+        };
+        let mut metrics = Self {
+            two_byte: subtype == Some(b"Type0"),
+            first_char: get(b"FirstChar")
+                .and_then(|first| first.as_i64().ok())
+                .unwrap_or(0) as u32,
+            widths: get(b"Widths")
+                .and_then(|widths| widths.as_array().ok())
+                .map(|widths| {
+                    widths
+                        .iter()
+                        .map(|width| number(deref(doc, width)).unwrap_or(0.0))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            cid_widths: HashMap::new(),
+            default_width: 500.0,
+            scale,
+        };
+        if metrics.two_byte {
+            let descendant = get(b"DescendantFonts")
+                .and_then(|fonts| fonts.as_array().ok())
+                .and_then(|fonts| fonts.first())
+                .and_then(|font| deref(doc, font).as_dict().ok());
+            if let Some(descendant) = descendant {
+                metrics.default_width = descendant
+                    .get(b"DW")
+                    .ok()
+                    .and_then(number)
+                    .unwrap_or(1000.0);
+                if let Ok(widths) = descendant.get(b"W") {
+                    metrics.cid_widths = cid_widths(doc, deref(doc, widths));
+                }
+            }
+        }
+        metrics
+    }
+
+    /// the width of the glyph of `code`, in thousandths of an em
+    fn width(&self, code: u32) -> f32 {
+        let width = if self.two_byte {
+            self.cid_widths.get(&code).copied()
+        } else {
+            code.checked_sub(self.first_char)
+                .and_then(|idx| self.widths.get(idx as usize).copied())
+        };
+        width.unwrap_or(self.default_width) * self.scale
+    }
+}
+
+/// the `/W` array of a composite font: `c [w1 w2 ...]` or `c_first c_last w`
+fn cid_widths(doc: &Document, widths: &Object) -> HashMap<u32, f32> {
+    let mut result = HashMap::new();
+    let items = match widths.as_array() {
+        Ok(items) => items,
+        Err(_) => return result,
+    };
+    let mut idx = 0;
+    while idx + 1 < items.len() {
+        let first = deref(doc, &items[idx]).as_i64().unwrap_or(0).max(0) as u32;
+        match deref(doc, &items[idx + 1]) {
+            Object::Array(list) => {
+                for (offset, width) in list.iter().enumerate() {
+                    if let Some(width) = number(deref(doc, width)) {
+                        result.insert(first + offset as u32, width);
+                    }
+                }
+                idx += 2;
+            }
+            last => {
+                let last = last.as_i64().unwrap_or(0).max(0) as u32;
+                let width = items.get(idx + 2).and_then(|w| number(deref(doc, w)));
+                if let Some(width) = width {
+                    for cid in first..=last.min(first + 0xffff) {
+                        result.insert(cid, width);
+                    }
+                }
+                idx += 3;
+            }
+        }
+    }
+    result
+}
+
+/// Draws the operations of a content stream into a pixmap
+struct Renderer<'a> {
+    doc: &'a Document,
+    pixmap: Pixmap,
+    state: GraphicsState,
+    stack: Vec<GraphicsState>,
+    path: PathBuilder,
+    /// `W` or `W*` intersects the clip with the path once it is painted
+    pending_clip: Option<FillRule>,
+    fonts: HashMap<ObjectId, Rc<FontMetrics>>,
+}
+
+impl Renderer<'_> {
+    fn run(&mut self, content: &[u8], resources: &Dictionary, depth: usize) {
+        let content = match Content::decode(content) {
+            Ok(content) => content,
+            Err(err) => {
+                log::debug!("failed to decode a content stream: {}", err);
+                return;
+            }
+        };
+        // text objects are not nested, the matrices are kept here
+        let mut text_matrix = Transform::identity();
+        let mut line_matrix = Transform::identity();
+
+        for operation in &content.operations {
+            let operands = &operation.operands;
+            let num = |idx: usize| operands.get(idx).and_then(number).unwrap_or(0.0);
+            match operation.operator.as_str() {
+                "q" => self.stack.push(self.state.clone()),
+                "Q" => {
+                    if let Some(state) = self.stack.pop() {
+                        self.state = state;
+                    }
+                }
+                "cm" => {
+                    if let Some(m) = matrix(operands) {
+                        self.state.ctm = self.state.ctm.pre_concat(m);
+                    }
+                }
+                "w" => self.state.line_width = num(0),
+                "g" | "rg" | "k" | "sc" | "scn" => {
+                    let components = operands.iter().filter_map(number).collect::<Vec<_>>();
+                    self.state.fill = color(&components);
+                }
+                "G" | "RG" | "K" | "SC" | "SCN" => {
+                    let components = operands.iter().filter_map(number).collect::<Vec<_>>();
+                    self.state.stroke = color(&components);
+                }
+                // a new color space starts black
+                "cs" => self.state.fill = Color::BLACK,
+                "CS" => self.state.stroke = Color::BLACK,
+
+                "m" => self.path.move_to(num(0), num(1)),
+                "l" => self.path.line_to(num(0), num(1)),
+                "c" => self
+                    .path
+                    .cubic_to(num(0), num(1), num(2), num(3), num(4), num(5)),
+                "v" => {
+                    if let Some(current) = self.path.last_point() {
+                        self.path
+                            .cubic_to(current.x, current.y, num(0), num(1), num(2), num(3));
+                    }
+                }
+                "y" => self
+                    .path
+                    .cubic_to(num(0), num(1), num(2), num(3), num(2), num(3)),
+                "h" => self.path.close(),
+                "re" => {
+                    let (x, y, w, h) = (num(0), num(1), num(2), num(3));
+                    self.path.move_to(x, y);
+                    self.path.line_to(x + w, y);
+                    self.path.line_to(x + w, y + h);
+                    self.path.line_to(x, y + h);
+                    self.path.close();
+                }
+                "W" => self.pending_clip = Some(FillRule::Winding),
+                "W*" => self.pending_clip = Some(FillRule::EvenOdd),
+                "f" | "F" => self.paint(Some(FillRule::Winding), false),
+                "f*" => self.paint(Some(FillRule::EvenOdd), false),
+                "S" => self.paint(None, true),
+                "s" => {
+                    self.path.close();
+                    self.paint(None, true);
+                }
+                "B" => self.paint(Some(FillRule::Winding), true),
+                "B*" => self.paint(Some(FillRule::EvenOdd), true),
+                "b" => {
+                    self.path.close();
+                    self.paint(Some(FillRule::Winding), true);
+                }
+                "b*" => {
+                    self.path.close();
+                    self.paint(Some(FillRule::EvenOdd), true);
+                }
+                "n" => self.paint(None, false),
+
+                "BT" => {
+                    text_matrix = Transform::identity();
+                    line_matrix = Transform::identity();
+                }
+                "Tf" => {
+                    self.state.text.size = num(1);
+                    self.state.text.font = operands
+                        .first()
+                        .and_then(|name| name.as_name().ok())
+                        .and_then(|name| self.font(resources, name));
+                }
+                "Tc" => self.state.text.char_spacing = num(0),
+                "Tw" => self.state.text.word_spacing = num(0),
+                "Tz" => self.state.text.horizontal_scale = num(0) / 100.0,
+                "TL" => self.state.text.leading = num(0),
+                "Ts" => self.state.text.rise = num(0),
+                "Tr" => self.state.text.render_mode = num(0) as i64,
+                "Td" | "TD" => {
+                    if operation.operator == "TD" {
+                        self.state.text.leading = -num(1);
+                    }
+                    line_matrix = line_matrix.pre_translate(num(0), num(1));
+                    text_matrix = line_matrix;
+                }
+                "Tm" => {
+                    if let Some(m) = matrix(operands) {
+                        line_matrix = m;
+                        text_matrix = m;
+                    }
+                }
+                "T*" => {
+                    line_matrix = line_matrix.pre_translate(0.0, -self.state.text.leading);
+                    text_matrix = line_matrix;
+                }
+                "Tj" | "'" | "\"" | "TJ" => {
+                    if operation.operator != "Tj" && operation.operator != "TJ" {
+                        line_matrix = line_matrix.pre_translate(0.0, -self.state.text.leading);
+                        text_matrix = line_matrix;
+                    }
+                    if operation.operator == "\"" {
+                        self.state.text.word_spacing = num(0);
+                        self.state.text.char_spacing = num(1);
+                    }
+                    let items = match operands.last() {
+                        Some(Object::Array(items)) if operation.operator == "TJ" => items.clone(),
+                        Some(string) => vec![string.clone()],
+                        None => Vec::new(),
+                    };
+                    self.show_text(&items, &mut text_matrix);
+                }
+
+                "Do" => {
+                    if let Some(name) = operands.first().and_then(|name| name.as_name().ok()) {
+                        self.draw_xobject(resources, name, depth);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn resource<'r>(
+        &'r self,
+        resources: &'r Dictionary,
+        kind: &[u8],
+        name: &[u8],
+    ) -> Option<(Option<ObjectId>, &'r Object)> {
+        let dict = deref(self.doc, resources.get(kind).ok()?).as_dict().ok()?;
+        let object = dict.get(name).ok()?;
+        self.doc.dereference(object).ok()
+    }
+
+    fn font(&mut self, resources: &Dictionary, name: &[u8]) -> Option<Rc<FontMetrics>> {
+        let (id, font) = self.resource(resources, b"Font", name)?;
+        let font = font.as_dict().ok()?;
+        if let Some(metrics) = id.and_then(|id| self.fonts.get(&id)) {
+            return Some(metrics.clone());
+        }
+        let metrics = Rc::new(FontMetrics::from_dict(self.doc, font));
+        if let Some(id) = id {
+            self.fonts.insert(id, metrics.clone());
+        }
+        Some(metrics)
+    }
+
+    /// paints the current path and applies a pending clip, then starts a new path
+    fn paint(&mut self, fill: Option<FillRule>, stroke: bool) {
+        let path = std::mem::replace(&mut self.path, PathBuilder::new()).finish();
+        let clip = self.pending_clip.take();
+        let path = match path {
+            Some(path) => path,
+            None => return,
+        };
+        let transform = self.state.ctm;
+        let mask = self.state.clip.clone();
+        if let Some(fill_rule) = fill {
+            let paint = solid(self.state.fill);
+            self.pixmap
+                .fill_path(&path, &paint, fill_rule, transform, mask.as_deref());
+        }
+        if stroke {
+            let paint = solid(self.state.stroke);
+            let stroke = Stroke {
+                width: self.state.line_width.max(0.0),
+                ..Stroke::default()
+            };
+            self.pixmap
+                .stroke_path(&path, &paint, &stroke, transform, mask.as_deref());
+        }
+        if let Some(fill_rule) = clip {
+            let mut mask = match &self.state.clip {
+                Some(mask) => (**mask).clone(),
+                None => match Mask::new(self.pixmap.width(), self.pixmap.height()) {
+                    Some(mut mask) => {
+                        mask.data_mut().fill(255);
+                        mask
+                    }
+                    None => return,
+                },
+            };
+            mask.intersect_path(&path, fill_rule, true, transform);
+            self.state.clip = Some(Rc::new(mask));
+        }
+    }
+
+    /// draws the words of the strings in `items` as bars and advances the text matrix
+    fn show_text(&mut self, items: &[Object], text_matrix: &mut Transform) {
+        let text = self.state.text.clone();
+        let font = match &text.font {
+            Some(font) => font.clone(),
+            None => return,
+        };
+        let visible = !matches!(text.render_mode, 3 | 7);
+        // the start of the current word, in text space
+        let mut word_start: Option<f32> = None;
+        let mut x = 0.0;
+
+        for item in items {
+            let bytes = match item {
+                Object::String(bytes, _) => bytes.as_slice(),
+                other => {
+                    // a number moves the next glyph left, in thousandths of an em
+                    let adjust = number(other).unwrap_or(0.0);
+                    if adjust < -WORD_GAP {
+                        self.draw_word(&mut word_start, x, text_matrix, visible);
+                    }
+                    x -= adjust / 1000.0 * text.size * text.horizontal_scale;
+                    continue;
+                }
+            };
+            let codes: Vec<u32> = if font.two_byte {
+                bytes
+                    .chunks(2)
+                    .map(|pair| pair.iter().fold(0, |code, b| (code << 8) | u32::from(*b)))
+                    .collect()
+            } else {
+                bytes.iter().map(|b| u32::from(*b)).collect()
+            };
+            for code in codes {
+                let space = !font.two_byte && code == 32;
+                if space {
+                    self.draw_word(&mut word_start, x, text_matrix, visible);
+                } else if word_start.is_none() {
+                    word_start = Some(x);
+                }
+                let mut advance = font.width(code) / 1000.0 * text.size + text.char_spacing;
+                if space {
+                    advance += text.word_spacing;
+                }
+                x += advance * text.horizontal_scale;
+            }
+        }
+        self.draw_word(&mut word_start, x, text_matrix, visible);
+        *text_matrix = text_matrix.pre_translate(x, 0.0);
+    }
+
+    /// draws the bar of a word from `start` to `end` in text space
+    fn draw_word(
+        &mut self,
+        start: &mut Option<f32>,
+        end: f32,
+        text_matrix: &Transform,
+        visible: bool,
+    ) {
+        let start = match start.take() {
+            Some(start) => start,
+            None => return,
+        };
+        let text = &self.state.text;
+        let (bottom, top) = (text.rise, text.rise + text.size * TEXT_HEIGHT);
+        let rect = match Rect::from_ltrb(
+            start.min(end),
+            bottom.min(top),
+            start.max(end),
+            bottom.max(top),
+        ) {
+            Some(rect) if visible => rect,
+            _ => return,
+        };
+        let mut color = self.state.fill;
+        color.set_alpha(TEXT_OPACITY);
+        let transform = self.state.ctm.pre_concat(*text_matrix);
+        let mask = self.state.clip.clone();
+        self.pixmap
+            .fill_rect(rect, &solid(color), transform, mask.as_deref());
+    }
+
+    fn draw_xobject(&mut self, resources: &Dictionary, name: &[u8], depth: usize) {
+        let stream = match self.resource(resources, b"XObject", name) {
+            Some((_, Object::Stream(stream))) => stream.clone(),
+            _ => return,
+        };
+        let subtype = stream.dict.get(b"Subtype").and_then(Object::as_name).ok();
+        match subtype {
+            Some(b"Image") => self.draw_image(&stream),
+            Some(b"Form") if depth < MAX_FORM_DEPTH => {
+                let content = match stream.get_plain_content() {
+                    Ok(content) => content,
+                    Err(_) => return,
+                };
+                let form_resources = stream
+                    .dict
+                    .get(b"Resources")
+                    .ok()
+                    .and_then(|r| deref(self.doc, r).as_dict().ok())
+                    .cloned()
+                    .unwrap_or_else(|| resources.clone());
+                self.stack.push(self.state.clone());
+                if let Some(m) = stream
+                    .dict
+                    .get(b"Matrix")
+                    .ok()
+                    .and_then(|m| m.as_array().ok())
+                {
+                    if let Some(m) = matrix(m) {
+                        self.state.ctm = self.state.ctm.pre_concat(m);
+                    }
+                }
+                self.run(&content, &form_resources, depth + 1);
+                if let Some(state) = self.stack.pop() {
+                    self.state = state;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// draws an image into the unit square of user space
+    fn draw_image(&mut self, stream: &lopdf::Stream) {
+        let dict = &stream.dict;
+        let width = dict.get(b"Width").and_then(Object::as_i64).unwrap_or(0);
+        let height = dict.get(b"Height").and_then(Object::as_i64).unwrap_or(0);
+        if width <= 0 || height <= 0 {
+            return;
+        }
+        let (width, height) = (width as u32, height as u32);
+        let image = if u64::from(width) * u64::from(height) > MAX_IMAGE_PIXELS {
+            None
+        } else {
+            decode_image(self.doc, stream, width, height, self.state.fill)
+        };
+        // images which cannot be decoded, e.g. JPEG 2000, are drawn as a gray box
+        let image = image.unwrap_or_else(|| {
+            let mut placeholder = Pixmap::new(1, 1).expect("1x1 pixmap");
+            placeholder.fill(Color::from_rgba8(200, 200, 200, 255));
+            placeholder
+        });
+
+        // image space has y downwards, the unit square is flipped
+        let transform = self.state.ctm.pre_concat(Transform::from_row(
+            1.0 / image.width() as f32,
+            0.0,
+            0.0,
+            -1.0 / image.height() as f32,
+            0.0,
+            1.0,
+        ));
+        let paint = PixmapPaint {
+            quality: FilterQuality::Bilinear,
+            ..PixmapPaint::default()
+        };
+        let mask = self.state.clip.clone();
+        self.pixmap
+            .draw_pixmap(0, 0, image.as_ref(), &paint, transform, mask.as_deref());
+    }
+}
+
+fn solid(color: Color) -> Paint<'static> {
+    let mut paint = Paint::default();
+    paint.set_color(color);
+    paint.anti_alias = true;
+    paint
+}
+
+/// decodes an image xobject into a pixmap, `None` for unsupported filters and color spaces
+fn decode_image(
+    doc: &Document,
+    stream: &lopdf::Stream,
+    width: u32,
+    height: u32,
+    fill: Color,
+) -> Option<Pixmap> {
+    let dict = &stream.dict;
+    let filters = stream.filters().unwrap_or_default();
+    let mut pixels = Vec::with_capacity((width * height * 4) as usize);
+
+    if filters.last() == Some(&&b"DCTDecode"[..]) {
+        if filters.len() > 1 {
+            return None;
+        }
+        let mut decoder = jpeg_decoder::Decoder::new(stream.content.as_slice());
+        let data = decoder.decode().ok()?;
+        let info = decoder.info()?;
+        let components = match info.pixel_format {
+            jpeg_decoder::PixelFormat::L8 => 1,
+            jpeg_decoder::PixelFormat::RGB24 => 3,
+            jpeg_decoder::PixelFormat::CMYK32 => 4,
+            jpeg_decoder::PixelFormat::L16 => return None,
+        };
+        for sample in data.chunks_exact(components) {
+            pixels.extend_from_slice(&rgba(sample, components, components == 4));
+        }
+        return pixmap_from(pixels, u32::from(info.width), u32::from(info.height));
+    }
+
+    let data = stream.get_plain_content().ok()?;
+    let image_mask = dict
+        .get(b"ImageMask")
+        .and_then(Object::as_bool)
+        .unwrap_or(false);
+    let bits = if image_mask {
+        1
+    } else {
+        dict.get(b"BitsPerComponent")
+            .and_then(Object::as_i64)
+            .unwrap_or(8)
+    };
+    if !matches!(bits, 1 | 2 | 4 | 8) {
+        return None;
+    }
+    let space = if image_mask {
+        ColorSpace::Gray
+    } else {
+        ColorSpace::of(doc, dict.get(b"ColorSpace").ok()?, 0)?
+    };
+    let components = space.components();
+    let row_len = (width as usize * components * bits as usize).div_ceil(8);
+    let max = ((1u32 << bits) - 1) as f32;
+
+    for row in data.chunks(row_len).take(height as usize) {
+        for x in 0..width as usize {
+            let mut sample = [0u8; 4];
+            for (c, value) in sample.iter_mut().enumerate().take(components) {
+                let raw = read_bits(row, (x * components + c) * bits as usize, bits as usize);
+                *value = if matches!(space, ColorSpace::Indexed(..)) {
+                    raw as u8
+                } else {
+                    (raw as f32 / max * 255.0).round() as u8
+                };
+            }
+            let pixel = if image_mask {
+                // samples of 0 are painted with the fill color
+                let paint = sample[0] == 0;
+                let (r, g, b) = to_rgb8(fill);
+                [r, g, b, if paint { 255 } else { 0 }]
+            } else {
+                match &space {
+                    ColorSpace::Indexed(base, lookup) => {
+                        let base_components = base.components();
+                        let start = sample[0] as usize * base_components;
+                        let entry = lookup.get(start..start + base_components)?;
+                        rgba(entry, base_components, false)
+                    }
+                    _ => rgba(&sample[..components], components, false),
+                }
+            };
+            pixels.extend_from_slice(&pixel);
+        }
+    }
+    pixels.resize((width * height * 4) as usize, 255);
+    pixmap_from(pixels, width, height)
+}
+
+fn read_bits(row: &[u8], offset: usize, bits: usize) -> u32 {
+    if bits == 8 {
+        return u32::from(row.get(offset / 8).copied().unwrap_or(0));
+    }
+    let byte = row.get(offset / 8).copied().unwrap_or(0);
+    let shift = 8 - bits - offset % 8;
+    u32::from(byte >> shift) & ((1 << bits) - 1)
+}
+
+/// `components` samples of a gray, rgb or cmyk pixel as opaque rgba
+fn rgba(sample: &[u8], components: usize, inverted_cmyk: bool) -> [u8; 4] {
+    match components {
+        1 => [sample[0], sample[0], sample[0], 255],
+        3 => [sample[0], sample[1], sample[2], 255],
+        4 => {
+            // Adobe's JPEG files store CMYK inverted
+            let value = |idx: usize| {
+                let v = u32::from(sample[idx]);
+                if inverted_cmyk {
+                    v
+                } else {
+                    255 - v
+                }
+            };
+            let k = value(3);
+            let channel = |idx: usize| (value(idx) * k / 255) as u8;
+            [channel(0), channel(1), channel(2), 255]
+        }
+        _ => [128, 128, 128, 255],
+    }
+}
+
+fn to_rgb8(color: Color) -> (u8, u8, u8) {
+    let color = color.to_color_u8();
+    (color.red(), color.green(), color.blue())
+}
+
+fn pixmap_from(pixels: Vec<u8>, width: u32, height: u32) -> Option<Pixmap> {
+    // the pixmap holds premultiplied colors
+    let mut pixels = pixels;
+    for pixel in pixels.chunks_exact_mut(4) {
+        let alpha = u32::from(pixel[3]);
+        for channel in &mut pixel[..3] {
+            *channel = (u32::from(*channel) * alpha / 255) as u8;
+        }
+    }
+    Pixmap::from_vec(pixels, tiny_skia::IntSize::from_wh(width, height)?)
+}
+
+/// The color spaces of images which can be drawn
+enum ColorSpace {
+    Gray,
+    Rgb,
+    Cmyk,
+    /// the base space and the lookup table
+    Indexed(Box<ColorSpace>, Vec<u8>),
+}
+
+impl ColorSpace {
+    /// the color space `object` names, `depth` being the indexed color spaces it is
+    /// nested in
+    fn of(doc: &Document, object: &Object, depth: usize) -> Option<Self> {
+        if depth > MAX_COLOR_SPACE_DEPTH {
+            return None;
+        }
+        let object = deref(doc, object);
+        if let Ok(name) = object.as_name() {
+            return match name {
+                b"DeviceGray" | b"CalGray" | b"G" => Some(Self::Gray),
+                b"DeviceRGB" | b"CalRGB" | b"RGB" => Some(Self::Rgb),
+                b"DeviceCMYK" | b"CMYK" => Some(Self::Cmyk),
+                _ => None,
+            };
+        }
+        let array = object.as_array().ok()?;
+        let family = array.first()?.as_name().ok()?;
+        match family {
+            b"ICCBased" => {
+                let profile = deref(doc, array.get(1)?).as_stream().ok()?;
+                match profile.dict.get(b"N").and_then(Object::as_i64).ok()? {
+                    1 => Some(Self::Gray),
+                    3 => Some(Self::Rgb),
+                    4 => Some(Self::Cmyk),
+                    _ => None,
+                }
+            }
+            b"Indexed" | b"I" => {
+                let base = Self::of(doc, array.get(1)?, depth + 1)?;
+                let lookup = match deref(doc, array.get(3)?) {
+                    Object::String(bytes, _) => bytes.clone(),
+                    Object::Stream(stream) => stream.get_plain_content().ok()?,
+                    _ => return None,
+                };
+                Some(Self::Indexed(Box::new(base), lookup))
+            }
+            b"CalRGB" => Some(Self::Rgb),
+            b"CalGray" => Some(Self::Gray),
+            _ => None,
+        }
+    }
+
+    fn components(&self) -> usize {
+        match self {
+            Self::Gray | Self::Indexed(..) => 1,
+            Self::Rgb => 3,
+            Self::Cmyk => 4,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{draw_first_page, ColorSpace, ThumbnailCache, STALE_TMP_AGE, THUMBNAIL_WIDTH};
+    use crate::domain::{pdf_file::PdfFile, pdf_info::PdfInfo};
+    use lopdf::{Document, Object};
+    use std::{
+        fs,
+        path::Path,
+        time::{Duration, SystemTime},
+    };
+
+    /// a page with a red square in the lower left quarter and a line of text at the top
+    fn write_pdf(path: &std::path::Path) {
+        let content = "1 0 0 rg 0 0 100 100 re f BT /F1 20 Tf 10 170 Td (Hello world) Tj ET";
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 /MediaBox [0 0 200 200] >>".to_string(),
+            "<< /Type /Page /Parent 2 0 R /Contents 4 0 R \
+             /Resources << /Font << /F1 5 0 R >> >> >>"
+                .to_string(),
+            format!(
+                "<< /Length {} >>\nstream\n{}\nendstream",
+                content.len(),
+                content
+            ),
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>".to_string(),
+        ];
+        let mut file = b"%PDF-1.4\n".to_vec();
+        let mut offsets = Vec::new();
+        for (idx, object) in objects.iter().enumerate() {
+            offsets.push(file.len());
+            file.extend(format!("{} 0 obj\n{}\nendobj\n", idx + 1, object).as_bytes());
+        }
+        let xref = file.len();
+        file.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes());
+        for offset in offsets {
+            file.extend(format!("{:010} 00000 n \n", offset).as_bytes());
+        }
+        file.extend(
+            format!(
+                "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+                objects.len() + 1,
+                xref
+            )
+            .as_bytes(),
+        );
+        fs::write(path, file).unwrap();
+    }
+
+    fn pdf_file(path: &Path) -> PdfFile {
+        PdfFile {
+            file_name: path.file_name().unwrap().to_string_lossy().to_string(),
+            path: path.to_path_buf(),
+            size: 1,
+            created: None,
+            modified: None,
+            info: PdfInfo::default(),
+        }
+    }
+
+    /// sets the modification time of `path` to `age` ago
+    fn age(path: &Path, age: Duration) {
+        fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(SystemTime::now() - age)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_render_and_cache_first_page() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.pdf");
+        write_pdf(&path);
+
+        let pixmap = draw_first_page(&path).unwrap();
+        assert_eq!(
+            (pixmap.width(), pixmap.height()),
+            (THUMBNAIL_WIDTH, THUMBNAIL_WIDTH)
+        );
+        let pixel = |x: u32, y: u32| pixmap.pixel(x, y).unwrap();
+        // the square, the page and the bar of "Hello"
+        assert_eq!(pixel(50, THUMBNAIL_WIDTH - 50).red(), 255);
+        assert_eq!(pixel(50, THUMBNAIL_WIDTH - 50).green(), 0);
+        assert_eq!(
+            pixel(THUMBNAIL_WIDTH - 10, THUMBNAIL_WIDTH - 10).green(),
+            255
+        );
+        // the baseline is 30 points from the top, the bar reaches 10 points above it
+        let text = pixel(40, THUMBNAIL_WIDTH * 25 / 200);
+        assert!(text.green() < 255 && text.green() > 0);
+
+        let pdf_file = pdf_file(&path);
+        let cache = ThumbnailCache::new(&dir.path().join("thumbnails"));
+        cache.load_or_render(&pdf_file).unwrap();
+        // the cached png is used once the pdf is gone
+        fs::remove_file(&path).unwrap();
+        let cached = cache.load_or_render(&pdf_file).unwrap();
+        assert_eq!(
+            cached.pixel(50, THUMBNAIL_WIDTH - 50),
+            pixmap.pixel(50, THUMBNAIL_WIDTH - 50)
+        );
+    }
+
+    #[test]
+    fn test_least_recently_shown_thumbnails_are_pruned() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = ["a.pdf", "b.pdf", "c.pdf"].map(|name| {
+            let path = dir.path().join(name);
+            write_pdf(&path);
+            pdf_file(&path)
+        });
+        let mut cache = ThumbnailCache::new(&dir.path().join("thumbnails"));
+        cache.limit = 2;
+
+        cache.load_or_render(&files[0]).unwrap();
+        cache.load_or_render(&files[1]).unwrap();
+        age(&cache.path_of(&files[0]), Duration::from_secs(20));
+        age(&cache.path_of(&files[1]), Duration::from_secs(10));
+        // showing a from the cache makes b the least recently shown
+        cache.load_or_render(&files[0]).unwrap();
+        cache.load_or_render(&files[2]).unwrap();
+
+        assert!(cache.path_of(&files[0]).exists());
+        assert!(!cache.path_of(&files[1]).exists());
+        assert!(cache.path_of(&files[2]).exists());
+    }
+
+    #[test]
+    fn test_stale_temporary_files_are_pruned() {
+        let dir = tempfile::tempdir().unwrap();
+        let thumbnails = dir.path().join("thumbnails");
+        fs::create_dir(&thumbnails).unwrap();
+        let stale = thumbnails.join("0000000000000001.png.tmp");
+        let fresh = thumbnails.join("0000000000000002.png.tmp");
+        fs::write(&stale, "").unwrap();
+        fs::write(&fresh, "").unwrap();
+        age(&stale, STALE_TMP_AGE * 2);
+
+        let path = dir.path().join("a.pdf");
+        write_pdf(&path);
+        ThumbnailCache::new(&thumbnails)
+            .load_or_render(&pdf_file(&path))
+            .unwrap();
+        assert!(!stale.exists());
+        // possibly written by another instance right now
+        assert!(fresh.exists());
+    }
+
+    #[test]
+    fn test_broken_file_is_not_cached() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.pdf");
+        fs::write(&path, "not a pdf").unwrap();
+        let cache = ThumbnailCache::new(&dir.path().join("thumbnails"));
+        assert!(cache.load_or_render(&pdf_file(&path)).is_err());
+        assert!(!cache.path_of(&pdf_file(&path)).exists());
+
+        let missing = dir.path().join("missing.pdf");
+        assert!(cache.load_or_render(&pdf_file(&missing)).is_err());
+    }
+
+    #[test]
+    fn test_indexed_color_space_nested_in_itself() {
+        let mut doc = Document::with_version("1.4");
+        let id = doc.new_object_id();
+        let indexed = vec![
+            Object::Name(b"Indexed".to_vec()),
+            Object::Reference(id),
+            Object::Integer(0),
+            Object::string_literal(vec![0]),
+        ];
+        doc.objects.insert(id, Object::Array(indexed));
+        assert!(ColorSpace::of(&doc, &Object::Reference(id), 0).is_none());
+    }
+}
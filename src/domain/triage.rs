@@ -0,0 +1,129 @@
+use std::{
+    cmp,
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
+
+/// What was done with a file of the queue
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Imported,
+    Trashed,
+    /// left where it is, and left out of the next sessions
+    Skipped,
+}
+
+/// How many files of the queue had each outcome
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Tally {
+    pub imported: usize,
+    pub trashed: usize,
+    pub skipped: usize,
+}
+
+/// The unmanaged files to go through one at a time, in order.
+///
+/// the files skipped in an earlier session are left out, unless only they are left so
+/// that they are gone through again
+#[derive(Debug, Clone, Default)]
+pub struct TriageQueue {
+    files: Vec<PathBuf>,
+    /// of the current file, `files.len()` once every file has been dealt with
+    position: usize,
+    tally: Tally,
+}
+
+impl TriageQueue {
+    pub fn new(files: Vec<PathBuf>, skipped: &BTreeSet<PathBuf>) -> Self {
+        let fresh = files
+            .iter()
+            .filter(|path| !skipped.contains(*path))
+            .cloned()
+            .collect::<Vec<_>>();
+        let files = match fresh.is_empty() {
+            true => files,
+            false => fresh,
+        };
+        Self {
+            files,
+            position: 0,
+            tally: Tally::default(),
+        }
+    }
+
+    /// the file to deal with, `None` once the queue is done
+    pub fn current(&self) -> Option<&Path> {
+        self.files.get(self.position).map(PathBuf::as_path)
+    }
+
+    /// moves on to the next file once the current one had `outcome`
+    pub fn advance(&mut self, outcome: Outcome) {
+        if self.current().is_none() {
+            return;
+        }
+        match outcome {
+            Outcome::Imported => self.tally.imported += 1,
+            Outcome::Trashed => self.tally.trashed += 1,
+            Outcome::Skipped => self.tally.skipped += 1,
+        }
+        self.position += 1;
+    }
+
+    /// the number of the current file counted from 1 and the number of files, e.g. `3/27`
+    pub fn progress(&self) -> (usize, usize) {
+        (
+            cmp::min(self.position + 1, self.files.len()),
+            self.files.len(),
+        )
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    pub fn tally(&self) -> Tally {
+        self.tally
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Outcome, Tally, TriageQueue};
+    use std::{collections::BTreeSet, path::PathBuf};
+
+    fn files() -> Vec<PathBuf> {
+        ["a.pdf", "b.pdf", "c.pdf"].map(PathBuf::from).to_vec()
+    }
+
+    #[test]
+    fn test_queue_leaves_out_skipped_files() {
+        let skipped = BTreeSet::from([PathBuf::from("b.pdf")]);
+        let mut queue = TriageQueue::new(files(), &skipped);
+        assert_eq!(queue.progress(), (1, 2));
+        queue.advance(Outcome::Imported);
+        assert_eq!(queue.current(), Some(PathBuf::from("c.pdf").as_path()));
+        queue.advance(Outcome::Trashed);
+        assert_eq!(queue.current(), None);
+        assert_eq!(queue.progress(), (2, 2));
+    }
+
+    #[test]
+    fn test_tally() {
+        let mut queue = TriageQueue::new(files(), &BTreeSet::new());
+        for outcome in [Outcome::Imported, Outcome::Skipped, Outcome::Trashed] {
+            queue.advance(outcome);
+        }
+        let tally = Tally {
+            imported: 1,
+            trashed: 1,
+            skipped: 1,
+        };
+        assert_eq!(queue.tally(), tally);
+    }
+
+    #[test]
+    fn test_only_skipped_files_are_gone_through_again() {
+        let skipped = files().into_iter().collect();
+        assert_eq!(TriageQueue::new(files(), &skipped).progress(), (1, 3));
+    }
+}
@@ -0,0 +1,221 @@
+use std::{fmt, io, path::PathBuf};
+
+/// Errors raised by pdfstore itself.
+///
+/// Recoverable errors are shown to the user and logged while the app keeps running,
+/// see `Error::is_recoverable`.
+#[derive(Debug)]
+pub enum Error {
+    /// a directory could not be listed
+    UnreadableDirectory {
+        path: PathBuf,
+        source: io::Error,
+    },
+    /// a file could not be read
+    UnreadableFile {
+        path: PathBuf,
+        source: io::Error,
+    },
+    /// a file or directory could not be written, created or renamed
+    UnwritableFile {
+        path: PathBuf,
+        source: io::Error,
+    },
+    /// a file could not be removed
+    UnremovableFile {
+        path: PathBuf,
+        source: io::Error,
+    },
+    /// the user has no permission to read or change a file or directory
+    PermissionDenied {
+        path: PathBuf,
+    },
+    /// a file with a pdf extension which is not a readable pdf
+    BrokenPdf {
+        path: PathBuf,
+        reason: String,
+    },
+    /// an import would overwrite an existing file
+    AlreadyExists {
+        path: PathBuf,
+    },
+    /// an import of a document which is already in the library under another name
+    Duplicate {
+        path: PathBuf,
+        /// the key of the document in the library
+        existing: String,
+        /// the identifier both documents share, e.g. `doi 10.1145/3292500.3330701`
+        identifier: String,
+    },
+    /// a file name given by the user which cannot be used
+    InvalidName {
+        name: String,
+        reason: String,
+    },
+    /// a metadata service could not be reached or sent a response which cannot be read
+    MetadataUnavailable {
+        url: String,
+        reason: String,
+    },
+    /// a search query which cannot be parsed
+    InvalidQuery {
+        /// of the mistake, counted in characters from 0
+        column: usize,
+        reason: String,
+    },
+    /// the viewer of the system could not be started for a file
+    ViewerUnavailable {
+        path: PathBuf,
+        source: io::Error,
+    },
+    /// a file could not be moved to the trash of the system
+    TrashFailed {
+        path: PathBuf,
+        reason: String,
+    },
+    /// the library store exists but cannot be parsed
+    CorruptLibrary {
+        path: PathBuf,
+        reason: String,
+    },
+    Io(io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    /// builds the error for a directory which could not be listed
+    pub fn reading_dir(path: impl Into<PathBuf>, source: io::Error) -> Self {
+        let path = path.into();
+        match source.kind() {
+            io::ErrorKind::PermissionDenied => Self::PermissionDenied { path },
+            _ => Self::UnreadableDirectory { path, source },
+        }
+    }
+
+    /// builds the error for a file which could not be read
+    pub fn reading_file(path: impl Into<PathBuf>, source: io::Error) -> Self {
+        let path = path.into();
+        match source.kind() {
+            io::ErrorKind::PermissionDenied => Self::PermissionDenied { path },
+            _ => Self::UnreadableFile { path, source },
+        }
+    }
+
+    /// builds the error for a file or directory which could not be written, created or
+    /// renamed
+    pub fn writing_file(path: impl Into<PathBuf>, source: io::Error) -> Self {
+        let path = path.into();
+        match source.kind() {
+            io::ErrorKind::PermissionDenied => Self::PermissionDenied { path },
+            _ => Self::UnwritableFile { path, source },
+        }
+    }
+
+    /// builds the error for a file which could not be removed
+    pub fn removing_file(path: impl Into<PathBuf>, source: io::Error) -> Self {
+        let path = path.into();
+        match source.kind() {
+            io::ErrorKind::PermissionDenied => Self::PermissionDenied { path },
+            _ => Self::UnremovableFile { path, source },
+        }
+    }
+
+    /// whether the app can keep working normally after this error
+    pub fn is_recoverable(&self) -> bool {
+        match self {
+            Self::UnreadableDirectory { .. }
+            | Self::UnreadableFile { .. }
+            | Self::UnwritableFile { .. }
+            | Self::UnremovableFile { .. }
+            | Self::PermissionDenied { .. }
+            | Self::BrokenPdf { .. }
+            | Self::AlreadyExists { .. }
+            | Self::Duplicate { .. }
+            | Self::InvalidName { .. }
+            | Self::InvalidQuery { .. }
+            | Self::MetadataUnavailable { .. }
+            | Self::ViewerUnavailable { .. }
+            | Self::TrashFailed { .. } => true,
+            Self::CorruptLibrary { .. } | Self::Io(_) => false,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnreadableDirectory { path, source } => {
+                write!(f, "cannot read directory {}: {}", path.display(), source)
+            }
+            Self::UnreadableFile { path, source } => {
+                write!(f, "cannot read file {}: {}", path.display(), source)
+            }
+            Self::UnwritableFile { path, source } => {
+                write!(f, "cannot write {}: {}", path.display(), source)
+            }
+            Self::UnremovableFile { path, source } => {
+                write!(f, "cannot remove {}: {}", path.display(), source)
+            }
+            Self::PermissionDenied { path } => {
+                write!(f, "permission denied: {}", path.display())
+            }
+            Self::BrokenPdf { path, reason } => {
+                write!(f, "broken pdf {}: {}", path.display(), reason)
+            }
+            Self::AlreadyExists { path } => {
+                write!(f, "{} already exists", path.display())
+            }
+            Self::Duplicate {
+                path,
+                existing,
+                identifier,
+            } => write!(
+                f,
+                "{} is already in the library as {} (same {})",
+                path.display(),
+                existing,
+                identifier
+            ),
+            Self::InvalidName { name, reason } => {
+                write!(f, "invalid file name \"{}\": {}", name, reason)
+            }
+            Self::InvalidQuery { column, reason } => {
+                write!(f, "invalid query at column {}: {}", column + 1, reason)
+            }
+            Self::MetadataUnavailable { url, reason } => {
+                write!(f, "cannot fetch metadata from {}: {}", url, reason)
+            }
+            Self::ViewerUnavailable { path, source } => {
+                write!(f, "cannot open {}: {}", path.display(), source)
+            }
+            Self::TrashFailed { path, reason } => {
+                write!(f, "cannot move {} to the trash: {}", path.display(), reason)
+            }
+            Self::CorruptLibrary { path, reason } => {
+                write!(f, "corrupt library {}: {}", path.display(), reason)
+            }
+            Self::Io(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::UnreadableDirectory { source, .. }
+            | Self::UnreadableFile { source, .. }
+            | Self::UnwritableFile { source, .. }
+            | Self::UnremovableFile { source, .. }
+            | Self::ViewerUnavailable { source, .. } => Some(source),
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
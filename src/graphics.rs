@@ -0,0 +1,431 @@
+use std::{
+    env,
+    io::{self, Write},
+    str::FromStr,
+};
+
+use base64::Engine;
+use serde::Deserialize;
+use tiny_skia::Pixmap;
+use tui::{buffer::Buffer, layout::Rect, style::Color, widgets::Widget};
+
+/// the size of a cell in pixels when the terminal does not tell it
+const DEFAULT_CELL_SIZE: (u16, u16) = (8, 16);
+/// the largest payload of a kitty graphics escape sequence
+const KITTY_CHUNK: usize = 4096;
+/// levels of each channel of the sixel palette, 6 * 6 * 6 colors
+const SIXEL_LEVELS: u32 = 6;
+
+/// How images are drawn in the terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphicsProtocol {
+    /// the best protocol the terminal is known to support, guessed from the variables it
+    /// sets, which may be wrong e.g. over ssh
+    Auto,
+    /// the kitty graphics protocol, also supported by WezTerm and Ghostty
+    Kitty,
+    Sixel,
+    /// two pixels per cell with the upper half block, works in any terminal with true color
+    #[default]
+    #[serde(rename = "halfblocks")]
+    HalfBlocks,
+    /// images are not drawn
+    Off,
+}
+
+impl GraphicsProtocol {
+    /// resolves `Auto` from the environment the terminal sets
+    pub fn resolve(self) -> Self {
+        if self != Self::Auto {
+            return self;
+        }
+        let var = |name: &str| env::var(name).unwrap_or_default().to_lowercase();
+        let (term, program) = (var("TERM"), var("TERM_PROGRAM"));
+        // escape sequences have to be wrapped to pass through multiplexers
+        if env::var_os("TMUX").is_some() || term.starts_with("screen") {
+            Self::HalfBlocks
+        } else if env::var_os("KITTY_WINDOW_ID").is_some()
+            || term.contains("kitty")
+            || term.contains("ghostty")
+            || program == "wezterm"
+            || program == "ghostty"
+        {
+            Self::Kitty
+        } else if term.contains("sixel")
+            || term.starts_with("foot")
+            || term.starts_with("mlterm")
+            || program == "iterm.app"
+        {
+            Self::Sixel
+        } else {
+            Self::HalfBlocks
+        }
+    }
+
+    /// whether images are drawn by the terminal over the cells rather than with characters
+    pub fn is_overlay(self) -> bool {
+        matches!(self, Self::Kitty | Self::Sixel)
+    }
+}
+
+impl FromStr for GraphicsProtocol {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            "kitty" => Ok(Self::Kitty),
+            "sixel" => Ok(Self::Sixel),
+            "halfblocks" => Ok(Self::HalfBlocks),
+            "off" => Ok(Self::Off),
+            _ => Err(format!(
+                "unknown graphics \"{}\", use auto, kitty, sixel, halfblocks or off",
+                s
+            )),
+        }
+    }
+}
+
+/// the size of a cell in pixels, from the terminal's window size
+#[cfg(unix)]
+pub fn cell_size() -> (u16, u16) {
+    let mut size = libc::winsize {
+        ws_row: 0,
+        ws_col: 0,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+    // SAFETY: TIOCGWINSZ only writes a `winsize` into the pointer
+    let ok = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) } == 0;
+    if !ok || size.ws_col == 0 || size.ws_row == 0 || size.ws_xpixel == 0 || size.ws_ypixel == 0 {
+        return DEFAULT_CELL_SIZE;
+    }
+    (size.ws_xpixel / size.ws_col, size.ws_ypixel / size.ws_row)
+}
+
+/// the size of a cell in pixels, the console does not tell it
+#[cfg(not(unix))]
+pub fn cell_size() -> (u16, u16) {
+    DEFAULT_CELL_SIZE
+}
+
+/// the largest area at the top of `area`, centered, which shows an image of
+/// `width` x `height` pixels unstretched on cells of `cell` pixels
+pub fn fit(width: u32, height: u32, area: Rect, cell: (u16, u16)) -> Rect {
+    if width == 0 || height == 0 || area.width == 0 || area.height == 0 {
+        return Rect::new(area.x, area.y, 0, 0);
+    }
+    let (cell_w, cell_h) = (f64::from(cell.0.max(1)), f64::from(cell.1.max(1)));
+    let scale = (f64::from(area.width) * cell_w / f64::from(width))
+        .min(f64::from(area.height) * cell_h / f64::from(height));
+    let cols = ((f64::from(width) * scale / cell_w).floor() as u16).clamp(1, area.width);
+    let rows = ((f64::from(height) * scale / cell_h).floor() as u16).clamp(1, area.height);
+    Rect::new(area.x + (area.width - cols) / 2, area.y, cols, rows)
+}
+
+/// `pixmap` scaled to `width` x `height`, each pixel the average of the pixels it covers
+pub fn resize(pixmap: &Pixmap, width: u32, height: u32) -> Option<Pixmap> {
+    let mut resized = Pixmap::new(width, height)?;
+    let (src_w, src_h) = (pixmap.width() as usize, pixmap.height() as usize);
+    let src = pixmap.data();
+    let dst = resized.data_mut();
+    for y in 0..height as usize {
+        let (y0, y1) = span(y, height as usize, src_h);
+        for x in 0..width as usize {
+            let (x0, x1) = span(x, width as usize, src_w);
+            let mut sum = [0u32; 4];
+            for sy in y0..y1 {
+                for sx in x0..x1 {
+                    let idx = (sy * src_w + sx) * 4;
+                    for (channel, total) in sum.iter_mut().enumerate() {
+                        *total += u32::from(src[idx + channel]);
+                    }
+                }
+            }
+            let count = ((y1 - y0) * (x1 - x0)) as u32;
+            let idx = (y * width as usize + x) * 4;
+            for (channel, total) in sum.iter().enumerate() {
+                dst[idx + channel] = (total / count) as u8;
+            }
+        }
+    }
+    Some(resized)
+}
+
+/// the source pixels `[start, end)` covered by the pixel `idx` of `len`, at least one
+fn span(idx: usize, len: usize, src_len: usize) -> (usize, usize) {
+    let start = idx * src_len / len;
+    let end = ((idx + 1) * src_len / len).max(start + 1).min(src_len);
+    (start.min(src_len - 1), end)
+}
+
+/// Draws an image with the upper half block, whose foreground is the upper pixel
+/// and background the lower one
+pub struct HalfBlocks<'a> {
+    pixmap: &'a Pixmap,
+}
+
+impl<'a> HalfBlocks<'a> {
+    pub fn new(pixmap: &'a Pixmap) -> Self {
+        Self { pixmap }
+    }
+}
+
+impl Widget for HalfBlocks<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        // fitted in rows of half cells, each pixel is half as high as a cell
+        let (cell_w, cell_h) = cell_size();
+        let halves = Rect::new(area.x, area.y, area.width, area.height.saturating_mul(2));
+        let area = fit(
+            self.pixmap.width(),
+            self.pixmap.height(),
+            halves,
+            (cell_w, cell_h / 2),
+        );
+        let rows = area.height.div_ceil(2);
+        let pixels = match resize(self.pixmap, u32::from(area.width), u32::from(area.height)) {
+            Some(pixels) => pixels,
+            None => return,
+        };
+        let color = |x: u16, y: u16| {
+            let pixel = pixels.pixel(u32::from(x), u32::from(y))?.demultiply();
+            Some(Color::Rgb(pixel.red(), pixel.green(), pixel.blue()))
+        };
+        for row in 0..rows {
+            for col in 0..area.width {
+                let cell = buf.get_mut(area.x + col, area.y + row);
+                let upper = color(col, row * 2).unwrap_or(Color::Reset);
+                let lower = color(col, row * 2 + 1).unwrap_or(Color::Reset);
+                cell.set_symbol("▀").set_fg(upper).set_bg(lower);
+            }
+        }
+    }
+}
+
+/// An image drawn by the terminal over some cells, and which one it is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Placement {
+    pub area: Rect,
+    /// changes whenever the image does
+    pub image: u64,
+}
+
+/// Shows at most one image over the cells with the kitty or sixel protocol.
+///
+/// tui does not know about the image, so it is only written when it changes. A sixel
+/// image is part of the screen's content and stays until the cells under it are drawn again
+pub struct ImageOverlay {
+    protocol: GraphicsProtocol,
+    shown: Option<Placement>,
+    /// the screen was cleared, the image has to be written again
+    stale: bool,
+}
+
+impl ImageOverlay {
+    pub fn new(protocol: GraphicsProtocol) -> Self {
+        Self {
+            protocol,
+            shown: None,
+            stale: false,
+        }
+    }
+
+    /// whether the screen has to be cleared and drawn again before showing `next`,
+    /// to remove the image which is shown
+    pub fn needs_clear(&self, next: Option<Placement>) -> bool {
+        self.protocol == GraphicsProtocol::Sixel && self.shown.is_some() && self.shown != next
+    }
+
+    /// writes the image again on the next `show`, e.g. after the screen was resized
+    pub fn invalidate(&mut self) {
+        self.stale = true;
+    }
+
+    /// shows `next` instead of the shown image, if it is another image or at another place
+    pub fn show(
+        &mut self,
+        out: &mut impl Write,
+        next: Option<(Placement, &Pixmap)>,
+    ) -> io::Result<()> {
+        let placement = next.as_ref().map(|(placement, _)| *placement);
+        if placement == self.shown && !self.stale {
+            return Ok(());
+        }
+        self.stale = false;
+        if self.protocol == GraphicsProtocol::Kitty && self.shown.is_some() {
+            // deletes every placement and frees the image data
+            write!(out, "\x1b_Ga=d,d=A,q=2\x1b\\")?;
+        }
+        self.shown = placement;
+        let (placement, pixmap) = match next {
+            Some(next) => next,
+            None => return out.flush(),
+        };
+        let area = placement.area;
+        // saves the cursor and moves it to the top left cell, which is 1-based
+        write!(out, "\x1b7\x1b[{};{}H", area.y + 1, area.x + 1)?;
+        match self.protocol {
+            GraphicsProtocol::Kitty => write_kitty(out, pixmap, area)?,
+            GraphicsProtocol::Sixel => {
+                let (cell_w, cell_h) = cell_size();
+                let width = u32::from(area.width) * u32::from(cell_w);
+                // a sixel band is 6 pixels high, the last band must not reach the next row
+                let height = u32::from(area.height) * u32::from(cell_h) / 6 * 6;
+                if let Some(pixmap) = resize(pixmap, width, height) {
+                    write_sixel(out, &pixmap)?;
+                }
+            }
+            _ => {}
+        }
+        write!(out, "\x1b8")?;
+        out.flush()
+    }
+}
+
+/// transmits `pixmap` as a png and places it over `area`, scaled by the terminal
+fn write_kitty(out: &mut impl Write, pixmap: &Pixmap, area: Rect) -> io::Result<()> {
+    let png = pixmap.encode_png().map_err(io::Error::other)?;
+    let encoded = base64::engine::general_purpose::STANDARD.encode(png);
+    let chunks = encoded.as_bytes().chunks(KITTY_CHUNK).collect::<Vec<_>>();
+    for (idx, chunk) in chunks.iter().enumerate() {
+        let more = u8::from(idx + 1 < chunks.len());
+        if idx == 0 {
+            // q=2 silences the responses, C=1 keeps the cursor where it is
+            write!(
+                out,
+                "\x1b_Ga=T,f=100,t=d,q=2,C=1,c={},r={},m={};",
+                area.width, area.height, more
+            )?;
+        } else {
+            write!(out, "\x1b_Gm={};", more)?;
+        }
+        out.write_all(chunk)?;
+        write!(out, "\x1b\\")?;
+    }
+    Ok(())
+}
+
+/// writes `pixmap` as a sixel image in a palette of 216 colors
+fn write_sixel(out: &mut impl Write, pixmap: &Pixmap) -> io::Result<()> {
+    let (width, height) = (pixmap.width() as usize, pixmap.height() as usize);
+    let indices = pixmap
+        .pixels()
+        .iter()
+        .map(|pixel| {
+            let pixel = pixel.demultiply();
+            let level = |channel: u8| (u32::from(channel) * (SIXEL_LEVELS - 1) + 127) / 255;
+            (level(pixel.red()) * SIXEL_LEVELS + level(pixel.green())) * SIXEL_LEVELS
+                + level(pixel.blue())
+        })
+        .collect::<Vec<_>>();
+
+    // 0;1 leaves the pixels without color as they are, the raster attributes set a square aspect
+    let mut sixel = format!("\x1bP0;1;0q\"1;1;{};{}", width, height);
+    let colors = SIXEL_LEVELS.pow(3);
+    for color in 0..colors {
+        let percent = |level: u32| level * 100 / (SIXEL_LEVELS - 1);
+        let (r, g, b) = (
+            color / (SIXEL_LEVELS * SIXEL_LEVELS),
+            color / SIXEL_LEVELS % SIXEL_LEVELS,
+            color % SIXEL_LEVELS,
+        );
+        sixel.push_str(&format!(
+            "#{};2;{};{};{}",
+            color,
+            percent(r),
+            percent(g),
+            percent(b)
+        ));
+    }
+
+    let mut bits = vec![0u8; width * colors as usize];
+    let mut used = vec![false; colors as usize];
+    for band in (0..height).step_by(6) {
+        bits.iter_mut().for_each(|bit| *bit = 0);
+        used.iter_mut().for_each(|used| *used = false);
+        for row in band..(band + 6).min(height) {
+            for x in 0..width {
+                let color = indices[row * width + x] as usize;
+                bits[color * width + x] |= 1 << (row - band);
+                used[color] = true;
+            }
+        }
+        for color in (0..colors as usize).filter(|color| used[*color]) {
+            sixel.push_str(&format!("#{}", color));
+            let row = &bits[color * width..(color + 1) * width];
+            let mut x = 0;
+            while x < width {
+                let run = row[x..].iter().take_while(|bit| **bit == row[x]).count();
+                let c = char::from(0x3f + row[x]);
+                if run > 3 {
+                    sixel.push_str(&format!("!{}{}", run, c));
+                } else {
+                    (0..run).for_each(|_| sixel.push(c));
+                }
+                x += run;
+            }
+            // back to the start of the band for the next color
+            sixel.push('$');
+        }
+        sixel.push('-');
+    }
+    sixel.push_str("\x1b\\");
+    out.write_all(sixel.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fit, resize, write_sixel, GraphicsProtocol};
+    use tiny_skia::{Color, ColorU8, Pixmap};
+    use tui::layout::Rect;
+
+    #[test]
+    fn test_fit_keeps_the_aspect_ratio() {
+        // a portrait page on cells twice as high as wide
+        let area = Rect::new(10, 5, 40, 20);
+        assert_eq!(fit(100, 200, area, (8, 16)), Rect::new(20, 5, 20, 20));
+        // a landscape page is limited by the width
+        assert_eq!(fit(400, 100, area, (8, 16)), Rect::new(10, 5, 40, 5));
+    }
+
+    #[test]
+    fn test_fit_degenerate_sizes() {
+        let area = Rect::new(10, 5, 40, 20);
+        assert_eq!(fit(0, 200, area, (8, 16)), Rect::new(10, 5, 0, 0));
+        assert_eq!(
+            fit(100, 200, Rect::new(10, 5, 0, 20), (8, 16)),
+            Rect::new(10, 5, 0, 0)
+        );
+        // an unknown cell size of 0 pixels is taken as 1, a sliver keeps one cell
+        assert_eq!(fit(1, 10_000, area, (0, 0)), Rect::new(29, 5, 1, 20));
+    }
+
+    #[test]
+    fn test_resize_averages_pixels() {
+        let mut pixmap = Pixmap::new(2, 1).unwrap();
+        pixmap.fill(Color::BLACK);
+        pixmap.pixels_mut()[1] = ColorU8::from_rgba(255, 255, 255, 255).premultiply();
+        let resized = resize(&pixmap, 1, 1).unwrap();
+        assert_eq!(resized.pixel(0, 0).unwrap().red(), 127);
+        assert!(resize(&pixmap, 0, 1).is_none());
+    }
+
+    #[test]
+    fn test_parse_protocol() {
+        assert_eq!("sixel".parse(), Ok(GraphicsProtocol::Sixel));
+        assert!("png".parse::<GraphicsProtocol>().is_err());
+    }
+
+    #[test]
+    fn test_sixel_bands_and_runs() {
+        let mut pixmap = Pixmap::new(8, 7).unwrap();
+        pixmap.fill(Color::WHITE);
+        let mut out = Vec::new();
+        write_sixel(&mut out, &pixmap).unwrap();
+        let sixel = String::from_utf8(out).unwrap();
+        assert!(sixel.starts_with("\x1bP0;1;0q\"1;1;8;7"));
+        // white is the last color, the second band has a single row
+        assert!(sixel.ends_with("#215!8~$-#215!8@$-\x1b\\"));
+    }
+}
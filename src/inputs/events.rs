@@ -1,6 +1,6 @@
 use std::sync::mpsc::{channel, Receiver, RecvError, Sender};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use super::key::Key;
 use super::InputEvent;
@@ -20,14 +20,28 @@ impl Events {
 
         let event_tx = tx.clone();
         thread::spawn(move || {
+            let mut last_tick = Instant::now();
             loop {
-                // poll for tick rate duration, if no event, sent tick event.
-                if crossterm::event::poll(tick_rate).unwrap() {
-                    if let crossterm::event::Event::Key(key) = crossterm::event::read().unwrap() {
-                        let key = Key::from(key);
-                        event_tx.send(InputEvent::Input(key)).unwrap();
+                // poll until the next tick is due, if no event, sent tick event.
+                let timeout = tick_rate
+                    .checked_sub(last_tick.elapsed())
+                    .unwrap_or(Duration::ZERO);
+                if crossterm::event::poll(timeout).unwrap() {
+                    match crossterm::event::read().unwrap() {
+                        crossterm::event::Event::Key(key) => {
+                            let key = Key::from(key);
+                            event_tx.send(InputEvent::Input(key)).unwrap();
+                        }
+                        crossterm::event::Event::Resize(width, height) => {
+                            event_tx.send(InputEvent::Resize(width, height)).unwrap();
+                        }
+                        crossterm::event::Event::Mouse(_) => {}
                     }
                 }
+                if last_tick.elapsed() >= tick_rate {
+                    event_tx.send(InputEvent::Tick).unwrap();
+                    last_tick = Instant::now();
+                }
             }
         });
 
@@ -69,7 +69,7 @@ pub enum Key {
 }
 
 impl Key {
-    /// If exit
+    // If exit
     // pub fn is_exit(&self) -> bool {
     //     matches!(self, Key::Ctrl('c') | Key::Char('q') | Key::Esc)
     // }
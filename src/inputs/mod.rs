@@ -5,4 +5,8 @@ pub mod key;
 
 pub enum InputEvent {
     Input(Key),
+    /// The terminal has been resized to (columns, rows)
+    Resize(u16, u16),
+    /// Emitted every tick rate, used by time-based UI
+    Tick,
 }
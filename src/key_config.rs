@@ -12,6 +12,59 @@ pub struct KeyConfig {
     pub scroll_down: Key,
     //
     pub exit_popup: Key,
+    pub open_help: Key,
+    pub search: Key,
+    pub sort: Key,
+    pub import: Key,
+    pub import_all: Key,
+    pub export: Key,
+    pub edit_citation: Key,
+    pub fetch_metadata: Key,
+    pub export_bibliography: Key,
+    pub import_bib: Key,
+    // reading
+    pub cycle_status: Key,
+    pub next_read_page: Key,
+    pub previous_read_page: Key,
+    /// the key of each rating, the first one clears it
+    pub rate: [Key; 6],
+    pub filter_status: Key,
+    // collections
+    pub add_to_collection: Key,
+    pub new_collection: Key,
+    pub delete_collection: Key,
+    // saved searches
+    pub save_search: Key,
+    pub delete_search: Key,
+    // marks and bulk operations
+    pub toggle_mark: Key,
+    pub visual_mark: Key,
+    pub mark_all: Key,
+    pub invert_marks: Key,
+    pub delete_files: Key,
+    pub tag_files: Key,
+    pub move_files: Key,
+    // folders of the managed list
+    pub expand_folder: Key,
+    pub collapse_folder: Key,
+    pub new_folder: Key,
+    pub rename_folder: Key,
+    pub organize: Key,
+    // opening and the recent lists
+    pub open_pdf: Key,
+    pub recently_added: Key,
+    pub recently_opened: Key,
+    // triage of the unmanaged files
+    pub triage: Key,
+    pub skip_file: Key,
+    // detail pane
+    pub toggle_preview: Key,
+    pub next_page: Key,
+    pub previous_page: Key,
+    // log pane
+    pub toggle_log: Key,
+    pub log_level_up: Key,
+    pub log_level_down: Key,
     //
     pub enter: Key,
     pub exit: Key,
@@ -28,6 +81,49 @@ impl Default for KeyConfig {
             scroll_up: Key::Char('k'),
             scroll_down: Key::Char('j'),
             exit_popup: Key::Esc,
+            open_help: Key::Char('?'),
+            search: Key::Char('/'),
+            sort: Key::Char('s'),
+            import: Key::Char('i'),
+            import_all: Key::Char('I'),
+            export: Key::Char('e'),
+            edit_citation: Key::Char('c'),
+            fetch_metadata: Key::Char('m'),
+            export_bibliography: Key::Char('B'),
+            import_bib: Key::Char('b'),
+            cycle_status: Key::Char('r'),
+            next_read_page: Key::Char(']'),
+            previous_read_page: Key::Char('['),
+            rate: ['0', '1', '2', '3', '4', '5'].map(Key::Char),
+            filter_status: Key::Char('f'),
+            add_to_collection: Key::Char('a'),
+            new_collection: Key::Char('n'),
+            delete_collection: Key::Char('D'),
+            save_search: Key::Char('n'),
+            delete_search: Key::Char('D'),
+            toggle_mark: Key::Char(' '),
+            visual_mark: Key::Char('v'),
+            mark_all: Key::Ctrl('a'),
+            invert_marks: Key::Char('*'),
+            delete_files: Key::Char('d'),
+            tag_files: Key::Char('t'),
+            move_files: Key::Char('M'),
+            expand_folder: Key::Char('l'),
+            collapse_folder: Key::Char('h'),
+            new_folder: Key::Char('n'),
+            rename_folder: Key::Char('R'),
+            organize: Key::Char('O'),
+            open_pdf: Key::Char('o'),
+            recently_added: Key::Char('N'),
+            recently_opened: Key::Char('H'),
+            triage: Key::Char('T'),
+            skip_file: Key::Char('s'),
+            toggle_preview: Key::Char('p'),
+            next_page: Key::PageDown,
+            previous_page: Key::PageUp,
+            toggle_log: Key::Char('L'),
+            log_level_up: Key::Char('+'),
+            log_level_down: Key::Char('-'),
             enter: Key::Enter,
             exit: Key::Ctrl('c'),
             quit: Key::Char('q'),
@@ -0,0 +1,35 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::Context;
+
+use crate::config::{state_dir, LogConfig};
+
+/// initializes the logger shown in the log pane.
+///
+/// returns the path of the log file if the log is also written to a file
+pub fn init(config: &LogConfig) -> anyhow::Result<Option<PathBuf>> {
+    tui_logger::init_logger(config.level).context("failed to initialize the logger")?;
+    tui_logger::set_default_level(config.level);
+
+    if !config.file {
+        return Ok(None);
+    }
+    let dir = state_dir().context("no state directory to write the log file to")?;
+    fs::create_dir_all(&dir)
+        .with_context(|| format!("failed to create directory {}", dir.display()))?;
+    let path = dir.join("pdfstore.log");
+    let file_name = path
+        .to_str()
+        .context("log file path is not valid unicode")?;
+    tui_logger::set_log_file(file_name)
+        .with_context(|| format!("failed to open log file {}", path.display()))?;
+    Ok(Some(path))
+}
+
+/// moves the buffered records to the log pane and the log file.
+///
+/// tui-logger only does it when the pane is drawn, so this keeps the file up to date
+/// while the pane is hidden
+pub fn flush() {
+    tui_logger::move_events();
+}
@@ -1,29 +1,60 @@
+use clap::Parser;
 use inputs::events::Events;
 use inputs::InputEvent;
-use std::cell::RefCell;
-use std::io::stdout;
-use std::rc::Rc;
+use std::io::{stdout, Stdout};
 use std::time::Duration;
 use tui::backend::CrosstermBackend;
 use tui::Terminal;
 
 pub mod actions;
 pub mod app;
+pub mod cli;
 pub mod components;
+pub mod config;
 pub mod domain;
+pub mod error;
+pub mod graphics;
 pub mod inputs;
 pub mod key_config;
+pub mod logging;
+pub mod opener;
 pub mod state;
 pub mod version;
+pub mod watcher;
+pub mod worker;
 
 use crate::app::App;
+use crate::cli::Cli;
+use crate::config::Config;
+use crate::graphics::ImageOverlay;
 use crate::key_config::KeyConfig;
+use crate::version::Version;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    // TODO: How to use Rc & RefCell
+    let cli = Cli::parse();
+    if cli.version {
+        println!("pdfstore {}", Version::new());
+        return Ok(());
+    }
+
+    let mut config = Config::load()?;
+    if let Some(log_file) = logging::init(&config.log)? {
+        log::info!("writing log to {}", log_file.display());
+    }
+    domain::pdf_text::install_panic_hook();
+
+    // headless commands never touch the terminal
+    if let Some(command) = cli.command {
+        let code = cli::run(command, cli.json, &config);
+        logging::flush();
+        std::process::exit(code);
+    }
+
+    config.preview.graphics = config.preview.graphics.resolve();
+    let mut overlay = ImageOverlay::new(config.preview.graphics);
     let key_config: KeyConfig = Default::default();
-    let app = Rc::new(RefCell::new(App::new(key_config)));
+    let mut app = App::new(config, key_config);
 
     // Configure Crossterm backend for tui
     let stdout = stdout();
@@ -37,26 +68,47 @@ async fn main() -> anyhow::Result<()> {
     let tick_rate = Duration::from_millis(200);
     let events = Events::new(tick_rate);
 
-    let mut app = app.borrow_mut();
     app.update_inbox_list().await?;
 
     loop {
-        terminal.draw(|f| {
-            if let Err(err) = app.draw(f) {
-                log::error!("failed to draw: {:?}", err);
-                std::process::exit(1);
+        draw(&mut terminal, &mut app)?;
+        let placement = app.placed_image().map(|(placement, _)| placement);
+        if overlay.needs_clear(placement) {
+            terminal.clear()?;
+            draw(&mut terminal, &mut app)?;
+        }
+        if let Err(err) = overlay.show(terminal.backend_mut(), app.placed_image()) {
+            log::warn!("failed to draw the page: {}", err);
+        }
+
+        let event = match events.next() {
+            Ok(event) => event,
+            Err(err) => {
+                // the input thread is gone, nothing can be handled anymore
+                log::error!("failed to receive events: {}", err);
+                break;
             }
-        })?;
+        };
 
-        match events.next()? {
+        match event {
             InputEvent::Input(key) => match app.event(key).await {
                 Ok(state) => {
                     if !state.is_consumed() {
                         break;
                     }
                 }
-                Err(err) => break,
+                Err(err) => app.show_error(err),
             },
+            // tui resizes its buffers on the next draw, so only the layout dependent state is updated here
+            InputEvent::Resize(width, height) => {
+                app.resize(width, height);
+                overlay.invalidate();
+            }
+            InputEvent::Tick => {
+                if let Err(err) = app.on_tick().await {
+                    app.show_error(err);
+                }
+            }
         }
 
         if app.is_quit() {
@@ -65,9 +117,20 @@ async fn main() -> anyhow::Result<()> {
     }
 
     // Restore the terminal and close application
+    overlay.show(terminal.backend_mut(), None)?;
     terminal.clear()?;
     terminal.show_cursor()?;
     crossterm::terminal::disable_raw_mode()?;
 
     Ok(())
 }
+
+/// draws the app, an error of a component is shown in the error popup
+fn draw(terminal: &mut Terminal<CrosstermBackend<Stdout>>, app: &mut App) -> std::io::Result<()> {
+    let mut draw_result = Ok(());
+    terminal.draw(|f| draw_result = app.draw(f))?;
+    if let Err(err) = draw_result {
+        app.show_error(err.context("failed to draw"));
+    }
+    Ok(())
+}
@@ -0,0 +1,36 @@
+use std::{
+    path::Path,
+    process::{Command, Stdio},
+    thread,
+};
+
+use crate::error::{Error, Result};
+
+/// the command, and its leading arguments, which opens a file in the viewer the user chose for
+/// it. `start` is a builtin of `cmd`, and its first quoted argument is the window title
+#[cfg(target_os = "macos")]
+const OPEN_COMMAND: &[&str] = &["open"];
+#[cfg(windows)]
+const OPEN_COMMAND: &[&str] = &["cmd", "/C", "start", ""];
+#[cfg(not(any(target_os = "macos", windows)))]
+const OPEN_COMMAND: &[&str] = &["xdg-open"];
+
+/// opens the pdf at `path` in the viewer of the system, without waiting for it to close.
+///
+/// the viewer does not write to the terminal, which pdfstore draws on
+pub fn open(path: &Path) -> Result<()> {
+    let mut child = Command::new(OPEN_COMMAND[0])
+        .args(&OPEN_COMMAND[1..])
+        .arg(path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|source| Error::ViewerUnavailable {
+            path: path.to_path_buf(),
+            source,
+        })?;
+    // reaped once it exits so that it does not linger as a zombie
+    thread::spawn(move || child.wait());
+    Ok(())
+}
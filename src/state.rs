@@ -1,7 +1,8 @@
 use std::time::Duration;
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub enum AppState {
+    #[default]
     Init,
     Initialized {
         duration: Duration,
@@ -78,9 +79,3 @@ impl AppState {
         }
     }
 }
-
-impl Default for AppState {
-    fn default() -> Self {
-        Self::Init
-    }
-}
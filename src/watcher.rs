@@ -0,0 +1,94 @@
+use std::{
+    path::Path,
+    sync::mpsc::{self, Receiver},
+    time::{Duration, Instant},
+};
+
+use notify::{event::ModifyKind, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+/// how long the files have to stay unchanged before the lists are reloaded, so that a
+/// copy or a move of many files is reloaded once
+const SETTLE_TIME: Duration = Duration::from_secs(1);
+
+/// Watches the library and the unmanaged directory for pdfs added, renamed or removed
+/// outside pdfstore, e.g. in a file manager.
+///
+/// `changed` tells whether the lists should be reloaded, usually on every tick
+pub struct DirWatcher {
+    /// stops watching when dropped, `None` if it could not be started
+    _watcher: Option<RecommendedWatcher>,
+    events: Receiver<()>,
+    last_change: Option<Instant>,
+}
+
+impl DirWatcher {
+    /// watches the library with its folders and the unmanaged directory, a directory which
+    /// cannot be watched is only logged
+    pub fn new(managed_dir: &Path, unmanaged_dir: &Path) -> Self {
+        let (tx, events) = mpsc::channel();
+        let managed = managed_dir.to_path_buf();
+        let handler = move |event: notify::Result<Event>| match event {
+            Ok(event) if is_relevant(&event, &managed) => {
+                let _ = tx.send(());
+            }
+            Ok(_) => {}
+            Err(err) => log::debug!("watch error: {}", err),
+        };
+        let watcher = notify::recommended_watcher(handler).and_then(|mut watcher| {
+            watcher.watch(managed_dir, RecursiveMode::Recursive)?;
+            if !unmanaged_dir.starts_with(managed_dir) {
+                watcher.watch(unmanaged_dir, RecursiveMode::NonRecursive)?;
+            }
+            Ok(watcher)
+        });
+        let watcher = match watcher {
+            Ok(watcher) => Some(watcher),
+            Err(err) => {
+                log::warn!("the directories are not watched for changes: {}", err);
+                None
+            }
+        };
+        Self {
+            _watcher: watcher,
+            events,
+            last_change: None,
+        }
+    }
+
+    /// whether pdfs changed since the last reload and have settled since
+    pub fn changed(&mut self) -> bool {
+        if self.events.try_iter().count() > 0 {
+            self.last_change = Some(Instant::now());
+        }
+        match self.last_change {
+            Some(last_change) if last_change.elapsed() >= SETTLE_TIME => {
+                self.last_change = None;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// whether `event` adds, renames or removes a pdf or a folder of the library, the files
+/// read for the previews and the library store written next to them are left out
+fn is_relevant(event: &Event, managed_dir: &Path) -> bool {
+    let kind = matches!(
+        event.kind,
+        EventKind::Create(_) | EventKind::Remove(_) | EventKind::Modify(ModifyKind::Name(_))
+    );
+    kind && event
+        .paths
+        .iter()
+        .any(|path| is_pdf(path) || (path.starts_with(managed_dir) && is_folder(path)))
+}
+
+fn is_pdf(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("pdf"))
+}
+
+/// a folder, or what looks like one once it is gone
+fn is_folder(path: &Path) -> bool {
+    path.is_dir() || (!path.exists() && path.extension().is_none())
+}
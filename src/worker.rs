@@ -0,0 +1,44 @@
+use std::{
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+};
+
+/// Runs jobs one after another on a background thread, so that slow work such as
+/// reading pdfs does not block the terminal UI.
+///
+/// results are collected with `results`, usually on every tick
+pub struct Worker<J, R> {
+    jobs: Sender<J>,
+    results: Receiver<R>,
+}
+
+impl<J: Send + 'static, R: Send + 'static> Worker<J, R> {
+    pub fn spawn(name: &str, work: impl Fn(J) -> R + Send + 'static) -> Self {
+        let (jobs, job_rx) = mpsc::channel::<J>();
+        let (result_tx, results) = mpsc::channel();
+        let spawned = thread::Builder::new()
+            .name(name.to_string())
+            .spawn(move || {
+                for job in job_rx {
+                    if result_tx.send(work(job)).is_err() {
+                        break;
+                    }
+                }
+            });
+        if let Err(err) = spawned {
+            log::error!("failed to start the {} worker: {}", name, err);
+        }
+        Self { jobs, results }
+    }
+
+    pub fn submit(&self, job: J) {
+        if self.jobs.send(job).is_err() {
+            log::warn!("dropped a job, the worker has stopped");
+        }
+    }
+
+    /// the results finished since the last call
+    pub fn results(&self) -> Vec<R> {
+        self.results.try_iter().collect()
+    }
+}